@@ -0,0 +1,67 @@
+use std::{collections::HashMap, io::Cursor};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use ymir::{
+    cache::CacheSerializer,
+    config::Cache,
+    projects::{Project, ProjectLanguage},
+    utils::GitInfo,
+};
+
+fn projects(count: usize) -> Vec<Project> {
+    (0..count)
+        .map(|i| {
+            let language = ProjectLanguage {
+                files: 12,
+                lines: 1200,
+                code: 1000,
+                comments: 120,
+                blanks: 80,
+            };
+            Project {
+                path: format!("/home/user/projects/group-{}/project-{i}", i % 50).into(),
+                size: i as u64 * 1024,
+                git_info: GitInfo {
+                    remote_url: Some(format!("https://github.com/user/project-{i}.git")),
+                    last_commit_msg: Some("Fix the thing that broke".to_string()),
+                    commit_count: 150,
+                    branch: Some("main".to_string()),
+                    ..Default::default()
+                },
+                languages: HashMap::from([(0, language.clone()), (1, language.clone())]),
+                languages_total: language,
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+fn cache(c: &mut Criterion) {
+    let mut group = c.benchmark_group("cache");
+
+    for count in [100, 1_000, 10_000] {
+        for compressed in [false, true] {
+            let cache = Cache {
+                projects: projects(count),
+                compressed,
+            };
+            let bytes = cache.serialize().unwrap();
+            let name = if compressed { "compressed" } else { "plain" };
+
+            group.bench_function(BenchmarkId::new(format!("serialize/{name}"), count), |b| {
+                b.iter(|| cache.serialize().unwrap());
+            });
+            group.bench_function(
+                BenchmarkId::new(format!("deserialize/{name}"), count),
+                |b| {
+                    b.iter(|| Cache::deserialize(&mut Cursor::new(&bytes[..])).unwrap());
+                },
+            );
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, cache);
+criterion_main!(benches);
@@ -0,0 +1,37 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use ymir::huffman::{huffman_decode, huffman_encode};
+
+/// Deterministic bytes skewed towards a small alphabet, roughly like a serialized cache
+fn sample(len: usize) -> Vec<u8> {
+    let mut state = 0x2545_f491_u32;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            (state % 64).min(state % 251) as u8
+        })
+        .collect()
+}
+
+fn huffman(c: &mut Criterion) {
+    let mut group = c.benchmark_group("huffman");
+
+    for len in [4 * 1024, 64 * 1024, 1024 * 1024] {
+        let data = sample(len);
+        let encoded = huffman_encode(&data);
+        group.throughput(Throughput::Bytes(len as u64));
+
+        group.bench_with_input(BenchmarkId::new("encode", len), &data, |b, data| {
+            b.iter(|| huffman_encode(data));
+        });
+        group.bench_with_input(BenchmarkId::new("decode", len), &encoded, |b, encoded| {
+            b.iter(|| huffman_decode(encoded));
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, huffman);
+criterion_main!(benches);
@@ -0,0 +1,32 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use ratatui::{buffer::Buffer, layout::Rect, widgets::Widget};
+use ymir::{app::AppState, projects::Project};
+
+fn projects(count: usize) -> Vec<Project> {
+    (0..count)
+        .map(|i| Project {
+            path: format!("/home/user/projects/group-{}/project-{i}", i % 50).into(),
+            size: i as u64 * 1024,
+            ..Default::default()
+        })
+        .collect()
+}
+
+fn render(c: &mut Criterion) {
+    let mut group = c.benchmark_group("render");
+    let area = Rect::new(0, 0, 200, 60);
+
+    for count in [1_000, 10_000] {
+        let state = AppState::new(projects(count));
+        let mut buffer = Buffer::empty(area);
+
+        group.bench_function(BenchmarkId::new("frame", count), |b| {
+            b.iter(|| (&state).render(area, &mut buffer));
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, render);
+criterion_main!(benches);
@@ -0,0 +1,63 @@
+use std::fs;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use git2::{Repository, Signature, Time};
+use tempfile::TempDir;
+use ymir::{config::Settings, projects};
+
+const MAIN_RS: &str = "// Entry point\nfn main() {\n    println!(\"bench\");\n}\n";
+
+/// Tree of `count` committed repositories with a handful of source files each, removed on drop
+fn tree(count: usize) -> TempDir {
+    let root = tempfile::Builder::new()
+        .prefix(&format!("ymir-bench-scan-{count}-"))
+        .tempdir()
+        .unwrap();
+
+    for i in 0..count {
+        let dir = root.path().join(format!("group-{}/project-{i}", i % 10));
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join("src/main.rs"), MAIN_RS).unwrap();
+        fs::write(dir.join("src/lib.py"), "def answer():\n    return 42\n").unwrap();
+        fs::write(dir.join("README.md"), "# Project\n\nSome text\n").unwrap();
+
+        let repo = Repository::init(&dir).unwrap();
+        let mut index = repo.index().unwrap();
+        index
+            .add_all(["*"], git2::IndexAddOption::DEFAULT, None)
+            .unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature =
+            Signature::new("ymir", "ymir@localhost", &Time::new(1_600_000_000, 0)).unwrap();
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            "Initial commit",
+            &tree,
+            &[],
+        )
+        .unwrap();
+    }
+
+    root
+}
+
+fn scan(c: &mut Criterion) {
+    let mut group = c.benchmark_group("scan");
+    group.sample_size(10);
+    let settings = Settings::default();
+
+    for count in [10, 100] {
+        let tree = tree(count);
+        let root = tree.path().to_path_buf();
+        group.bench_with_input(BenchmarkId::new("find", count), &root, |b, root| {
+            b.iter(|| projects::find(root, &settings));
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, scan);
+criterion_main!(benches);
@@ -0,0 +1,10 @@
+#![no_main]
+
+use std::io::Cursor;
+
+use libfuzzer_sys::fuzz_target;
+use ymir::{cache::CacheSerializer, config::Cache};
+
+fuzz_target!(|data: &[u8]| {
+    let _ = Cache::deserialize(&mut Cursor::new(data));
+});
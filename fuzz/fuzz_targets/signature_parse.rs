@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use ymir::signature::CommitSignature;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(armored) = std::str::from_utf8(data) {
+        let _ = CommitSignature::parse(armored);
+    }
+});
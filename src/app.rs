@@ -1,579 +1,5345 @@
 //! App for ymir
+//!
+//! [`AppState`] is everything on screen, it only changes in [`AppState::update`] and renders as
+//! a plain function of itself. Keys, ipc messages and finished background work all reach it as
+//! a [`Message`], whatever needs the terminal, the filesystem or another thread comes back out
+//! as an [`Effect`] that [`App`] carries out and answers with more messages.
 
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+    time::Duration,
+};
+
+use anyhow::Context;
+use log::error;
 use ratatui::{
     buffer::Buffer,
-    crossterm::event::{self, Event, KeyCode, KeyEvent},
-    layout::{Constraint, Layout, Rect},
+    crossterm::{
+        cursor::{MoveTo, RestorePosition, SavePosition},
+        event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
+        queue,
+        style::Print,
+    },
+    layout::{Constraint, Flex, Layout, Rect},
     style::{Color, Modifier, Style, Stylize},
     symbols,
     text::{Line, Span},
     widgets::{
-        Block, Borders, Cell, HighlightSpacing, List, ListItem, ListState, Padding, Paragraph, Row,
-        StatefulWidget, Table, Widget, Wrap,
+        Block, Borders, Cell, Clear, HighlightSpacing, List, ListItem, ListState, Padding,
+        Paragraph, Row, StatefulWidget, Table, Widget, Wrap,
     },
     DefaultTerminal,
 };
 
-use ratatui::style::palette::tailwind::{CYAN, NEUTRAL, RED, SLATE};
+use ratatui::style::palette::tailwind::{BLUE, CYAN, GREEN, NEUTRAL, RED, SLATE, YELLOW};
 use tokei::LanguageType;
 
 use crate::{
-    projects::Project,
+    archive::{self, Candidates},
+    audit::Audit,
+    commit_search::{self, CommitMatch, Pattern},
+    config::{self, Cache, ConfirmAction, ProjectLabel, Settings, Truncation},
+    content_search::{self, ContentMatch},
+    credentials::Credential,
+    devenv::DevEnv,
+    duplicates::{self, Consolidation, Duplicate},
+    effort::Effort,
+    enrichment, export,
+    graphics::{Protocol, Slice},
+    history::{History, OpenCounts},
+    icons,
+    ignore_stats::IgnoreStats,
+    ipc::{self, IpcMessage, IpcServer},
+    linguist, manifest,
+    palette::{self, Action},
+    projects,
+    projects::{Project, PARTIAL},
+    recent_files::RecentFiles,
+    row_style::RowStyles,
+    sort_expr::SortExpr,
     sorting::{Filter, Sorting},
+    tasks::{self, Task},
+    template::ListTemplate,
+    terminal,
+    utils::{
+        expand_home, format_bytes, format_duration, format_number, format_relative, free_space,
+        truncate_middle, GitInfo, RemoteUrl, RepoState,
+    },
+    zoxide,
 };
 
-#[allow(clippy::struct_excessive_bools)]
+/// Runs an [`AppState`] in the terminal
 pub struct App {
+    state: AppState,
+    ipc: Option<IpcServer>,
+    /// Events of the scan running on a background thread
+    scan: Option<Receiver<ScanEvent>>,
+    /// Progress of the export running on a background thread
+    export: Option<Receiver<Message>>,
+    /// Answer of the commit search running on a background thread
+    commit_search: Option<Receiver<Message>>,
+    /// Answer of the content search running on a background thread
+    content_search: Option<Receiver<Message>>,
+    /// Language chart last written to the terminal, where and of what
+    chart: Option<(Rect, Vec<Slice>)>,
+    /// Window title last written to the terminal
+    title: Option<String>,
+    /// Projects to ask GitHub about, for the queue running on a background thread
+    enrichment: Option<mpsc::Sender<Vec<(PathBuf, String)>>>,
+    /// Where projects stand with the queue
+    enriched: Option<Receiver<Message>>,
+    /// What was done to projects this session
+    audit: Audit,
+    /// Performed once the terminal is set up, like opening the project given with `--open`
+    startup: Option<Effect>,
+    /// Project to select given on the command line and whether to open it, waiting for the scan
+    /// started with the app to find it
+    selection: Option<(String, bool)>,
+}
+
+#[allow(clippy::struct_excessive_bools)]
+pub struct AppState {
     should_exit: bool,
     show_project_info: bool,
     show_languages: bool,
+    show_git: bool,
+    show_crates: bool,
+    show_metadata: bool,
+    show_errors: bool,
+    /// What ignore_dirs skipped in the last scan, shown while it's loaded
+    ignore_stats: Option<IgnoreStats>,
+    /// How many times projects were opened, shown while it's loaded
+    open_counts: Option<OpenCounts>,
+    /// Files modified last across projects, shown while it's loaded
+    recent_files: Option<RecentFiles>,
+    /// What GitHub said about projects, or why it didn't yet, with `github` set
+    enrichment: HashMap<PathBuf, enrichment::Status>,
+    /// When the panes not showing the selected project last loaded their data, unix seconds
+    loaded_at: HashMap<Pane, i64>,
     projects_list: ProjectsList,
     sort_type: Sorting,
     filter_type: Filter,
+    /// Only show projects whose primary remote is owned by this user or organization
+    org: Option<String>,
     invert: bool,
     git_name: String,
+    settings: Settings,
+    /// Directories projects are shown from, rescanning needs them
+    scan_roots: Vec<PathBuf>,
+    /// Why loading projects failed, shown instead of the list when there are none
+    load_error: Option<String>,
+    /// Outcome of the last action or why it didn't run, shown in place of the help line
+    notice: Option<String>,
+    /// Projects found so far and the latest one while a scan runs
+    scan: Option<(usize, PathBuf)>,
+    /// Directories the running scan looks in, what it finds replaces only the projects in them
+    scanned_roots: Vec<PathBuf>,
+    /// Scans merge what they find into the cache, off with `--no-cache` or an unreadable cache
+    write_cache: bool,
+    /// Size of the terminal, the list scrolls to keep the selection visible in it
+    area: Rect,
+
+    /// Text typed after `:`
+    command: Option<String>,
 
     // Search
     search_text: Option<String>,
     search_index: usize,
     search_count: usize,
+
+    /// Selection in the filter picker while it's open
+    filter_picker: Option<ListState>,
+    /// Tasks of the selected project and the selection among them while the picker is open
+    task_picker: Option<(Vec<Task>, ListState)>,
+    /// Text typed into the command palette and the selection among the actions matching it
+    palette: Option<(String, ListState)>,
+    /// Action waiting to be confirmed with `y` and the question asked about it, see `confirm`
+    pending: Option<(String, Effect)>,
+    /// Free bytes on the filesystem of the first scan root, shown in the header
+    free_space: Option<u64>,
+    /// Query of the running commit search
+    commit_search: Option<String>,
+    /// Commits matching the query, grouped by project, and the selection among them
+    commit_results: Option<(String, Vec<CommitMatch>, ListState)>,
+    /// Query of the running content search
+    content_search: Option<String>,
+    /// Lines matching the query, grouped by project, and the selection among them
+    content_results: Option<(String, Vec<ContentMatch>, ListState)>,
+    /// Destination of the running export, how many files are written and how many there are
+    export: Option<(String, usize, usize)>,
+    /// Parsed `list_template`, rows show the label when it's unset or invalid
+    list_template: Option<ListTemplate>,
+    /// Parsed `row_styles`, empty when they're invalid
+    row_styles: RowStyles,
+    /// Parsed `custom_sorts`, empty when they're invalid
+    custom_sorts: Vec<SortExpr>,
+    /// Zoxide scores for the Frecency sorting
+    frecency: HashMap<PathBuf, f64>,
+    /// Protocol the language chart is drawn with, `None` shows only the table
+    graphics: Option<Protocol>,
+
+    // Vim style navigation
+    /// Count typed before a movement, `15` in `15j`
+    count: Option<usize>,
+    /// `m` or `'` waiting for the mark name
+    pending_mark: Option<MarkAction>,
+    marks: HashMap<char, PathBuf>,
+    /// Letters typed of a quick-jump label, labels are shown while it's set
+    jump: Option<String>,
+
+    /// Views left with filtering and sorting keys, most recent last, `U` goes back to them
+    undo: Vec<View>,
+    /// Views left with `U`, `R` goes forward to them again
+    redo: Vec<View>,
+}
+
+/// How many views `U` can go back
+const MAX_UNDO: usize = 100;
+
+/// Filter and sorting of the list, what undo and redo restore
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct View {
+    filter: Filter,
+    org: Option<String>,
+    sort: Sorting,
+    invert: bool,
+}
+
+#[derive(Clone, Copy)]
+enum MarkAction {
+    Set,
+    Jump,
+}
+
+/// Something that happened, the only way [`AppState`] changes
+#[derive(Debug)]
+pub enum Message {
+    Key(KeyEvent),
+    Resize(Rect),
+    Ipc(IpcMessage),
+    /// Projects found since the last progress and how many were found so far
+    ScanProgress(usize, Vec<Project>),
+    /// Everything under the scan roots once the scan wrote the cache, with why it failed
+    ScanDone(Vec<Project>, Option<String>),
+    /// The scan thread went away without finishing
+    ScanStopped,
+    /// Answer to [`Effect::ReadCache`]
+    CacheRead(Result<Vec<Project>, String>),
+    /// Answer to [`Effect::ChangeRoot`], the directory and what the cache has
+    RootResolved {
+        root: PathBuf,
+        append: bool,
+        cached: Vec<Project>,
+    },
+    /// Tasks found in the directory
+    TasksFound(PathBuf, Vec<Task>),
+    IgnoreStatsLoaded(IgnoreStats),
+    OpenCountsLoaded(OpenCounts),
+    RecentFilesLoaded(RecentFiles),
+    /// A consolidation was applied, the cache no longer has a deleted clone
+    Consolidated(Consolidation, PathBuf, PathBuf),
+    /// A project was tarred into the archive and deleted, the cache no longer has it
+    Archived(PathBuf, PathBuf),
+    /// Answer to [`Effect::CheckFreeSpace`]
+    FreeSpace(u64),
+    /// Files written by the export and how many there are
+    ExportProgress(usize, usize),
+    /// The export finished, where it wrote the project to
+    Exported(Result<PathBuf, String>),
+    /// Answer to [`Effect::SearchCommits`]
+    CommitsFound(Result<Vec<CommitMatch>, String>),
+    /// Answer to [`Effect::SearchContents`]
+    ContentsFound(Result<Vec<ContentMatch>, String>),
+    /// Where a project stands with the GitHub enrichment queue
+    Enriched(PathBuf, enrichment::Status),
+    /// Shown in the footer, how an effect went
+    Notice(String),
+}
+
+/// Work [`AppState::update`] leaves to [`App`]
+#[derive(Debug)]
+pub enum Effect {
+    /// Scan `roots` in the background, the list then shows everything under `visible`
+    Scan {
+        roots: Vec<PathBuf>,
+        visible: Vec<PathBuf>,
+        settings: Settings,
+        /// Merge the result into the cache, without it only the list has it
+        cache: bool,
+    },
+    /// Hand the terminal to the task running in the directory until it exits
+    RunTask(Task, PathBuf),
+    /// Show the project in the file manager
+    Reveal(PathBuf),
+    /// Show what was done to projects this session in the pager
+    ShowAudit,
+    /// Open a shell in the project, recording the visit
+    OpenProject(PathBuf),
+    /// Page through everything known about the project as JSON
+    ShowJson(Project),
+    FindTasks(PathBuf),
+    LoadIgnoreStats,
+    LoadOpenCounts,
+    LoadRecentFiles,
+    ReadCache,
+    /// Resolve the directory typed after `:cd` or `:add`
+    ChangeRoot {
+        input: String,
+        append: bool,
+    },
+    Consolidate(Consolidation, PathBuf, PathBuf),
+    /// Tar the project into the directory and delete it
+    Archive(PathBuf, PathBuf),
+    /// Write a copy of the project to the file typed after `:archive` in the background
+    ExportArchive(PathBuf, String),
+    /// Look for commits matching the pattern in the repositories in the background
+    SearchCommits(Vec<PathBuf>, Pattern),
+    /// Look for lines matching the pattern in files of the projects in the background
+    SearchContents(Vec<PathBuf>, Pattern),
+    /// Write bookmarks for the projects to the file typed after `:export`
+    ExportBookmarks(String, Vec<Project>),
+    /// Write a VS Code workspace with the projects to the file typed after `:workspace`
+    ExportWorkspace(String, Vec<Project>),
+    FocusTerminal,
+    /// Read how much space is left where the projects are, after something freed or used some
+    CheckFreeSpace,
+}
+
+/// Expands `~` and makes `input` absolute, failing if it isn't a directory
+fn resolve_dir(input: &str) -> anyhow::Result<PathBuf> {
+    let input = input.trim();
+    if input.is_empty() {
+        anyhow::bail!("Missing directory");
+    }
+
+    let path = expand_home(input)?
+        .canonicalize()
+        .with_context(|| format!("Failed to open {input}"))?;
+    if !path.is_dir() {
+        anyhow::bail!("{input} is not a directory");
+    }
+    Ok(path)
+}
+
+enum ScanEvent {
+    Progress(usize, Box<Project>),
+    Done(Vec<Project>, Option<String>),
 }
 
+/// How long to wait for input before checking for ipc messages
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
 const SELECTED_STYLE: Style = Style::new().bg(NEUTRAL.c900).add_modifier(Modifier::BOLD);
 const INACTIVE_COLOR: Color = RED.c700;
+const CI_COLOR: Color = GREEN.c500;
+const CONTAINER_COLOR: Color = BLUE.c400;
+const JUMP_STYLE: Style = Style::new()
+    .fg(NEUTRAL.c950)
+    .bg(YELLOW.c400)
+    .add_modifier(Modifier::BOLD);
+/// Letters of quick-jump labels, home row first
+const JUMP_KEYS: &[u8] = b"asdfghjklqwertyuiopzxcvbnm";
+const MATCH_STYLE: Style = Style::new().fg(YELLOW.c400).add_modifier(Modifier::BOLD);
 pub const TEXT_FG_COLOR: Color = SLATE.c200;
 
 impl App {
     /// Create a new app with the given list of projects
     pub fn new(projects_list: Vec<Project>) -> Self {
+        let git_name = git2::Config::open_default().map_or(String::new(), |v| {
+            v.get_string("user.name").unwrap_or_default()
+        });
         Self {
-            should_exit: false,
-            show_project_info: true,
-            show_languages: true,
-            sort_type: Sorting::Name,
-            filter_type: Filter::All,
-            projects_list: ProjectsList::from_iter(projects_list),
-            invert: false,
-            git_name: git2::Config::open_default().map_or(String::new(), |v| {
-                v.get_string("user.name").unwrap_or_default()
-            }),
-            search_text: None,
-            search_index: 0,
-            search_count: 0,
+            state: AppState::new(projects_list).with_git_name(git_name),
+            ipc: None,
+            scan: None,
+            export: None,
+            commit_search: None,
+            content_search: None,
+            chart: None,
+            title: None,
+            enrichment: None,
+            enriched: None,
+            audit: Audit::new(),
+            startup: None,
+            selection: None,
         }
     }
 
-    pub fn run(mut self, mut terminal: DefaultTerminal) -> anyhow::Result<()> {
-        while !self.should_exit {
-            terminal.draw(|frame| frame.render_widget(&mut self, frame.area()))?;
-            if let Event::Key(key) = event::read()? {
-                if self.search_text.is_some() {
-                    self.handle_search_key(key);
-                } else {
-                    self.handle_key(key);
-                }
-            };
+    /// Apply display and navigation settings
+    pub fn with_settings(mut self, settings: &Settings) -> Self {
+        self.state = self.state.with_settings(settings);
+        if settings.zoxide {
+            self.state = self.state.with_frecency(zoxide::scores());
         }
-        Ok(())
+        if settings.language_chart && !settings.theme.is_accessible() {
+            self.state = self.state.with_graphics(Protocol::detect());
+        }
+        self
     }
 
-    fn handle_key(&mut self, key: KeyEvent) {
-        if key.kind != event::KeyEventKind::Press {
-            return;
+    /// Directory to rescan when asked to
+    pub fn with_scan_root(mut self, root: PathBuf) -> Self {
+        self.state = self.state.with_scan_root(root);
+        self
+    }
+
+    /// Scan the scan roots once running instead of waiting for it, projects show up as they're
+    /// found, `cache` false leaves the cache as it is for this and later scans
+    pub fn with_scan(mut self, cache: bool) -> Self {
+        self.state.write_cache = cache;
+        self.startup = self.state.rescan();
+        self
+    }
+
+    /// Select the project `query` matches best, opening it right away with `open`
+    ///
+    /// With [`App::with_scan`] this happens once the scan finished.
+    pub fn with_selection(mut self, query: &str, open: bool) -> Self {
+        if self.state.scan.is_some() {
+            self.selection = Some((query.to_string(), open));
+            return self;
+        }
+        let path = self.state.select_best(query);
+        if open {
+            self.startup = path.map(Effect::OpenProject);
         }
+        self
+    }
 
-        match key.code {
-            KeyCode::Char('q') | KeyCode::Esc => self.should_exit = true,
-            // Movement
-            KeyCode::Char('j') | KeyCode::Down => self.select_next(),
-            KeyCode::Char('k') | KeyCode::Up => self.select_previous(),
-            KeyCode::Char('d') => self.select_next_10(),
-            KeyCode::Char('u') => self.select_previous_10(),
-            KeyCode::Char('g') | KeyCode::Home => self.select_first(),
-            KeyCode::Char('G') | KeyCode::End => self.select_last(),
+    pub fn with_load_error(mut self, error: Option<String>) -> Self {
+        self.state = self.state.with_load_error(error);
+        self
+    }
+
+    /// Handle messages sent by other instances while running
+    pub fn with_ipc(mut self, ipc: Option<IpcServer>) -> Self {
+        self.ipc = ipc;
+        self
+    }
 
-            // Toggle
-            KeyCode::Char('1') => self.show_project_info = !self.show_project_info,
-            KeyCode::Char('2') => self.show_languages = !self.show_languages,
+    pub fn run(mut self, mut terminal: DefaultTerminal) -> anyhow::Result<()> {
+        let size = terminal.size()?;
+        self.dispatch(
+            Message::Resize(Rect::new(0, 0, size.width, size.height)),
+            &mut terminal,
+        );
+        if let Some(message) = self.perform(Effect::CheckFreeSpace, &mut terminal) {
+            self.dispatch(message, &mut terminal);
+        }
+        self.enrich();
+        if let Some(message) = self
+            .startup
+            .take()
+            .and_then(|effect| self.perform(effect, &mut terminal))
+        {
+            self.dispatch(message, &mut terminal);
+        }
 
-            // Sorting
-            KeyCode::Char('h') | KeyCode::Left => {
-                self.sort_type = self.sort_type.previous();
-                self.projects_list
-                    .sort_projects(&self.sort_type, self.invert);
+        if self.state.settings.terminal_title {
+            terminal::emit(terminal::PUSH_TITLE);
+        }
+        while !self.state.should_exit {
+            terminal.draw(|frame| frame.render_widget(&self.state, frame.area()))?;
+            self.draw_chart(&mut terminal)?;
+            self.update_title();
+
+            if let Some(message) = self.ipc.as_ref().and_then(|v| v.receiver.try_recv().ok()) {
+                self.dispatch(Message::Ipc(message), &mut terminal);
+                continue;
             }
-            KeyCode::Char('l') | KeyCode::Right => {
-                self.sort_type = self.sort_type.next();
-                self.projects_list
-                    .sort_projects(&self.sort_type, self.invert);
+
+            for message in self.scan_messages() {
+                self.dispatch(message, &mut terminal);
             }
-            KeyCode::Char('i') => {
-                self.invert = !self.invert;
-                self.projects_list
-                    .sort_projects(&self.sort_type, self.invert);
+            let enriched: Vec<Message> = self
+                .enriched
+                .as_ref()
+                .map(|v| v.try_iter().collect())
+                .unwrap_or_default();
+            for message in enriched {
+                self.dispatch(message, &mut terminal);
             }
-
-            // Filtering
-            KeyCode::Char('y') => {
-                self.filter_type = self.filter_type.previous();
-                self.projects_list
-                    .filter_projects(&self.filter_type, &self.git_name);
+            if self.state.scan.is_none() {
+                if let Some((query, open)) = self.selection.take() {
+                    let path = self.state.select_best(&query).filter(|_| open);
+                    if let Some(message) =
+                        path.and_then(|v| self.perform(Effect::OpenProject(v), &mut terminal))
+                    {
+                        self.dispatch(message, &mut terminal);
+                    }
+                }
             }
-            KeyCode::Char('o') => {
-                self.filter_type = self.filter_type.next();
-                self.projects_list
-                    .filter_projects(&self.filter_type, &self.git_name);
+            for message in self.export_messages() {
+                self.dispatch(message, &mut terminal);
+            }
+            if let Some(message) = self.commit_search_message() {
+                self.dispatch(message, &mut terminal);
+            }
+            if let Some(message) = self.content_search_message() {
+                self.dispatch(message, &mut terminal);
             }
 
-            // Searching
-            KeyCode::Char('/') => {
-                if self.search_text.is_some() {
-                    self.search_text = None;
-                } else {
-                    self.search_text = Some(String::new());
-                }
+            if !event::poll(POLL_INTERVAL)? {
+                continue;
             }
 
-            _ => {}
+            match event::read()? {
+                Event::Key(key) => self.dispatch(Message::Key(key), &mut terminal),
+                Event::Resize(width, height) => self.dispatch(
+                    Message::Resize(Rect::new(0, 0, width, height)),
+                    &mut terminal,
+                ),
+                _ => {}
+            }
+        }
+
+        if let Some(protocol) = self.state.graphics {
+            write!(io::stdout(), "{}", protocol.clear())?;
+        }
+        if self.state.settings.terminal_title {
+            terminal::emit(terminal::POP_TITLE);
         }
+        Ok(())
     }
 
-    fn handle_search_key(&mut self, key: KeyEvent) {
-        if key.kind != event::KeyEventKind::Press {
+    /// Titles the window after the selected project when it changed since last frame
+    fn update_title(&mut self) {
+        if !self.state.settings.terminal_title {
             return;
         }
+        let title = match self.state.selected() {
+            Some(project) => format!("ymir — {}", self.state.settings.label(project)),
+            None => "ymir".to_string(),
+        };
+        if self.title.as_ref() != Some(&title) {
+            terminal::emit(&terminal::title(&title));
+            self.title = Some(title);
+        }
+    }
 
-        match key.code {
-            KeyCode::Esc => {
-                self.search_text = None;
-                self.search_index = 0;
+    /// Writes the language chart over the cells left for it when it changed since last frame
+    fn draw_chart(&mut self, terminal: &mut DefaultTerminal) -> anyhow::Result<()> {
+        let Some(protocol) = self.state.graphics else {
+            return Ok(());
+        };
+        let chart = self.state.language_chart(terminal.get_frame().area());
+        if chart == self.chart {
+            return Ok(());
+        }
+
+        let mut stdout = io::stdout();
+        write!(stdout, "{}", protocol.clear())?;
+        // Cells under a sixel image only lose it when they're written again
+        if protocol == Protocol::Sixel && self.chart.is_some() {
+            terminal.clear()?;
+            terminal.draw(|frame| frame.render_widget(&self.state, frame.area()))?;
+        }
+        if let Some((area, slices)) = &chart {
+            queue!(
+                stdout,
+                SavePosition,
+                MoveTo(area.x, area.y),
+                Print(protocol.encode(slices, area.height)),
+                RestorePosition
+            )?;
+        }
+        stdout.flush()?;
+
+        self.chart = chart;
+        Ok(())
+    }
+
+    /// Updates the state with `message` and carries out the effects that follow
+    fn dispatch(&mut self, message: Message, terminal: &mut DefaultTerminal) {
+        let projects_changed = matches!(
+            message,
+            Message::ScanDone(..) | Message::CacheRead(_) | Message::RootResolved { .. }
+        );
+        let mut next = self.state.update(message);
+        while let Some(effect) = next.take() {
+            next = self
+                .perform(effect, terminal)
+                .and_then(|message| self.state.update(message));
+        }
+        if projects_changed {
+            self.enrich();
+        }
+    }
+
+    /// Hands the projects on GitHub to the enrichment queue, starting it the first time
+    fn enrich(&mut self) {
+        if !self.state.settings.github {
+            return;
+        }
+        let repos: Vec<(PathBuf, String)> = self
+            .state
+            .projects_list
+            .base
+            .iter()
+            .filter_map(|v| Some((v.path.clone(), enrichment::github_repo(v)?)))
+            .collect();
+        if repos.is_empty() {
+            return;
+        }
+
+        let sender = self.enrichment.get_or_insert_with(|| {
+            let (repos_sender, repos) = mpsc::channel();
+            let (sender, receiver) = mpsc::channel();
+            let fallback = self.state.settings.github_token.clone();
+            thread::spawn(move || {
+                // The keyring may block, so it's read here and not before the first frame
+                let token = Credential::GitHub.token().or(fallback);
+                enrichment::run(&repos, token.as_deref(), |path, status| {
+                    sender.send(Message::Enriched(path, status)).is_ok()
+                });
+            });
+            self.enriched = Some(receiver);
+            repos_sender
+        });
+        if sender.send(repos).is_err() {
+            error!("Enrichment queue stopped unexpectedly");
+        }
+    }
+
+    fn perform(&mut self, effect: Effect, terminal: &mut DefaultTerminal) -> Option<Message> {
+        match effect {
+            Effect::Scan {
+                roots,
+                visible,
+                settings,
+                cache,
+            } => {
+                let (sender, receiver) = mpsc::channel();
+                thread::spawn(move || {
+                    let progress = |count, project: &Project| {
+                        let _ = sender.send(ScanEvent::Progress(count, Box::new(project.clone())));
+                    };
+                    let (projects, error) = if cache {
+                        projects::scan_into_cache(&roots, &visible, &settings, progress)
+                    } else {
+                        (projects::find_all(&roots, &settings, progress), None)
+                    };
+                    let _ = sender.send(ScanEvent::Done(projects, error));
+                });
+                self.scan = Some(receiver);
+                // The root may be on another filesystem
+                self.perform(Effect::CheckFreeSpace, terminal)
             }
-            KeyCode::Char(c) => {
-                if let Some(v) = self.search_text.as_mut() {
-                    v.push(c);
+            Effect::RunTask(task, dir) => {
+                if !matches!(task.source, "ymir" | "shell") {
+                    self.audit
+                        .record(&format!("Ran {task} in {}", dir.display()));
                 }
-                self.search_count = self.projects_list.search(
-                    &self.search_text.clone().unwrap_or_default(),
-                    self.search_index,
-                );
+                ratatui::restore();
+                let result = task.run(&dir);
+                *terminal = ratatui::init();
+                self.chart = None;
+                // The task may have titled the window itself
+                self.title = None;
+                result
+                    .err()
+                    .map(|err| Message::Notice(format!("Failed to run {task}: {err}")))
             }
-            KeyCode::Backspace => {
-                if let Some(v) = self.search_text.as_mut() {
-                    v.pop();
+            Effect::ShowAudit => {
+                let report = self.audit.report();
+                self.perform(
+                    Effect::RunTask(Task::pager(report), PathBuf::from(".")),
+                    terminal,
+                )
+            }
+            Effect::Reveal(path) => {
+                let file_manager = self.state.settings.file_manager.as_deref();
+                tasks::reveal(&path, file_manager)
+                    .err()
+                    .map(|err| Message::Notice(format!("{err:#}")))
+            }
+            Effect::OpenProject(path) => {
+                if let Err(err) = History::record(&path) {
+                    error!("Failed to record history: {err:#}");
                 }
-                self.search_count = self.projects_list.search(
-                    &self.search_text.clone().unwrap_or_default(),
-                    self.search_index,
-                );
+                if self.state.settings.zoxide {
+                    if let Err(err) = zoxide::add(&path) {
+                        error!("Failed to add to zoxide: {err:#}");
+                    }
+                }
+                self.audit.record(&format!("Opened {}", path.display()));
+                if self.state.settings.report_cwd {
+                    terminal::emit(&terminal::cwd(&path));
+                }
+                let shell = Task::shell(&path, self.state.settings.allow_direnv);
+                self.perform(Effect::RunTask(shell, path), terminal)
+                    .or_else(|| {
+                        self.state
+                            .open_counts
+                            .is_some()
+                            .then(|| Message::OpenCountsLoaded(OpenCounts::load()))
+                    })
             }
-            KeyCode::Enter => {
-                self.search_count = self.projects_list.search(
-                    &self.search_text.clone().unwrap_or_default(),
-                    self.search_index,
-                );
-
-                if self.search_index >= self.search_count.wrapping_sub(1) {
-                    self.search_index = 0;
-                } else {
-                    self.search_index += 1;
+            Effect::ShowJson(project) => {
+                match export::project_json(&project, &History::load(), &self.state.settings)
+                    .and_then(|v| serde_json::to_string_pretty(&v))
+                {
+                    Ok(json) => {
+                        self.perform(Effect::RunTask(Task::pager(json), project.path), terminal)
+                    }
+                    Err(err) => Some(Message::Notice(format!(
+                        "Failed to serialize project: {err}"
+                    ))),
                 }
             }
-            _ => {}
+            Effect::FindTasks(dir) => {
+                let tasks = tasks::find(&dir);
+                Some(Message::TasksFound(dir, tasks))
+            }
+            Effect::LoadIgnoreStats => Some(Message::IgnoreStatsLoaded(IgnoreStats::load())),
+            Effect::LoadOpenCounts => Some(Message::OpenCountsLoaded(OpenCounts::load())),
+            Effect::LoadRecentFiles => Some(Message::RecentFilesLoaded(RecentFiles::load())),
+            Effect::ReadCache => Some(Message::CacheRead(
+                Cache::try_read_cache().map_err(|err| format!("{err:#}")),
+            )),
+            Effect::ChangeRoot { input, append } => Some(match resolve_dir(&input) {
+                Ok(root) => Message::RootResolved {
+                    root,
+                    append,
+                    cached: Cache::try_read_cache().unwrap_or_default(),
+                },
+                Err(err) => Message::Notice(format!("{err:#}")),
+            }),
+            Effect::Consolidate(action, clone, primary) => {
+                if let Err(err) = action.apply(&clone, &primary) {
+                    return Some(Message::Notice(format!("{err:#}")));
+                }
+                self.audit.record(&match action {
+                    Consolidation::Delete => format!(
+                        "Deleted {}, a clone of {}",
+                        clone.display(),
+                        primary.display()
+                    ),
+                    Consolidation::Worktree => format!(
+                        "Made {} a worktree of {}",
+                        clone.display(),
+                        primary.display()
+                    ),
+                });
+                if action == Consolidation::Delete {
+                    self.uncache(&clone);
+                }
+                Some(Message::Consolidated(action, clone, primary))
+            }
+            Effect::Archive(project, dir) => {
+                let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+                match archive::archive(&project, &dir, &date) {
+                    Ok(tarball) => {
+                        self.audit.record(&format!(
+                            "Archived {} to {}",
+                            project.display(),
+                            tarball.display()
+                        ));
+                        self.uncache(&project);
+                        Some(Message::Archived(project, tarball))
+                    }
+                    Err(err) => Some(Message::Notice(format!("{err:#}"))),
+                }
+            }
+            Effect::ExportArchive(project, input) => {
+                let destination = match expand_home(input.trim()) {
+                    Ok(v) => v,
+                    Err(err) => return Some(Message::Exported(Err(format!("{err:#}")))),
+                };
+                let settings = &self.state.settings;
+                let (ignore_dirs, git) = (settings.ignore_dirs.clone(), settings.export_git);
+                let (sender, receiver) = mpsc::channel();
+                thread::spawn(move || {
+                    let files = archive::files(&project, &ignore_dirs, git);
+                    let result = archive::export(&project, &destination, files, |done, total| {
+                        let _ = sender.send(Message::ExportProgress(done, total));
+                    });
+                    let result = result
+                        .map(|()| destination)
+                        .map_err(|err| format!("{err:#}"));
+                    let _ = sender.send(Message::Exported(result));
+                });
+                self.export = Some(receiver);
+                None
+            }
+            Effect::SearchCommits(projects, pattern) => {
+                let (sender, receiver) = mpsc::channel();
+                thread::spawn(move || {
+                    let matches = commit_search::search(&projects, &pattern);
+                    let _ = sender.send(Message::CommitsFound(Ok(matches)));
+                });
+                self.commit_search = Some(receiver);
+                None
+            }
+            Effect::SearchContents(projects, pattern) => {
+                let ignore_dirs = self.state.settings.ignore_dirs.clone();
+                let (sender, receiver) = mpsc::channel();
+                thread::spawn(move || {
+                    let matches = content_search::search(&projects, &pattern, &ignore_dirs);
+                    let _ = sender.send(Message::ContentsFound(Ok(matches)));
+                });
+                self.content_search = Some(receiver);
+                None
+            }
+            Effect::ExportBookmarks(input, projects) => {
+                let result = expand_home(input.trim()).and_then(|path| {
+                    export::write_new(&path, export::bookmarks_html(&projects).as_bytes())?;
+                    Ok(path)
+                });
+                if let Ok(path) = &result {
+                    self.audit.record(&format!(
+                        "Exported bookmarks of {} projects to {}",
+                        projects.len(),
+                        path.display()
+                    ));
+                }
+                Some(Message::Notice(match result {
+                    Ok(path) => format!("Exported bookmarks to {}", path.display()),
+                    Err(err) => format!("{err:#}"),
+                }))
+            }
+            Effect::ExportWorkspace(input, projects) => {
+                let result = expand_home(input.trim()).and_then(|path| {
+                    let json = export::code_workspace(&projects, &self.state.settings)?;
+                    export::write_new(&path, json.as_bytes())?;
+                    Ok(path)
+                });
+                if let Ok(path) = &result {
+                    self.audit.record(&format!(
+                        "Exported a workspace of {} projects to {}",
+                        projects.len(),
+                        path.display()
+                    ));
+                }
+                Some(Message::Notice(match result {
+                    Ok(path) => format!("Exported workspace to {}", path.display()),
+                    Err(err) => format!("{err:#}"),
+                }))
+            }
+            Effect::FocusTerminal => {
+                ipc::focus_terminal();
+                None
+            }
+            Effect::CheckFreeSpace => {
+                let root = self.state.scan_roots.first()?;
+                free_space(root)
+                    .inspect_err(|err| warn!("{err:#}"))
+                    .ok()
+                    .map(Message::FreeSpace)
+            }
         }
     }
 
-    fn select_next(&mut self) {
-        self.projects_list.state.select_next();
-    }
+    /// Everything the background scan sent since the last frame
+    fn scan_messages(&mut self) -> Vec<Message> {
+        let Some(receiver) = self.scan.as_ref() else {
+            return Vec::new();
+        };
 
-    fn select_previous(&mut self) {
-        self.projects_list.state.select_previous();
+        let mut messages = Vec::new();
+        loop {
+            match receiver.try_recv() {
+                // Everything found since the last frame goes into the list at once
+                Ok(ScanEvent::Progress(count, project)) => match messages.last_mut() {
+                    Some(Message::ScanProgress(last, found)) => {
+                        *last = count;
+                        found.push(*project);
+                    }
+                    _ => messages.push(Message::ScanProgress(count, vec![*project])),
+                },
+                Ok(ScanEvent::Done(projects, error)) => {
+                    messages.push(Message::ScanDone(projects, error));
+                    break;
+                }
+                Err(TryRecvError::Empty) => return messages,
+                Err(TryRecvError::Disconnected) => {
+                    messages.push(Message::ScanStopped);
+                    break;
+                }
+            }
+        }
+        self.scan = None;
+        messages
     }
 
-    fn select_next_10(&mut self) {
-        self.projects_list.state.select(Some(
-            self.projects_list.state.selected().map_or(0, |v| v + 10),
-        ));
+    /// Progress of the export since the last frame
+    fn export_messages(&mut self) -> Vec<Message> {
+        let Some(receiver) = self.export.as_ref() else {
+            return Vec::new();
+        };
+
+        let mut messages = Vec::new();
+        loop {
+            match receiver.try_recv() {
+                Ok(message @ Message::Exported(_)) => {
+                    if let Message::Exported(Ok(path)) = &message {
+                        self.audit
+                            .record(&format!("Exported an archive to {}", path.display()));
+                    }
+                    messages.push(message);
+                    break;
+                }
+                Ok(message) => messages.push(message),
+                Err(TryRecvError::Empty) => return messages,
+                Err(TryRecvError::Disconnected) => {
+                    let error = "Export stopped unexpectedly".to_string();
+                    messages.push(Message::Exported(Err(error)));
+                    break;
+                }
+            }
+        }
+        self.export = None;
+        messages
     }
 
-    fn select_previous_10(&mut self) {
-        self.projects_list.state.select(Some(
-            self.projects_list
-                .state
-                .selected()
-                .map_or(self.projects_list.items.len(), |v| v.saturating_sub(10)),
-        ));
+    /// What the commit search found, once it's done
+    fn commit_search_message(&mut self) -> Option<Message> {
+        let message = match self.commit_search.as_ref()?.try_recv() {
+            Ok(message) => message,
+            Err(TryRecvError::Empty) => return None,
+            Err(TryRecvError::Disconnected) => {
+                Message::CommitsFound(Err("Commit search stopped unexpectedly".to_string()))
+            }
+        };
+        self.commit_search = None;
+        Some(message)
     }
 
-    fn select_first(&mut self) {
-        self.projects_list.state.select_first();
+    /// What the content search found, once it's done
+    fn content_search_message(&mut self) -> Option<Message> {
+        let message = match self.content_search.as_ref()?.try_recv() {
+            Ok(message) => message,
+            Err(TryRecvError::Empty) => return None,
+            Err(TryRecvError::Disconnected) => {
+                Message::ContentsFound(Err("Content search stopped unexpectedly".to_string()))
+            }
+        };
+        self.content_search = None;
+        Some(message)
     }
 
-    fn select_last(&mut self) {
-        self.projects_list.state.select_last();
+    /// Removes a deleted project from the cache, unless scans don't write to it
+    fn uncache(&self, path: &Path) {
+        if !self.state.write_cache {
+            return;
+        }
+        if let Err(err) = Cache::retain(self.state.settings.compress_cache, |v| v.path != path) {
+            error!("Failed to write cache: {err:#}");
+        }
     }
 }
 
-impl Widget for &mut App {
-    fn render(self, area: Rect, buf: &mut Buffer) {
-        let [header_area, main_area, footer_area] = Layout::vertical([
-            Constraint::Length(1),
-            Constraint::Fill(1),
-            Constraint::Length(1),
-        ])
-        .areas(area);
-
-        let [list_area, data_area] = if self.show_project_info || self.show_languages {
-            Layout::horizontal([Constraint::Fill(1), Constraint::Fill(1)]).areas(main_area)
-        } else {
-            Layout::horizontal([Constraint::Fill(1), Constraint::Fill(0)]).areas(main_area)
-        };
-
-        let [list_area, search_area] = if self.search_text.is_some() {
-            Layout::vertical([Constraint::Fill(1), Constraint::Length(3)]).areas(list_area)
-        } else {
-            Layout::vertical([Constraint::Fill(1), Constraint::Fill(0)]).areas(list_area)
+impl AppState {
+    /// State showing the given list of projects
+    pub fn new(projects_list: Vec<Project>) -> Self {
+        let mut state = Self {
+            should_exit: false,
+            show_project_info: true,
+            show_languages: true,
+            show_git: true,
+            show_crates: true,
+            show_metadata: true,
+            show_errors: true,
+            ignore_stats: None,
+            open_counts: None,
+            recent_files: None,
+            enrichment: HashMap::new(),
+            loaded_at: HashMap::new(),
+            sort_type: Sorting::Name,
+            filter_type: Filter::All,
+            org: None,
+            projects_list: ProjectsList::from_iter(projects_list),
+            invert: false,
+            git_name: String::new(),
+            settings: Settings::default(),
+            scan_roots: Vec::new(),
+            load_error: None,
+            notice: None,
+            scan: None,
+            scanned_roots: Vec::new(),
+            write_cache: true,
+            area: Rect::default(),
+            command: None,
+            search_text: None,
+            search_index: 0,
+            search_count: 0,
+            filter_picker: None,
+            task_picker: None,
+            palette: None,
+            pending: None,
+            commit_search: None,
+            commit_results: None,
+            content_search: None,
+            content_results: None,
+            export: None,
+            free_space: None,
+            list_template: None,
+            row_styles: RowStyles::parse(&Settings::default().row_styles).unwrap_or_default(),
+            custom_sorts: Vec::new(),
+            frecency: HashMap::new(),
+            graphics: None,
+            count: None,
+            pending_mark: None,
+            jump: None,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            marks: HashMap::new(),
         };
+        state.update_list();
+        state
+    }
 
-        let [info_area, langs_area] = if self.show_project_info && self.show_languages {
-            Layout::vertical([Constraint::Fill(1), Constraint::Fill(1)]).areas(data_area)
-        } else if !self.show_project_info && self.show_languages {
-            Layout::vertical([Constraint::Fill(0), Constraint::Fill(1)]).areas(data_area)
-        } else if self.show_project_info && !self.show_languages {
-            Layout::vertical([Constraint::Fill(1), Constraint::Fill(0)]).areas(data_area)
-        } else {
-            Layout::vertical([Constraint::Fill(0), Constraint::Fill(0)]).areas(data_area)
+    /// Apply display and navigation settings
+    pub fn with_settings(mut self, settings: &Settings) -> Self {
+        self.settings = settings.clone();
+        self.list_template = match settings.list_template.as_deref().map(ListTemplate::parse) {
+            Some(Ok(template)) => Some(template),
+            Some(Err(err)) => {
+                self.notice = Some(format!("{err:#}"));
+                None
+            }
+            None => None,
         };
-
-        App::render_header(header_area, buf);
-        App::render_footer(footer_area, buf);
-        self.render_list(list_area, buf);
-
-        if self.show_project_info {
-            self.render_project_info(info_area, buf);
+        self.row_styles = RowStyles::parse(&settings.row_styles).unwrap_or_else(|err| {
+            self.notice = Some(format!("{err:#}"));
+            RowStyles::default()
+        });
+        self.custom_sorts = SortExpr::parse_all(&settings.custom_sorts).unwrap_or_else(|err| {
+            self.notice = Some(format!("{err:#}"));
+            Vec::new()
+        });
+        match self.sort_type {
+            Sorting::Custom(i) if i >= self.custom_sorts.len() => self.sort_type = Sorting::Name,
+            Sorting::Frecency if !settings.zoxide => self.sort_type = Sorting::Name,
+            _ => {}
         }
+        self.update_list();
+        self
+    }
 
-        if self.search_text.is_some() {
-            self.render_search(search_area, buf);
-        }
+    /// Name of the user, the Owned filter matches remotes owned by it
+    pub fn with_git_name(mut self, name: String) -> Self {
+        self.git_name = name;
+        self.update_list();
+        self
+    }
 
-        if self.show_languages {
-            self.render_project_langs(langs_area, buf);
-        }
+    /// Zoxide scores for the Frecency sorting
+    pub fn with_frecency(mut self, frecency: HashMap<PathBuf, f64>) -> Self {
+        self.frecency = frecency;
+        self.update_list();
+        self
     }
-}
 
-impl App {
-    pub fn render_header(area: Rect, buf: &mut Buffer) {
-        Paragraph::new("Ymir project finder")
-            .bold()
-            .centered()
-            .render(area, buf);
+    /// Leave room for a language chart drawn with `protocol` next to the table
+    pub const fn with_graphics(mut self, protocol: Option<Protocol>) -> Self {
+        self.graphics = protocol;
+        self
     }
 
-    pub fn render_footer(area: Rect, buf: &mut Buffer) {
-        Paragraph::new("Use ↓↑ to move, ← to unselect, g/G to go top/bottom.")
-            .centered()
-            .render(area, buf);
+    /// Directory to rescan when asked to
+    pub fn with_scan_root(mut self, root: PathBuf) -> Self {
+        self.scan_roots.push(root);
+        self
     }
 
-    fn render_list(&mut self, area: Rect, buf: &mut Buffer) {
-        let sort_title = vec![
+    pub fn with_load_error(mut self, error: Option<String>) -> Self {
+        self.load_error = error;
+        self
+    }
+
+    pub fn should_exit(&self) -> bool {
+        self.should_exit
+    }
+
+    /// Selects the project `query` matches best in the list, returning its path
+    pub fn select_best(&mut self, query: &str) -> Option<PathBuf> {
+        let Some(project) = projects::best_match(&self.projects_list.items, query) else {
+            self.notice = Some(format!("No project matches {query}"));
+            return None;
+        };
+        let path = project.path.clone();
+        self.projects_list.select_path(&path);
+        self.follow_selection();
+        Some(path)
+    }
+
+    /// Project under the cursor
+    pub fn selected(&self) -> Option<&Project> {
+        self.projects_list
+            .state
+            .selected()
+            .and_then(|i| self.projects_list.items.get(i))
+    }
+
+    /// Projects in the list as filtered and sorted
+    pub fn items(&self) -> &[Project] {
+        &self.projects_list.items
+    }
+
+    pub fn update(&mut self, message: Message) -> Option<Effect> {
+        let effect = match message {
+            Message::Key(key) if key.kind != event::KeyEventKind::Press => None,
+            Message::Key(key) => {
+                if self.pending.is_some() {
+                    self.handle_confirmation_key(key)
+                } else if self.palette.is_some() {
+                    self.handle_palette_key(key)
+                } else if self.search_text.is_some() {
+                    self.handle_search_key(key);
+                    None
+                } else if self.command.is_some() {
+                    self.handle_command_key(key)
+                } else if self.filter_picker.is_some() {
+                    self.handle_picker_key(key);
+                    None
+                } else if self.task_picker.is_some() {
+                    self.handle_task_picker_key(key)
+                } else if self.commit_results.is_some() {
+                    self.handle_commit_results_key(key);
+                    None
+                } else if self.content_results.is_some() {
+                    self.handle_content_results_key(key)
+                } else {
+                    self.handle_key(key)
+                }
+            }
+            Message::Resize(area) => {
+                self.area = area;
+                None
+            }
+            Message::Ipc(message) => self.handle_ipc(message),
+            Message::ScanProgress(count, found) => {
+                if let (Some(scan), Some(last)) = (self.scan.as_mut(), found.last()) {
+                    *scan = (count, last.path.clone());
+                }
+                self.add_scanned(found);
+                None
+            }
+            Message::ScanDone(found, error) => {
+                self.scan = None;
+                let roots = std::mem::take(&mut self.scanned_roots);
+                if self.write_cache {
+                    self.projects_list.set_base(found);
+                } else {
+                    // Without the cache to merge into the scan only returns what's in its roots
+                    let mut base = std::mem::take(&mut self.projects_list.base);
+                    base.retain(|v| {
+                        !projects::in_roots(&v.path, &roots)
+                            && projects::in_roots(&v.path, &self.scan_roots)
+                    });
+                    base.extend(found);
+                    self.projects_list.set_base(base);
+                }
+                self.load_error = error;
+                self.update_list();
+                // Both panes show what the scan wrote, recent files are read after ignore stats
+                if self.ignore_stats.is_some() {
+                    Some(Effect::LoadIgnoreStats)
+                } else {
+                    self.recent_files
+                        .is_some()
+                        .then_some(Effect::LoadRecentFiles)
+                }
+            }
+            Message::CommitsFound(result) => {
+                let query = self.commit_search.take().unwrap_or_default();
+                match result {
+                    Ok(matches) if matches.is_empty() => {
+                        self.notice = Some(format!("No commit matches {query}"));
+                    }
+                    Ok(matches) => {
+                        let picker = ListState::default().with_selected(Some(0));
+                        self.commit_results = Some((query, matches, picker));
+                    }
+                    Err(err) => self.notice = Some(err),
+                }
+                None
+            }
+            Message::ContentsFound(result) => {
+                let query = self.content_search.take().unwrap_or_default();
+                match result {
+                    Ok(matches) if matches.is_empty() => {
+                        self.notice = Some(format!("No line matches {query}"));
+                    }
+                    Ok(matches) => {
+                        let picker = ListState::default().with_selected(Some(0));
+                        self.content_results = Some((query, matches, picker));
+                    }
+                    Err(err) => self.notice = Some(err),
+                }
+                None
+            }
+            Message::FreeSpace(bytes) => {
+                self.free_space = Some(bytes);
+                None
+            }
+            Message::ExportProgress(done, total) => {
+                if let Some(export) = self.export.as_mut() {
+                    (export.1, export.2) = (done, total);
+                }
+                None
+            }
+            Message::Exported(result) => {
+                self.export = None;
+                self.notice = Some(match result {
+                    Ok(path) => format!("Exported to {}", path.display()),
+                    Err(err) => err,
+                });
+                Some(Effect::CheckFreeSpace)
+            }
+            Message::ScanStopped => {
+                self.scan = None;
+                self.scanned_roots.clear();
+                self.load_error = Some("Scan stopped unexpectedly".to_string());
+                None
+            }
+            Message::CacheRead(result) => {
+                match result {
+                    Ok(projects) => {
+                        self.projects_list.set_base(
+                            projects
+                                .into_iter()
+                                .filter(|p| projects::in_roots(&p.path, &self.scan_roots))
+                                .collect(),
+                        );
+                        self.load_error = None;
+                    }
+                    Err(err) => self.load_error = Some(format!("Failed to read cache: {err}")),
+                }
+                self.update_list();
+                None
+            }
+            Message::RootResolved {
+                root,
+                append,
+                cached,
+            } => self.change_root(root, append, cached),
+            Message::TasksFound(dir, tasks) => {
+                if tasks.is_empty() {
+                    self.notice = Some(format!("No tasks found in {}", dir.display()));
+                } else {
+                    self.task_picker = Some((tasks, ListState::default().with_selected(Some(0))));
+                }
+                None
+            }
+            Message::IgnoreStatsLoaded(stats) => {
+                self.ignore_stats = Some(stats);
+                self.loaded(Pane::IgnoreStats);
+                self.recent_files
+                    .is_some()
+                    .then_some(Effect::LoadRecentFiles)
+            }
+            Message::OpenCountsLoaded(counts) => {
+                self.open_counts = Some(counts);
+                self.loaded(Pane::MostUsed);
+                None
+            }
+            Message::RecentFilesLoaded(files) => {
+                self.recent_files = Some(files);
+                self.loaded(Pane::RecentFiles);
+                None
+            }
+            Message::Consolidated(action, clone, primary) => {
+                self.consolidated(action, &clone, &primary)
+            }
+            Message::Archived(project, tarball) => {
+                self.forget(&project);
+                self.notice = Some(format!(
+                    "Archived {} to {}",
+                    project.display(),
+                    tarball.display()
+                ));
+                Some(Effect::CheckFreeSpace)
+            }
+            Message::Enriched(path, status) => {
+                self.enrichment.insert(path, status);
+                None
+            }
+            Message::Notice(text) => {
+                self.notice = Some(text);
+                None
+            }
+        };
+        self.follow_selection();
+        effect
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Option<Effect> {
+        if let Some(action) = self.pending_mark.take() {
+            if let KeyCode::Char(name) = key.code {
+                self.handle_mark(action, name);
+            }
+            return None;
+        }
+        if let Some(typed) = self.jump.take() {
+            if let KeyCode::Char(c) = key.code {
+                self.handle_jump(typed, c);
+            }
+            return None;
+        }
+
+        if let KeyCode::Char(digit @ '0'..='9') = key.code {
+            // A leading zero isn't a count, same as in vim
+            if self.settings.count_prefix && (digit != '0' || self.count.is_some()) {
+                let count = self.count.unwrap_or_default();
+                self.count = Some(
+                    count
+                        .saturating_mul(10)
+                        .saturating_add(digit.to_digit(10).unwrap_or_default() as usize),
+                );
+                return None;
+            }
+        }
+        let count = self.count.take().unwrap_or(1);
+
+        if let Some(pane) = shifted_function_key(key) {
+            return self.refresh_pane(pane);
+        }
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => self.should_exit = true,
+            // Movement
+            KeyCode::Char('j') | KeyCode::Down => self.select_next(count),
+            KeyCode::Char('k') | KeyCode::Up => self.select_previous(count),
+            KeyCode::Char('d') => self.select_next_10(count),
+            KeyCode::Char('u') => self.select_previous_10(count),
+            KeyCode::Char('g') | KeyCode::Home => self.projects_list.select_first(),
+            KeyCode::Char('G') | KeyCode::End => self.projects_list.select_last(),
+            KeyCode::Char('}') => (0..count).for_each(|_| self.projects_list.select_group(true)),
+            KeyCode::Char('{') => (0..count).for_each(|_| self.projects_list.select_group(false)),
+
+            // Marks
+            KeyCode::Char('m') => self.pending_mark = Some(MarkAction::Set),
+            KeyCode::Char('\'') => self.pending_mark = Some(MarkAction::Jump),
+            KeyCode::Char('s') if !self.projects_list.items.is_empty() => {
+                self.jump = Some(String::new());
+            }
+
+            // Toggle, with `count_prefix` the digits are taken by counts
+            KeyCode::Char('1') | KeyCode::F(1) => {
+                self.show_project_info = !self.show_project_info;
+            }
+            KeyCode::Char('2') | KeyCode::F(2) => self.show_languages = !self.show_languages,
+            KeyCode::Char('3') | KeyCode::F(3) => self.show_git = !self.show_git,
+            KeyCode::F(4) => self.show_crates = !self.show_crates,
+            KeyCode::F(5) => self.show_metadata = !self.show_metadata,
+            KeyCode::F(6) => self.show_errors = !self.show_errors,
+            KeyCode::F(7) => {
+                return self
+                    .ignore_stats
+                    .take()
+                    .is_none()
+                    .then_some(Effect::LoadIgnoreStats);
+            }
+            KeyCode::F(8) => {
+                return self
+                    .open_counts
+                    .take()
+                    .is_none()
+                    .then_some(Effect::LoadOpenCounts);
+            }
+            KeyCode::F(9) => {
+                return self
+                    .recent_files
+                    .take()
+                    .is_none()
+                    .then_some(Effect::LoadRecentFiles);
+            }
+
+            // Sorting
+            KeyCode::Char('h') | KeyCode::Left => {
+                self.remember_view();
+                self.sort_type = self
+                    .sort_type
+                    .previous(self.custom_sorts.len(), self.settings.zoxide);
+                self.update_list();
+            }
+            KeyCode::Char('l') | KeyCode::Right => {
+                self.remember_view();
+                self.sort_type = self
+                    .sort_type
+                    .next(self.custom_sorts.len(), self.settings.zoxide);
+                self.update_list();
+            }
+            KeyCode::Char('i') => {
+                self.remember_view();
+                self.invert = !self.invert;
+                self.update_list();
+            }
+
+            // Filtering
+            KeyCode::Char('y') => {
+                self.remember_view();
+                self.filter_type = self.filter_type.previous();
+                self.update_list();
+            }
+            KeyCode::Char('o') => {
+                self.remember_view();
+                self.filter_type = self.filter_type.next();
+                self.update_list();
+            }
+            // Rescan, plain r only on the empty screen where nothing else uses it
+            KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return self.rescan();
+            }
+            KeyCode::Char('r') if self.projects_list.base.is_empty() => return self.rescan(),
+            KeyCode::Char('c') => return self.compute_deferred(),
+
+            KeyCode::Char('f') => {
+                let selected = Filter::all().iter().position(|v| *v == self.filter_type);
+                self.filter_picker = Some(ListState::default().with_selected(selected));
+            }
+            KeyCode::Char('Y') => {
+                self.remember_view();
+                self.org = cycle_org(&self.projects_list.orgs(), self.org.as_deref(), false);
+                self.update_list();
+            }
+            KeyCode::Char('O') => {
+                self.remember_view();
+                self.org = cycle_org(&self.projects_list.orgs(), self.org.as_deref(), true);
+                self.update_list();
+            }
+
+            KeyCode::Char(':') => self.command = Some(String::new()),
+            KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.palette = Some((String::new(), ListState::default().with_selected(Some(0))));
+            }
+            KeyCode::Char('t') => {
+                return self.selected().map(|v| Effect::FindTasks(v.path.clone()))
+            }
+            KeyCode::Char('J') => return self.selected().map(|v| Effect::ShowJson(v.clone())),
+            KeyCode::Char('e') => {
+                let path = self.selected()?.path.clone();
+                let question = format!("Show {} in the file manager?", path.display());
+                return self.confirm(ConfirmAction::Reveal, question, Effect::Reveal(path));
+            }
+            KeyCode::Char('L') => return Some(Effect::ShowAudit),
+            KeyCode::Char('U') => self.undo_view(),
+            KeyCode::Char('R') => self.redo_view(),
+            KeyCode::Char('D') => return self.consolidate(Consolidation::Delete),
+            KeyCode::Char('W') => return self.consolidate(Consolidation::Worktree),
+            KeyCode::Char('X') => return self.archive(),
+            KeyCode::Char('A') => self.command = self.archive_command(),
+            KeyCode::Char('#') => self.command = Some("grep ".to_string()),
+            KeyCode::Enter => {
+                let path = self.selected()?.path.clone();
+                let question = format!("Open a shell in {}?", path.display());
+                return self.confirm(ConfirmAction::Open, question, Effect::OpenProject(path));
+            }
+
+            // Searching
+            KeyCode::Char('/') => {
+                if self.search_text.is_some() {
+                    self.search_text = None;
+                } else {
+                    self.search_text = Some(String::new());
+                }
+            }
+
+            _ => {}
+        }
+        None
+    }
+
+    fn handle_picker_key(&mut self, key: KeyEvent) {
+        let Some(picker) = self.filter_picker.as_mut() else {
+            return;
+        };
+
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q' | 'f') => self.filter_picker = None,
+            KeyCode::Char('j') | KeyCode::Down => picker.select_next(),
+            KeyCode::Char('k') | KeyCode::Up => picker.select_previous(),
+            KeyCode::Enter => {
+                let filters = Filter::all();
+                if let Some(filter) = picker.selected().and_then(|i| filters.get(i)).copied() {
+                    self.remember_view();
+                    self.filter_type = filter;
+                    self.update_list();
+                }
+                self.filter_picker = None;
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_task_picker_key(&mut self, key: KeyEvent) -> Option<Effect> {
+        let (tasks, picker) = self.task_picker.as_mut()?;
+
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q' | 't') => self.task_picker = None,
+            KeyCode::Char('j') | KeyCode::Down => picker.select_next(),
+            KeyCode::Char('k') | KeyCode::Up => picker.select_previous(),
+            KeyCode::Enter => {
+                let task = picker.selected().and_then(|i| tasks.get(i)).cloned();
+                let dir = self.selected().map(|v| v.path.clone());
+                self.task_picker = None;
+                if let (Some(task), Some(dir)) = (task, dir) {
+                    let question = format!("Run {task} in {}?", dir.display());
+                    return self.confirm(ConfirmAction::Task, question, Effect::RunTask(task, dir));
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+
+    /// Typing narrows the actions down, Enter does the selected one
+    fn handle_palette_key(&mut self, key: KeyEvent) -> Option<Effect> {
+        let (query, picker) = self.palette.as_mut()?;
+        let control = key.modifiers.contains(KeyModifiers::CONTROL);
+
+        match key.code {
+            KeyCode::Esc => self.palette = None,
+            KeyCode::Char('p') if control => picker.select_previous(),
+            KeyCode::Char('n') if control => picker.select_next(),
+            KeyCode::Down => picker.select_next(),
+            KeyCode::Up => picker.select_previous(),
+            KeyCode::Backspace => {
+                query.pop();
+                picker.select_first();
+            }
+            KeyCode::Char(c) => {
+                query.push(c);
+                picker.select_first();
+            }
+            KeyCode::Enter => {
+                let entries = palette::entries(self.custom_sorts.len(), self.settings.zoxide);
+                let action = picker
+                    .selected()
+                    .and_then(|i| palette::search(&entries, query).get(i).map(|v| v.action));
+                self.palette = None;
+                return action.and_then(|v| self.run_action(v));
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn run_action(&mut self, action: Action) -> Option<Effect> {
+        match action {
+            Action::Key(key) => return self.handle_key(key),
+            Action::Sort(sorting) => {
+                self.remember_view();
+                self.sort_type = sorting;
+                self.update_list();
+            }
+            Action::Filter(filter) => {
+                self.remember_view();
+                self.filter_type = filter;
+                self.update_list();
+            }
+            Action::Command(command) => self.command = Some(command.to_string()),
+        }
+        None
+    }
+
+    fn handle_commit_results_key(&mut self, key: KeyEvent) {
+        let Some((_, matches, picker)) = self.commit_results.as_mut() else {
+            return;
+        };
+
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => self.commit_results = None,
+            KeyCode::Char('j') | KeyCode::Down => picker.select_next(),
+            KeyCode::Char('k') | KeyCode::Up => picker.select_previous(),
+            KeyCode::Enter => {
+                let project = picker
+                    .selected()
+                    .and_then(|i| matches.get(i))
+                    .map(|v| v.project.clone());
+                self.commit_results = None;
+                if let Some(project) = project {
+                    self.projects_list.select_path(&project);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Enter opens the editor at the selected line and selects its project
+    fn handle_content_results_key(&mut self, key: KeyEvent) -> Option<Effect> {
+        let (_, matches, picker) = self.content_results.as_mut()?;
+
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => self.content_results = None,
+            KeyCode::Char('j') | KeyCode::Down => picker.select_next(),
+            KeyCode::Char('k') | KeyCode::Up => picker.select_previous(),
+            KeyCode::Enter => {
+                let found = picker.selected().and_then(|i| matches.get(i))?.clone();
+                self.projects_list.select_path(&found.project);
+                let editor = Task::editor(&found.path, found.line);
+                let question = format!("Edit {}?", found.path.display());
+                return self.confirm(
+                    ConfirmAction::Open,
+                    question,
+                    Effect::RunTask(editor, found.project),
+                );
+            }
+            _ => {}
+        }
+        None
+    }
+
+    /// Carries out `effect` right away or asks `question` first, as `confirm` and
+    /// `confirm_actions` say for `action`
+    fn confirm(
+        &mut self,
+        action: ConfirmAction,
+        question: String,
+        effect: Effect,
+    ) -> Option<Effect> {
+        if self.settings.confirms(action) {
+            self.pending = Some((question, effect));
+            None
+        } else {
+            self.start(effect)
+        }
+    }
+
+    fn handle_confirmation_key(&mut self, key: KeyEvent) -> Option<Effect> {
+        let (_, effect) = self.pending.take()?;
+        if matches!(key.code, KeyCode::Char('y' | 'Y')) {
+            self.start(effect)
+        } else {
+            None
+        }
+    }
+
+    /// Shows that a confirmed effect is underway
+    fn start(&mut self, effect: Effect) -> Option<Effect> {
+        if let Effect::ExportArchive(_, destination) = &effect {
+            self.export = Some((destination.trim().to_string(), 0, 0));
+        }
+        Some(effect)
+    }
+
+    /// Consolidating the selected project into its primary clone, once confirmed
+    fn consolidate(&mut self, action: Consolidation) -> Option<Effect> {
+        // The scan would write the deleted clone back to the cache and the list
+        if self.scan.is_some() {
+            self.notice = Some("Wait for the current scan to finish".to_string());
+            return None;
+        }
+        let project = self.selected()?;
+
+        match self.projects_list.duplicates.get(&project.path) {
+            Some(duplicate) if duplicate.primary != project.path => {
+                let (clone, primary) = (project.path.clone(), duplicate.primary.clone());
+                let question = format!(
+                    "{action} {}, a clone of {}?",
+                    clone.display(),
+                    primary.display()
+                );
+                let confirm = match action {
+                    Consolidation::Delete => ConfirmAction::Delete,
+                    Consolidation::Worktree => ConfirmAction::Worktree,
+                };
+                return self.confirm(
+                    confirm,
+                    question,
+                    Effect::Consolidate(action, clone, primary),
+                );
+            }
+            Some(_) => {
+                self.notice = Some(format!("{} is the primary clone", project.path.display()));
+            }
+            None => {
+                self.notice = Some(format!(
+                    "{} shares its remote with no other project",
+                    project.path.display()
+                ));
+            }
+        }
+        None
+    }
+
+    fn consolidated(
+        &mut self,
+        action: Consolidation,
+        clone: &Path,
+        primary: &Path,
+    ) -> Option<Effect> {
+        match action {
+            Consolidation::Delete => {
+                self.forget(clone);
+                self.notice = Some(format!("Deleted {}", clone.display()));
+                Some(Effect::CheckFreeSpace)
+            }
+            // The clone is a different project now, reading it again picks that up
+            Consolidation::Worktree => {
+                let effect = self.start_scan(vec![clone.to_path_buf()], self.settings.clone());
+                self.notice = Some(format!(
+                    "{} is now a worktree of {}",
+                    clone.display(),
+                    primary.display()
+                ));
+                effect
+            }
+        }
+    }
+
+    /// Archiving the selected project, once confirmed
+    fn archive(&mut self) -> Option<Effect> {
+        if self.scan.is_some() {
+            self.notice = Some("Wait for the current scan to finish".to_string());
+            return None;
+        }
+        let path = self.selected()?.path.clone();
+
+        let Some(dir) = self.settings.archive_dir() else {
+            self.notice = Some("Failed to find the home directory".to_string());
+            return None;
+        };
+        let question = format!(
+            "Archive {} into {} and delete it?",
+            path.display(),
+            dir.display()
+        );
+        self.confirm(ConfirmAction::Archive, question, Effect::Archive(path, dir))
+    }
+
+    /// `:archive` prompt for the selected project, suggesting a dated file in `archive_dir`
+    fn archive_command(&self) -> Option<String> {
+        let project = self.selected()?;
+        let name = project.path.file_name()?.to_string_lossy();
+        let date = chrono::Local::now().format("%Y-%m-%d");
+        let file = format!("{name}-{date}.tar.zst");
+        let destination = self
+            .settings
+            .archive_dir()
+            .map_or_else(|| PathBuf::from(&file), |v| v.join(&file));
+        Some(format!("archive {}", destination.display()))
+    }
+
+    /// Drops a project that no longer exists from the list
+    fn forget(&mut self, path: &Path) {
+        let mut base = std::mem::take(&mut self.projects_list.base);
+        base.retain(|v| v.path != path);
+        self.projects_list.set_base(base);
+        self.update_list();
+    }
+
+    fn handle_command_key(&mut self, key: KeyEvent) -> Option<Effect> {
+        let command = self.command.as_mut()?;
+
+        match key.code {
+            KeyCode::Esc => self.command = None,
+            KeyCode::Char(c) => command.push(c),
+            // Backspace on an empty prompt closes it, same as in vim
+            KeyCode::Backspace if command.pop().is_none() => self.command = None,
+            KeyCode::Enter => {
+                if let Some(command) = self.command.take() {
+                    return self.run_command(&command);
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+
+    /// Runs a command typed after `:`
+    ///
+    /// - `cd <dir>` shows projects from `dir` instead
+    /// - `add <dir>` shows projects from `dir` next to the current ones
+    /// - `export <file>` writes bookmarks for the projects in the list, as filtered, to `file`
+    /// - `workspace <file>` writes a VS Code workspace with the projects in the list to `file`
+    fn run_command(&mut self, command: &str) -> Option<Effect> {
+        let (name, arg) = command
+            .trim()
+            .split_once(char::is_whitespace)
+            .unwrap_or((command.trim(), ""));
+
+        match name {
+            "cd" | "add" if self.scan.is_some() => {
+                self.notice = Some("Wait for the current scan to finish".to_string());
+            }
+            "cd" | "add" => {
+                return Some(Effect::ChangeRoot {
+                    input: arg.to_string(),
+                    append: name == "add",
+                });
+            }
+            "export" if arg.trim().is_empty() => {
+                self.notice = Some("Missing file to export to".to_string());
+            }
+            "export" => {
+                let question = format!(
+                    "Write bookmarks for {} projects to {}?",
+                    self.projects_list.items.len(),
+                    arg.trim()
+                );
+                let effect =
+                    Effect::ExportBookmarks(arg.to_string(), self.projects_list.items.clone());
+                return self.confirm(ConfirmAction::Export, question, effect);
+            }
+            "workspace" if arg.trim().is_empty() => {
+                self.notice = Some("Missing file to write the workspace to".to_string());
+            }
+            "workspace" => {
+                let question = format!(
+                    "Write a workspace of {} projects to {}?",
+                    self.projects_list.items.len(),
+                    arg.trim()
+                );
+                let effect =
+                    Effect::ExportWorkspace(arg.to_string(), self.projects_list.items.clone());
+                return self.confirm(ConfirmAction::Export, question, effect);
+            }
+            "archive" if self.export.is_some() => {
+                self.notice = Some("Wait for the current export to finish".to_string());
+            }
+            "archive" if arg.trim().is_empty() => {
+                self.notice = Some("Missing file to archive to".to_string());
+            }
+            "archive" => {
+                let project = self.selected()?.path.clone();
+                let question = format!("Export {} to {}?", project.display(), arg.trim());
+                let effect = Effect::ExportArchive(project, arg.trim().to_string());
+                return self.confirm(ConfirmAction::Export, question, effect);
+            }
+            "commits" if self.commit_search.is_some() => {
+                self.notice = Some("Wait for the current commit search to finish".to_string());
+            }
+            "commits" if arg.trim().is_empty() => {
+                self.notice = Some("Missing text to search commits for".to_string());
+            }
+            "commits" => match Pattern::parse(arg) {
+                Ok(pattern) => {
+                    self.commit_search = Some(arg.trim().to_string());
+                    let mut projects: Vec<PathBuf> = self
+                        .projects_list
+                        .base
+                        .iter()
+                        .filter(|v| v.git_info.state.has_commits())
+                        .map(|v| v.path.clone())
+                        .collect();
+                    projects.sort();
+                    return Some(Effect::SearchCommits(projects, pattern));
+                }
+                Err(err) => self.notice = Some(format!("{err:#}")),
+            },
+            "grep" if self.content_search.is_some() => {
+                self.notice = Some("Wait for the current content search to finish".to_string());
+            }
+            "grep" if arg.trim().is_empty() => {
+                self.notice = Some("Missing text to search files for".to_string());
+            }
+            "grep" => match Pattern::parse(arg) {
+                Ok(pattern) => {
+                    self.content_search = Some(arg.trim().to_string());
+                    let mut projects: Vec<PathBuf> = self
+                        .projects_list
+                        .base
+                        .iter()
+                        .map(|v| v.path.clone())
+                        .collect();
+                    projects.sort();
+                    return Some(Effect::SearchContents(projects, pattern));
+                }
+                Err(err) => self.notice = Some(format!("{err:#}")),
+            },
+            "" => {}
+            _ => self.notice = Some(format!("Unknown command {name}")),
+        }
+        None
+    }
+
+    /// Shows projects from `root`, from the cache if it has any and scanning otherwise
+    fn change_root(&mut self, root: PathBuf, append: bool, cached: Vec<Project>) -> Option<Effect> {
+        if self.scan.is_some() {
+            self.notice = Some("Wait for the current scan to finish".to_string());
+            return None;
+        }
+
+        if append {
+            if projects::in_roots(&root, &self.scan_roots) {
+                return None;
+            }
+            // A parent replaces the roots inside it
+            self.scan_roots.retain(|v| !v.starts_with(&root));
+        } else {
+            self.scan_roots.clear();
+        }
+        self.scan_roots.push(root.clone());
+        self.load_error = None;
+        self.notice = None;
+
+        if cached.iter().any(|p| p.path.starts_with(&root)) {
+            self.projects_list.set_base(
+                cached
+                    .into_iter()
+                    .filter(|p| projects::in_roots(&p.path, &self.scan_roots))
+                    .collect(),
+            );
+            self.update_list();
+            Some(Effect::CheckFreeSpace)
+        } else {
+            self.start_scan(vec![root], self.settings.clone())
+        }
+    }
+
+    fn handle_mark(&mut self, action: MarkAction, name: char) {
+        match action {
+            MarkAction::Set => {
+                if let Some(path) = self.selected().map(|v| v.path.clone()) {
+                    self.marks.insert(name, path);
+                }
+            }
+            MarkAction::Jump => {
+                if let Some(path) = self.marks.get(&name) {
+                    self.projects_list.select_path(path);
+                }
+            }
+        }
+    }
+
+    /// Selects the row labeled `typed` and `c`, keeps waiting while it's the start of a label
+    fn handle_jump(&mut self, mut typed: String, c: char) {
+        typed.push(c);
+        let offset = self.projects_list.state.offset();
+        let rows = self
+            .list_height()
+            .min(self.projects_list.items.len().saturating_sub(offset));
+
+        let labels = (0..rows).map(jump_label);
+        if let Some(row) = labels.clone().position(|v| v == typed) {
+            self.projects_list.state.select(Some(offset + row));
+        } else if labels.into_iter().any(|v| v.starts_with(&typed)) {
+            self.jump = Some(typed);
+        }
+    }
+
+    fn handle_search_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Esc => {
+                self.search_text = None;
+                self.search_index = 0;
+            }
+            KeyCode::Char(c) => {
+                if let Some(v) = self.search_text.as_mut() {
+                    v.push(c);
+                }
+                self.search_count = self.projects_list.search(
+                    &self.search_text.clone().unwrap_or_default(),
+                    self.search_index,
+                    self.settings.label,
+                );
+            }
+            KeyCode::Backspace => {
+                if let Some(v) = self.search_text.as_mut() {
+                    v.pop();
+                }
+                self.search_count = self.projects_list.search(
+                    &self.search_text.clone().unwrap_or_default(),
+                    self.search_index,
+                    self.settings.label,
+                );
+            }
+            KeyCode::Enter => {
+                self.search_count = self.projects_list.search(
+                    &self.search_text.clone().unwrap_or_default(),
+                    self.search_index,
+                    self.settings.label,
+                );
+
+                if self.search_index >= self.search_count.wrapping_sub(1) {
+                    self.search_index = 0;
+                } else {
+                    self.search_index += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_ipc(&mut self, message: IpcMessage) -> Option<Effect> {
+        match message {
+            IpcMessage::Focus => Some(Effect::FocusTerminal),
+            IpcMessage::Refresh => Some(Effect::ReadCache),
+            IpcMessage::Select(path) => {
+                self.projects_list.select_path(&path);
+                Some(Effect::FocusTerminal)
+            }
+        }
+    }
+
+    /// Shows projects a running scan found, in place of what the list had for them
+    fn add_scanned(&mut self, found: Vec<Project>) {
+        let found: Vec<Project> = found
+            .into_iter()
+            .filter(|v| projects::in_roots(&v.path, &self.scan_roots))
+            .collect();
+        if found.is_empty() {
+            return;
+        }
+        let mut base = std::mem::take(&mut self.projects_list.base);
+        base.retain(|v| !found.iter().any(|found| found.path == v.path));
+        base.extend(found);
+        self.projects_list.set_base(base);
+        self.update_list();
+    }
+
+    /// Runs discovery on the scan roots again in the background, the cache and list are replaced
+    /// once it finishes
+    fn rescan(&mut self) -> Option<Effect> {
+        self.start_scan(self.scan_roots.clone(), self.settings.clone())
+    }
+
+    /// Loads the data of the pane toggled with `F<pane>` again, leaving everything else as it is
+    fn refresh_pane(&mut self, pane: u8) -> Option<Effect> {
+        match pane {
+            // Every pane about the selected project shows what scanning it found
+            1..=6 if self.scan.is_some() => {
+                self.notice = Some("Wait for the current scan to finish".to_string());
+                None
+            }
+            1..=6 => {
+                let path = self.selected()?.path.clone();
+                self.start_scan(vec![path], self.settings.clone())
+            }
+            7 => Some(Effect::LoadIgnoreStats),
+            8 => Some(Effect::LoadOpenCounts),
+            9 => Some(Effect::LoadRecentFiles),
+            _ => None,
+        }
+    }
+
+    fn loaded(&mut self, pane: Pane) {
+        self.loaded_at
+            .insert(pane, chrono::Local::now().timestamp());
+    }
+
+    /// Scans the selected project ignoring `max_project_size`, if its stats were deferred
+    fn compute_deferred(&mut self) -> Option<Effect> {
+        let path = self.selected().filter(|v| v.deferred)?.path.clone();
+        let settings = Settings {
+            max_project_size: None,
+            ..self.settings.clone()
+        };
+        self.start_scan(vec![path], settings)
+    }
+
+    /// Scans `roots` in the background, the list then shows everything under the scan roots
+    fn start_scan(&mut self, roots: Vec<PathBuf>, settings: Settings) -> Option<Effect> {
+        if roots.is_empty() || self.scan.is_some() {
+            return None;
+        }
+
+        self.scan = Some((0, PathBuf::new()));
+        self.scanned_roots.clone_from(&roots);
+        Some(Effect::Scan {
+            roots,
+            visible: self.scan_roots.clone(),
+            settings,
+            cache: self.write_cache,
+        })
+    }
+
+    /// Runs the list pipeline again after any of its inputs changed
+    fn view(&self) -> View {
+        View {
+            filter: self.filter_type,
+            org: self.org.clone(),
+            sort: self.sort_type,
+            invert: self.invert,
+        }
+    }
+
+    fn set_view(&mut self, view: View) {
+        self.filter_type = view.filter;
+        self.org = view.org;
+        self.sort_type = view.sort;
+        self.invert = view.invert;
+        self.update_list();
+    }
+
+    /// Keeps the current view for `U`, called right before it changes
+    fn remember_view(&mut self) {
+        if self.undo.len() >= MAX_UNDO {
+            self.undo.remove(0);
+        }
+        self.undo.push(self.view());
+        self.redo.clear();
+    }
+
+    fn undo_view(&mut self) {
+        let Some(view) = self.undo.pop() else {
+            self.notice = Some("Nothing to undo".to_string());
+            return;
+        };
+        self.redo.push(self.view());
+        self.set_view(view);
+    }
+
+    fn redo_view(&mut self) {
+        let Some(view) = self.redo.pop() else {
+            self.notice = Some("Nothing to redo".to_string());
+            return;
+        };
+        self.undo.push(self.view());
+        self.set_view(view);
+    }
+
+    fn update_list(&mut self) {
+        self.projects_list.apply(&ListView {
+            filter: &self.filter_type,
+            org: self.org.as_deref(),
+            username: &self.git_name,
+            sort: &self.sort_type,
+            custom_sorts: &self.custom_sorts,
+            invert: self.invert,
+            label: self.settings.label,
+            frecency: &self.frecency,
+            archive: Candidates::new(&self.settings, chrono::Local::now().timestamp()),
+        });
+    }
+
+    /// Scrolls the list just enough for the selection to stay in view
+    fn follow_selection(&mut self) {
+        if self.area.is_empty() {
+            return;
+        }
+        let height = self.list_height();
+        let list = &mut self.projects_list;
+        *list.state.offset_mut() = scroll(
+            list.state.offset(),
+            list.state.selected(),
+            list.items.len(),
+            height,
+        );
+    }
+
+    /// Rows of projects the list has room for
+    fn list_height(&self) -> usize {
+        usize::from(self.layout(self.area).list.height.saturating_sub(2))
+    }
+
+    fn select_next(&mut self, count: usize) {
+        self.projects_list.select_offset(
+            count.try_into().unwrap_or(isize::MAX),
+            self.settings.wrap_navigation,
+        );
+    }
+
+    fn select_previous(&mut self, count: usize) {
+        self.projects_list.select_offset(
+            -count.try_into().unwrap_or(isize::MAX),
+            self.settings.wrap_navigation,
+        );
+    }
+
+    /// Paging always stops at the ends, wrapping a page would land on an arbitrary project
+    fn select_next_10(&mut self, count: usize) {
+        self.projects_list.select_offset(
+            count.saturating_mul(10).try_into().unwrap_or(isize::MAX),
+            false,
+        );
+    }
+
+    fn select_previous_10(&mut self, count: usize) {
+        self.projects_list.select_offset(
+            -count.saturating_mul(10).try_into().unwrap_or(isize::MAX),
+            false,
+        );
+    }
+}
+
+/// First row to show of a list `len` long in `height` rows, moved from `offset` as little as
+/// keeping `selected` in view takes
+fn scroll(offset: usize, selected: Option<usize>, len: usize, height: usize) -> usize {
+    let height = height.max(1);
+    let mut offset = offset.min(len.saturating_sub(height));
+    if let Some(selected) = selected.map(|v| v.min(len.saturating_sub(1))) {
+        if selected < offset {
+            offset = selected;
+        } else if selected >= offset + height {
+            offset = selected + 1 - height;
+        }
+    }
+    offset
+}
+
+/// Where everything goes in a frame
+struct Areas {
+    header: Rect,
+    main: Rect,
+    footer: Rect,
+    list: Rect,
+    search: Rect,
+    data: Rect,
+    panes: Vec<Pane>,
+}
+
+impl AppState {
+    fn layout(&self, area: Rect) -> Areas {
+        let [header, main, footer] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ])
+        .areas(area);
+
+        let selected = self.selected();
+        let panes: Vec<Pane> = [
+            (self.show_project_info, Pane::Info),
+            (self.show_languages, Pane::Languages),
+            (self.show_git, Pane::Git),
+            // Only shown for projects with anything to show
+            (
+                self.show_crates && selected.is_some_and(|v| !v.crates.is_empty()),
+                Pane::Crates,
+            ),
+            (
+                self.show_metadata && selected.is_some_and(|v| !v.metadata.is_empty()),
+                Pane::Metadata,
+            ),
+            (
+                self.show_errors && selected.is_some_and(|v| !v.scan_errors.is_empty()),
+                Pane::Errors,
+            ),
+            (self.ignore_stats.is_some(), Pane::IgnoreStats),
+            (self.open_counts.is_some(), Pane::MostUsed),
+            (self.recent_files.is_some(), Pane::RecentFiles),
+        ]
+        .into_iter()
+        .filter_map(|(show, pane)| show.then_some(pane))
+        .collect();
+
+        let [list, data] = if panes.is_empty() {
+            Layout::horizontal([Constraint::Fill(1), Constraint::Fill(0)]).areas(main)
+        } else {
+            Layout::horizontal([Constraint::Fill(1), Constraint::Fill(1)]).areas(main)
+        };
+
+        let [list, search] = if self.search_text.is_some() {
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(3)]).areas(list)
+        } else {
+            Layout::vertical([Constraint::Fill(1), Constraint::Fill(0)]).areas(list)
+        };
+
+        Areas {
+            header,
+            main,
+            footer,
+            list,
+            search,
+            data,
+            panes,
+        }
+    }
+}
+
+/// Projects listed in the Most Used pane
+const MOST_USED_LEN: usize = 10;
+
+/// Languages with their own slice in the chart, the rest share one
+const CHART_SLICES: usize = 7;
+const CHART_OTHER_COLOR: [u8; 3] = [0x80, 0x80, 0x80];
+
+impl AppState {
+    /// Code of the selected project by language as `(color, lines)`, largest first
+    fn language_slices(&self) -> Vec<Slice> {
+        let Some(project) = self.selected() else {
+            return Vec::new();
+        };
+
+        let mut languages: Vec<(LanguageType, u32)> = project
+            .languages
+            .iter()
+            .filter_map(|(id, v)| Some((*LanguageType::list().get(usize::from(*id))?, v.code)))
+            .filter(|(language, code)| {
+                *code > 0 && !self.settings.ignores_language(&project.path, *language)
+            })
+            .collect();
+        languages.sort_by_key(|(language, code)| (std::cmp::Reverse(*code), *language));
+
+        let mut slices: Vec<Slice> = languages
+            .iter()
+            .take(CHART_SLICES)
+            .map(|(language, code)| {
+                let color = match linguist::color(*language) {
+                    Some(Color::Rgb(r, g, b)) => [r, g, b],
+                    _ => CHART_OTHER_COLOR,
+                };
+                (color, *code)
+            })
+            .collect();
+        let other: u32 = languages.iter().skip(CHART_SLICES).map(|v| v.1).sum();
+        if other > 0 {
+            slices.push((CHART_OTHER_COLOR, other));
+        }
+        slices
+    }
+
+    /// Cells on the right of the languages pane inside `block` left for the chart, `None` when
+    /// there's no room or nothing to draw
+    fn chart_area(&self, inner: Rect) -> Option<Rect> {
+        self.graphics?;
+        let rows = inner.height.min(12);
+        let cols = rows * 2;
+        // The table keeps enough room for names and a few counts
+        if rows < 4 || inner.width < cols + 40 || self.language_slices().is_empty() {
+            return None;
+        }
+        Some(Rect::new(inner.right() - cols, inner.y, cols, rows))
+    }
+
+    /// Where the language chart goes in a frame of `area` and what's in it
+    pub fn language_chart(&self, area: Rect) -> Option<(Rect, Vec<Slice>)> {
+        self.graphics?;
+        let areas = self.layout(area);
+        if self.projects_list.base.is_empty() {
+            return None;
+        }
+
+        let index = areas
+            .panes
+            .iter()
+            .position(|v| matches!(v, Pane::Languages))?;
+        let pane =
+            Layout::vertical(vec![Constraint::Fill(1); areas.panes.len()]).split(areas.data)[index];
+        let chart = self.chart_area(languages_block().inner(pane))?;
+        Some((chart, self.language_slices()))
+    }
+}
+
+impl Widget for &AppState {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        self.render_frame(area, buf);
+        self.settings.theme.apply(area, buf);
+    }
+}
+
+impl AppState {
+    fn render_frame(&self, area: Rect, buf: &mut Buffer) {
+        let areas = self.layout(area);
+
+        self.render_header(areas.header, buf);
+        self.render_footer(areas.footer, buf);
+
+        if self.projects_list.base.is_empty() {
+            self.render_empty(areas.main, buf);
+            return;
+        }
+
+        self.render_list(areas.list, buf);
+
+        if self.search_text.is_some() {
+            self.render_search(areas.search, buf);
+        }
+
+        if self.filter_picker.is_some() {
+            self.render_filter_picker(areas.list, buf);
+        }
+
+        if self.task_picker.is_some() {
+            self.render_task_picker(areas.list, buf);
+        }
+        if self.palette.is_some() {
+            self.render_palette(areas.list, buf);
+        }
+        if self.commit_results.is_some() {
+            self.render_commit_results(areas.list, buf);
+        }
+        if self.content_results.is_some() {
+            self.render_content_results(areas.list, buf);
+        }
+
+        let pane_areas =
+            Layout::vertical(vec![Constraint::Fill(1); areas.panes.len()]).split(areas.data);
+        for (pane, area) in areas.panes.into_iter().zip(pane_areas.iter()) {
+            match pane {
+                Pane::Info => self.render_project_info(*area, buf),
+                Pane::Languages => self.render_project_langs(*area, buf),
+                Pane::Git => self.render_git(*area, buf),
+                Pane::Crates => self.render_crates(*area, buf),
+                Pane::Metadata => self.render_metadata(*area, buf),
+                Pane::Errors => self.render_errors(*area, buf),
+                Pane::IgnoreStats => self.render_ignore_stats(*area, buf),
+                Pane::MostUsed => self.render_most_used(*area, buf),
+                Pane::RecentFiles => self.render_recent_files(*area, buf),
+            }
+        }
+    }
+}
+
+/// Detail panes stacked next to the list, toggled with their function key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Pane {
+    Info,
+    Languages,
+    Git,
+    Crates,
+    Metadata,
+    Errors,
+    IgnoreStats,
+    MostUsed,
+    RecentFiles,
+}
+
+fn languages_block() -> Block<'static> {
+    Block::new()
+        .title(pane_title("F2", "Languages"))
+        .borders(Borders::ALL)
+        .border_set(symbols::border::ROUNDED)
+        .padding(Padding::horizontal(1))
+}
+
+/// Number of the function key pressed with shift, terminals without modifiers on them send F13
+/// for shift-F1 and so on
+fn shifted_function_key(key: KeyEvent) -> Option<u8> {
+    match key.code {
+        KeyCode::F(n) if key.modifiers.contains(KeyModifiers::SHIFT) => Some(n),
+        KeyCode::F(n @ 13..=24) => Some(n - 12),
+        _ => None,
+    }
+}
+
+fn pane_title<'a>(key: &'a str, name: &'a str) -> Line<'a> {
+    Line::from(vec![
+        Span::from("["),
+        Span::styled(key, Style::default().fg(CYAN.c500)),
+        Span::from(format!("] {name}")),
+    ])
+    .left_aligned()
+}
+
+impl AppState {
+    pub fn render_header(&self, area: Rect, buf: &mut Buffer) {
+        let mut title = vec![Span::from("Ymir project finder")];
+        if let Some(profile) = config::profile() {
+            title.push(Span::styled(
+                format!(" [{profile}]"),
+                Style::default().fg(YELLOW.c500),
+            ));
+        }
+
+        Paragraph::new(Line::from(title))
+            .bold()
+            .centered()
+            .render(area, buf);
+
+        if let Some(bytes) = self.free_space {
+            Paragraph::new(format!("{} free ", format_bytes(bytes)))
+                .fg(TEXT_FG_COLOR)
+                .right_aligned()
+                .render(area, buf);
+        }
+        if let Some((count, _)) = &self.scan {
+            Paragraph::new(format!(" Scanning, {count} found"))
+                .fg(CYAN.c500)
+                .render(area, buf);
+        }
+    }
+
+    pub fn render_footer(&self, area: Rect, buf: &mut Buffer) {
+        if let Some(command) = &self.command {
+            Paragraph::new(format!(":{command}")).render(area, buf);
+            return;
+        }
+
+        if let Some(query) = &self.commit_search {
+            Paragraph::new(format!("Searching commits for {query}"))
+                .centered()
+                .render(area, buf);
+            return;
+        }
+
+        if let Some(query) = &self.content_search {
+            Paragraph::new(format!("Searching files for {query}"))
+                .centered()
+                .render(area, buf);
+            return;
+        }
+
+        if self.jump.is_some() {
+            Paragraph::new("Type a label to jump to its row, anything else cancels")
+                .centered()
+                .render(area, buf);
+            return;
+        }
+
+        if let Some((question, _)) = &self.pending {
+            let key = |key| Span::styled(key, Style::default().fg(CYAN.c500));
+            Paragraph::new(Line::from(vec![
+                Span::from(format!("{question} ")),
+                key("y"),
+                Span::from("/"),
+                key("n"),
+            ]))
+            .centered()
+            .render(area, buf);
+            return;
+        }
+
+        if let Some((destination, done, total)) = &self.export {
+            Paragraph::new(format!(
+                "Exporting, {done}/{total} files written: {destination}"
+            ))
+            .centered()
+            .render(area, buf);
+            return;
+        }
+
+        if let Some((count, path)) = &self.scan {
+            Paragraph::new(format!(
+                "Scanning, {count} projects found: {}",
+                path.display()
+            ))
+            .centered()
+            .render(area, buf);
+            return;
+        }
+
+        if let Some(text) = self.notice.as_ref().or(self.load_error.as_ref()) {
+            Paragraph::new(text.as_str())
+                .fg(INACTIVE_COLOR)
+                .centered()
+                .render(area, buf);
+            return;
+        }
+
+        Paragraph::new("Use ↓↑ to move, ← to unselect, g/G to go top/bottom.")
+            .centered()
+            .render(area, buf);
+    }
+
+    /// Shown instead of the list when there are no projects at all, explains where they were
+    /// looked for and how to look again
+    fn render_empty(&self, area: Rect, buf: &mut Buffer) {
+        let root = if self.scan_roots.is_empty() {
+            "the scanned directory".to_string()
+        } else {
+            self.scan_roots
+                .iter()
+                .map(|v| v.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        let mut lines = vec![match &self.load_error {
+            // An error from before is stale while scanning again
+            _ if self.scan.is_some() => {
+                Line::from(format!("Looking for projects in {root}")).bold()
+            }
+            Some(error) => {
+                Line::from(format!("Failed to load projects: {error}")).fg(INACTIVE_COLOR)
+            }
+            None => Line::from(format!("No projects found in {root}")).bold(),
+        }];
+        if let Some(notice) = &self.notice {
+            lines.push(Line::from(notice.as_str()).fg(INACTIVE_COLOR));
+        }
+        lines.extend([
+            Line::default(),
+            Line::from(
+                "Every directory containing a .git directory is a project, these are skipped:",
+            ),
+            Line::from(self.settings.ignore_dirs.join(", ")).fg(TEXT_FG_COLOR),
+            Line::default(),
+        ]);
+
+        let key = |key| Span::styled(key, Style::default().fg(CYAN.c500));
+        if !self.scan_roots.is_empty() {
+            lines.push(Line::from(vec![
+                Span::from("Press "),
+                key("r"),
+                Span::from(" or "),
+                key("Ctrl-r"),
+                Span::from(" to scan again or "),
+                key("q"),
+                Span::from(" to quit"),
+            ]));
+        }
+        lines.push(Line::from(vec![
+            Span::from("Type "),
+            key(":cd <dir>"),
+            Span::from(" to look in another directory"),
+        ]));
+
+        let block = Block::new()
+            .borders(Borders::ALL)
+            .border_set(symbols::border::ROUNDED)
+            .padding(Padding::uniform(1));
+
+        Paragraph::new(lines)
+            .block(block)
+            .centered()
+            .wrap(Wrap { trim: false })
+            .render(area, buf);
+    }
+
+    fn render_list(&self, area: Rect, buf: &mut Buffer) {
+        let sort_title = vec![
             Span::styled(" <h ", Style::default().fg(CYAN.c500)),
-            Span::from(self.sort_type.to_string()),
+            Span::from(match self.sort_type {
+                Sorting::Custom(i) => self
+                    .custom_sorts
+                    .get(i)
+                    .map_or_else(|| self.sort_type.to_string(), |v| v.name.clone()),
+                _ => self.sort_type.to_string(),
+            }),
             Span::styled(" l> ", Style::default().fg(CYAN.c500)),
         ];
 
-        let filter_title = vec![
-            Span::styled(" <y ", Style::default().fg(CYAN.c500)),
-            Span::from(self.filter_type.to_string()),
-            Span::styled(" o> ", Style::default().fg(CYAN.c500)),
-        ];
+        let filter_title = vec![
+            Span::styled(" <y ", Style::default().fg(CYAN.c500)),
+            Span::from(self.filter_type.to_string()),
+            Span::styled(" o> ", Style::default().fg(CYAN.c500)),
+        ];
+
+        let org_title = self.org.as_ref().map(|org| {
+            let size: u64 = self.projects_list.items.iter().map(|v| v.size).sum();
+            Line::from(vec![
+                Span::styled(" <Y ", Style::default().fg(CYAN.c500)),
+                Span::from(format!(
+                    "{org} ({}, {})",
+                    self.projects_list.items.len(),
+                    format_bytes(size)
+                )),
+                Span::styled(" O> ", Style::default().fg(CYAN.c500)),
+            ])
+            .right_aligned()
+        });
+
+        let mut invert_title = Line::from(vec![
+            Span::styled(" i", Style::default().fg(CYAN.c500)),
+            Span::from("nvert "),
+        ])
+        .right_aligned();
+
+        if self.invert {
+            invert_title = invert_title.add_modifier(Modifier::BOLD);
+        }
+
+        let mut block = Block::new()
+            .title(
+                Line::raw(format!("Projects ({})", self.projects_list.items.len())).left_aligned(),
+            )
+            .title(invert_title);
+        if let Some(org_title) = org_title {
+            block = block.title(org_title);
+        }
+        let block = block
+            .title(Line::from(filter_title).right_aligned())
+            .title(Line::from(sort_title).right_aligned())
+            .borders(Borders::ALL)
+            .border_set(symbols::border::ROUNDED);
+
+        // Only the visible window is turned into list items, building all of them every frame
+        // lags input once there are thousands of projects
+        let height = usize::from(block.inner(area).height).max(1);
+        let len = self.projects_list.items.len();
+        let state = &self.projects_list.state;
+        let selected = state.selected().map(|v| v.min(len.saturating_sub(1)));
+        // Already scrolled by update, this only matters for areas it wasn't told about
+        let offset = scroll(state.offset(), selected, len, height);
+
+        let query = self.search_text.as_deref().unwrap_or_default();
+        let now = chrono::Local::now().timestamp();
+        // Templates line up columns, cutting their middle would shift them
+        let truncation = match self.list_template {
+            Some(_) => Truncation::End,
+            None => self.settings.truncation,
+        };
+        let width = usize::from(block.inner(area).width);
+        let items: Vec<ListItem> = self.projects_list.items[offset..len.min(offset + height)]
+            .iter()
+            .map(|v| {
+                let text = self.list_template.as_ref().map_or_else(
+                    || self.settings.label(v),
+                    |template| template.render(v, &self.settings, now),
+                );
+                let fit = (truncation == Truncation::Middle).then_some(width);
+                list_item(v, text, query, fit, &self.settings).style(self.row_styles.style(v, now))
+            })
+            .collect();
+
+        let inner = block.inner(area);
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(SELECTED_STYLE)
+            .highlight_spacing(HighlightSpacing::Always);
+
+        let mut window = ListState::default().with_selected(selected.map(|v| v - offset));
+        StatefulWidget::render(list, area, buf, &mut window);
+
+        // Labels go over the start of the rows, where the markers are
+        if let Some(typed) = &self.jump {
+            for row in 0..height.min(len.saturating_sub(offset)) {
+                let label = jump_label(row);
+                if label.starts_with(typed.as_str()) {
+                    let y = inner.y + u16::try_from(row).unwrap_or(u16::MAX);
+                    buf.set_string(inner.x, y, label, JUMP_STYLE);
+                }
+            }
+        }
+    }
+
+    /// Popup over the list with the tasks of the selected project
+    fn render_task_picker(&self, area: Rect, buf: &mut Buffer) {
+        let Some((tasks, picker)) = &self.task_picker else {
+            return;
+        };
+
+        let items: Vec<ListItem> = tasks
+            .iter()
+            .map(|task| {
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        format!("{:5} ", task.source),
+                        Style::default().fg(CYAN.c500),
+                    ),
+                    Span::from(task.name.as_str()),
+                ]))
+            })
+            .collect();
+        let width = tasks
+            .iter()
+            .map(|v| v.name.chars().count())
+            .max()
+            .unwrap_or_default()
+            .max(10);
+
+        let [area] = Layout::vertical([Constraint::Length(
+            u16::try_from(items.len() + 2).unwrap_or(u16::MAX),
+        )])
+        .flex(Flex::Center)
+        .areas(area);
+        let [area] = Layout::horizontal([Constraint::Length(
+            u16::try_from(width + 10).unwrap_or(u16::MAX),
+        )])
+        .flex(Flex::Center)
+        .areas(area);
+
+        let block = Block::new()
+            .title(Line::raw("Tasks").left_aligned())
+            .borders(Borders::ALL)
+            .border_set(symbols::border::ROUNDED);
+
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(SELECTED_STYLE)
+            .highlight_spacing(HighlightSpacing::Always);
+
+        Clear.render(area, buf);
+        StatefulWidget::render(list, area, buf, &mut picker.clone());
+    }
+
+    /// Popup over the list with the typed text above the actions matching it and their keys
+    fn render_palette(&self, area: Rect, buf: &mut Buffer) {
+        let Some((query, picker)) = &self.palette else {
+            return;
+        };
+        let entries = palette::entries(self.custom_sorts.len(), self.settings.zoxide);
+        let matches = palette::search(&entries, query);
+
+        let width = entries
+            .iter()
+            .map(|v| v.name.chars().count() + v.key.chars().count())
+            .max()
+            .unwrap_or_default();
+        let [area] = Layout::horizontal([Constraint::Length(
+            u16::try_from(width + 8).unwrap_or(u16::MAX),
+        )])
+        .flex(Flex::Center)
+        .areas(area);
+        let [area] = Layout::vertical([Constraint::Percentage(80)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        let block = Block::new()
+            .title(Line::raw("Actions").left_aligned())
+            .borders(Borders::ALL)
+            .border_set(symbols::border::ROUNDED);
+        let inner = block.inner(area);
+        Clear.render(area, buf);
+        block.render(area, buf);
+        let [input, list] =
+            Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(inner);
+
+        Line::from(vec![
+            Span::styled("> ", Style::default().fg(CYAN.c500)),
+            Span::from(query.as_str()),
+        ])
+        .render(input, buf);
+
+        let names = usize::from(list.width).saturating_sub(2);
+        let items: Vec<ListItem> = matches
+            .iter()
+            .map(|entry| {
+                let gap =
+                    names.saturating_sub(entry.name.chars().count() + entry.key.chars().count());
+                ListItem::new(Line::from(vec![
+                    Span::from(entry.name.as_str()),
+                    Span::from(" ".repeat(gap)),
+                    Span::styled(entry.key.as_str(), Style::default().fg(CYAN.c500)),
+                ]))
+            })
+            .collect();
+        let list_widget = List::new(items)
+            .highlight_style(SELECTED_STYLE)
+            .highlight_spacing(HighlightSpacing::Always);
+        StatefulWidget::render(list_widget, list, buf, &mut picker.clone());
+    }
+
+    /// Matching commits over the list, the project is named above its first one
+    fn render_commit_results(&self, area: Rect, buf: &mut Buffer) {
+        let Some((query, matches, picker)) = &self.commit_results else {
+            return;
+        };
+
+        let name = |path: &Path| {
+            path.file_name().map_or_else(
+                || path.display().to_string(),
+                |v| v.to_string_lossy().into(),
+            )
+        };
+        let width = matches
+            .iter()
+            .map(|v| name(&v.project).chars().count())
+            .max()
+            .unwrap_or_default();
+        let items: Vec<ListItem> = matches
+            .iter()
+            .enumerate()
+            .map(|(i, commit)| {
+                let first = i == 0 || matches[i - 1].project != commit.project;
+                let project = if first {
+                    name(&commit.project)
+                } else {
+                    String::new()
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        format!("{project:width$} "),
+                        Style::default().fg(YELLOW.c400),
+                    ),
+                    Span::styled(format!("{} ", commit.id), Style::default().fg(CYAN.c500)),
+                    Span::from(commit.summary.as_str()),
+                ]))
+            })
+            .collect();
+
+        let title = format!("Commits matching {query} ({})", matches.len());
+        let block = Block::new()
+            .title(Line::raw(title).left_aligned())
+            .borders(Borders::ALL)
+            .border_set(symbols::border::ROUNDED);
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(SELECTED_STYLE)
+            .highlight_spacing(HighlightSpacing::Always);
+
+        Clear.render(area, buf);
+        StatefulWidget::render(list, area, buf, &mut picker.clone());
+    }
+
+    /// Matching lines over the list, the project is named above its first one
+    fn render_content_results(&self, area: Rect, buf: &mut Buffer) {
+        let Some((query, matches, picker)) = &self.content_results else {
+            return;
+        };
+
+        let name = |path: &Path| {
+            path.file_name().map_or_else(
+                || path.display().to_string(),
+                |v| v.to_string_lossy().into(),
+            )
+        };
+        let width = matches
+            .iter()
+            .map(|v| name(&v.project).chars().count())
+            .max()
+            .unwrap_or_default();
+        let items: Vec<ListItem> = matches
+            .iter()
+            .enumerate()
+            .map(|(i, found)| {
+                let first = i == 0 || matches[i - 1].project != found.project;
+                let project = if first {
+                    name(&found.project)
+                } else {
+                    String::new()
+                };
+                let file = found
+                    .path
+                    .strip_prefix(&found.project)
+                    .unwrap_or(&found.path);
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        format!("{project:width$} "),
+                        Style::default().fg(YELLOW.c400),
+                    ),
+                    Span::styled(
+                        format!("{}:{} ", file.display(), found.line),
+                        Style::default().fg(CYAN.c500),
+                    ),
+                    Span::from(found.text.as_str()),
+                ]))
+            })
+            .collect();
+
+        let title = format!("Lines matching {query} ({})", matches.len());
+        let block = Block::new()
+            .title(Line::raw(title).left_aligned())
+            .borders(Borders::ALL)
+            .border_set(symbols::border::ROUNDED);
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(SELECTED_STYLE)
+            .highlight_spacing(HighlightSpacing::Always);
+
+        Clear.render(area, buf);
+        StatefulWidget::render(list, area, buf, &mut picker.clone());
+    }
+
+    /// Popup over the list with every filter and how many projects it would show
+    fn render_filter_picker(&self, area: Rect, buf: &mut Buffer) {
+        let filters = Filter::all();
+        let counts = self
+            .projects_list
+            .filter_counts
+            .as_ref()
+            .map_or_else(Vec::new, |(_, v)| v.clone());
+        let width = filters
+            .iter()
+            .map(|v| v.to_string().len())
+            .max()
+            .unwrap_or_default();
+
+        let items: Vec<ListItem> = filters
+            .iter()
+            .zip(counts)
+            .map(|(filter, count)| {
+                let mut item = ListItem::new(format!("{:width$}  {count:>5}", filter.to_string()));
+                if count == 0 {
+                    item = item.fg(INACTIVE_COLOR);
+                }
+                item
+            })
+            .collect();
+
+        let [area] = Layout::vertical([Constraint::Length(
+            u16::try_from(items.len() + 2).unwrap_or(u16::MAX),
+        )])
+        .flex(Flex::Center)
+        .areas(area);
+        let [area] = Layout::horizontal([Constraint::Length(
+            u16::try_from(width + 12).unwrap_or(u16::MAX),
+        )])
+        .flex(Flex::Center)
+        .areas(area);
+
+        let block = Block::new()
+            .title(Line::raw("Filter").left_aligned())
+            .borders(Borders::ALL)
+            .border_set(symbols::border::ROUNDED);
+
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(SELECTED_STYLE)
+            .highlight_spacing(HighlightSpacing::Always);
+
+        Clear.render(area, buf);
+        if let Some(picker) = &self.filter_picker {
+            StatefulWidget::render(list, area, buf, &mut picker.clone());
+        }
+    }
+
+    fn render_search(&self, area: Rect, buf: &mut Buffer) {
+        let block = Block::new()
+            .title(
+                Line::from(format!("[{}/{}]", self.search_index + 1, self.search_count))
+                    .right_aligned(),
+            )
+            .borders(Borders::ALL)
+            .border_set(symbols::border::ROUNDED);
+
+        Paragraph::new(self.search_text.as_ref().map_or("", |v| v))
+            .block(block)
+            .render(area, buf);
+    }
+
+    fn render_project_info(&self, area: Rect, buf: &mut Buffer) {
+        let info = self.projects_list.state.selected().map_or_else(
+            || "Nothing selected...".to_string(),
+            |i| {
+                let project = &self.projects_list.items[i];
+                let number = |v| format_number(u64::from(v), self.settings.number_format);
+                let partial = if project.partial { PARTIAL } else { "" };
+                let mut info = format!(
+                    "{project}\nLines of Code: {}{partial}\nGenerated Code: {} lines in {} files",
+                    number(project.languages_total.code),
+                    number(project.generated.code),
+                    number(project.generated.files),
+                );
+                if let (Some(age), Some(span), Some(rate)) = (
+                    project.age(chrono::Local::now().timestamp()),
+                    project.activity_span(),
+                    project.commits_per_month(),
+                ) {
+                    info.push_str(&format!(
+                        "\nAge: {}, active for {}, {rate:.1} commits per month",
+                        format_duration(age),
+                        format_duration(span),
+                    ));
+                }
+                if let Some(effort) = Effort::of(project, self.settings.effort_salary)
+                    .filter(|_| self.settings.estimate_effort)
+                {
+                    info.push_str(&format!("\nEstimated Effort: {effort}"));
+                }
+                if let Some(status) = self.enrichment.get(&project.path) {
+                    info.push_str(&format!("\nGitHub: {status}"));
+                }
+                if project.lfs.dominates(project.size) {
+                    info.push_str("\nMost of the size is LFS objects, git lfs prune can drop old ones");
+                }
+                if project.git_info.commit_count == 0 {
+                    let reason = project
+                        .git_info
+                        .state
+                        .inactive_reason()
+                        .unwrap_or("No commits are reachable from HEAD");
+                    info.push_str(&format!("\nInactive: {reason}"));
+                }
+                if let Some(duplicate) = self.projects_list.duplicates.get(&project.path) {
+                    if duplicate.primary == project.path {
+                        info.push_str(&format!(
+                            "\nPrimary of {} clones of {}",
+                            duplicate.clones, duplicate.remote
+                        ));
+                    } else {
+                        info.push_str(&format!(
+                            "\nExtra clone of {}, primary is {}, press D to delete or W to make \
+                             it a worktree",
+                            duplicate.remote,
+                            duplicate.primary.display()
+                        ));
+                    }
+                }
+                if Candidates::new(&self.settings, chrono::Local::now().timestamp())
+                    .matches(project)
+                {
+                    info.push_str("\nArchive candidate: stale, small and without a remote, press X \
+                                   to archive it");
+                }
+                if project.deferred {
+                    info.push_str(
+                        "\nLines of code deferred: larger than max_project_size, press c to count them",
+                    );
+                }
+                info
+            },
+        );
+
+        let block = Block::new()
+            .title(pane_title("F1", "Project Info"))
+            .title(self.age_title(self.selected().map_or(0, |v| v.scanned_at)))
+            .borders(Borders::ALL)
+            .border_set(symbols::border::ROUNDED)
+            .padding(Padding::horizontal(1));
+
+        Paragraph::new(info)
+            .block(block)
+            .fg(TEXT_FG_COLOR)
+            .wrap(Wrap { trim: false })
+            .render(area, buf);
+    }
+
+    fn render_git(&self, area: Rect, buf: &mut Buffer) {
+        let info = self.projects_list.state.selected().map_or_else(
+            || "Nothing selected...".to_string(),
+            |i| {
+                let git_info = &self.projects_list.items[i].git_info;
+                if self.settings.use_icons {
+                    git_info_with_icons(git_info)
+                } else {
+                    git_info.to_string()
+                }
+            },
+        );
+
+        let block = Block::new()
+            .title(pane_title("F3", "Git"))
+            .borders(Borders::ALL)
+            .border_set(symbols::border::ROUNDED)
+            .padding(Padding::horizontal(1));
+
+        Paragraph::new(info)
+            .block(block)
+            .fg(TEXT_FG_COLOR)
+            .wrap(Wrap { trim: false })
+            .render(area, buf);
+    }
+
+    fn render_crates(&self, area: Rect, buf: &mut Buffer) {
+        let crates = self
+            .projects_list
+            .state
+            .selected()
+            .map_or(&[][..], |i| &self.projects_list.items[i].crates);
+
+        let rows = crates.iter().map(|v| {
+            Row::new([
+                v.name.clone(),
+                v.kind().to_string(),
+                v.edition.clone().unwrap_or_else(|| "2015".to_string()),
+            ])
+        });
+        let header = ["Crate", "Kind", "Edition"]
+            .into_iter()
+            .map(Cell::from)
+            .collect::<Row>()
+            .height(1);
+
+        let block = Block::new()
+            .title(pane_title("F4", "Crates"))
+            .borders(Borders::ALL)
+            .border_set(symbols::border::ROUNDED)
+            .padding(Padding::horizontal(1));
+
+        Widget::render(
+            Table::new(
+                rows,
+                [
+                    Constraint::Fill(1),
+                    Constraint::Length(8),
+                    Constraint::Length(7),
+                ],
+            )
+            .header(header)
+            .fg(TEXT_FG_COLOR)
+            .block(block),
+            area,
+            buf,
+        );
+    }
+
+    fn render_metadata(&self, area: Rect, buf: &mut Buffer) {
+        let lines: Vec<Line> = self
+            .projects_list
+            .state
+            .selected()
+            .map(|i| &self.projects_list.items[i].metadata)
+            .into_iter()
+            .flatten()
+            .map(|(key, value)| {
+                Line::from(vec![
+                    Span::styled(format!("{key}: "), Style::default().fg(CYAN.c500)),
+                    Span::from(value.as_str()),
+                ])
+            })
+            .collect();
+
+        let block = Block::new()
+            .title(pane_title("F5", "Metadata"))
+            .borders(Borders::ALL)
+            .border_set(symbols::border::ROUNDED)
+            .padding(Padding::horizontal(1));
+
+        Paragraph::new(lines)
+            .block(block)
+            .fg(TEXT_FG_COLOR)
+            .wrap(Wrap { trim: false })
+            .render(area, buf);
+    }
+
+    fn render_errors(&self, area: Rect, buf: &mut Buffer) {
+        let lines: Vec<Line> = self
+            .projects_list
+            .state
+            .selected()
+            .map(|i| &self.projects_list.items[i].scan_errors)
+            .into_iter()
+            .flatten()
+            .map(|v| Line::styled(v.as_str(), Style::default().fg(INACTIVE_COLOR)))
+            .collect();
+
+        let block = Block::new()
+            .title(pane_title("F6", "Scan Errors"))
+            .borders(Borders::ALL)
+            .border_set(symbols::border::ROUNDED)
+            .padding(Padding::horizontal(1));
+
+        Paragraph::new(lines)
+            .block(block)
+            .wrap(Wrap { trim: false })
+            .render(area, buf);
+    }
+
+    fn render_ignore_stats(&self, area: Rect, buf: &mut Buffer) {
+        let Some(stats) = &self.ignore_stats else {
+            return;
+        };
+
+        let mut lines: Vec<Line> = Vec::new();
+        for (rule, ignored) in &stats.rules {
+            lines.push(Line::from(vec![
+                Span::styled(format!("{rule}: "), Style::default().fg(CYAN.c500)),
+                Span::from(format!("{} dirs", ignored.dirs)),
+            ]));
+            lines.extend(ignored.projects.iter().map(|v| {
+                Line::styled(
+                    format!("  hides project {}", v.display()),
+                    Style::default().fg(INACTIVE_COLOR),
+                )
+            }));
+        }
+        if stats.rules.is_empty() {
+            lines.push(Line::from("Nothing was skipped by ignore_dirs"));
+        }
+        lines.push(Line::from(format!("Unreadable: {}", stats.unreadable)));
+
+        let block = Block::new()
+            .title(pane_title("F7", "Ignored in Last Scan"))
+            .title(self.loaded_title(Pane::IgnoreStats))
+            .borders(Borders::ALL)
+            .border_set(symbols::border::ROUNDED)
+            .padding(Padding::horizontal(1));
+
+        Paragraph::new(lines)
+            .block(block)
+            .fg(TEXT_FG_COLOR)
+            .wrap(Wrap { trim: false })
+            .render(area, buf);
+    }
+
+    /// How long ago `since`, unix seconds, right of a pane's title, empty for `0` which is never
+    fn age_title(&self, since: u64) -> Line<'static> {
+        if since == 0 {
+            return Line::default();
+        }
+        let age = chrono::Local::now().timestamp() - i64::try_from(since).unwrap_or(i64::MAX);
+        if age > i64::from(self.settings.pane_stale_hours) * 60 * 60 {
+            Line::styled(
+                format!(" stale, {} ", format_relative(age)),
+                Style::default().fg(YELLOW.c500),
+            )
+        } else {
+            Line::styled(
+                format!(" {} ", format_relative(age)),
+                Style::default().fg(INACTIVE_COLOR),
+            )
+        }
+        .right_aligned()
+    }
+
+    fn loaded_title(&self, pane: Pane) -> Line<'static> {
+        let since = self.loaded_at.get(&pane).copied().unwrap_or_default();
+        self.age_title(u64::try_from(since).unwrap_or_default())
+    }
+
+    /// Most opened projects with a bar for their opens
+    fn render_most_used(&self, area: Rect, buf: &mut Buffer) {
+        let Some(counts) = &self.open_counts else {
+            return;
+        };
+
+        let block = Block::new()
+            .title(pane_title("F8", "Most Used"))
+            .title(self.loaded_title(Pane::MostUsed))
+            .borders(Borders::ALL)
+            .border_set(symbols::border::ROUNDED)
+            .padding(Padding::horizontal(1));
+
+        let top = counts.top(MOST_USED_LEN);
+        let name = |path: &Path| {
+            path.file_name().map_or_else(
+                || path.display().to_string(),
+                |v| v.to_string_lossy().into(),
+            )
+        };
+        let name_width = top
+            .iter()
+            .map(|(path, _)| name(path).chars().count())
+            .max()
+            .unwrap_or_default();
+        let max = top.first().map_or(1, |v| v.1);
+        let count_width = max.to_string().len();
+        let bar_width = usize::from(block.inner(area).width)
+            .saturating_sub(name_width + count_width + 2)
+            .max(1);
+
+        let mut lines: Vec<Line> = top
+            .iter()
+            .map(|(path, count)| {
+                #[allow(clippy::cast_possible_truncation)]
+                let bar = (u128::from(*count) * bar_width as u128 / u128::from(max)) as usize;
+                Line::from(vec![
+                    Span::from(format!("{:name_width$} ", name(path))),
+                    Span::styled("█".repeat(bar.max(1)), Style::default().fg(CYAN.c500)),
+                    Span::from(format!(" {count}")),
+                ])
+            })
+            .collect();
+        if lines.is_empty() {
+            lines.push(Line::from("No project has been opened yet"));
+        }
+
+        Paragraph::new(lines)
+            .block(block)
+            .fg(TEXT_FG_COLOR)
+            .render(area, buf);
+    }
+
+    /// Files modified last in any project, with how long ago and the project they're in
+    fn render_recent_files(&self, area: Rect, buf: &mut Buffer) {
+        let Some(recent) = &self.recent_files else {
+            return;
+        };
+
+        let block = Block::new()
+            .title(pane_title("F9", "Recent Files"))
+            .title(self.loaded_title(Pane::RecentFiles))
+            .borders(Borders::ALL)
+            .border_set(symbols::border::ROUNDED)
+            .padding(Padding::horizontal(1));
+
+        let now = chrono::Local::now().timestamp();
+        let height = usize::from(block.inner(area).height);
+        let mut lines: Vec<Line> = recent
+            .files
+            .iter()
+            .take(height)
+            .map(|file| {
+                let project = file.project.file_name().unwrap_or_default();
+                let path = file.path.strip_prefix(&file.project).unwrap_or(&file.path);
+                let age = now - i64::try_from(file.modified).unwrap_or(i64::MAX);
+                Line::from(vec![
+                    Span::styled(
+                        format!("{:>14} ", format_relative(age)),
+                        Style::default().fg(CYAN.c500),
+                    ),
+                    Span::from(format!("{}: {}", project.to_string_lossy(), path.display())),
+                ])
+            })
+            .collect();
+        if lines.is_empty() && !self.settings.recent_files {
+            lines.push(Line::from(
+                "Scans don't note recent files, recent_files is off",
+            ));
+        } else if lines.is_empty() {
+            lines.push(Line::from("No files were seen by the last scan"));
+        }
+
+        Paragraph::new(lines)
+            .block(block)
+            .fg(TEXT_FG_COLOR)
+            .render(area, buf);
+    }
+
+    fn render_project_langs(&self, area: Rect, buf: &mut Buffer) {
+        let number = |v| format_number(u64::from(v), self.settings.number_format);
+        let mut total_files = 0;
+        let mut total_lines = 0;
+        let mut total_code = 0;
+        let mut total_comments = 0;
+        let mut total_blanks = 0;
+
+        let mut rows: Vec<[String; 6]> =
+            self.projects_list
+                .state
+                .selected()
+                .map_or_else(Vec::new, |i| {
+                    // Most code first, the map has no order of its own
+                    let mut languages: Vec<_> =
+                        self.projects_list.items[i].languages.iter().collect();
+                    languages.sort_by_key(|(ltype, l)| (std::cmp::Reverse(l.code), **ltype));
+                    languages
+                        .into_iter()
+                        .map(|(ltype, l)| {
+                            let language = LanguageType::list().get(*ltype as usize);
+                            let ignored = language.is_some_and(|&v| {
+                                self.settings
+                                    .ignores_language(&self.projects_list.items[i].path, v)
+                            });
+                            if !ignored {
+                                total_files += l.files;
+                                total_lines += l.lines;
+                                total_code += l.code;
+                                total_comments += l.comments;
+                                total_blanks += l.blanks;
+                            }
+
+                            [
+                                language.map_or("Error".to_string(), |&v| {
+                                    let name = if self.settings.use_icons {
+                                        format!("{} {v}", icons::language(v))
+                                    } else {
+                                        v.to_string()
+                                    };
+                                    if ignored {
+                                        format!("{name} (ignored)")
+                                    } else {
+                                        name
+                                    }
+                                }),
+                                number(l.files),
+                                number(l.lines),
+                                number(l.code),
+                                number(l.comments),
+                                number(l.blanks),
+                            ]
+                        })
+                        .collect()
+                });
+
+        // Listed like a language, but left out of the total
+        if let Some(generated) = self
+            .projects_list
+            .state
+            .selected()
+            .map(|i| &self.projects_list.items[i].generated)
+            .filter(|v| v.files > 0)
+        {
+            rows.push([
+                "Generated (ignored)".to_string(),
+                number(generated.files),
+                number(generated.lines),
+                number(generated.code),
+                number(generated.comments),
+                number(generated.blanks),
+            ]);
+        }
+
+        let header = ["Language", "Files", "Lines", "Code", "Comments", "Blanks"].map(String::from);
+        let footer = [
+            "Total".to_string(),
+            number(total_files),
+            number(total_lines),
+            number(total_code),
+            number(total_comments),
+            number(total_blanks),
+        ];
+
+        // Sized to the widest cell, the header being the minimum. Names keep their width in narrow
+        // panes and take whatever is left over in wide ones
+        let mut widths = [0; 6];
+        for row in rows.iter().chain([&header, &footer]) {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(u16::try_from(cell.chars().count()).unwrap_or(u16::MAX));
+            }
+        }
+        let constraints = widths.iter().enumerate().map(|(i, &width)| {
+            if i == 0 {
+                Constraint::Min(width)
+            } else {
+                Constraint::Length(width)
+            }
+        });
+
+        // Numbers line up on the right
+        let row = |cells: [String; 6]| {
+            cells
+                .into_iter()
+                .enumerate()
+                .map(|(i, cell)| {
+                    if i == 0 {
+                        Cell::from(cell)
+                    } else {
+                        Cell::from(Line::from(cell).right_aligned())
+                    }
+                })
+                .collect::<Row>()
+        };
+
+        let block = languages_block();
+        let mut inner = block.inner(area);
+        block.render(area, buf);
+        // Left blank, the chart is written over it after the frame
+        if let Some(chart) = self.chart_area(inner) {
+            inner.width -= chart.width + 1;
+        }
+
+        Widget::render(
+            Table::new(rows.into_iter().map(row), constraints)
+                .header(row(header).height(1))
+                .footer(row(footer).height(1)),
+            inner,
+            buf,
+        );
+    }
+}
+
+pub fn get_remote_username(project: &Project) -> String {
+    project
+        .git_info
+        .remote_url
+        .as_deref()
+        .and_then(RemoteUrl::parse)
+        .map(|v| v.owner)
+        .unwrap_or_default()
+}
+
+/// Next or previous organization after `current`, going through no organization at both ends
+fn cycle_org(orgs: &[String], current: Option<&str>, forward: bool) -> Option<String> {
+    let position = current.and_then(|org| orgs.iter().position(|v| v == org));
+    let next = match (position, forward) {
+        (None, true) => Some(0),
+        (None, false) => orgs.len().checked_sub(1),
+        (Some(i), true) => Some(i + 1).filter(|&v| v < orgs.len()),
+        (Some(i), false) => i.checked_sub(1),
+    };
+
+    next.map(|i| orgs[i].clone())
+}
+
+/// Everything that decides which projects are listed and in what order
+struct ListView<'a> {
+    filter: &'a Filter,
+    org: Option<&'a str>,
+    /// Git user name, what owned projects are matched against
+    username: &'a str,
+    sort: &'a Sorting,
+    custom_sorts: &'a [SortExpr],
+    invert: bool,
+    label: ProjectLabel,
+    /// Zoxide scores by path, empty unless `zoxide` is set
+    frecency: &'a HashMap<PathBuf, f64>,
+    archive: Candidates,
+}
+
+impl ListView<'_> {
+    fn matches(&self, v: &Project, duplicates: &HashMap<PathBuf, Duplicate>) -> bool {
+        self.org.is_none_or(|org| get_remote_username(v) == org)
+            && self.matches_filter(self.filter, v, duplicates)
+    }
+
+    fn matches_filter(
+        &self,
+        filter: &Filter,
+        v: &Project,
+        duplicates: &HashMap<PathBuf, Duplicate>,
+    ) -> bool {
+        match filter {
+            Filter::All => true,
+            Filter::Owned => get_remote_username(v) == self.username,
+            Filter::NotOwned => get_remote_username(v) != self.username,
+            Filter::HasRemote => v.git_info.remote_url.is_some(),
+            Filter::NoRemote => v.git_info.remote_url.is_none(),
+            Filter::Unsigned => v.git_info.state.has_commits() && v.git_info.signature.is_none(),
+            Filter::NoPreCommit => !v.git_info.hooks.iter().any(|hook| hook == "pre-commit"),
+            Filter::HasCi => !v.ci.is_empty(),
+            Filter::Ci(provider) => v.ci.contains(provider),
+            Filter::NoCi => v.ci.is_empty(),
+            Filter::OffDefaultBranch => v.git_info.off_default_branch(),
+            Filter::SubmodulesOutOfDate => v.submodules.out_of_date(),
+            Filter::Inactive => v.git_info.commit_count == 0,
+            Filter::ScanErrors => !v.scan_errors.is_empty(),
+            Filter::Duplicates => duplicates.contains_key(&v.path),
+            Filter::ArchiveCandidates => self.archive.matches(v),
+        }
+    }
+
+    fn sort(&self, items: &mut [Project]) {
+        match self.sort {
+            Sorting::Name => items.sort_by_cached_key(|a| a.label(self.label)),
+            Sorting::Size => items.sort_by_key(|a| a.size),
+            Sorting::Commits => items.sort_by_key(|a| a.git_info.commit_count),
+            Sorting::CreationDate => items.sort_by_key(|a| a.git_info.init_date),
+            Sorting::ModificationDate => items.sort_by_key(|a| a.git_info.last_commit_date),
+            Sorting::Age => {
+                let now = chrono::Local::now().timestamp();
+                items.sort_by_key(|a| a.age(now));
+            }
+            Sorting::ActivitySpan => items.sort_by_key(Project::activity_span),
+            Sorting::CommitRate => {
+                let rate = |v: &Project| v.commits_per_month().unwrap_or(0.0);
+                items.sort_by(|a, b| rate(a).total_cmp(&rate(b)));
+            }
+            Sorting::Loc => items.sort_by_key(|a| a.languages_total.lines),
+            Sorting::Frecency => {
+                let score = |v: &Project| self.frecency.get(&v.path).copied().unwrap_or(0.0);
+                items.sort_by(|a, b| score(b).total_cmp(&score(a)));
+            }
+            Sorting::Custom(i) => {
+                if let Some(expr) = self.custom_sorts.get(*i) {
+                    expr.sort(items, chrono::Local::now().timestamp());
+                }
+            }
+        }
+
+        if self.invert {
+            items.reverse();
+        }
+    }
+}
+
+struct ProjectsList {
+    /// Every scanned project, the start of the pipeline
+    base: Vec<Project>,
+    /// How many projects each of [`Filter::all`] lets through, kept until `base` or the org
+    /// changes since only those affect it
+    filter_counts: Option<(Option<String>, Vec<usize>)>,
+    /// Clones sharing a remote in `base`, kept until `base` changes
+    duplicates: HashMap<PathBuf, Duplicate>,
+    /// `base` filtered and sorted, what's shown
+    items: Vec<Project>,
+    state: ListState,
+}
+
+impl ProjectsList {
+    /// Rebuilds `items` from `base`, filtering before sorting so neither undoes the other
+    fn apply(&mut self, view: &ListView) {
+        let mut items: Vec<Project> = self
+            .base
+            .iter()
+            .filter(|v| view.matches(v, &self.duplicates))
+            .cloned()
+            .collect();
+        view.sort(&mut items);
+        // Clones of a remote next to each other with the primary first, sorted among the groups
+        if *view.filter == Filter::Duplicates {
+            items.sort_by_cached_key(|v| {
+                self.duplicates
+                    .get(&v.path)
+                    .map(|d| (d.remote.clone(), d.primary != v.path))
+            });
+        }
+
+        self.replace_items(items);
+        self.update_filter_counts(view);
+    }
+
+    fn set_base(&mut self, base: Vec<Project>) {
+        self.duplicates = duplicates::find(&base);
+        self.base = base;
+        self.filter_counts = None;
+    }
+
+    fn update_filter_counts(&mut self, view: &ListView) {
+        if self
+            .filter_counts
+            .as_ref()
+            .is_some_and(|(org, _)| org.as_deref() == view.org)
+        {
+            return;
+        }
+
+        let filters = Filter::all();
+        let mut counts = vec![0; filters.len()];
+        for project in self
+            .base
+            .iter()
+            .filter(|v| view.org.is_none_or(|org| get_remote_username(v) == org))
+        {
+            for (count, filter) in counts.iter_mut().zip(&filters) {
+                *count += usize::from(view.matches_filter(filter, project, &self.duplicates));
+            }
+        }
+
+        self.filter_counts = Some((view.org.map(ToString::to_string), counts));
+    }
+
+    /// Owners of all primary remotes, sorted and without duplicates
+    fn orgs(&self) -> Vec<String> {
+        let mut orgs: Vec<String> = self
+            .base
+            .iter()
+            .map(get_remote_username)
+            .filter(|v| !v.is_empty())
+            .collect();
+        orgs.sort();
+        orgs.dedup();
+        orgs
+    }
+
+    /// Swaps in rebuilt items, keeping the selected project selected
+    ///
+    /// When it's no longer listed the closest project to it in the old order that still is takes
+    /// its place.
+    fn replace_items(&mut self, items: Vec<Project>) {
+        let previous = std::mem::replace(&mut self.items, items);
+        let Some(selected) = self.state.selected().filter(|&v| v < previous.len()) else {
+            self.select_first();
+            return;
+        };
+
+        let positions: HashMap<&Path, usize> = self
+            .items
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (v.path.as_path(), i))
+            .collect();
+
+        let position = |i: usize| {
+            previous
+                .get(i)
+                .and_then(|v| positions.get(v.path.as_path()))
+        };
+        let nearest = (0..previous.len()).find_map(|distance| {
+            position(selected + distance)
+                .or_else(|| selected.checked_sub(distance).and_then(position))
+                .copied()
+        });
+
+        self.state.select(nearest);
+    }
+
+    fn select_first(&mut self) {
+        self.state.select((!self.items.is_empty()).then_some(0));
+    }
+
+    fn select_last(&mut self) {
+        self.state.select(self.items.len().checked_sub(1));
+    }
+
+    /// Moves the selection by `offset`, stopping at the ends of the list or wrapping around them
+    ///
+    /// Without a selection moving forward starts at the first project and backward at the last.
+    fn select_offset(&mut self, offset: isize, wrap: bool) {
+        let Some(last) = self.items.len().checked_sub(1) else {
+            self.state.select(None);
+            return;
+        };
+
+        let Some(current) = self.state.selected().map(|v| v.min(last)) else {
+            self.state.select(Some(if offset < 0 { last } else { 0 }));
+            return;
+        };
+
+        let target = if wrap {
+            let len = isize::try_from(self.items.len()).unwrap_or(isize::MAX);
+            let current = isize::try_from(current).unwrap_or_default();
+            usize::try_from((current + offset % len).rem_euclid(len)).unwrap_or_default()
+        } else {
+            current.saturating_add_signed(offset).min(last)
+        };
+
+        self.state.select(Some(target));
+    }
+
+    /// Moves to the start of the next or current group of projects sharing a parent directory,
+    /// going back from the start of a group lands on the start of the previous one
+    fn select_group(&mut self, forward: bool) {
+        let Some(index) = self.state.selected() else {
+            self.select_first();
+            return;
+        };
+        let parent = |i: usize| self.items.get(i).and_then(|v| v.path.parent());
+
+        let target = if forward {
+            (index + 1..self.items.len())
+                .find(|&i| parent(i) != parent(index))
+                .unwrap_or(self.items.len().saturating_sub(1))
+        } else {
+            let start = |mut i: usize| {
+                while i > 0 && parent(i - 1) == parent(i) {
+                    i -= 1;
+                }
+                i
+            };
+            match start(index) {
+                0 => 0,
+                current if current < index => current,
+                current => start(current - 1),
+            }
+        };
+
+        self.state.select(Some(target));
+    }
+
+    /// Selects the deepest project containing `path`
+    fn select_path(&mut self, path: &Path) {
+        let index = self
+            .items
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| path.starts_with(&p.path))
+            .max_by_key(|(_, p)| p.path.components().count())
+            .map(|(idx, _)| idx);
+
+        if index.is_some() {
+            self.state.select(index);
+        }
+    }
+
+    fn search(&mut self, search_text: &str, index: usize, label: ProjectLabel) -> usize {
+        let filtered_indices: Vec<usize> = self
+            .items
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| matches_search(p, search_text, label))
+            .map(|(idx, _)| idx)
+            .collect();
+
+        if let Some(selected_idx) = filtered_indices.get(index) {
+            self.state.select(Some(*selected_idx));
+        } else {
+            self.state.select(None);
+        }
+
+        filtered_indices.len()
+    }
+}
+
+/// Plain text is looked for in the path, label, crate names and metadata, `key:text` only in
+/// the metadata under `key`, like `go.module:acme`, when `key` is one of
+/// [`manifest::METADATA_KEYS`]
+fn matches_search(project: &Project, text: &str, label: ProjectLabel) -> bool {
+    if let Some((key, value)) = text
+        .split_once(':')
+        .filter(|(key, _)| manifest::METADATA_KEYS.contains(key))
+    {
+        return project.metadata.get(key).is_some_and(|v| v.contains(value));
+    }
+
+    project.path.to_string_lossy().contains(text)
+        || project.label(label).contains(text)
+        || project.crates.iter().any(|v| v.name.contains(text))
+        || project.metadata.values().any(|v| v.contains(text))
+}
+
+impl FromIterator<Project> for ProjectsList {
+    fn from_iter<I: IntoIterator<Item = Project>>(iter: I) -> Self {
+        let state = ListState::default();
+        let items: Vec<Project> = iter.into_iter().collect();
+        Self {
+            items: items.clone(),
+            duplicates: duplicates::find(&items),
+            base: items,
+            filter_counts: None,
+            state,
+        }
+    }
+}
+
+/// Splits `text` into spans with every occurrence of `query` highlighted
+fn highlight(text: String, query: &str) -> Line<'static> {
+    if query.is_empty() {
+        return Line::from(text);
+    }
+
+    let mut spans = Vec::new();
+    let mut last = 0;
+    for (start, matched) in text.match_indices(query) {
+        if start > last {
+            spans.push(Span::raw(text[last..start].to_string()));
+        }
+        spans.push(Span::styled(matched.to_string(), MATCH_STYLE));
+        last = start + matched.len();
+    }
+    if last < text.len() {
+        spans.push(Span::raw(text[last..].to_string()));
+    }
+
+    Line::from(spans)
+}
+
+/// Two letter quick-jump label of the `row`th visible row
+fn jump_label(row: usize) -> String {
+    let len = JUMP_KEYS.len();
+    [row / len % len, row % len]
+        .iter()
+        .map(|&i| char::from(JUMP_KEYS[i]))
+        .collect()
+}
+
+/// Git pane text with the state and host glyphs in front of their values
+fn git_info_with_icons(git_info: &GitInfo) -> String {
+    let host = git_info
+        .remote_url
+        .as_deref()
+        .and_then(RemoteUrl::parse)
+        .map(|v| icons::host(&v.host));
+
+    git_info
+        .to_string()
+        .lines()
+        .map(|line| {
+            if let Some(rest) = line.strip_prefix("State: ") {
+                format!("State: {} {rest}", icons::state(git_info.state))
+            } else if let (Some(rest), Some(host)) = (line.strip_prefix("Remote: "), host) {
+                format!("Remote: {host} {rest}")
+            } else {
+                line.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Row of the list, `fit` is the width `text` is cut in the middle to fit in with the badges
+fn list_item(
+    value: &Project,
+    text: String,
+    query: &str,
+    fit: Option<usize>,
+    settings: &Settings,
+) -> ListItem<'static> {
+    let use_icons = settings.use_icons;
+    let badge = |icon: &str, text: &str| {
+        if use_icons {
+            format!(" {icon}")
+        } else {
+            format!(" {text}")
+        }
+    };
+
+    // Spans before the text, the line gets the ones after it
+    let mut prefix = Vec::new();
+    let mut line = Line::default();
+    // Markers only tell languages apart by color
+    let language_markers = settings.language_markers && !settings.theme.is_accessible();
+    if use_icons || language_markers {
+        let language = value.top_language();
+        let color = language
+            .and_then(linguist::color)
+            .filter(|_| language_markers);
+        // Projects without a marker keep the space so names stay aligned
+        let marker = match (use_icons, color) {
+            (true, _) => language.map_or(" ", icons::language),
+            (false, Some(_)) => "●",
+            (false, None) => " ",
+        };
+        prefix.push(Span::styled(
+            format!("{marker} "),
+            color.map_or_else(Style::default, |v| Style::default().fg(v)),
+        ));
+    }
+    if !use_icons && settings.theme.is_accessible() {
+        if let Some(tag) = value.git_info.state.tag() {
+            prefix.insert(0, Span::from(format!("{tag} ")));
+        }
+    }
+    if use_icons {
+        if let Some(remote) = value
+            .git_info
+            .remote_url
+            .as_deref()
+            .and_then(RemoteUrl::parse)
+        {
+            line.push_span(Span::from(format!(" {}", icons::host(&remote.host))));
+        }
+        if value.git_info.state != RepoState::Ok {
+            line.push_span(Span::from(format!(
+                " {}",
+                icons::state(value.git_info.state)
+            )));
+        }
+    }
+    if !value.ci.is_empty() {
+        line.push_span(Span::styled(
+            badge(icons::CI, "CI"),
+            Style::default().fg(CI_COLOR),
+        ));
+    }
+    if value.dev_envs.iter().any(|v| {
+        matches!(
+            v,
+            DevEnv::Dockerfile | DevEnv::Compose | DevEnv::Devcontainer
+        )
+    }) {
+        line.push_span(Span::styled(
+            badge(icons::DOCKER, "docker"),
+            Style::default().fg(CONTAINER_COLOR),
+        ));
+    }
+    if value.dev_envs.iter().any(|v| v.is_nix()) {
+        line.push_span(Span::styled(
+            badge(icons::NIX, "nix"),
+            Style::default().fg(CONTAINER_COLOR),
+        ));
+    }
+    if value.dev_envs.contains(&DevEnv::Direnv) {
+        line.push_span(Span::styled(
+            badge(icons::DIRENV, "direnv"),
+            Style::default().fg(CONTAINER_COLOR),
+        ));
+    }
+
+    let text = match fit {
+        Some(width) => {
+            let badges = line.width() + prefix.iter().map(Span::width).sum::<usize>();
+            truncate_middle(&text, width.saturating_sub(badges))
+        }
+        None => text,
+    };
+    prefix.extend(highlight(text, query).spans);
+    prefix.extend(line.spans);
+    ListItem::new(Line::from(prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        path::{Path, PathBuf},
+    };
+
+    use ratatui::{
+        backend::TestBackend,
+        crossterm::event::{KeyCode, KeyEvent, KeyModifiers},
+        layout::Rect,
+        Terminal,
+    };
+    use tokei::LanguageType;
+
+    use super::{
+        git_info_with_icons, highlight, matches_search, resolve_dir, scroll, AppState, Effect,
+        ListView, Message, ProjectsList, MATCH_STYLE,
+    };
+    use crate::{
+        archive::Candidates,
+        ci::CiProvider,
+        commit_search::CommitMatch,
+        config::{
+            ConfirmAction, ConfirmPolicy, CustomSortConfig, ProjectLabel, Settings, Theme,
+            Truncation,
+        },
+        content_search::ContentMatch,
+        enrichment,
+        graphics::Protocol,
+        history::OpenCounts,
+        icons,
+        manifest::RustCrate,
+        projects::{Project, ProjectLanguage},
+        recent_files::{RecentFile, RecentFiles},
+        sorting::{Filter, Sorting},
+        tasks::Task,
+        test_utils::temp_dir,
+        utils::{GitInfo, RepoState},
+    };
+
+    fn list(paths: &[&str]) -> ProjectsList {
+        paths
+            .iter()
+            .map(|path| Project {
+                path: path.into(),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn offsets_are_clamped() {
+        let mut list = list(&["a", "b", "c"]);
+
+        list.select_offset(10, false);
+        assert_eq!(list.state.selected(), Some(0));
+        list.select_offset(10, false);
+        assert_eq!(list.state.selected(), Some(2));
+        list.select_offset(-10, false);
+        assert_eq!(list.state.selected(), Some(0));
+
+        list.state.select(None);
+        list.select_offset(-1, false);
+        assert_eq!(list.state.selected(), Some(2));
+    }
+
+    #[test]
+    fn offsets_wrap_around() {
+        let mut list = list(&["a", "b", "c"]);
+        list.select_first();
+
+        list.select_offset(-1, true);
+        assert_eq!(list.state.selected(), Some(2));
+        list.select_offset(1, true);
+        assert_eq!(list.state.selected(), Some(0));
+        list.select_offset(7, true);
+        assert_eq!(list.state.selected(), Some(1));
+        list.select_offset(isize::MIN, true);
+        assert!(list.state.selected().is_some_and(|v| v < 3));
+    }
+
+    #[test]
+    fn empty_list_has_no_selection() {
+        let mut list = list(&[]);
+
+        list.select_offset(1, false);
+        assert_eq!(list.state.selected(), None);
+        list.select_offset(-1, true);
+        assert_eq!(list.state.selected(), None);
+        list.select_last();
+        assert_eq!(list.state.selected(), None);
+    }
+
+    #[test]
+    fn selection_survives_rebuilds() {
+        let mut list = list(&["a", "b", "c", "d"]);
+        list.state.select(Some(2));
+
+        let reversed = list.items.iter().rev().cloned().collect();
+        list.replace_items(reversed);
+        assert_eq!(list.state.selected(), Some(1));
+
+        // "c" is gone, "b" and "d" are equally close and the later one wins
+        let remaining = list
+            .items
+            .iter()
+            .filter(|v| v.path != Path::new("c"))
+            .cloned()
+            .collect();
+        list.replace_items(remaining);
+        assert_eq!(
+            list.items[list.state.selected().unwrap()].path,
+            Path::new("b")
+        );
+
+        list.replace_items(Vec::new());
+        assert_eq!(list.state.selected(), None);
+    }
+
+    #[test]
+    fn filter_and_sort_compose() {
+        let projects = [
+            ("a", 3, true),
+            ("b", 1, false),
+            ("c", 2, true),
+            ("d", 0, true),
+        ];
+        let mut list: ProjectsList = projects
+            .into_iter()
+            .map(|(path, size, remote)| Project {
+                path: path.into(),
+                size,
+                git_info: GitInfo {
+                    remote_url: remote.then(|| "https://example.com/a/b".to_string()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .collect();
+
+        let mut view = ListView {
+            filter: &Filter::HasRemote,
+            org: None,
+            username: "",
+            sort: &Sorting::Size,
+            custom_sorts: &[],
+            invert: false,
+            label: ProjectLabel::Path,
+            frecency: &HashMap::new(),
+            archive: Candidates::new(&Settings::default(), 0),
+        };
+        list.apply(&view);
+        let paths: Vec<_> = list.items.iter().map(|v| v.path.clone()).collect();
+        assert_eq!(paths, ["d", "c", "a"].map(PathBuf::from));
+
+        // Changing only the sort keeps the filter applied
+        view.invert = true;
+        list.apply(&view);
+        let paths: Vec<_> = list.items.iter().map(|v| v.path.clone()).collect();
+        assert_eq!(paths, ["a", "c", "d"].map(PathBuf::from));
+    }
+
+    #[test]
+    fn sorts_by_commit_rate() {
+        const MONTH: u32 = 30 * 24 * 60 * 60;
+        let project = |path: &str, commits, months: u32| Project {
+            path: path.into(),
+            git_info: GitInfo {
+                state: RepoState::Ok,
+                commit_count: commits,
+                init_date: MONTH,
+                last_commit_date: MONTH * (1 + months),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        // 2 a month, 30 in less than a month, 5 a month
+        let mut list: ProjectsList = [
+            project("steady", 24, 12),
+            project("burst", 30, 0),
+            project("busy", 60, 12),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(list.base[1].commits_per_month(), Some(30.0));
+        assert_eq!(list.base[0].activity_span(), Some(i64::from(MONTH) * 12));
+
+        let view = ListView {
+            filter: &Filter::All,
+            org: None,
+            username: "",
+            sort: &Sorting::CommitRate,
+            custom_sorts: &[],
+            invert: false,
+            label: ProjectLabel::Path,
+            frecency: &HashMap::new(),
+            archive: Candidates::new(&Settings::default(), 0),
+        };
+        list.apply(&view);
+        let paths: Vec<_> = list.items.iter().map(|v| v.path.clone()).collect();
+        assert_eq!(paths, ["steady", "busy", "burst"].map(PathBuf::from));
+
+        list.apply(&ListView {
+            sort: &Sorting::ActivitySpan,
+            ..view
+        });
+        assert_eq!(list.items[0].path, Path::new("burst"));
+    }
+
+    #[test]
+    fn groups_follow_parent_directories() {
+        let mut list = list(&["x/a", "x/b", "y/a", "y/b", "y/c", "z/a"]);
+        list.state.select(Some(1));
+
+        list.select_group(true);
+        assert_eq!(list.state.selected(), Some(2));
+        list.select_group(true);
+        assert_eq!(list.state.selected(), Some(5));
+
+        list.state.select(Some(4));
+        list.select_group(false);
+        assert_eq!(list.state.selected(), Some(2));
+        list.select_group(false);
+        assert_eq!(list.state.selected(), Some(0));
+    }
+
+    #[test]
+    fn digits_toggle_panes_unless_counting() {
+        let paths: Vec<String> = (0..20).map(|i| format!("/src/{i:02}")).collect();
+        let mut state = state(&paths.iter().map(String::as_str).collect::<Vec<_>>());
+        press(&mut state, [KeyCode::Char('j')]);
+        press(&mut state, chars("12j"));
+        assert_eq!(state.selected().unwrap().path, Path::new("/src/02"));
+        assert!(!state.show_project_info);
+        assert!(!state.show_languages);
+
+        let counting = Settings {
+            count_prefix: true,
+            ..Settings::default()
+        };
+        let mut state = state.with_settings(&counting);
+        press(&mut state, chars("12j"));
+        assert_eq!(state.selected().unwrap().path, Path::new("/src/14"));
+        assert!(!state.show_project_info);
+        press(&mut state, [KeyCode::F(1), KeyCode::F(2)]);
+        assert!(state.show_project_info);
+        assert!(state.show_languages);
+    }
+
+    #[test]
+    fn search_looks_in_metadata() {
+        let project = Project {
+            path: "/src/tool".into(),
+            metadata: [("go.module".to_string(), "github.com/acme/tool".to_string())].into(),
+            ..Default::default()
+        };
+
+        assert!(matches_search(&project, "acme", ProjectLabel::Path));
+        assert!(matches_search(
+            &project,
+            "go.module:acme",
+            ProjectLabel::Path
+        ));
+        assert!(!matches_search(
+            &project,
+            "npm.name:acme",
+            ProjectLabel::Path
+        ));
+        assert!(!matches_search(
+            &project,
+            "go.module:other",
+            ProjectLabel::Path
+        ));
+
+        // Other text with a colon is plain text
+        let remote = Project {
+            path: "/src/github.com:acme".into(),
+            ..Default::default()
+        };
+        assert!(matches_search(
+            &remote,
+            "github.com:acme",
+            ProjectLabel::Path
+        ));
+        assert!(!matches_search(&remote, "https://", ProjectLabel::Path));
+    }
+
+    #[test]
+    fn icons_replace_badge_text() {
+        let settings = Settings {
+            use_icons: true,
+            ..Settings::default()
+        };
+        let mut state = state(&["/src/alpha", "/src/beta"]);
+        let alpha = &mut state.projects_list.base[0];
+        alpha.ci = vec![CiProvider::GithubActions];
+        alpha.git_info.remote_url = Some("https://gitlab.com/acme/alpha".to_string());
+        state.projects_list.base[1].git_info.state = RepoState::Empty;
+        state.update_list();
+
+        let rows = screen(&state);
+        let alpha = &rows[row_of(&rows, "/src/alpha").unwrap()];
+        assert!(alpha.contains("/src/alpha CI"));
+        assert!(!alpha.contains(icons::host("gitlab.com")));
+
+        let state = state.with_settings(&settings);
+        let rows = screen(&state);
+        let alpha = &rows[row_of(&rows, "/src/alpha").unwrap()];
+        assert!(alpha.contains(&format!(
+            "/src/alpha {} {}",
+            icons::host("gitlab.com"),
+            icons::CI
+        )));
+        let beta = &rows[row_of(&rows, "/src/beta").unwrap()];
+        assert!(beta.contains(&format!("/src/beta {}", icons::state(RepoState::Empty))));
+
+        let git_info = GitInfo {
+            remote_url: Some("git@github.com:acme/alpha.git".to_string()),
+            state: RepoState::Detached,
+            ..GitInfo::default()
+        };
+        let text = git_info_with_icons(&git_info);
+        assert!(text.contains(&format!(
+            "State: {} Detached HEAD",
+            icons::state(RepoState::Detached)
+        )));
+        assert!(text.contains(&format!(
+            "Remote: {} git@github.com:acme/alpha.git",
+            icons::host("github.com")
+        )));
+    }
+
+    #[test]
+    fn rows_are_marked_with_their_main_language() {
+        let mut state = state(&["/src/alpha", "/src/beta"]);
+        state.projects_list.base[0] = state.projects_list.base[0]
+            .clone()
+            .with_code(LanguageType::Python, 10)
+            .with_code(LanguageType::Rust, 200);
+        state.update_list();
+
+        let marker = |state: &AppState, path: &str| {
+            let terminal = draw(state);
+            let buffer = terminal.backend().buffer();
+            let y = row_of(&screen(state), path).unwrap();
+            let x = (0..buffer.area.width).find(|&x| buffer[(x, y as u16)].symbol() == "●")?;
+            Some(buffer[(x, y as u16)].fg)
+        };
+        assert_eq!(
+            marker(&state, "/src/alpha"),
+            crate::linguist::color(LanguageType::Rust)
+        );
+        assert_eq!(marker(&state, "/src/beta"), None);
+
+        let settings = Settings {
+            theme: Theme::NoColor,
+            ..Settings::default()
+        };
+        let state = state.with_settings(&settings);
+        assert_eq!(marker(&state, "/src/alpha"), None);
+    }
+
+    #[test]
+    fn highlight_marks_every_match() {
+        let spans = |text: &str, query: &str| -> Vec<(String, bool)> {
+            highlight(text.to_string(), query)
+                .spans
+                .into_iter()
+                .map(|v| (v.content.to_string(), v.style == MATCH_STYLE))
+                .collect()
+        };
+
+        assert_eq!(
+            spans("/src/ymir/ymir-cli", "ymir"),
+            [
+                ("/src/".to_string(), false),
+                ("ymir".to_string(), true),
+                ("/".to_string(), false),
+                ("ymir".to_string(), true),
+                ("-cli".to_string(), false),
+            ]
+        );
+        assert_eq!(spans("ymir", "ymir"), [("ymir".to_string(), true)]);
+        assert_eq!(spans("ymir", ""), [("ymir".to_string(), false)]);
+        assert_eq!(spans("ymir", "tool"), [("ymir".to_string(), false)]);
+    }
+
+    fn state(paths: &[&str]) -> AppState {
+        let mut state = AppState::new(
+            paths
+                .iter()
+                .map(|path| Project {
+                    path: path.into(),
+                    ..Default::default()
+                })
+                .collect(),
+        );
+        state.update(Message::Resize(Rect::new(0, 0, 60, 12)));
+        state
+    }
+
+    /// Presses every key in turn, returning the effects asked for
+    fn press(state: &mut AppState, keys: impl IntoIterator<Item = KeyCode>) -> Vec<Effect> {
+        keys.into_iter()
+            .filter_map(|code| state.update(Message::Key(KeyEvent::from(code))))
+            .collect()
+    }
+
+    fn chars(text: &str) -> Vec<KeyCode> {
+        text.chars().map(KeyCode::Char).collect()
+    }
+
+    /// Terminal of the size the state was last told about, with a frame of it drawn
+    fn draw(state: &AppState) -> Terminal<TestBackend> {
+        let mut terminal =
+            Terminal::new(TestBackend::new(state.area.width, state.area.height)).unwrap();
+        terminal
+            .draw(|frame| frame.render_widget(state, frame.area()))
+            .unwrap();
+        terminal
+    }
+
+    fn screen(state: &AppState) -> Vec<String> {
+        let terminal = draw(state);
+        let buffer = terminal.backend().buffer();
+        (0..buffer.area.height)
+            .map(|y| {
+                (0..buffer.area.width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn row_of(screen: &[String], text: &str) -> Option<usize> {
+        screen.iter().position(|v| v.contains(text))
+    }
+
+    #[test]
+    fn sorting_reorders_rows() {
+        let mut state = state(&["/src/beta", "/src/alpha", "/src/gamma"]);
+        let rows = screen(&state);
+        assert!(row_of(&rows, "/src/alpha") < row_of(&rows, "/src/beta"));
+        assert!(row_of(&rows, "/src/beta") < row_of(&rows, "/src/gamma"));
+
+        press(&mut state, chars("i"));
+        let rows = screen(&state);
+        assert!(row_of(&rows, "/src/gamma") < row_of(&rows, "/src/alpha"));
+
+        press(&mut state, chars("l"));
+        assert!(screen(&state)[1].contains(&Sorting::Name.next(0, false).to_string()));
+    }
+
+    #[test]
+    fn custom_sorts_follow_the_built_in_ones() {
+        let settings = Settings {
+            custom_sorts: vec![CustomSortConfig {
+                name: "Bytes per Line".to_string(),
+                expr: "size / loc".to_string(),
+            }],
+            ..Settings::default()
+        };
+        let mut state = state(&["/src/alpha", "/src/beta"]).with_settings(&settings);
+        state.projects_list.base[0].size = 1000;
+        state.projects_list.base[0].languages_total.code = 10;
+        state.projects_list.base[1].size = 1000;
+        state.projects_list.base[1].languages_total.code = 100;
+        state.sort_type = Sorting::Loc;
+
+        press(&mut state, chars("l"));
+        let rows = screen(&state);
+        assert!(rows[1].contains("per Line"));
+        assert!(row_of(&rows, "/src/beta") < row_of(&rows, "/src/alpha"));
+        press(&mut state, chars("l"));
+        assert_eq!(state.sort_type, Sorting::Name);
+        press(&mut state, chars("h"));
+        assert_eq!(state.sort_type, Sorting::Custom(0));
+
+        let invalid = Settings {
+            custom_sorts: vec![CustomSortConfig {
+                name: "Broken".to_string(),
+                expr: "size /".to_string(),
+            }],
+            ..Settings::default()
+        };
+        let state = state.with_settings(&invalid);
+        assert_eq!(state.sort_type, Sorting::Name);
+        assert!(state.notice.unwrap().contains("Invalid sort Broken"));
+    }
+
+    #[test]
+    fn select_picks_the_best_match() {
+        let mut state = state(&[
+            "/src/ymir-web",
+            "/src/ymir",
+            "/src/tools/ymir-cli",
+            "/work/ymir/docs",
+        ]);
+        assert_eq!(state.select_best("YMIR"), Some(PathBuf::from("/src/ymir")));
+        assert_eq!(state.selected().unwrap().path, Path::new("/src/ymir"));
+        assert_eq!(
+            state.select_best("ymir-"),
+            Some(PathBuf::from("/src/ymir-web"))
+        );
+        assert_eq!(
+            state.select_best("work/"),
+            Some(PathBuf::from("/work/ymir/docs"))
+        );
+
+        assert_eq!(state.select_best("nothing"), None);
+        assert_eq!(state.selected().unwrap().path, Path::new("/work/ymir/docs"));
+        assert_eq!(state.notice.as_deref(), Some("No project matches nothing"));
+        assert_eq!(state.load_error, None);
+    }
+
+    #[test]
+    fn filter_picker_applies_the_choice() {
+        let mut state = state(&["/src/alpha", "/src/beta"]);
+        state.projects_list.base[1].git_info.remote_url =
+            Some("https://github.com/acme/beta".to_string());
+        state.update_list();
+
+        press(&mut state, chars("f"));
+        assert!(row_of(&screen(&state), "Filter").is_some());
+        press(&mut state, chars("jjj"));
+        press(&mut state, [KeyCode::Enter]);
+
+        let rows = screen(&state);
+        assert_eq!(state.filter_type, Filter::HasRemote);
+        assert!(row_of(&rows, "/src/beta").is_some());
+        assert!(row_of(&rows, "/src/alpha").is_none());
+        assert!(rows[1].contains("Projects (1)"));
+    }
+
+    #[test]
+    fn search_selects_matches() {
+        let mut state = state(&["/src/alpha", "/src/beta", "/src/gamma"]);
+        press(&mut state, chars("/gam"));
+
+        assert_eq!(state.selected().unwrap().path, Path::new("/src/gamma"));
+        let rows = screen(&state);
+        assert!(row_of(&rows, "[1/1]").is_some());
+        assert!(row_of(&rows, "gam ").is_some());
+
+        press(&mut state, [KeyCode::Esc]);
+        assert!(row_of(&screen(&state), "[1/1]").is_none());
+        assert_eq!(state.selected().unwrap().path, Path::new("/src/gamma"));
+    }
+
+    #[test]
+    fn selection_stays_in_view() {
+        let paths: Vec<String> = (0..30).map(|i| format!("/src/p{i:02}")).collect();
+        let mut state = state(&paths.iter().map(String::as_str).collect::<Vec<_>>());
+
+        press(&mut state, chars("G"));
+        assert_eq!(state.projects_list.state.offset(), 22);
+        assert!(row_of(&screen(&state), "/src/p29").is_some());
+
+        press(&mut state, chars("5k"));
+        assert_eq!(state.projects_list.state.offset(), 22);
+        press(&mut state, chars("g"));
+        let rows = screen(&state);
+        assert!(row_of(&rows, "/src/p00").is_some());
+        assert!(row_of(&rows, "/src/p29").is_none());
+    }
+
+    #[test]
+    fn scrolling_moves_as_little_as_it_can() {
+        // Selection already in view keeps the offset
+        assert_eq!(scroll(5, Some(7), 30, 8), 5);
+        assert_eq!(scroll(5, Some(13), 30, 8), 6);
+        assert_eq!(scroll(5, Some(2), 30, 8), 2);
+        // A list that got shorter shows as much as fits
+        assert_eq!(scroll(22, Some(4), 5, 8), 0);
+        assert_eq!(scroll(22, None, 26, 8), 18);
+        assert_eq!(scroll(3, Some(40), 30, 8), 22);
+        assert_eq!(scroll(3, Some(0), 0, 0), 0);
+    }
+
+    #[test]
+    fn cd_and_add_switch_roots() {
+        let cached = || -> Vec<Project> {
+            ["/src/alpha", "/work/tool"]
+                .into_iter()
+                .map(|path| Project {
+                    path: path.into(),
+                    ..Default::default()
+                })
+                .collect()
+        };
+        let resolved = |state: &mut AppState, root: &str, append| {
+            state.update(Message::RootResolved {
+                root: root.into(),
+                append,
+                cached: cached(),
+            })
+        };
+        let listed = |state: &AppState| -> Vec<PathBuf> {
+            state
+                .projects_list
+                .items
+                .iter()
+                .map(|v| v.path.clone())
+                .collect()
+        };
+        let mut state = state(&["/src/alpha"]).with_scan_root("/src".into());
+
+        let effects = press(&mut state, chars(":add  ~/work "));
+        assert!(effects.is_empty());
+        let effects = press(&mut state, [KeyCode::Enter]);
+        assert!(
+            matches!(&effects[..], [Effect::ChangeRoot { input, append: true }] if input == " ~/work")
+        );
+
+        // Cached projects show without scanning
+        assert!(matches!(
+            resolved(&mut state, "/work", false),
+            Some(Effect::CheckFreeSpace)
+        ));
+        assert_eq!(state.scan_roots, [PathBuf::from("/work")]);
+        assert_eq!(listed(&state), [PathBuf::from("/work/tool")]);
+
+        resolved(&mut state, "/src", true);
+        assert_eq!(listed(&state).len(), 2);
+        assert!(resolved(&mut state, "/src/alpha", true).is_none());
+        assert_eq!(state.scan_roots.len(), 2);
+        // A parent replaces the roots inside it
+        resolved(&mut state, "/", true);
+        assert_eq!(state.scan_roots, [PathBuf::from("/")]);
+
+        let effect = resolved(&mut state, "/elsewhere", false);
+        assert!(
+            matches!(&effect, Some(Effect::Scan { roots, .. }) if roots == &[PathBuf::from("/elsewhere")])
+        );
+        press(&mut state, chars(":cd /src"));
+        assert!(press(&mut state, [KeyCode::Enter]).is_empty());
+        assert_eq!(
+            state.notice.as_deref(),
+            Some("Wait for the current scan to finish")
+        );
+    }
+
+    #[test]
+    fn resolves_existing_directories() {
+        let dir = temp_dir("resolve-dir");
+        std::fs::write(dir.join("file"), "").unwrap();
+
+        let canonical = dir.canonicalize().unwrap();
+        let input = format!(" {}/. ", dir.display());
+        assert_eq!(resolve_dir(&input).unwrap(), canonical);
+        assert_eq!(
+            resolve_dir("  ").unwrap_err().to_string(),
+            "Missing directory"
+        );
+        let missing = dir.join("missing").display().to_string();
+        assert_eq!(
+            resolve_dir(&missing).unwrap_err().to_string(),
+            format!("Failed to open {missing}")
+        );
+        let file = dir.join("file").display().to_string();
+        assert_eq!(
+            resolve_dir(&file).unwrap_err().to_string(),
+            format!("{file} is not a directory")
+        );
+    }
+
+    #[test]
+    fn keys_leave_io_to_effects() {
+        let mut state = state(&["/src/alpha"]);
 
-        let mut invert_title = Line::from(vec![
-            Span::styled(" i", Style::default().fg(CYAN.c500)),
-            Span::from("nvert "),
-        ])
-        .right_aligned();
+        let effects = press(&mut state, [KeyCode::Enter]);
+        assert!(
+            matches!(&effects[..], [Effect::OpenProject(path)] if path == Path::new("/src/alpha"))
+        );
 
-        if self.invert {
-            invert_title = invert_title.add_modifier(Modifier::BOLD);
-        }
+        let effects = press(&mut state, chars("L"));
+        assert!(matches!(&effects[..], [Effect::ShowAudit]));
 
-        let block = Block::new()
-            .title(
-                Line::raw(format!("Projects ({})", self.projects_list.items.len())).left_aligned(),
-            )
-            .title(invert_title)
-            .title(Line::from(filter_title).right_aligned())
-            .title(Line::from(sort_title).right_aligned())
-            .borders(Borders::ALL)
-            .border_set(symbols::border::ROUNDED);
+        let effects = press(&mut state, chars("e"));
+        assert!(matches!(&effects[..], [Effect::Reveal(path)] if path == Path::new("/src/alpha")));
 
-        let items: Vec<ListItem> = self
-            .projects_list
-            .items
-            .iter()
-            .map(ListItem::from)
-            .collect();
+        let effects = press(&mut state, chars("t"));
+        let [Effect::FindTasks(dir)] = &effects[..] else {
+            panic!("{effects:?}");
+        };
+        state.update(Message::TasksFound(dir.clone(), Vec::new()));
+        assert!(row_of(&screen(&state), "No tasks found in /src/alpha").is_some());
 
-        let list = List::new(items)
-            .block(block)
-            .highlight_style(SELECTED_STYLE)
-            .highlight_spacing(HighlightSpacing::Always);
+        let effects = press(&mut state, chars(":cd /tmp"));
+        assert!(effects.is_empty());
+        let effects = press(&mut state, [KeyCode::Enter]);
+        assert!(
+            matches!(&effects[..], [Effect::ChangeRoot { input, append: false }] if input == "/tmp")
+        );
+    }
+
+    #[test]
+    fn scanned_projects_show_up_right_away() {
+        let project = |path: &str| Project {
+            path: path.into(),
+            ..Default::default()
+        };
+        let mut state = AppState::new(Vec::new()).with_scan_root("/src".into());
+        state.update(Message::Resize(Rect::new(0, 0, 60, 12)));
+        state.write_cache = false;
+        assert!(matches!(
+            state.rescan(),
+            Some(Effect::Scan { cache: false, .. })
+        ));
+        let rows = screen(&state);
+        assert!(row_of(&rows, "Scanning, 0 found").is_some());
+        assert!(row_of(&rows, "Looking for projects in /src").is_some());
+
+        state.update(Message::ScanProgress(
+            2,
+            vec![project("/src/beta"), project("/elsewhere/gamma")],
+        ));
+        state.update(Message::ScanProgress(3, vec![project("/src/alpha")]));
+        let rows = screen(&state);
+        assert!(row_of(&rows, "Scanning, 3 found").is_some());
+        assert!(row_of(&rows, "/src/alpha").unwrap() < row_of(&rows, "/src/beta").unwrap());
+        assert!(row_of(&rows, "gamma").is_none());
 
-        StatefulWidget::render(list, area, buf, &mut self.projects_list.state);
+        state.update(Message::ScanDone(vec![project("/src/alpha")], None));
+        let rows = screen(&state);
+        assert!(row_of(&rows, "Scanning").is_none());
+        assert!(row_of(&rows, "/src/beta").is_none());
     }
 
-    fn render_search(&self, area: Rect, buf: &mut Buffer) {
-        let block = Block::new()
-            .title(
-                Line::from(format!("[{}/{}]", self.search_index + 1, self.search_count))
-                    .right_aligned(),
-            )
-            .borders(Borders::ALL)
-            .border_set(symbols::border::ROUNDED);
+    #[test]
+    fn ctrl_r_rescans_in_the_background() {
+        let ctrl_r = KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL);
+        let mut state = state(&["/src/alpha", "/src/beta"]).with_scan_root("/src".into());
+        press(&mut state, chars("j"));
 
-        Paragraph::new(self.search_text.as_ref().map_or("", |v| v))
-            .block(block)
-            .render(area, buf);
+        let effect = state.update(Message::Key(ctrl_r));
+        assert!(
+            matches!(&effect, Some(Effect::Scan { roots, .. }) if roots == &[PathBuf::from("/src")])
+        );
+        assert!(state.update(Message::Key(ctrl_r)).is_none());
+        // The list stays usable while scanning
+        press(&mut state, chars("k"));
+        assert_eq!(state.selected().unwrap().path, Path::new("/src/alpha"));
+        assert!(row_of(&screen(&state), "Scanning").is_some());
+
+        state.update(Message::ScanDone(
+            ["/src/alpha", "/src/beta", "/src/gamma"]
+                .into_iter()
+                .map(|path| Project {
+                    path: path.into(),
+                    ..Default::default()
+                })
+                .collect(),
+            None,
+        ));
+        let rows = screen(&state);
+        assert!(row_of(&rows, "Scanning").is_none());
+        assert!(row_of(&rows, "/src/gamma").is_some());
+        assert_eq!(state.selected().unwrap().path, Path::new("/src/alpha"));
+        assert!(state.update(Message::Key(ctrl_r)).is_some());
     }
 
-    fn render_project_info(&self, area: Rect, buf: &mut Buffer) {
-        let info = self.projects_list.state.selected().map_or_else(
-            || "Nothing selected...".to_string(),
-            |i| self.projects_list.items[i].to_string(),
+    #[test]
+    fn scans_without_the_cache_keep_the_rest_of_the_list() {
+        let mut state = state(&["/src/alpha", "/src/beta"]).with_scan_root("/src".into());
+        state.write_cache = false;
+        state.projects_list.base[0].deferred = true;
+        state.update_list();
+        let listed = |state: &AppState| -> Vec<(PathBuf, u64)> {
+            let mut listed: Vec<_> = state
+                .projects_list
+                .base
+                .iter()
+                .map(|v| (v.path.clone(), v.size))
+                .collect();
+            listed.sort();
+            listed
+        };
+
+        let effects = press(&mut state, chars("c"));
+        assert!(
+            matches!(&effects[..], [Effect::Scan { roots, cache: false, .. }] if roots == &[PathBuf::from("/src/alpha")])
+        );
+        state.update(Message::ScanDone(
+            vec![Project {
+                path: "/src/alpha".into(),
+                size: 5,
+                ..Default::default()
+            }],
+            None,
+        ));
+        assert_eq!(
+            listed(&state),
+            [
+                (PathBuf::from("/src/alpha"), 5),
+                (PathBuf::from("/src/beta"), 0)
+            ]
         );
 
-        let title = vec![
-            Span::from("["),
-            Span::styled("1", Style::default().fg(CYAN.c500)),
-            Span::from("] Project Info"),
-        ];
+        let effect = state.update(Message::RootResolved {
+            root: "/work".into(),
+            append: true,
+            cached: Vec::new(),
+        });
+        assert!(
+            matches!(&effect, Some(Effect::Scan { roots, cache: false, .. }) if roots == &[PathBuf::from("/work")])
+        );
+        state.update(Message::ScanDone(
+            vec![Project {
+                path: "/work/tool".into(),
+                ..Default::default()
+            }],
+            None,
+        ));
+        assert_eq!(listed(&state).len(), 3);
 
-        let block = Block::new()
-            .title(Line::from(title).left_aligned())
-            .borders(Borders::ALL)
-            .border_set(symbols::border::ROUNDED)
-            .padding(Padding::horizontal(1));
+        // Projects gone from a rescanned root leave the list
+        state.rescan();
+        state.update(Message::ScanDone(Vec::new(), None));
+        assert!(listed(&state).is_empty());
+    }
 
-        Paragraph::new(info)
-            .block(block)
-            .fg(TEXT_FG_COLOR)
-            .wrap(Wrap { trim: false })
-            .render(area, buf);
+    #[test]
+    fn github_details_show_in_the_info_pane() {
+        let mut state = state(&["/src/alpha"]);
+        state.update(Message::Resize(Rect::new(0, 0, 120, 30)));
+        press(&mut state, (2..=6).map(KeyCode::F));
+        state.update(Message::Enriched(
+            "/src/alpha".into(),
+            enrichment::Status::Pending,
+        ));
+        assert!(row_of(&screen(&state), "GitHub: enrichment pending").is_some());
+
+        let details = enrichment::Details {
+            stars: 12,
+            open_issues: 3,
+            archived: true,
+        };
+        state.update(Message::Enriched(
+            "/src/alpha".into(),
+            enrichment::Status::Done(details),
+        ));
+        assert!(row_of(&screen(&state), "GitHub: 12 stars, 3 open issues, archived").is_some());
     }
 
-    fn render_project_langs(&self, area: Rect, buf: &mut Buffer) {
-        let mut total_files = 0;
-        let mut total_lines = 0;
-        let mut total_code = 0;
-        let mut total_comments = 0;
-        let mut total_blanks = 0;
+    #[test]
+    fn archiving_asks_first() {
+        let mut state = state(&["/src/alpha", "/src/beta"]);
 
-        let rows: Vec<Row> = self
-            .projects_list
-            .state
-            .selected()
-            .map_or_else(Vec::new, |i| {
-                self.projects_list.items[i]
-                    .languages
-                    .iter()
-                    .map(|(ltype, l)| {
-                        total_files += l.files;
-                        total_lines += l.lines;
-                        total_code += l.code;
-                        total_comments += l.comments;
-                        total_blanks += l.blanks;
-
-                        Row::new(vec![
-                            LanguageType::list()
-                                .get(*ltype as usize)
-                                .map_or("Error".to_string(), ToString::to_string),
-                            l.files.to_string(),
-                            l.lines.to_string(),
-                            l.code.to_string(),
-                            l.comments.to_string(),
-                            l.blanks.to_string(),
-                        ])
-                    })
-                    .collect::<Vec<Row>>()
-            });
+        let effects = press(&mut state, chars("Xn"));
+        assert!(effects.is_empty());
+        let effects = press(&mut state, chars("Xy"));
+        let [Effect::Archive(project, _)] = &effects[..] else {
+            panic!("{effects:?}");
+        };
+        assert_eq!(project, Path::new("/src/alpha"));
 
-        let header = ["Language", "Files", "Lines", "Code", "Comments", "Blanks"]
-            .into_iter()
-            .map(Cell::from)
-            .collect::<Row>()
-            .height(1);
+        let tarball = PathBuf::from("/archive/alpha.tar.gz");
+        state.update(Message::Archived(project.clone(), tarball));
+        let paths: Vec<_> = state.items().iter().map(|v| v.path.clone()).collect();
+        assert_eq!(paths, [PathBuf::from("/src/beta")]);
+    }
 
-        let footer = [
-            "Total".to_string(),
-            total_files.to_string(),
-            total_lines.to_string(),
-            total_code.to_string(),
-            total_comments.to_string(),
-            total_blanks.to_string(),
-        ]
-        .into_iter()
-        .map(Cell::from)
-        .collect::<Row>()
-        .height(1);
-
-        let title = vec![
-            Span::from("["),
-            Span::styled("2", Style::default().fg(CYAN.c500)),
-            Span::from("] Languages"),
-        ];
+    #[test]
+    fn deleting_waits_for_the_scan() {
+        let mut state = state(&["/src/alpha", "/src/beta"]).with_scan_root("/src".into());
+        state.rescan();
 
-        let block = Block::new()
-            .title(Line::from(title).left_aligned())
-            .borders(Borders::ALL)
-            .border_set(symbols::border::ROUNDED)
-            .padding(Padding::horizontal(1));
+        for key in ["X", "D", "W"] {
+            assert!(press(&mut state, chars(key)).is_empty());
+            assert_eq!(
+                state.notice.as_deref(),
+                Some("Wait for the current scan to finish")
+            );
+        }
+        assert!(press(&mut state, chars("y")).is_empty());
+    }
 
-        Widget::render(
-            Table::new(
-                rows,
-                [
-                    Constraint::Percentage(25),
-                    Constraint::Percentage(15),
-                    Constraint::Percentage(15),
-                    Constraint::Percentage(15),
-                    Constraint::Percentage(15),
-                    Constraint::Percentage(15),
-                ],
-            )
-            .header(header)
-            .footer(footer)
-            .block(block),
-            area,
-            buf,
-        );
+    #[test]
+    fn quick_jump_selects_labeled_rows() {
+        let mut state = state(&["/src/alpha", "/src/beta", "/src/gamma"]);
+
+        press(&mut state, chars("s"));
+        let rows = screen(&state);
+        assert!(rows[row_of(&rows, "/src/alpha").unwrap()].starts_with("│aa"));
+        assert!(rows[row_of(&rows, "/src/gamma").unwrap()].starts_with("│ad"));
+
+        press(&mut state, chars("a"));
+        assert_eq!(state.jump.as_deref(), Some("a"));
+        press(&mut state, chars("d"));
+        assert_eq!(state.selected().unwrap().path, Path::new("/src/gamma"));
+        assert!(row_of(&screen(&state), "│ad").is_none());
+
+        // Anything that isn't a label cancels
+        press(&mut state, chars("sz"));
+        assert!(state.jump.is_none());
+        assert_eq!(state.selected().unwrap().path, Path::new("/src/gamma"));
     }
-}
 
-pub fn get_remote_username(project: &Project) -> String {
-    project
-        .git_info
-        .remote_url
-        .as_ref()
-        .map_or("", |v| v.split('/').nth(3).unwrap_or_default())
-        .to_string()
-}
+    #[test]
+    fn long_paths_keep_their_name() {
+        let path = "/src/clients/acme/internal/tools/deeply/nested/project-name";
+        let mut state = state(&[path]);
+        let row = row_of(&screen(&state), "/src/clients/").unwrap();
+        assert!(!screen(&state)[row].contains("/project-name"));
 
-struct ProjectsList {
-    items: Vec<Project>,
-    items_state: Vec<Project>,
-    state: ListState,
-}
+        let settings = Settings {
+            truncation: Truncation::Middle,
+            ..Settings::default()
+        };
+        state = state.with_settings(&settings);
+        assert!(screen(&state)[row].contains("/src/clients…/project-name│"));
+    }
 
-impl ProjectsList {
-    fn sort_projects(&mut self, sort_type: &Sorting, invert: bool) {
-        let mut items: Vec<Project> = self.items.clone();
+    #[test]
+    fn header_shows_free_space() {
+        let mut state = state(&["/src/alpha"]);
+        assert!(row_of(&screen(&state), " free").is_none());
 
-        match sort_type {
-            Sorting::Name => {
-                items.sort_by(|a, b| a.path.cmp(&b.path));
-            }
-            Sorting::Size => {
-                items.sort_by(|a, b| a.size.cmp(&b.size));
-            }
-            Sorting::Commits => {
-                items.sort_by(|a, b| a.git_info.commit_count.cmp(&b.git_info.commit_count));
-            }
-            Sorting::CreationDate => {
-                items.sort_by(|a, b| a.git_info.init_date.cmp(&b.git_info.init_date));
-            }
-            Sorting::ModificationDate => {
-                items.sort_by(|a, b| {
-                    a.git_info
-                        .last_commit_date
-                        .cmp(&b.git_info.last_commit_date)
-                });
-            }
-            Sorting::Loc => {
-                items.sort_by(|a, b| a.languages_total.lines.cmp(&b.languages_total.lines));
-            }
-        }
+        state.update(Message::FreeSpace(3 << 30));
+        assert_eq!(row_of(&screen(&state), "3.0G free"), Some(0));
+    }
 
-        if invert {
-            items.reverse();
-        }
+    #[test]
+    fn confirmation_follows_the_policy() {
+        let settings = Settings {
+            confirm: ConfirmPolicy::Always,
+            confirm_actions: HashMap::from([(ConfirmAction::Reveal, false)]),
+            ..Settings::default()
+        };
+        let mut state = state(&["/src/alpha"]).with_settings(&settings);
 
-        self.items = items;
-        self.state.select(Some(0));
+        let effects = press(&mut state, [KeyCode::Enter]);
+        assert!(effects.is_empty());
+        assert!(row_of(&screen(&state), "Open a shell in /src/alpha? y/n").is_some());
+        let effects = press(&mut state, chars("y"));
+        assert!(matches!(&effects[..], [Effect::OpenProject(_)]));
+        assert!(matches!(
+            &press(&mut state, chars("e"))[..],
+            [Effect::Reveal(_)]
+        ));
+
+        let settings = Settings {
+            confirm: ConfirmPolicy::Never,
+            ..Settings::default()
+        };
+        let mut state = state.with_settings(&settings);
+        assert!(matches!(
+            &press(&mut state, chars("X"))[..],
+            [Effect::Archive(..)]
+        ));
     }
 
-    fn filter_projects(&mut self, filter_type: &Filter, username: &str) {
-        let items = self.items_state.clone();
+    #[test]
+    fn undo_and_redo_views() {
+        let mut state = state(&["/src/alpha", "/src/beta"]);
+        let start = state.view();
 
-        let items = match filter_type {
-            Filter::All => items,
-            Filter::Owned => items
-                .into_iter()
-                .filter(|v| get_remote_username(v) == username)
-                .collect(),
-            Filter::NotOwned => items
-                .into_iter()
-                .filter(|v| get_remote_username(v) != username)
-                .collect(),
-            Filter::HasRemote => items
-                .into_iter()
-                .filter(|v| v.git_info.remote_url.is_some())
-                .collect(),
-            Filter::NoRemote => items
-                .into_iter()
-                .filter(|v| v.git_info.remote_url.is_none())
-                .collect(),
+        press(&mut state, chars("oli"));
+        let changed = state.view();
+        assert_eq!(changed.filter, Filter::Owned);
+        assert!(changed.invert);
+
+        press(&mut state, chars("UUU"));
+        assert_eq!(state.view(), start);
+        press(&mut state, chars("U"));
+        assert!(row_of(&screen(&state), "Nothing to undo").is_some());
+
+        press(&mut state, chars("RRR"));
+        assert_eq!(state.view(), changed);
+        // A new change drops what could be redone
+        press(&mut state, chars("UoR"));
+        assert!(row_of(&screen(&state), "Nothing to redo").is_some());
+    }
+
+    #[test]
+    fn palette_runs_the_chosen_action() {
+        let mut state = state(&["/src/alpha", "/src/beta"]);
+        let ctrl_p = KeyEvent::new(KeyCode::Char('p'), KeyModifiers::CONTROL);
+
+        state.update(Message::Key(ctrl_p));
+        press(&mut state, chars("sort size"));
+        let screen = screen(&state);
+        assert!(row_of(&screen, "> sort size").is_some());
+        assert!(row_of(&screen, "Sort by Size").is_some());
+        press(&mut state, [KeyCode::Enter]);
+        assert_eq!(state.sort_type, Sorting::Size);
+        assert!(state.palette.is_none());
+        // Undone like the key doing the same
+        press(&mut state, chars("U"));
+        assert_eq!(state.sort_type, Sorting::Name);
+
+        state.update(Message::Key(ctrl_p));
+        press(&mut state, chars("json"));
+        let effects = press(&mut state, [KeyCode::Enter]);
+        assert!(matches!(effects.as_slice(), [Effect::ShowJson(_)]));
+
+        state.update(Message::Key(ctrl_p));
+        press(&mut state, chars("xport bookm"));
+        press(&mut state, [KeyCode::Enter]);
+        assert_eq!(state.command.as_deref(), Some("export "));
+        press(&mut state, [KeyCode::Esc]);
+
+        state.update(Message::Key(ctrl_p));
+        press(&mut state, chars("q"));
+        press(&mut state, [KeyCode::Esc]);
+        assert!(state.palette.is_none());
+        assert!(!state.should_exit);
+    }
+
+    #[test]
+    fn panes_show_their_age_and_refresh_alone() {
+        let now = u64::try_from(chrono::Local::now().timestamp()).unwrap();
+        let mut state = AppState::new(vec![Project {
+            path: "/src/alpha".into(),
+            scanned_at: now - 3 * 24 * 60 * 60,
+            ..Default::default()
+        }]);
+        state.update(Message::Resize(Rect::new(0, 0, 100, 20)));
+        assert!(row_of(&screen(&state), "stale, 3 days ago").is_some());
+        state.settings.pane_stale_hours = 24 * 7;
+        let screen = screen(&state);
+        assert!(row_of(&screen, "3 days ago").is_some());
+        assert!(row_of(&screen, "stale").is_none());
+
+        let shift = |n| Message::Key(KeyEvent::new(KeyCode::F(n), KeyModifiers::SHIFT));
+        assert!(matches!(
+            state.update(shift(3)),
+            Some(Effect::Scan { roots, .. }) if roots == [PathBuf::from("/src/alpha")]
+        ));
+        assert!(state.update(shift(4)).is_none());
+        assert!(matches!(
+            state.update(Message::Key(KeyEvent::from(KeyCode::F(20)))),
+            Some(Effect::LoadOpenCounts)
+        ));
+        // Plain function keys still toggle
+        assert!(state.show_git);
+        press(&mut state, [KeyCode::F(3)]);
+        assert!(!state.show_git);
+    }
+
+    #[test]
+    fn export_shows_progress() {
+        let mut state = state(&["/src/alpha"]);
+
+        press(&mut state, chars("A"));
+        assert!(row_of(&screen(&state), ":archive ").is_some());
+        let effects = press(&mut state, [KeyCode::Enter]);
+        let [Effect::ExportArchive(project, destination)] = &effects[..] else {
+            panic!("{effects:?}");
         };
+        assert_eq!(project, Path::new("/src/alpha"));
+        assert!(destination.contains("alpha-") && destination.ends_with(".tar.zst"));
 
-        self.items = items;
-        if self.items.is_empty() {
-            self.state.select(None);
-        } else {
-            self.state.select(Some(0));
-        }
+        state.update(Message::ExportProgress(3, 8));
+        assert!(row_of(&screen(&state), "3/8 files").is_some());
+        state.update(Message::Exported(Ok(PathBuf::from(
+            "/archive/alpha.tar.zst",
+        ))));
+        assert!(row_of(&screen(&state), "Exported to /archive/alpha.tar.zst").is_some());
     }
 
-    fn search(&mut self, search_text: &str, index: usize) -> usize {
-        let filtered_indices: Vec<usize> = self
-            .items
-            .iter()
-            .enumerate()
-            .filter(|(_, p)| p.path.to_string_lossy().to_string().contains(search_text))
-            .map(|(idx, _)| idx)
-            .collect();
+    /// Snapshots a frame of the state, dates are in the local timezone so they're blanked out
+    macro_rules! assert_frame {
+        ($state:expr) => {
+            insta::with_settings!({
+                filters => vec![(r"\d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2}", "[date]             ")],
+            }, {
+                insta::assert_snapshot!(draw(&$state).backend());
+            });
+        };
+    }
 
-        if let Some(selected_idx) = filtered_indices.get(index) {
-            self.state.select(Some(*selected_idx));
-        } else {
-            self.state.select(None);
+    fn language(files: u32, code: u32) -> ProjectLanguage {
+        ProjectLanguage {
+            files,
+            lines: code + code / 4,
+            code,
+            comments: code / 10,
+            blanks: code / 4 - code / 10,
         }
+    }
 
-        filtered_indices.len()
+    /// A few projects filling every pane, nothing in them depends on the current time
+    fn fixture(width: u16, height: u16) -> AppState {
+        let id = |name| {
+            LanguageType::list()
+                .iter()
+                .position(|v| v.name() == name)
+                .and_then(|v| u8::try_from(v).ok())
+                .unwrap()
+        };
+        let ymir = Project {
+            path: "/src/ymir".into(),
+            size: 3 << 20,
+            languages: [
+                (id("Rust"), language(42, 9000)),
+                (id("TOML"), language(3, 80)),
+            ]
+            .into(),
+            languages_total: language(45, 9080),
+            ci: vec![CiProvider::GithubActions],
+            crates: vec![RustCrate {
+                name: "ymir".to_string(),
+                edition: Some("2021".to_string()),
+                lib: true,
+                bin: true,
+            }],
+            git_info: GitInfo {
+                remote_url: Some("https://github.com/ponurakk/ymir".to_string()),
+                last_commit_msg: Some("Add snapshot tests".to_string()),
+                commit_count: 420,
+                branch: Some("feature".to_string()),
+                default_branch: Some("main".to_string()),
+                ..GitInfo::default()
+            },
+            ..Project::default()
+        };
+        let web = Project {
+            path: "/src/web".into(),
+            size: 12 << 10,
+            languages: [(id("TypeScript"), language(8, 700))].into(),
+            languages_total: language(8, 700),
+            metadata: [("npm.name".to_string(), "web".to_string())].into(),
+            ..Project::default()
+        };
+        let empty = Project {
+            path: "/src/empty".into(),
+            git_info: GitInfo {
+                state: RepoState::Empty,
+                ..GitInfo::default()
+            },
+            ..Project::default()
+        };
+
+        let mut state = AppState::new(vec![ymir, web, empty]);
+        state.update(Message::Resize(Rect::new(0, 0, width, height)));
+        state
     }
-}
 
-impl FromIterator<Project> for ProjectsList {
-    fn from_iter<I: IntoIterator<Item = Project>>(iter: I) -> Self {
-        let state = ListState::default();
-        let items: Vec<Project> = iter.into_iter().collect();
-        Self {
-            items: items.clone(),
-            items_state: items,
-            state,
-        }
+    #[test]
+    fn snapshot_overview() {
+        let mut state = fixture(100, 30);
+        press(&mut state, chars("G"));
+        assert_frame!(state);
+    }
+
+    #[test]
+    fn snapshot_small_terminal() {
+        let mut state = fixture(40, 12);
+        press(&mut state, chars("G"));
+        assert_frame!(state);
+    }
+
+    #[test]
+    fn snapshot_panes_of_an_empty_repository() {
+        let state = fixture(100, 20);
+        assert_frame!(state);
+    }
+
+    #[test]
+    fn snapshot_search_bar() {
+        let mut state = fixture(80, 14);
+        press(&mut state, chars("/web"));
+        assert_frame!(state);
+    }
+
+    #[test]
+    fn snapshot_filter_picker() {
+        let mut state = fixture(80, 30);
+        press(&mut state, chars("fj"));
+        assert_frame!(state);
+    }
+
+    #[test]
+    fn snapshot_task_picker() {
+        let mut state = fixture(80, 14);
+        let task = |source, name: &str| Task {
+            source,
+            name: name.to_string(),
+            program: "true".to_string(),
+            args: Vec::new(),
+            pause: true,
+            input: None,
+        };
+        state.update(Message::TasksFound(
+            "/src/empty".into(),
+            vec![task("make", "build"), task("npm", "test")],
+        ));
+        assert_frame!(state);
+    }
+
+    #[test]
+    fn snapshot_no_color_spells_out_states() {
+        let settings = Settings {
+            theme: Theme::NoColor,
+            ..Settings::default()
+        };
+        let state = fixture(80, 10).with_settings(&settings);
+        assert_frame!(state);
     }
-}
 
-impl From<&Project> for ListItem<'_> {
-    fn from(value: &Project) -> Self {
-        let mut item = ListItem::new(value.path.display().to_string());
+    #[test]
+    fn chart_is_left_room_next_to_the_languages() {
+        let mut state = fixture(120, 30);
+        press(&mut state, chars("G"));
+        assert_eq!(state.language_chart(state.area), None);
 
-        if value.git_info.commit_count == 0 {
-            item = item.fg(INACTIVE_COLOR);
+        let state = state.with_graphics(Some(Protocol::Kitty));
+        let (area, slices) = state.language_chart(state.area).unwrap();
+        assert_eq!(slices.len(), 2);
+        assert!(slices[0].1 > slices[1].1);
+
+        let terminal = draw(&state);
+        let buffer = terminal.backend().buffer();
+        for y in area.top()..area.bottom() {
+            for x in area.left()..area.right() {
+                assert_eq!(buffer[(x, y)].symbol(), " ");
+            }
         }
+    }
+
+    #[test]
+    fn snapshot_most_used() {
+        let mut state = fixture(100, 20);
+        press(&mut state, [KeyCode::F(1), KeyCode::F(2), KeyCode::F(3)]);
+        let counts = OpenCounts {
+            counts: [
+                ("/src/ymir".into(), 42),
+                ("/src/web".into(), 7),
+                ("/src/empty".into(), 1),
+            ]
+            .into(),
+        };
+        state.update(Message::OpenCountsLoaded(counts));
+        assert_frame!(state);
+    }
+
+    #[test]
+    fn commit_search_jumps_to_the_project() {
+        let mut state = state(&["/src/alpha", "/src/beta"]);
+
+        press(&mut state, chars(":commits tls"));
+        let effects = press(&mut state, [KeyCode::Enter]);
+        let [Effect::SearchCommits(projects, _)] = &effects[..] else {
+            panic!("{effects:?}");
+        };
+        assert_eq!(projects.len(), 2);
+        assert!(row_of(&screen(&state), "Searching commits for tls").is_some());
+
+        let commit = |project: &str, id: &str, summary: &str| CommitMatch {
+            project: project.into(),
+            id: id.to_string(),
+            summary: summary.to_string(),
+            time: 0,
+        };
+        state.update(Message::CommitsFound(Ok(vec![
+            commit("/src/beta", "1a2b3c4", "Fix tls handshake"),
+            commit("/src/beta", "5d6e7f8", "Retry tls"),
+        ])));
+        let screen = screen(&state);
+        let row = row_of(&screen, "│beta 1a2b3c4 Fix tls").unwrap();
+        assert!(screen[row + 1].contains("     5d6e7f8 Retry tls"));
+
+        press(&mut state, [KeyCode::Down, KeyCode::Enter]);
+        assert_eq!(state.selected().unwrap().path, Path::new("/src/beta"));
+        assert!(state.commit_results.is_none());
+    }
+
+    #[test]
+    fn content_search_opens_the_editor() {
+        let mut state = state(&["/src/alpha", "/src/beta"]);
+
+        press(&mut state, chars("#todo"));
+        assert!(row_of(&screen(&state), ":grep todo").is_some());
+        let effects = press(&mut state, [KeyCode::Enter]);
+        let [Effect::SearchContents(projects, _)] = &effects[..] else {
+            panic!("{effects:?}");
+        };
+        assert_eq!(projects.len(), 2);
+        assert!(row_of(&screen(&state), "Searching files for todo").is_some());
+
+        let found = |path: &str, line, text: &str| ContentMatch {
+            project: "/src/beta".into(),
+            path: PathBuf::from("/src/beta").join(path),
+            line,
+            text: text.to_string(),
+        };
+        state.update(Message::ContentsFound(Ok(vec![
+            found("main.rs", 2, "// TODO: retry"),
+            found("lib.rs", 7, "// todo"),
+        ])));
+        let screen = screen(&state);
+        let row = row_of(&screen, "│beta main.rs:2 // TODO").unwrap();
+        assert!(screen[row + 1].contains("     lib.rs:7 // todo"));
+
+        let effects = press(&mut state, [KeyCode::Down, KeyCode::Enter]);
+        let [Effect::RunTask(task, dir)] = &effects[..] else {
+            panic!("{effects:?}");
+        };
+        assert_eq!(task.name, "editor");
+        assert!(task
+            .args
+            .ends_with(&["+7".to_string(), "/src/beta/lib.rs".to_string()]));
+        assert_eq!(dir, Path::new("/src/beta"));
+        assert_eq!(state.selected().unwrap().path, Path::new("/src/beta"));
+    }
+
+    #[test]
+    fn recent_files_pane() {
+        let mut state = fixture(100, 20);
+        let effects = press(&mut state, [KeyCode::F(9)]);
+        assert!(matches!(&effects[..], [Effect::LoadRecentFiles]));
+
+        let modified = u64::try_from(chrono::Local::now().timestamp()).unwrap() - 2 * 60 * 60;
+        let file = |path: &str| RecentFile {
+            path: PathBuf::from("/src/ymir").join(path),
+            project: "/src/ymir".into(),
+            modified,
+        };
+        let files = vec![file("src/app.rs"), file("Cargo.toml")];
+        state.update(Message::RecentFilesLoaded(RecentFiles { files }));
+        let screen = screen(&state);
+        let row = row_of(&screen, "2 hours ago ymir: src/app.rs").unwrap();
+        assert_eq!(row_of(&screen, "ymir: Cargo.toml"), Some(row + 1));
+    }
+
+    #[test]
+    fn snapshot_empty_screen() {
+        let mut state = AppState::new(Vec::new())
+            .with_scan_root("/src".into())
+            .with_load_error(Some("Failed to read cache".to_string()));
+        state.update(Message::Resize(Rect::new(0, 0, 80, 16)));
+        assert_frame!(state);
+    }
+
+    #[test]
+    fn empty_screen_scans_again_with_r() {
+        // With projects listed only Ctrl-r scans
+        let mut listed = state(&["/src/alpha"]).with_scan_root("/src".into());
+        assert!(press(&mut listed, chars("r")).is_empty());
+
+        let mut state = AppState::new(Vec::new())
+            .with_scan_root("/src".into())
+            .with_load_error(Some("Failed to read cache".to_string()));
+        state.update(Message::Resize(Rect::new(0, 0, 80, 16)));
+        assert!(row_of(
+            &screen(&state),
+            "Failed to load projects: Failed to read cache"
+        )
+        .is_some());
+
+        let effects = press(&mut state, chars("r"));
+        assert!(
+            matches!(&effects[..], [Effect::Scan { roots, .. }] if roots == &[PathBuf::from("/src")])
+        );
+        let screen = screen(&state);
+        assert!(row_of(&screen, "Looking for projects in /src").is_some());
+        assert!(row_of(&screen, "Failed to load").is_none());
+        // One scan at a time
+        assert!(press(&mut state, chars("r")).is_empty());
+    }
 
-        item
+    #[test]
+    fn notices_dont_read_as_load_errors() {
+        let mut state = AppState::new(Vec::new()).with_scan_root("/src".into());
+        state.update(Message::Resize(Rect::new(0, 0, 80, 16)));
+        state.update(Message::Notice("Deleted /src/ymir-copy".to_string()));
+        let screen = screen(&state);
+        assert!(row_of(&screen, "No projects found in /src").is_some());
+        assert!(row_of(&screen, "Deleted /src/ymir-copy").is_some());
+        assert!(row_of(&screen, "Failed to load projects").is_none());
     }
 }
@@ -0,0 +1,338 @@
+//! Archive candidates, listed by the Archive Candidates filter
+//!
+//! A candidate hasn't been committed to for `stale_days`, has no remote to recover it from and
+//! is no larger than `archive_max_size`. Archiving tars the whole directory, `.git` and
+//! uncommitted changes included, into `archive_dir` and deletes the working copy once `tar`
+//! succeeded.
+//!
+//! Exporting with `A` keeps the project, it writes a `.tar.zst` or `.zip` of it without what
+//! `ignore_dirs` names and, unless `export_git` is set, without `.git`.
+
+use std::{
+    fs,
+    io::{BufRead, BufReader, Write},
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    thread,
+};
+
+use anyhow::{bail, Context};
+use walkdir::WalkDir;
+
+use crate::{config::Settings, projects::Project};
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// What makes a project an archive candidate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Candidates {
+    pub stale_days: u32,
+    /// Largest size in bytes, any size when unset
+    pub max_size: Option<u64>,
+    /// Unix timestamp staleness is measured from
+    pub now: i64,
+}
+
+impl Candidates {
+    pub fn new(settings: &Settings, now: i64) -> Self {
+        Self {
+            stale_days: settings.stale_days,
+            max_size: settings.archive_max_size(),
+            now,
+        }
+    }
+
+    /// Projects without commits are as stale as it gets
+    pub fn matches(&self, project: &Project) -> bool {
+        let info = &project.git_info;
+        let stale = !info.state.has_commits()
+            || (self.now - i64::from(info.last_commit_date)) / SECONDS_PER_DAY
+                >= i64::from(self.stale_days);
+
+        stale && info.remote_url.is_none() && self.max_size.is_none_or(|v| project.size <= v)
+    }
+}
+
+/// Tars `project` into `dir` as `<name>-<date>.tar.gz` and deletes it, returning the tarball
+pub fn archive(project: &Path, dir: &Path, date: &str) -> anyhow::Result<PathBuf> {
+    let (Some(parent), Some(name)) = (project.parent(), project.file_name()) else {
+        bail!("{} has no name", project.display());
+    };
+    let mut file_name = name.to_os_string();
+    file_name.push(format!("-{date}.tar.gz"));
+    let tarball = dir.join(file_name);
+    if tarball.exists() {
+        bail!("{} already exists", tarball.display());
+    }
+
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let status = Command::new("tar")
+        .arg("-czf")
+        .arg(&tarball)
+        .arg("-C")
+        .arg(parent)
+        // A project named like an option is still a name
+        .arg("--")
+        .arg(name)
+        .status()
+        .context("Failed to run tar")?;
+    if !status.success() {
+        let _ = fs::remove_file(&tarball);
+        bail!("tar exited with {status}");
+    }
+
+    fs::remove_dir_all(project)
+        .with_context(|| format!("Failed to delete {}", project.display()))?;
+    Ok(tarball)
+}
+
+/// Kind of file [`export`] writes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    TarZst,
+    Zip,
+}
+
+impl Format {
+    /// Format going by the extension of `destination`, `.tar.zst` for anything but `.zip`
+    pub fn of(destination: &Path) -> Self {
+        match destination.extension() {
+            Some(v) if v.eq_ignore_ascii_case("zip") => Self::Zip,
+            _ => Self::TarZst,
+        }
+    }
+}
+
+/// Files of `project` relative to it, leaving out `ignore_dirs` and `.git` unless `git` is set
+pub fn files(project: &Path, ignore_dirs: &[String], git: bool) -> Vec<PathBuf> {
+    WalkDir::new(project)
+        .min_depth(1)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| {
+            let name = e.file_name().to_string_lossy();
+            !e.file_type().is_dir()
+                || !(ignore_dirs.iter().any(|v| *v == name) || (!git && name == ".git"))
+        })
+        .filter_map(|e| {
+            e.inspect_err(|err| warn!("Failed to read {err}"))
+                .ok()
+                .filter(|e| !e.file_type().is_dir())
+        })
+        .filter_map(|e| e.path().strip_prefix(project).ok().map(Path::to_path_buf))
+        .collect()
+}
+
+/// Writes `files` of `project` to `destination` with `tar` or `zip`, calling `progress` with how
+/// many are written and how many there are as they're added
+pub fn export(
+    project: &Path,
+    destination: &Path,
+    files: Vec<PathBuf>,
+    mut progress: impl FnMut(usize, usize),
+) -> anyhow::Result<()> {
+    if destination.exists() {
+        bail!("{} already exists", destination.display());
+    }
+    let format = Format::of(destination);
+    // zip reads one name per line, tar gets them separated by NUL
+    if format == Format::Zip && files.iter().any(|v| v.to_string_lossy().contains('\n')) {
+        bail!("{} has file names with line breaks", project.display());
+    }
+    if let Some(parent) = destination.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+
+    // Both list what they add on stdout, one file per line
+    let mut command = match format {
+        Format::TarZst => {
+            let mut command = Command::new("tar");
+            command
+                .args(["--zstd", "-cvf"])
+                .arg(destination)
+                // Names starting with `-` would be read as options otherwise, like `-C..`
+                .args(["--verbatim-files-from", "--null", "-T", "-"]);
+            command
+        }
+        Format::Zip => {
+            let mut command = Command::new("zip");
+            command.arg(destination).arg("-@");
+            command
+        }
+    };
+    let program = command.get_program().to_string_lossy().into_owned();
+    let mut child = command
+        .current_dir(project)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("Failed to run {program}"))?;
+
+    let total = files.len();
+    let mut stdin = child.stdin.take().context("Failed to open stdin")?;
+    // Written from another thread so a full stdout pipe can't block both sides
+    let writer = thread::spawn(move || {
+        for file in files {
+            match format {
+                Format::TarZst => {
+                    stdin.write_all(file.as_os_str().as_bytes())?;
+                    stdin.write_all(b"\0")?;
+                }
+                Format::Zip => writeln!(stdin, "{}", file.display())?,
+            }
+        }
+        Ok::<_, std::io::Error>(())
+    });
+
+    let stdout = child.stdout.take().context("Failed to open stdout")?;
+    for (done, _) in BufReader::new(stdout)
+        .lines()
+        .map_while(Result::ok)
+        .enumerate()
+    {
+        progress((done + 1).min(total), total);
+    }
+
+    let written = writer
+        .join()
+        .map_err(|_| anyhow::anyhow!("{program} input panicked"))?;
+    let status = child.wait()?;
+    if let Err(err) = written.context("Failed to list files").and_then(|()| {
+        if status.success() {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!("{program} exited with {status}"))
+        }
+    }) {
+        let _ = fs::remove_file(destination);
+        return Err(err);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf, process::Command};
+
+    use super::{archive, export, files, Candidates, Format, SECONDS_PER_DAY};
+    use crate::{projects::Project, test_utils::temp_dir, utils::RepoState};
+
+    #[test]
+    fn candidates_are_stale_local_and_small() {
+        let candidates = Candidates {
+            stale_days: 30,
+            max_size: Some(1000),
+            now: 100 * SECONDS_PER_DAY,
+        };
+        let mut project = Project {
+            size: 1000,
+            ..Project::default()
+        };
+        project.git_info.state = RepoState::Ok;
+        project.git_info.last_commit_date = u32::try_from(60 * SECONDS_PER_DAY).unwrap();
+        assert!(candidates.matches(&project));
+
+        let mut recent = project.clone();
+        recent.git_info.last_commit_date = u32::try_from(80 * SECONDS_PER_DAY).unwrap();
+        assert!(!candidates.matches(&recent));
+
+        let mut pushed = project.clone();
+        pushed.git_info.remote_url = Some("https://github.com/ponurakk/ymir".to_string());
+        assert!(!candidates.matches(&pushed));
+
+        let mut large = project;
+        large.size = 1001;
+        assert!(!candidates.matches(&large));
+        assert!(Candidates {
+            max_size: None,
+            ..candidates
+        }
+        .matches(&large));
+    }
+
+    #[test]
+    fn archiving_replaces_the_project_with_a_tarball() {
+        let dir = temp_dir("archive");
+        let project = dir.join("src").join("old");
+        fs::create_dir_all(&project).unwrap();
+        fs::write(project.join("main.rs"), "fn main() {}").unwrap();
+
+        let tarball = archive(&project, &dir.join("archive"), "2024-01-31").unwrap();
+        assert_eq!(tarball, dir.join("archive").join("old-2024-01-31.tar.gz"));
+        assert!(tarball.is_file());
+        assert!(!project.exists());
+
+        // The same name on the same day would overwrite it
+        fs::create_dir_all(&project).unwrap();
+        assert!(archive(&project, &dir.join("archive"), "2024-01-31").is_err());
+        assert!(project.exists());
+    }
+
+    /// Whether `program` runs with `args`, the tests using it are skipped without it
+    fn available(program: &str, args: &[&str]) -> bool {
+        let found = Command::new(program)
+            .args(args)
+            .output()
+            .is_ok_and(|v| v.status.success());
+        if !found {
+            eprintln!("Skipped, {program} {args:?} isn't available");
+        }
+        found
+    }
+
+    #[test]
+    fn export_leaves_out_ignored_dirs() {
+        if !available("tar", &["--zstd", "--version"]) || !available("zstd", &["--version"]) {
+            return;
+        }
+        let dir = temp_dir("export");
+        let project = dir.join("web");
+        for path in ["src", "node_modules/left-pad", ".git"] {
+            fs::create_dir_all(project.join(path)).unwrap();
+        }
+        for path in [
+            "src/main.ts",
+            "-C..",
+            "node_modules/left-pad/index.js",
+            ".git/HEAD",
+        ] {
+            fs::write(project.join(path), "").unwrap();
+        }
+
+        let ignore_dirs = ["node_modules".to_string()];
+        assert_eq!(
+            files(&project, &ignore_dirs, false),
+            ["-C..", "src/main.ts"].map(PathBuf::from)
+        );
+        assert_eq!(
+            files(&project, &ignore_dirs, true),
+            ["-C..", ".git/HEAD", "src/main.ts"].map(PathBuf::from)
+        );
+
+        assert_eq!(Format::of(&dir.join("web.ZIP")), Format::Zip);
+        let destination = dir.join("out").join("web.tar.zst");
+        assert_eq!(Format::of(&destination), Format::TarZst);
+        let mut seen = Vec::new();
+        export(
+            &project,
+            &destination,
+            files(&project, &ignore_dirs, true),
+            |done, total| seen.push((done, total)),
+        )
+        .unwrap();
+        assert_eq!(seen, [(1, 3), (2, 3), (3, 3)]);
+        let listed = Command::new("tar")
+            .args(["--zstd", "-tf"])
+            .arg(&destination)
+            .output()
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(listed.stdout).unwrap(),
+            "-C..\n.git/HEAD\nsrc/main.ts\n"
+        );
+        assert!(export(&project, &destination, Vec::new(), |_, _| {}).is_err());
+    }
+}
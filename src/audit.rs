@@ -0,0 +1,109 @@
+//! What ymir did to projects, like opening, deleting and archiving them
+//!
+//! Every action is kept for the session, `L` shows them, and appended to `audit.log` next to the
+//! history as a line with the local time and what happened.
+
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::config;
+
+#[derive(Debug, Default)]
+pub struct Audit {
+    /// Lines of this session, oldest first
+    session: Vec<String>,
+    /// Where lines are appended, nowhere without a config directory
+    file: Option<PathBuf>,
+}
+
+impl Audit {
+    pub fn path() -> Option<PathBuf> {
+        config::app_dir().map(|dir| dir.join("audit.log"))
+    }
+
+    pub fn new() -> Self {
+        Self {
+            session: Vec::new(),
+            file: Self::path(),
+        }
+    }
+
+    /// Records `action`, like `Opened /src/ymir`, failing to write the file is only logged
+    pub fn record(&mut self, action: &str) {
+        let line = format!(
+            "{} {action}",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S")
+        );
+        if let Some(file) = &self.file {
+            if let Err(err) = append(file, &line) {
+                error!("Failed to write {}: {err}", file.display());
+            }
+        }
+        self.session.push(line);
+    }
+
+    /// Everything done this session, for the pager
+    pub fn report(&self) -> String {
+        if self.session.is_empty() {
+            return "Nothing done this session\n".to_string();
+        }
+        let mut report = self.session.join("\n");
+        report.push('\n');
+        if let Some(file) = &self.file {
+            report.push_str(&format!("\nEarlier sessions are in {}\n", file.display()));
+        }
+        report
+    }
+}
+
+fn append(file: &Path, line: &str) -> std::io::Result<()> {
+    if let Some(dir) = file.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(file)?;
+    writeln!(file, "{line}")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::Audit;
+    use crate::test_utils::temp_dir;
+
+    #[test]
+    fn appends_to_the_file_and_the_session() {
+        let dir = temp_dir("audit");
+        let file = dir.join("audit.log");
+        fs::write(&file, "2020-01-01 00:00:00 Opened /src/old\n").unwrap();
+
+        let mut audit = Audit {
+            session: Vec::new(),
+            file: Some(file.clone()),
+        };
+        assert!(audit.report().starts_with("Nothing done"));
+        audit.record("Opened /src/alpha");
+        audit.record("Deleted /src/beta");
+
+        let lines: Vec<String> = fs::read_to_string(&file)
+            .unwrap()
+            .lines()
+            // After the date and time
+            .map(|v| v.splitn(3, ' ').nth(2).unwrap().to_string())
+            .collect();
+        assert_eq!(
+            lines,
+            ["Opened /src/old", "Opened /src/alpha", "Deleted /src/beta"]
+        );
+        let report = audit.report();
+        assert_eq!(report.lines().count(), 4);
+        assert!(report
+            .lines()
+            .nth(1)
+            .unwrap()
+            .ends_with(" Deleted /src/beta"));
+    }
+}
@@ -1,30 +1,45 @@
 use std::{
-    collections::HashMap,
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
     io::{Cursor, Read},
     path::PathBuf,
+    time::{Duration, Instant},
 };
 
-use anyhow::{bail, Context};
-
 use crate::{
+    ci::CiProvider,
     config::Cache,
+    devenv::DevEnv,
+    error::{Result, YmirError},
     huffman::{huffman_decode, huffman_encode},
+    lfs::Lfs,
+    manifest::{ProjectMetadata, RustCrate},
     projects::{Project, ProjectLanguage},
-    utils::GitInfo,
+    signature::{CommitSignature, SignatureKind},
+    submodules::Submodules,
+    utils::{format_bytes, GitInfo, GitRemote, Ownership, RepoState},
 };
 
 const MAGIC: &[u8; 4] = b"YMIR";
-const VERSION: u8 = 4;
+const VERSION: u8 = 23;
+
+/// Longest project path accepted from a cache file, matches `PATH_MAX` on linux
+const MAX_PATH_LEN: usize = 4096;
+/// Smallest number of bytes a serialized project can take
+const MIN_PROJECT_LEN: usize = 2 + 8 + 8 + 1 + 28 + 2 + 20 + 2 + 20 + 2 + 2 + 2 + 2 + 8 + 1;
+/// Language maps are keyed by `u8`, so there can't be more distinct entries than this
+const MAX_LANGUAGES: usize = u8::MAX as usize + 1;
 
 pub trait CacheSerializer {
-    fn serialize(&self) -> anyhow::Result<Vec<u8>>;
-    fn deserialize(cursor: &mut Cursor<&[u8]>) -> anyhow::Result<Self>
+    fn serialize(&self) -> Result<Vec<u8>>;
+    fn deserialize(cursor: &mut Cursor<&[u8]>) -> Result<Self>
     where
         Self: Sized;
 }
 
 impl CacheSerializer for Cache {
-    fn serialize(&self) -> anyhow::Result<Vec<u8>> {
+    fn serialize(&self) -> Result<Vec<u8>> {
         let mut buffer: Vec<u8> = Vec::new();
         // Projects len
         buffer.extend_from_slice(&u16::try_from(self.projects.len())?.to_le_bytes());
@@ -33,53 +48,156 @@ impl CacheSerializer for Cache {
             buffer.extend_from_slice(&project.serialize()?);
         }
 
-        // Huffman encoding
         let mut new_buffer: Vec<u8> = Vec::new();
         new_buffer.extend_from_slice(MAGIC);
         new_buffer.push(VERSION);
-        new_buffer.extend_from_slice(&huffman_encode(&buffer));
+        new_buffer.push(u8::from(self.compressed));
+
+        // Huffman encoding
+        if self.compressed {
+            new_buffer.extend_from_slice(&huffman_encode(&buffer));
+        } else {
+            new_buffer.extend_from_slice(&buffer);
+        }
 
         Ok(new_buffer)
     }
 
-    fn deserialize(cursor: &mut Cursor<&[u8]>) -> anyhow::Result<Self> {
-        let mut magic = [0u8; 4];
-        cursor
-            .read_exact(&mut magic)
-            .with_context(|| "Failed to read magic")?;
-        if &magic != MAGIC {
-            bail!("Invalid magic value");
-        }
-
-        let mut version = [0u8; 1];
-        cursor
-            .read_exact(&mut version)
-            .with_context(|| "Failed to read version")?;
-        if version[0] != VERSION {
-            bail!("Invalid version. Found: {}, current {VERSION}", version[0]);
-        }
+    fn deserialize(cursor: &mut Cursor<&[u8]>) -> Result<Self> {
+        let compressed = read_header(cursor)?;
 
         // Huffman decoding
-        let buffer =
-            huffman_decode(&cursor.clone().into_inner()[usize::try_from(cursor.position())?..])?;
-        let mut cursor = std::io::Cursor::new(buffer.as_slice());
+        let position = usize::try_from(cursor.position())?;
+        let payload = &cursor.get_ref()[position..];
+        let buffer = if compressed {
+            Cow::Owned(huffman_decode(payload)?)
+        } else {
+            Cow::Borrowed(payload)
+        };
+        let mut cursor = std::io::Cursor::new(buffer.as_ref());
 
-        let projects_len = cursor
-            .read_u16()
-            .with_context(|| "Failed to read projects_len")? as usize;
+        let projects_len = cursor.read_u16().corrupt("Failed to read projects_len")? as usize;
 
-        let mut projects: Vec<Project> = Vec::new();
+        if projects_len * MIN_PROJECT_LEN > cursor.remaining() {
+            return Err(YmirError::CorruptCache(format!(
+                "Project count {projects_len} doesn't fit in remaining {} bytes",
+                cursor.remaining()
+            )));
+        }
+
+        let mut projects: Vec<Project> = Vec::with_capacity(projects_len);
 
         for _ in 0..projects_len {
             projects.push(Project::deserialize(&mut cursor)?);
         }
 
-        Ok(Self { projects })
+        Ok(Self {
+            projects,
+            compressed,
+        })
+    }
+}
+
+/// Reads magic and version, returns whether the payload is huffman encoded
+fn read_header(cursor: &mut Cursor<&[u8]>) -> Result<bool> {
+    let mut magic = [0u8; 4];
+    cursor
+        .read_exact(&mut magic)
+        .corrupt("Failed to read magic")?;
+    if &magic != MAGIC {
+        return Err(YmirError::CorruptCache("Invalid magic value".to_string()));
+    }
+
+    let mut version = [0u8; 1];
+    cursor
+        .read_exact(&mut version)
+        .corrupt("Failed to read version")?;
+    if version[0] != VERSION {
+        return Err(YmirError::CacheVersion {
+            found: version[0],
+            expected: VERSION,
+        });
+    }
+
+    match cursor
+        .read_u8()
+        .corrupt("Failed to read compression flag")?
+    {
+        0 => Ok(false),
+        1 => Ok(true),
+        flag => Err(YmirError::CorruptCache(format!(
+            "Invalid compression flag {flag}"
+        ))),
+    }
+}
+
+/// Size and timing figures of a cache file
+pub struct CacheStats {
+    pub projects: usize,
+    pub file_size: usize,
+    pub compressed: bool,
+    pub raw_size: usize,
+    pub compressed_size: usize,
+    pub encode_time: Duration,
+    pub decode_time: Duration,
+}
+
+impl CacheStats {
+    /// Inspects a cache file, running huffman both ways to time it
+    pub fn new(bytes: &[u8]) -> Result<Self> {
+        let mut cursor = Cursor::new(bytes);
+        let compressed = read_header(&mut cursor)?;
+        let payload = &bytes[usize::try_from(cursor.position())?..];
+
+        let start = Instant::now();
+        let raw = if compressed {
+            huffman_decode(payload)?
+        } else {
+            payload.to_vec()
+        };
+        let decode_time = start.elapsed();
+
+        let start = Instant::now();
+        let encoded = huffman_encode(&raw);
+        let encode_time = start.elapsed();
+
+        let projects = Cursor::new(raw.as_slice())
+            .read_u16()
+            .corrupt("Failed to read projects_len")? as usize;
+
+        Ok(Self {
+            projects,
+            file_size: bytes.len(),
+            compressed,
+            raw_size: raw.len(),
+            compressed_size: encoded.len(),
+            encode_time,
+            decode_time,
+        })
+    }
+}
+
+impl Display for CacheStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        #[allow(clippy::cast_precision_loss)]
+        let ratio = self.compressed_size as f64 / self.raw_size.max(1) as f64 * 100.0;
+
+        write!(
+            f,
+            "Projects: {}\nFile size: {}\nCompressed: {}\nRaw size: {}\nCompressed size: {} ({ratio:.1}%)\nCompression time: {:.2?}\nDecompression time: {:.2?}",
+            self.projects,
+            format_bytes(self.file_size as u64),
+            if self.compressed { "yes" } else { "no" },
+            format_bytes(self.raw_size as u64),
+            format_bytes(self.compressed_size as u64),
+            self.encode_time,
+            self.decode_time,
+        )
     }
 }
 
 impl CacheSerializer for Project {
-    fn serialize(&self) -> anyhow::Result<Vec<u8>> {
+    fn serialize(&self) -> Result<Vec<u8>> {
         let mut buffer: Vec<u8> = Vec::new();
 
         let path = self.path.to_string_lossy();
@@ -87,43 +205,103 @@ impl CacheSerializer for Project {
         buffer.extend_from_slice(path.to_string().as_bytes());
 
         buffer.extend_from_slice(&self.size.to_le_bytes());
+        buffer.extend_from_slice(&self.scanned_at.to_le_bytes());
+        buffer.extend_from_slice(&self.head.clone().unwrap_or_default().serialize()?);
+        buffer.push(u8::from(self.deferred));
 
         buffer.extend_from_slice(&GitInfo::serialize(&self.git_info)?);
 
         buffer.extend_from_slice(&self.languages.serialize()?);
         buffer.extend_from_slice(&ProjectLanguage::serialize(&self.languages_total)?);
+        buffer.extend_from_slice(&self.ci.serialize()?);
+        buffer.extend_from_slice(&ProjectLanguage::serialize(&self.generated)?);
+        buffer.extend_from_slice(&self.crates.serialize()?);
+        buffer.extend_from_slice(&self.metadata.serialize()?);
+        buffer.extend_from_slice(&self.dev_envs.serialize()?);
+        buffer.extend_from_slice(&self.scan_errors.serialize()?);
+        buffer.extend_from_slice(&self.ownership.serialize()?);
+        buffer.push(u8::from(self.partial));
+        buffer.extend_from_slice(&self.lfs.serialize()?);
+        buffer.extend_from_slice(&self.submodules.serialize()?);
 
         Ok(buffer)
     }
 
-    fn deserialize(cursor: &mut Cursor<&[u8]>) -> anyhow::Result<Self> {
-        let path_len = cursor
-            .read_u16()
-            .with_context(|| "Failed to read path len")? as usize;
+    fn deserialize(cursor: &mut Cursor<&[u8]>) -> Result<Self> {
+        let path_len = cursor.read_u16().corrupt("Failed to read path len")? as usize;
+
+        if path_len > MAX_PATH_LEN {
+            return Err(YmirError::CorruptCache(format!(
+                "Path length {path_len} exceeds limit of {MAX_PATH_LEN}"
+            )));
+        }
 
         let path = cursor
             .read_string(path_len)
-            .with_context(|| "Failed to read path")?;
+            .corrupt("Failed to read path")?;
         let path = PathBuf::from(path);
 
-        let size = cursor.read_u64().with_context(|| "Failed to read size")?;
+        let size = cursor.read_u64().corrupt("Failed to read size")?;
+        let scanned_at = cursor.read_u64().corrupt("Failed to read scanned_at")?;
+        let head = String::deserialize(cursor)?;
+        let head = (!head.is_empty()).then_some(head);
+        let deferred = match cursor.read_u8().corrupt("Failed to read deferred")? {
+            0 => false,
+            1 => true,
+            flag => {
+                return Err(YmirError::CorruptCache(format!(
+                    "Invalid deferred flag {flag}"
+                )))
+            }
+        };
 
         let git_info = GitInfo::deserialize(cursor)?;
         let languages: HashMap<u8, ProjectLanguage> = HashMap::deserialize(cursor)?;
         let languages_total = ProjectLanguage::deserialize(cursor)?;
+        let ci = Vec::<CiProvider>::deserialize(cursor)?;
+        let generated = ProjectLanguage::deserialize(cursor)?;
+        let crates = Vec::<RustCrate>::deserialize(cursor)?;
+        let metadata = ProjectMetadata::deserialize(cursor)?;
+        let dev_envs = Vec::<DevEnv>::deserialize(cursor)?;
+        let scan_errors = Vec::<String>::deserialize(cursor)?;
+        let ownership = Ownership::deserialize(cursor)?;
+        let partial = match cursor.read_u8().corrupt("Failed to read partial")? {
+            0 => false,
+            1 => true,
+            flag => {
+                return Err(YmirError::CorruptCache(format!(
+                    "Invalid partial flag {flag}"
+                )))
+            }
+        };
+        let lfs = Lfs::deserialize(cursor)?;
+        let submodules = Submodules::deserialize(cursor)?;
 
         Ok(Self {
             path,
             size,
+            scanned_at,
+            head,
+            deferred,
             git_info,
             languages,
             languages_total,
+            ci,
+            generated,
+            crates,
+            metadata,
+            dev_envs,
+            scan_errors,
+            ownership,
+            partial,
+            lfs,
+            submodules,
         })
     }
 }
 
 impl CacheSerializer for GitInfo {
-    fn serialize(&self) -> anyhow::Result<Vec<u8>> {
+    fn serialize(&self) -> Result<Vec<u8>> {
         let mut buffer: Vec<u8> = Vec::new();
 
         if let Some(remote_url) = &self.remote_url {
@@ -133,6 +311,8 @@ impl CacheSerializer for GitInfo {
             buffer.extend_from_slice(&0_u16.to_le_bytes());
         }
 
+        buffer.extend_from_slice(&self.remotes.serialize()?);
+
         buffer.extend_from_slice(&self.init_date.to_le_bytes());
         buffer.extend_from_slice(&self.last_commit_date.to_le_bytes());
 
@@ -144,61 +324,283 @@ impl CacheSerializer for GitInfo {
         }
 
         buffer.extend_from_slice(&self.commit_count.to_le_bytes());
+        buffer.push(self.state as u8);
+
+        for branch in [&self.branch, &self.default_branch] {
+            let branch = branch.as_deref().unwrap_or_default();
+            buffer.extend_from_slice(&u16::try_from(branch.len())?.to_le_bytes());
+            buffer.extend_from_slice(branch.as_bytes());
+        }
+
+        if let Some(hooks_path) = &self.hooks_path {
+            buffer.extend_from_slice(&u16::try_from(hooks_path.len())?.to_le_bytes());
+            buffer.extend_from_slice(hooks_path.as_bytes());
+        } else {
+            buffer.extend_from_slice(&0_u16.to_le_bytes());
+        }
+
+        buffer.extend_from_slice(&self.hooks.serialize()?);
+
+        // Kind 0 marks an unsigned commit
+        if let Some(signature) = &self.signature {
+            buffer.push(signature.kind as u8);
+            let key = signature.key.as_deref().unwrap_or_default();
+            buffer.extend_from_slice(&u16::try_from(key.len())?.to_le_bytes());
+            buffer.extend_from_slice(key.as_bytes());
+        } else {
+            buffer.push(0);
+        }
 
         Ok(buffer)
     }
 
-    fn deserialize(cursor: &mut Cursor<&[u8]>) -> anyhow::Result<Self> {
-        let remote_url_len = cursor
-            .read_u16()
-            .with_context(|| "Failed to read remote url len")?;
+    fn deserialize(cursor: &mut Cursor<&[u8]>) -> Result<Self> {
+        let remote_url_len = cursor.read_u16().corrupt("Failed to read remote url len")?;
 
         let remote_url = if remote_url_len > 0 {
             cursor
                 .read_string(remote_url_len as usize)
-                .with_context(|| "Failed to read remote url")
+                .corrupt("Failed to read remote url")
                 .ok()
         } else {
             None
         };
 
-        let init_date = cursor
-            .read_u32()
-            .with_context(|| "Failed to read init date")?;
+        let remotes = Vec::<GitRemote>::deserialize(cursor)?;
+
+        let init_date = cursor.read_u32().corrupt("Failed to read init date")?;
 
         let last_commit_date = cursor
             .read_u32()
-            .with_context(|| "Failed to read last commit date")?;
+            .corrupt("Failed to read last commit date")?;
 
         let last_commit_msg_len = cursor
             .read_u16()
-            .with_context(|| "Failed to read last commit msg len")?;
+            .corrupt("Failed to read last commit msg len")?;
 
         let last_commit_msg = if last_commit_msg_len > 0 {
             cursor
                 .read_string(last_commit_msg_len as usize)
-                .with_context(|| "Failed to read last commit msg")
+                .corrupt("Failed to read last commit msg")
                 .ok()
         } else {
             None
         };
 
-        let commit_count = cursor
-            .read_u32()
-            .with_context(|| "Failed to read commit count")?;
+        let commit_count = cursor.read_u32().corrupt("Failed to read commit count")?;
+
+        let state = RepoState::try_from(cursor.read_u8().corrupt("Failed to read repo state")?)?;
+
+        let branch = String::deserialize(cursor)?;
+        let branch = (!branch.is_empty()).then_some(branch);
+        let default_branch = String::deserialize(cursor)?;
+        let default_branch = (!default_branch.is_empty()).then_some(default_branch);
+
+        let hooks_path_len = cursor.read_u16().corrupt("Failed to read hooks path len")?;
+
+        let hooks_path = if hooks_path_len > 0 {
+            Some(
+                cursor
+                    .read_string(hooks_path_len as usize)
+                    .corrupt("Failed to read hooks path")?,
+            )
+        } else {
+            None
+        };
+
+        let hooks = Vec::<String>::deserialize(cursor)?;
+
+        let signature_kind = cursor.read_u8().corrupt("Failed to read signature kind")?;
+
+        let signature = if signature_kind > 0 {
+            let kind = SignatureKind::try_from(signature_kind)?;
+            let key_len = cursor
+                .read_u16()
+                .corrupt("Failed to read signature key len")?;
+            let key = if key_len > 0 {
+                Some(
+                    cursor
+                        .read_string(key_len as usize)
+                        .corrupt("Failed to read signature key")?,
+                )
+            } else {
+                None
+            };
+            Some(CommitSignature { kind, key })
+        } else {
+            None
+        };
 
         Ok(Self {
             remote_url,
+            remotes,
             init_date,
             last_commit_date,
             last_commit_msg,
             commit_count,
+            state,
+            branch,
+            default_branch,
+            hooks_path,
+            hooks,
+            signature,
+        })
+    }
+}
+
+impl CacheSerializer for GitRemote {
+    fn serialize(&self) -> Result<Vec<u8>> {
+        let mut buffer: Vec<u8> = Vec::new();
+
+        buffer.extend_from_slice(&u16::try_from(self.name.len())?.to_le_bytes());
+        buffer.extend_from_slice(self.name.as_bytes());
+        buffer.extend_from_slice(&u16::try_from(self.url.len())?.to_le_bytes());
+        buffer.extend_from_slice(self.url.as_bytes());
+
+        Ok(buffer)
+    }
+
+    fn deserialize(cursor: &mut Cursor<&[u8]>) -> Result<Self> {
+        let name_len = cursor
+            .read_u16()
+            .corrupt("Failed to read remote name len")?;
+        let name = cursor
+            .read_string(name_len as usize)
+            .corrupt("Failed to read remote name")?;
+
+        let url_len = cursor.read_u16().corrupt("Failed to read remote url len")?;
+        let url = cursor
+            .read_string(url_len as usize)
+            .corrupt("Failed to read remote url")?;
+
+        Ok(Self { name, url })
+    }
+}
+
+impl CacheSerializer for CiProvider {
+    fn serialize(&self) -> Result<Vec<u8>> {
+        Ok(vec![*self as u8])
+    }
+
+    fn deserialize(cursor: &mut Cursor<&[u8]>) -> Result<Self> {
+        Self::try_from(cursor.read_u8().corrupt("Failed to read ci provider")?)
+    }
+}
+
+impl CacheSerializer for RustCrate {
+    fn serialize(&self) -> Result<Vec<u8>> {
+        let mut buffer = self.name.serialize()?;
+        buffer.extend_from_slice(&self.edition.clone().unwrap_or_default().serialize()?);
+        buffer.push(u8::from(self.lib) | u8::from(self.bin) << 1);
+
+        Ok(buffer)
+    }
+
+    fn deserialize(cursor: &mut Cursor<&[u8]>) -> Result<Self> {
+        let name = String::deserialize(cursor).corrupt("Failed to read crate name")?;
+        let edition = String::deserialize(cursor).corrupt("Failed to read crate edition")?;
+        let kind = cursor.read_u8().corrupt("Failed to read crate kind")?;
+
+        Ok(Self {
+            name,
+            edition: (!edition.is_empty()).then_some(edition),
+            lib: kind & 1 != 0,
+            bin: kind & 2 != 0,
+        })
+    }
+}
+
+impl CacheSerializer for DevEnv {
+    fn serialize(&self) -> Result<Vec<u8>> {
+        Ok(vec![*self as u8])
+    }
+
+    fn deserialize(cursor: &mut Cursor<&[u8]>) -> Result<Self> {
+        Self::try_from(cursor.read_u8().corrupt("Failed to read dev environment")?)
+    }
+}
+
+impl CacheSerializer for String {
+    fn serialize(&self) -> Result<Vec<u8>> {
+        let mut buffer: Vec<u8> = Vec::new();
+
+        buffer.extend_from_slice(&u16::try_from(self.len())?.to_le_bytes());
+        buffer.extend_from_slice(self.as_bytes());
+
+        Ok(buffer)
+    }
+
+    fn deserialize(cursor: &mut Cursor<&[u8]>) -> Result<Self> {
+        let len = cursor.read_u16().corrupt("Failed to read string len")?;
+
+        cursor.read_string(len as usize)
+    }
+}
+
+impl CacheSerializer for Ownership {
+    fn serialize(&self) -> Result<Vec<u8>> {
+        let mut buffer = self.owner.serialize()?;
+        buffer.extend_from_slice(&self.group.serialize()?);
+        buffer.extend_from_slice(&self.mode.to_le_bytes());
+
+        Ok(buffer)
+    }
+
+    fn deserialize(cursor: &mut Cursor<&[u8]>) -> Result<Self> {
+        let owner = String::deserialize(cursor).corrupt("Failed to read owner")?;
+        let group = String::deserialize(cursor).corrupt("Failed to read group")?;
+        let mode = cursor.read_u32().corrupt("Failed to read mode")?;
+
+        Ok(Self { owner, group, mode })
+    }
+}
+
+impl CacheSerializer for Lfs {
+    fn serialize(&self) -> Result<Vec<u8>> {
+        let mut buffer = self.patterns.serialize()?;
+        buffer.extend_from_slice(&self.size.to_le_bytes());
+
+        Ok(buffer)
+    }
+
+    fn deserialize(cursor: &mut Cursor<&[u8]>) -> Result<Self> {
+        let patterns = Vec::<String>::deserialize(cursor)?;
+        let size = cursor.read_u64().corrupt("Failed to read LFS size")?;
+
+        Ok(Self { patterns, size })
+    }
+}
+
+impl CacheSerializer for Submodules {
+    fn serialize(&self) -> Result<Vec<u8>> {
+        let mut buffer: Vec<u8> = Vec::new();
+
+        buffer.extend_from_slice(&self.count.to_le_bytes());
+        buffer.extend_from_slice(&self.uninitialized.to_le_bytes());
+        buffer.extend_from_slice(&self.out_of_sync.to_le_bytes());
+
+        Ok(buffer)
+    }
+
+    fn deserialize(cursor: &mut Cursor<&[u8]>) -> Result<Self> {
+        let count = cursor.read_u32().corrupt("Failed to read submodules")?;
+        let uninitialized = cursor
+            .read_u32()
+            .corrupt("Failed to read uninitialized submodules")?;
+        let out_of_sync = cursor
+            .read_u32()
+            .corrupt("Failed to read out of sync submodules")?;
+
+        Ok(Self {
+            count,
+            uninitialized,
+            out_of_sync,
         })
     }
 }
 
 impl CacheSerializer for ProjectLanguage {
-    fn serialize(&self) -> anyhow::Result<Vec<u8>> {
+    fn serialize(&self) -> Result<Vec<u8>> {
         let mut buffer: Vec<u8> = Vec::new();
 
         buffer.extend_from_slice(&self.files.to_le_bytes());
@@ -210,14 +612,12 @@ impl CacheSerializer for ProjectLanguage {
         Ok(buffer)
     }
 
-    fn deserialize(cursor: &mut Cursor<&[u8]>) -> anyhow::Result<Self> {
-        let files = cursor.read_u32().with_context(|| "Failed to read files")?;
-        let lines = cursor.read_u32().with_context(|| "Failed to read lines")?;
-        let code = cursor.read_u32().with_context(|| "Failed to read code")?;
-        let comments = cursor
-            .read_u32()
-            .with_context(|| "Failed to read comments")?;
-        let blanks = cursor.read_u32().with_context(|| "Failed to read blanks")?;
+    fn deserialize(cursor: &mut Cursor<&[u8]>) -> Result<Self> {
+        let files = cursor.read_u32().corrupt("Failed to read files")?;
+        let lines = cursor.read_u32().corrupt("Failed to read lines")?;
+        let code = cursor.read_u32().corrupt("Failed to read code")?;
+        let comments = cursor.read_u32().corrupt("Failed to read comments")?;
+        let blanks = cursor.read_u32().corrupt("Failed to read blanks")?;
 
         Ok(Self {
             files,
@@ -233,7 +633,7 @@ impl<T> CacheSerializer for HashMap<u8, T>
 where
     T: CacheSerializer,
 {
-    fn serialize(&self) -> anyhow::Result<Vec<u8>> {
+    fn serialize(&self) -> Result<Vec<u8>> {
         let mut buffer: Vec<u8> = Vec::new();
 
         buffer.extend_from_slice(&u16::try_from(self.len())?.to_le_bytes());
@@ -245,15 +645,19 @@ where
         Ok(buffer)
     }
 
-    fn deserialize(cursor: &mut Cursor<&[u8]>) -> anyhow::Result<Self> {
-        let hashmap_len = cursor
-            .read_u16()
-            .with_context(|| "Failed to read hashmap len")?;
+    fn deserialize(cursor: &mut Cursor<&[u8]>) -> Result<Self> {
+        let hashmap_len = cursor.read_u16().corrupt("Failed to read hashmap len")? as usize;
+
+        if hashmap_len > MAX_LANGUAGES {
+            return Err(YmirError::CorruptCache(format!(
+                "Hashmap length {hashmap_len} exceeds limit of {MAX_LANGUAGES}"
+            )));
+        }
 
-        let mut hashmap = Self::new();
+        let mut hashmap = Self::with_capacity(hashmap_len);
 
         for _ in 0..hashmap_len {
-            let key = cursor.read_u8().with_context(|| "Failed to read key")?;
+            let key = cursor.read_u8().corrupt("Failed to read key")?;
             let value = T::deserialize(cursor)?;
 
             hashmap.insert(key, value);
@@ -263,42 +667,439 @@ where
     }
 }
 
+impl CacheSerializer for BTreeMap<String, String> {
+    fn serialize(&self) -> Result<Vec<u8>> {
+        let mut buffer: Vec<u8> = Vec::new();
+
+        buffer.extend_from_slice(&u16::try_from(self.len())?.to_le_bytes());
+        for (key, value) in self {
+            buffer.extend_from_slice(&key.serialize()?);
+            buffer.extend_from_slice(&value.serialize()?);
+        }
+
+        Ok(buffer)
+    }
+
+    fn deserialize(cursor: &mut Cursor<&[u8]>) -> Result<Self> {
+        let map_len = cursor.read_u16().corrupt("Failed to read map len")? as usize;
+
+        // Every entry takes at least four bytes for the two lengths
+        if map_len * 4 > cursor.remaining() {
+            return Err(YmirError::CorruptCache(format!(
+                "Map length {map_len} exceeds remaining {} bytes",
+                cursor.remaining()
+            )));
+        }
+
+        let mut map = Self::new();
+        for _ in 0..map_len {
+            let key = String::deserialize(cursor).corrupt("Failed to read map key")?;
+            let value = String::deserialize(cursor).corrupt("Failed to read map value")?;
+            map.insert(key, value);
+        }
+
+        Ok(map)
+    }
+}
+
+impl<T> CacheSerializer for Vec<T>
+where
+    T: CacheSerializer,
+{
+    fn serialize(&self) -> Result<Vec<u8>> {
+        let mut buffer: Vec<u8> = Vec::new();
+
+        buffer.extend_from_slice(&u16::try_from(self.len())?.to_le_bytes());
+        for value in self {
+            buffer.extend_from_slice(&T::serialize(value)?);
+        }
+
+        Ok(buffer)
+    }
+
+    fn deserialize(cursor: &mut Cursor<&[u8]>) -> Result<Self> {
+        let vec_len = cursor.read_u16().corrupt("Failed to read vec len")? as usize;
+
+        // Every element takes at least one byte, so this also bounds the allocation
+        if vec_len > cursor.remaining() {
+            return Err(YmirError::CorruptCache(format!(
+                "Vec length {vec_len} exceeds remaining {} bytes",
+                cursor.remaining()
+            )));
+        }
+
+        let mut vec = Self::with_capacity(vec_len);
+        for _ in 0..vec_len {
+            vec.push(T::deserialize(cursor)?);
+        }
+
+        Ok(vec)
+    }
+}
+
+/// Names what was being read when the cache turned out to be corrupt
+trait Corrupt<T> {
+    fn corrupt(self, what: &str) -> Result<T>;
+}
+
+impl<T> Corrupt<T> for Result<T> {
+    fn corrupt(self, what: &str) -> Result<T> {
+        self.map_err(|err| match err {
+            YmirError::CorruptCache(reason) => YmirError::CorruptCache(format!("{what}: {reason}")),
+            err => err,
+        })
+    }
+}
+
+impl<T> Corrupt<T> for std::io::Result<T> {
+    fn corrupt(self, what: &str) -> Result<T> {
+        self.map_err(YmirError::truncated).corrupt(what)
+    }
+}
+
 pub trait CursorUtil {
-    fn read_u8(&mut self) -> anyhow::Result<u8>;
-    fn read_u16(&mut self) -> anyhow::Result<u16>;
-    fn read_u32(&mut self) -> anyhow::Result<u32>;
-    fn read_u64(&mut self) -> anyhow::Result<u64>;
-    fn read_string(&mut self, len: usize) -> anyhow::Result<String>;
+    fn read_u8(&mut self) -> Result<u8>;
+    fn read_u16(&mut self) -> Result<u16>;
+    fn read_u32(&mut self) -> Result<u32>;
+    fn read_u64(&mut self) -> Result<u64>;
+    fn read_string(&mut self, len: usize) -> Result<String>;
+    fn remaining(&self) -> usize;
 }
 
 impl CursorUtil for Cursor<&[u8]> {
-    fn read_u8(&mut self) -> anyhow::Result<u8> {
+    fn read_u8(&mut self) -> Result<u8> {
         let mut bytes = [0u8; 1];
-        self.read_exact(&mut bytes)?;
+        self.read_exact(&mut bytes).map_err(YmirError::truncated)?;
         Ok(u8::from_le_bytes(bytes))
     }
 
-    fn read_u16(&mut self) -> anyhow::Result<u16> {
+    fn read_u16(&mut self) -> Result<u16> {
         let mut bytes = [0u8; 2];
-        self.read_exact(&mut bytes)?;
+        self.read_exact(&mut bytes).map_err(YmirError::truncated)?;
         Ok(u16::from_le_bytes(bytes))
     }
 
-    fn read_u32(&mut self) -> anyhow::Result<u32> {
+    fn read_u32(&mut self) -> Result<u32> {
         let mut bytes = [0u8; 4];
-        self.read_exact(&mut bytes)?;
+        self.read_exact(&mut bytes).map_err(YmirError::truncated)?;
         Ok(u32::from_le_bytes(bytes))
     }
 
-    fn read_u64(&mut self) -> anyhow::Result<u64> {
+    fn read_u64(&mut self) -> Result<u64> {
         let mut bytes = [0u8; 8];
-        self.read_exact(&mut bytes)?;
+        self.read_exact(&mut bytes).map_err(YmirError::truncated)?;
         Ok(u64::from_le_bytes(bytes))
     }
 
-    fn read_string(&mut self, len: usize) -> anyhow::Result<String> {
+    fn read_string(&mut self, len: usize) -> Result<String> {
+        if len > self.remaining() {
+            return Err(YmirError::CorruptCache(format!(
+                "String length {len} exceeds remaining input of {} bytes",
+                self.remaining()
+            )));
+        }
+
         let mut bytes = vec![0u8; len];
-        self.read_exact(&mut bytes)?;
-        String::from_utf8(bytes).with_context(|| "Invalid UTF-8 key")
+        self.read_exact(&mut bytes).map_err(YmirError::truncated)?;
+        String::from_utf8(bytes)
+            .map_err(|_| YmirError::CorruptCache("Invalid UTF-8 key".to_string()))
+    }
+
+    fn remaining(&self) -> usize {
+        let position = usize::try_from(self.position()).unwrap_or(usize::MAX);
+        self.get_ref().len().saturating_sub(position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::{BTreeMap, HashMap},
+        io::Cursor,
+        path::PathBuf,
+    };
+
+    use proptest::{collection, option, prelude::*};
+
+    use super::CacheSerializer;
+    use crate::{
+        ci::CiProvider,
+        config::Cache,
+        devenv::DevEnv,
+        error::YmirError,
+        lfs::Lfs,
+        manifest::RustCrate,
+        projects::{Project, ProjectLanguage},
+        signature::{CommitSignature, SignatureKind},
+        submodules::Submodules,
+        utils::{GitInfo, GitRemote, Ownership, RepoState},
+    };
+
+    fn language() -> impl Strategy<Value = ProjectLanguage> {
+        (
+            any::<u32>(),
+            any::<u32>(),
+            any::<u32>(),
+            any::<u32>(),
+            any::<u32>(),
+        )
+            .prop_map(|(files, lines, code, comments, blanks)| ProjectLanguage {
+                files,
+                lines,
+                code,
+                comments,
+                blanks,
+            })
+    }
+
+    fn remote() -> impl Strategy<Value = GitRemote> {
+        ("\\PC{0,16}", "\\PC{0,64}").prop_map(|(name, url)| GitRemote { name, url })
+    }
+
+    fn signature() -> impl Strategy<Value = CommitSignature> {
+        let kind = prop_oneof![
+            Just(SignatureKind::Gpg),
+            Just(SignatureKind::Ssh),
+            Just(SignatureKind::X509),
+        ];
+        (kind, option::of("[0-9A-F]{16,40}")).prop_map(|(kind, key)| CommitSignature { kind, key })
+    }
+
+    fn ci() -> impl Strategy<Value = CiProvider> {
+        (0..CiProvider::ALL.len()).prop_map(|i| CiProvider::ALL[i])
+    }
+
+    fn rust_crate() -> impl Strategy<Value = RustCrate> {
+        (
+            "[a-z_-]{0,16}",
+            option::of("20[0-9]{2}"),
+            any::<bool>(),
+            any::<bool>(),
+        )
+            .prop_map(|(name, edition, lib, bin)| RustCrate {
+                name,
+                edition,
+                lib,
+                bin,
+            })
+    }
+
+    fn ownership() -> impl Strategy<Value = Ownership> {
+        ("[a-z0-9_-]{0,16}", "[a-z0-9_-]{0,16}", 0..0o10000_u32)
+            .prop_map(|(owner, group, mode)| Ownership { owner, group, mode })
+    }
+
+    fn lfs() -> impl Strategy<Value = Lfs> {
+        (collection::vec("\\PC{0,16}", 0..3), any::<u64>())
+            .prop_map(|(patterns, size)| Lfs { patterns, size })
+    }
+
+    fn submodules() -> impl Strategy<Value = Submodules> {
+        (any::<u32>(), any::<u32>(), any::<u32>()).prop_map(
+            |(count, uninitialized, out_of_sync)| Submodules {
+                count,
+                uninitialized,
+                out_of_sync,
+            },
+        )
+    }
+
+    fn dev_env() -> impl Strategy<Value = DevEnv> {
+        (0..DevEnv::ALL.len()).prop_map(|i| DevEnv::ALL[i])
+    }
+
+    fn state() -> impl Strategy<Value = RepoState> {
+        prop_oneof![
+            Just(RepoState::Ok),
+            Just(RepoState::Empty),
+            Just(RepoState::Detached),
+            Just(RepoState::Error),
+            Just(RepoState::Unborn),
+        ]
+    }
+
+    // Empty strings are stored the same way as `None`, so only generate non-empty ones
+    prop_compose! {
+        fn git_info()(
+            remote_url in option::of("\\PC{1,64}"),
+            remotes in collection::vec(remote(), 0..4),
+            init_date in any::<u32>(),
+            last_commit_date in any::<u32>(),
+            last_commit_msg in option::of("\\PC{1,64}"),
+            commit_count in any::<u32>(),
+            state in state(),
+            branch in option::of("[a-z/-]{1,32}"),
+            default_branch in option::of("[a-z/-]{1,32}"),
+            hooks_path in option::of("\\PC{1,64}"),
+            hooks in collection::vec("[a-z-]{1,16}", 0..4),
+            signature in option::of(signature()),
+        ) -> GitInfo {
+            GitInfo {
+                remote_url,
+                remotes,
+                init_date,
+                last_commit_date,
+                last_commit_msg,
+                commit_count,
+                state,
+                branch,
+                default_branch,
+                hooks_path,
+                hooks,
+                signature,
+            }
+        }
+    }
+
+    prop_compose! {
+        fn project()(
+            path in "\\PC{0,64}",
+            size in any::<u64>(),
+            scanned_at in any::<u64>(),
+            head in proptest::option::of("[0-9a-f]{40}"),
+            deferred in any::<bool>(),
+            git_info in git_info(),
+            languages in collection::hash_map(any::<u8>(), language(), 0..8),
+            languages_total in language(),
+            ci in collection::vec(ci(), 0..3),
+            generated in language(),
+            crates in collection::vec(rust_crate(), 0..3),
+            metadata in collection::btree_map("[a-z.]{1,16}", "\\PC{0,32}", 0..4),
+            dev_envs in collection::vec(dev_env(), 0..3),
+            scan_errors in collection::vec("\\PC{0,64}", 0..3),
+            ownership in ownership(),
+            partial in any::<bool>(),
+            lfs in lfs(),
+            submodules in submodules(),
+        ) -> Project {
+            Project {
+                path: PathBuf::from(path),
+                size,
+                scanned_at,
+                head,
+                deferred,
+                git_info,
+                languages,
+                languages_total,
+                ci,
+                generated,
+                crates,
+                metadata,
+                dev_envs,
+                scan_errors,
+                ownership,
+                partial,
+                lfs,
+                submodules,
+            }
+        }
+    }
+
+    fn round_trip(cache: &Cache) -> crate::error::Result<Cache> {
+        let bytes = cache.serialize()?;
+        Cache::deserialize(&mut Cursor::new(bytes.as_slice()))
+    }
+
+    #[test]
+    fn empty_cache_round_trips() {
+        let cache = round_trip(&Cache::default()).unwrap();
+        assert!(cache.projects.is_empty());
+    }
+
+    #[test]
+    fn empty_maps_round_trip() {
+        let project = Project {
+            path: PathBuf::new(),
+            size: 0,
+            scanned_at: 0,
+            head: None,
+            deferred: false,
+            git_info: GitInfo::default(),
+            languages: HashMap::new(),
+            languages_total: ProjectLanguage {
+                files: 0,
+                lines: 0,
+                code: 0,
+                comments: 0,
+                blanks: 0,
+            },
+            ci: Vec::new(),
+            generated: ProjectLanguage::default(),
+            crates: Vec::new(),
+            metadata: BTreeMap::new(),
+            dev_envs: Vec::new(),
+            scan_errors: Vec::new(),
+            ownership: Ownership::default(),
+            partial: false,
+            lfs: Lfs::default(),
+            submodules: Submodules::default(),
+        };
+        let cache = Cache {
+            projects: vec![project],
+            compressed: true,
+        };
+
+        assert_eq!(round_trip(&cache).unwrap().projects, cache.projects);
+    }
+
+    #[test]
+    fn oversized_project_count_is_rejected() {
+        let mut bytes = b"YMIR".to_vec();
+        bytes.extend_from_slice(&[super::VERSION, 1]);
+        bytes.extend_from_slice(&crate::huffman::huffman_encode(&u16::MAX.to_le_bytes()));
+
+        let err = Cache::deserialize(&mut Cursor::new(bytes.as_slice())).unwrap_err();
+        assert!(err.is_corrupt_cache());
+        assert!(err.to_string().contains("Project count"));
+    }
+
+    #[test]
+    fn other_version_is_told_apart() {
+        let mut bytes = Cache::default().serialize().unwrap();
+        bytes[4] = super::VERSION + 1;
+
+        let err = Cache::deserialize(&mut Cursor::new(bytes.as_slice())).unwrap_err();
+        assert!(matches!(
+            err,
+            YmirError::CacheVersion { found, expected } if found == expected + 1
+        ));
+    }
+
+    proptest! {
+        #[test]
+        fn projects_round_trip(
+            projects in collection::vec(project(), 0..16),
+            compressed in any::<bool>(),
+        ) {
+            let cache = Cache { projects, compressed };
+            prop_assert_eq!(round_trip(&cache).unwrap().projects, cache.projects);
+        }
+
+        #[test]
+        fn truncated_cache_is_rejected(
+            projects in collection::vec(project(), 1..8),
+            compressed in any::<bool>(),
+            cut in any::<prop::sample::Index>(),
+        ) {
+            let bytes = Cache { projects, compressed }.serialize().unwrap();
+            let truncated = &bytes[..cut.index(bytes.len())];
+            prop_assert!(Cache::deserialize(&mut Cursor::new(truncated)).is_err());
+        }
+
+        #[test]
+        fn arbitrary_bytes_do_not_panic(bytes in collection::vec(any::<u8>(), 0..512)) {
+            let _ = Cache::deserialize(&mut Cursor::new(bytes.as_slice()));
+        }
+
+        #[test]
+        fn arbitrary_payload_does_not_panic(
+            payload in collection::vec(any::<u8>(), 0..512),
+            compressed in any::<bool>(),
+        ) {
+            let mut bytes = b"YMIR".to_vec();
+            bytes.extend_from_slice(&[super::VERSION, u8::from(compressed)]);
+            bytes.extend_from_slice(&payload);
+            let _ = Cache::deserialize(&mut Cursor::new(bytes.as_slice()));
+        }
     }
 }
@@ -0,0 +1,161 @@
+//! Detection of CI configuration checked into a project
+
+use std::{fmt::Display, path::Path};
+
+use serde::Serialize;
+
+use crate::error::YmirError;
+
+/// CI services recognized by their configuration files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum CiProvider {
+    GithubActions,
+    GitlabCi,
+    Jenkins,
+    CircleCi,
+    Travis,
+    AzurePipelines,
+    BitbucketPipelines,
+    Drone,
+    Woodpecker,
+}
+
+impl CiProvider {
+    pub const ALL: [Self; 9] = [
+        Self::GithubActions,
+        Self::GitlabCi,
+        Self::Jenkins,
+        Self::CircleCi,
+        Self::Travis,
+        Self::AzurePipelines,
+        Self::BitbucketPipelines,
+        Self::Drone,
+        Self::Woodpecker,
+    ];
+
+    /// Files or directories, relative to the project root, that mark the provider as configured
+    const fn markers(self) -> &'static [&'static str] {
+        match self {
+            Self::GithubActions => &[".github/workflows"],
+            Self::GitlabCi => &[".gitlab-ci.yml"],
+            Self::Jenkins => &["Jenkinsfile"],
+            Self::CircleCi => &[".circleci/config.yml"],
+            Self::Travis => &[".travis.yml"],
+            Self::AzurePipelines => &["azure-pipelines.yml", ".azure-pipelines"],
+            Self::BitbucketPipelines => &["bitbucket-pipelines.yml"],
+            Self::Drone => &[".drone.yml"],
+            Self::Woodpecker => &[".woodpecker.yml", ".woodpecker.yaml", ".woodpecker"],
+        }
+    }
+
+    /// Providers configured in the project at `path`
+    pub fn detect(path: &Path) -> Vec<Self> {
+        Self::ALL
+            .into_iter()
+            .filter(|provider| {
+                provider.markers().iter().any(|marker| {
+                    let marker = path.join(marker);
+                    // An empty workflows directory doesn't run anything
+                    if marker.is_dir() {
+                        marker.read_dir().is_ok_and(|mut v| v.next().is_some())
+                    } else {
+                        marker.is_file()
+                    }
+                })
+            })
+            .collect()
+    }
+
+    pub const fn next(self) -> Option<Self> {
+        match self {
+            Self::GithubActions => Some(Self::GitlabCi),
+            Self::GitlabCi => Some(Self::Jenkins),
+            Self::Jenkins => Some(Self::CircleCi),
+            Self::CircleCi => Some(Self::Travis),
+            Self::Travis => Some(Self::AzurePipelines),
+            Self::AzurePipelines => Some(Self::BitbucketPipelines),
+            Self::BitbucketPipelines => Some(Self::Drone),
+            Self::Drone => Some(Self::Woodpecker),
+            Self::Woodpecker => None,
+        }
+    }
+
+    pub const fn previous(self) -> Option<Self> {
+        match self {
+            Self::Woodpecker => Some(Self::Drone),
+            Self::Drone => Some(Self::BitbucketPipelines),
+            Self::BitbucketPipelines => Some(Self::AzurePipelines),
+            Self::AzurePipelines => Some(Self::Travis),
+            Self::Travis => Some(Self::CircleCi),
+            Self::CircleCi => Some(Self::Jenkins),
+            Self::Jenkins => Some(Self::GitlabCi),
+            Self::GitlabCi => Some(Self::GithubActions),
+            Self::GithubActions => None,
+        }
+    }
+}
+
+impl TryFrom<u8> for CiProvider {
+    type Error = YmirError;
+
+    fn try_from(value: u8) -> Result<Self, YmirError> {
+        Self::ALL
+            .get(usize::from(value))
+            .copied()
+            .ok_or_else(|| YmirError::CorruptCache(format!("Invalid ci provider {value}")))
+    }
+}
+
+impl Display for CiProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::GithubActions => write!(f, "GitHub Actions"),
+            Self::GitlabCi => write!(f, "GitLab CI"),
+            Self::Jenkins => write!(f, "Jenkins"),
+            Self::CircleCi => write!(f, "CircleCI"),
+            Self::Travis => write!(f, "Travis CI"),
+            Self::AzurePipelines => write!(f, "Azure Pipelines"),
+            Self::BitbucketPipelines => write!(f, "Bitbucket Pipelines"),
+            Self::Drone => write!(f, "Drone"),
+            Self::Woodpecker => write!(f, "Woodpecker"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::CiProvider;
+    use crate::test_utils::temp_dir;
+
+    #[test]
+    fn detects_providers_by_their_config() {
+        let dir = temp_dir("ci");
+        assert_eq!(CiProvider::detect(&dir), []);
+
+        // An empty workflows directory doesn't count
+        fs::create_dir_all(dir.join(".github/workflows")).unwrap();
+        fs::create_dir_all(dir.join(".woodpecker")).unwrap();
+        fs::write(dir.join(".woodpecker/build.yml"), "steps: []\n").unwrap();
+        fs::write(dir.join("Jenkinsfile"), "pipeline {}\n").unwrap();
+        assert_eq!(
+            CiProvider::detect(&dir),
+            [CiProvider::Jenkins, CiProvider::Woodpecker]
+        );
+
+        fs::write(dir.join(".github/workflows/ci.yml"), "on: push\n").unwrap();
+        assert_eq!(CiProvider::detect(&dir)[0], CiProvider::GithubActions);
+    }
+
+    #[test]
+    fn next_and_previous_walk_every_provider() {
+        let forward: Vec<_> =
+            std::iter::successors(Some(CiProvider::ALL[0]), |v| v.next()).collect();
+        assert_eq!(forward, CiProvider::ALL);
+
+        let backward: Vec<_> =
+            std::iter::successors(Some(CiProvider::ALL[8]), |v| v.previous()).collect();
+        assert!(backward.iter().rev().eq(CiProvider::ALL.iter()));
+    }
+}
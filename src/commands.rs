@@ -0,0 +1,426 @@
+//! Subcommands that run without the TUI
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{bail, Context};
+
+use crate::{
+    cache::CacheStats,
+    config::{Cache, NumberFormat, ProjectLabel, Settings},
+    credentials::{self, Credential},
+    diff::ScanDiff,
+    export,
+    history::{History, OpenCounts},
+    hosts,
+    importers::Source,
+    projects::{self, Project},
+    trend::LanguageTrend,
+    utils::{format_bytes, format_number, format_relative, post_json},
+};
+
+/// `ymir cache <command>`
+pub fn cache(args: &[String], settings: &Settings) -> anyhow::Result<()> {
+    match args.first().map(String::as_str) {
+        Some("info") => cache_info(),
+        Some("compact") => cache_compact(settings),
+        Some("export") => cache_export(&args[1..]),
+        Some("import") => cache_import(&args[1..]),
+        Some("hosts") => cache_hosts(),
+        Some(command) => bail!("Unknown cache command: {command}"),
+        None => bail!("Missing cache command, expected: info, compact, export, import, hosts"),
+    }
+}
+
+fn cache_info() -> anyhow::Result<()> {
+    let Some(path) = Cache::path() else {
+        bail!("Failed to find config_directory");
+    };
+
+    let bytes = fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let stats = CacheStats::new(&bytes)?;
+
+    println!("Path: {}\n{stats}", path.display());
+    Ok(())
+}
+
+/// Drops projects that no longer exist from the cache, history and open counts and rewrites them
+fn cache_compact(settings: &Settings) -> anyhow::Result<()> {
+    let (Some(cache_path), Some(history_path), Some(counts_path)) =
+        (Cache::path(), History::path(), OpenCounts::path())
+    else {
+        bail!("Failed to find config_directory");
+    };
+    let files = [&cache_path, &history_path, &counts_path];
+    let size = || -> u64 {
+        files
+            .iter()
+            .map(|path| fs::metadata(path).map_or(0, |v| v.len()))
+            .sum()
+    };
+    let before = size();
+
+    let mut dropped_projects = 0;
+    if cache_path.exists() {
+        dropped_projects = Cache::retain(settings.compress_cache, |p| p.path.is_dir())?;
+    }
+
+    let mut dropped_history = 0;
+    if history_path.exists() {
+        let mut history = History::load();
+        dropped_history = history.compact();
+        history.save()?;
+    }
+    let mut counts = OpenCounts::load();
+    if counts.compact() > 0 {
+        counts.save()?;
+    }
+
+    let after = size();
+    println!(
+        "Dropped {dropped_projects} missing projects and {dropped_history} history entries\nReclaimed {}",
+        format_bytes(before.saturating_sub(after))
+    );
+    Ok(())
+}
+
+/// Copies the cache to `file` for `cache import` on another machine
+fn cache_export(args: &[String]) -> anyhow::Result<()> {
+    let (Some(file), Some(path)) = (args.first(), Cache::path()) else {
+        bail!("Missing file to export to");
+    };
+
+    // Writers replace the cache by renaming, so a plain copy never sees half of a write
+    fs::copy(&path, file).with_context(|| format!("Failed to write {file}"))?;
+    Ok(())
+}
+
+/// `ymir cache import <file> [host]`, the host is named after the file when not given
+fn cache_import(args: &[String]) -> anyhow::Result<()> {
+    let Some(file) = args.first().map(Path::new) else {
+        bail!("Missing file to import");
+    };
+    let Some(host) = args
+        .get(1)
+        .cloned()
+        .or_else(|| file.file_stem().map(|v| v.to_string_lossy().to_string()))
+    else {
+        bail!("Missing host name for {}", file.display());
+    };
+
+    let count = hosts::import(file, &host)?;
+    println!("Imported {count} projects of {host}");
+    Ok(())
+}
+
+/// Lists the projects of this machine and every imported one, marking repositories that are
+/// missing somewhere
+fn cache_hosts() -> anyhow::Result<()> {
+    let mut all = vec![(hosts::local_name(), Cache::try_read_cache()?)];
+    all.extend(hosts::imported()?);
+
+    let merged = hosts::merge(&all);
+    let hosts_width = merged
+        .iter()
+        .map(|v| v.hosts.join(", ").len())
+        .max()
+        .unwrap_or(0)
+        .max("HOSTS".len());
+    let label_width = merged.iter().map(|v| v.label.len()).max().unwrap_or(0);
+
+    println!(
+        "{:hosts_width$}  {:label_width$}  MISSING ON",
+        "HOSTS", "PROJECT"
+    );
+    for project in &merged {
+        println!(
+            "{:hosts_width$}  {:label_width$}  {}",
+            project.hosts.join(", "),
+            project.label,
+            project.missing.join(", ")
+        );
+    }
+
+    let missing = merged.iter().filter(|v| !v.missing.is_empty()).count();
+    println!(
+        "\n{} repositories on {} machines, {missing} missing on some",
+        merged.len(),
+        all.len()
+    );
+    Ok(())
+}
+
+/// `ymir auth <command>`
+pub fn auth(args: &[String], settings: &Settings) -> anyhow::Result<()> {
+    let credential = || -> anyhow::Result<Credential> {
+        let names = Credential::ALL.map(Credential::key).join(", ");
+        match args.get(1) {
+            Some(name) => Credential::parse(name)
+                .with_context(|| format!("Unknown service {name}, expected: {names}")),
+            None => bail!("Missing service, expected: {names}"),
+        }
+    };
+    match args.first().map(String::as_str) {
+        Some("login") => auth_login(credential()?, settings),
+        Some("status") => auth_status(settings),
+        Some("logout") => auth_logout(credential()?),
+        Some(command) => bail!("Unknown auth command: {command}"),
+        None => bail!("Missing auth command, expected: login, status, logout"),
+    }
+}
+
+/// Stores a token read from stdin, typed without echo or piped in like `gh auth token | ymir auth
+/// login github`
+fn auth_login(credential: Credential, settings: &Settings) -> anyhow::Result<()> {
+    let token = credentials::read_secret(&format!("{credential} token: "))?;
+    if token.is_empty() {
+        bail!("Empty token, nothing stored");
+    }
+    credential.store(&token)?;
+    println!("Stored the {credential} token in the keyring");
+    if credential == Credential::GitHub && settings.github_token.is_some() {
+        println!("github_token in the config isn't needed anymore, the keyring is read first");
+    }
+    Ok(())
+}
+
+/// Where the token of every service comes from
+fn auth_status(settings: &Settings) -> anyhow::Result<()> {
+    for credential in Credential::ALL {
+        let in_config = credential == Credential::GitHub && settings.github_token.is_some();
+        let source = match credential.stored() {
+            Ok(true) => "stored in the keyring".to_string(),
+            Ok(false) if in_config => "github_token in the config".to_string(),
+            Ok(false) => "not set".to_string(),
+            Err(err) if in_config => format!("github_token in the config, {err:#}"),
+            Err(err) => format!("{err:#}"),
+        };
+        println!("{credential}: {source}");
+    }
+    Ok(())
+}
+
+fn auth_logout(credential: Credential) -> anyhow::Result<()> {
+    if credential.remove()? {
+        println!("Removed the {credential} token from the keyring");
+    } else {
+        println!("No {credential} token in the keyring");
+    }
+    Ok(())
+}
+
+/// `ymir export <file>`, bookmarks for every cached project with a remote
+pub fn export(args: &[String]) -> anyhow::Result<()> {
+    let Some(path) = args.first() else {
+        bail!("Missing file to export to");
+    };
+
+    let projects = Cache::try_read_cache()?;
+    fs::write(path, export::bookmarks_html(&projects))
+        .with_context(|| format!("Failed to write {path}"))?;
+    Ok(())
+}
+
+/// `ymir trend OLD [NEW] [--json]`, lines of code per language between two caches saved with
+/// `ymir cache export`, the current cache when `NEW` isn't given
+pub fn trend(args: &[String], json: bool) -> anyhow::Result<()> {
+    let Some(old) = args.first() else {
+        bail!("Missing cache file to compare with");
+    };
+    let old = Cache::read_file(Path::new(old))?;
+    let new = match args.get(1) {
+        Some(new) => Cache::read_file(Path::new(new))?,
+        None => Cache::try_read_cache()?,
+    };
+
+    let trend = LanguageTrend::new(&old, &new);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&trend.to_json())?);
+    } else {
+        println!("{}", trend.report());
+    }
+    Ok(())
+}
+
+/// `ymir current [PATH]`, one line about the project containing `PATH` for shell prompts
+///
+/// Only the cache is read, nothing is printed outside of cached projects so prompts can embed it
+/// unconditionally.
+pub fn current(args: &[String]) -> anyhow::Result<()> {
+    let path = args
+        .first()
+        .map_or_else(std::env::current_dir, |v| Ok(PathBuf::from(v)))?;
+    let Ok(path) = path.canonicalize() else {
+        return Ok(());
+    };
+
+    let projects = Cache::try_read_cache()?;
+    let Some(project) = projects
+        .iter()
+        .filter(|p| path.starts_with(&p.path))
+        .max_by_key(|p| p.path.components().count())
+    else {
+        return Ok(());
+    };
+
+    let mut line = format!(
+        "{} {} loc",
+        project.label(ProjectLabel::Name),
+        format_number(u64::from(project.languages_total.code), NumberFormat::Short)
+    );
+    if project.git_info.state.has_commits() {
+        let age = chrono::Local::now().timestamp() - i64::from(project.git_info.last_commit_date);
+        line.push_str(&format!(", {}", format_relative(age)));
+    }
+    println!("{line}");
+    Ok(())
+}
+
+/// Root given on the command line or `default_dir`, canonicalized like the cache's paths
+fn root_arg(args: &[String], settings: &Settings) -> anyhow::Result<PathBuf> {
+    let Some(root) = args
+        .first()
+        .map(PathBuf::from)
+        .or_else(|| settings.default_dir.clone())
+    else {
+        bail!("Missing directory and no default_dir is configured");
+    };
+    root.canonicalize()
+        .with_context(|| format!("Failed to open {}", root.display()))
+}
+
+fn cached_under(root: &Path) -> Vec<Project> {
+    Cache::try_read_cache()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|p| p.path.starts_with(root))
+        .collect()
+}
+
+/// `ymir refresh [PATH]`, rescans into the cache without the TUI and reports what changed to the
+/// configured webhook and command
+pub fn refresh(args: &[String], settings: &Settings) -> anyhow::Result<()> {
+    let root = root_arg(args, settings)?;
+
+    let old = cached_under(&root);
+    let roots = [root];
+    let (new, error) = projects::scan_into_cache(&roots, &roots, settings, |_, _| {});
+    if let Some(error) = error {
+        bail!(error);
+    }
+
+    let diff = ScanDiff::new(&old, &new);
+    println!("{diff}");
+    notify_refresh(&diff, &roots[0], settings)
+}
+
+/// `ymir import <source>`, scans the projects another tool knows about into the cache
+pub fn import(args: &[String], settings: &Settings) -> anyhow::Result<()> {
+    let Some(source) = args.first() else {
+        bail!("Missing source to import from, expected ghq, projectile or vscode");
+    };
+
+    let paths = Source::parse(source)?.paths()?;
+    if paths.is_empty() {
+        bail!("No projects found in {source}");
+    }
+
+    let (projects, error) = projects::scan_into_cache(&paths, &paths, settings, |_, _| {});
+    if let Some(error) = error {
+        bail!(error);
+    }
+    println!("Imported {} projects from {source}", projects.len());
+    Ok(())
+}
+
+/// `ymir scan [PATH] [--explain]`, scans without touching the cache, with `explain` also telling
+/// what `ignore_dirs` skipped
+pub fn scan(args: &[String], settings: &Settings, explain: bool) -> anyhow::Result<()> {
+    let root = root_arg(args, settings)?;
+
+    if explain {
+        let (projects, stats) = projects::find_explained(&root, settings);
+        println!("Found {} projects in {}", projects.len(), root.display());
+        println!("\n{stats}");
+    } else {
+        let projects = projects::find(&root, settings);
+        println!("Found {} projects in {}", projects.len(), root.display());
+    }
+    Ok(())
+}
+
+/// `ymir show [PATH] [--json]`, prints the cached record of the project containing `PATH`, the
+/// current directory by default
+pub fn show(args: &[String], settings: &Settings, json: bool) -> anyhow::Result<()> {
+    let path = args
+        .first()
+        .map_or_else(|| PathBuf::from("."), PathBuf::from);
+    let path = path
+        .canonicalize()
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+
+    let projects = Cache::try_read_cache()?;
+    let Some(project) = projects
+        .iter()
+        .filter(|p| path.starts_with(&p.path))
+        .max_by_key(|p| p.path.components().count())
+    else {
+        bail!("{} isn't in a cached project", path.display());
+    };
+
+    if json {
+        let record = export::project_json(project, &History::load(), settings)?;
+        println!("{}", serde_json::to_string_pretty(&record)?);
+    } else {
+        println!(
+            "{project}
+
+{}",
+            project.git_info
+        );
+    }
+    Ok(())
+}
+
+/// `ymir diff [PATH]`, scans without touching the cache and prints how it differs from it
+pub fn diff(args: &[String], settings: &Settings, json: bool) -> anyhow::Result<()> {
+    let root = root_arg(args, settings)?;
+
+    let diff = ScanDiff::new(&cached_under(&root), &projects::find(&root, settings));
+    if json {
+        println!("{}", serde_json::to_string_pretty(&diff.to_json())?);
+    } else {
+        println!("{}", diff.report());
+    }
+    Ok(())
+}
+
+fn notify_refresh(diff: &ScanDiff, root: &Path, settings: &Settings) -> anyhow::Result<()> {
+    if let Some(webhook) = &settings.refresh_webhook {
+        let mut body = diff.to_json();
+        body["text"] = format!("ymir refreshed {}: {diff}", root.display()).into();
+        body["root"] = root.to_string_lossy().into();
+        post_json(webhook, &body)?;
+    }
+
+    if let Some(command) = &settings.refresh_command {
+        let status = Command::new("sh")
+            .args(["-c", command])
+            .env("YMIR_ROOT", root)
+            .env("YMIR_SUMMARY", diff.to_string())
+            .env("YMIR_PROJECTS", diff.projects.to_string())
+            .env("YMIR_ADDED", diff.added.len().to_string())
+            .env("YMIR_REMOVED", diff.removed.len().to_string())
+            .env("YMIR_SIZE_CHANGE", diff.size_change.to_string())
+            .status()
+            .with_context(|| format!("Failed to run {command}"))?;
+        if !status.success() {
+            bail!("{command} exited with {status}");
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,258 @@
+//! Reader for git's commit-graph file
+//!
+//! The commit-graph stores parents and commit times of every commit in one flat file, walking it
+//! avoids inflating each commit object, which is what dominates counting on big histories.
+//! See `Documentation/gitformat-commit-graph.txt` in git for the format.
+//!
+//! Only a single `objects/info/commit-graph` file is read. Split graphs, the chain under
+//! `objects/info/commit-graphs/` that `git commit-graph write --split` and `fetch.writeCommitGraph`
+//! leave, aren't supported and those repositories are walked without a graph.
+
+use std::{fs, path::Path};
+
+const SIGNATURE: &[u8; 4] = b"CGPH";
+const OID_LEN: usize = 20;
+const FANOUT_LEN: usize = 256 * 4;
+const COMMIT_DATA_LEN: usize = OID_LEN + 16;
+const CHUNK_ENTRY_LEN: usize = 12;
+
+const PARENT_NONE: u32 = 0x7000_0000;
+const PARENT_EXTRA: u32 = 0x8000_0000;
+const LAST_EDGE: u32 = 0x8000_0000;
+
+/// A single, non-split commit-graph file
+pub struct CommitGraph {
+    data: Vec<u8>,
+    len: usize,
+    fanout: usize,
+    oids: usize,
+    commits: usize,
+    edges: Option<usize>,
+}
+
+impl CommitGraph {
+    /// Loads `objects/info/commit-graph` from the common git directory, `None` if missing or
+    /// malformed
+    ///
+    /// Linked worktrees have their own git directory without objects, `common_dir` is the one of
+    /// the main repository they share them with.
+    pub fn open(common_dir: &Path) -> Option<Self> {
+        let data = fs::read(common_dir.join("objects/info/commit-graph")).ok()?;
+        let graph = Self::parse(data);
+        if graph.is_none() {
+            warn!(
+                "Ignoring malformed commit-graph in {}",
+                common_dir.display()
+            );
+        }
+        graph
+    }
+
+    fn parse(data: Vec<u8>) -> Option<Self> {
+        // Only version 1 with sha1 hashes and without base graphs is supported
+        if data.get(..4)? != SIGNATURE || data.get(4..6)? != [1, 1] || *data.get(7)? != 0 {
+            return None;
+        }
+
+        let chunk_count = usize::from(*data.get(6)?);
+        let mut fanout = None;
+        let mut oids = None;
+        let mut commits = None;
+        let mut edges = None;
+
+        for i in 0..chunk_count {
+            let entry = data.get(8 + i * CHUNK_ENTRY_LEN..8 + (i + 1) * CHUNK_ENTRY_LEN)?;
+            let offset = usize::try_from(u64::from_be_bytes(entry[4..].try_into().ok()?)).ok()?;
+            match &entry[..4] {
+                b"OIDF" => fanout = Some(offset),
+                b"OIDL" => oids = Some(offset),
+                b"CDAT" => commits = Some(offset),
+                b"EDGE" => edges = Some(offset),
+                _ => {}
+            }
+        }
+
+        let fanout = fanout?;
+        let len = usize::try_from(read_u32(&data, fanout + FANOUT_LEN - 4)?).ok()?;
+        let (oids, commits) = (oids?, commits?);
+
+        if data.len() < oids + len * OID_LEN || data.len() < commits + len * COMMIT_DATA_LEN {
+            return None;
+        }
+
+        Some(Self {
+            data,
+            len,
+            fanout,
+            oids,
+            commits,
+            edges,
+        })
+    }
+
+    /// Number of commits in the graph
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Position of a commit in the graph
+    pub fn find(&self, oid: &[u8]) -> Option<usize> {
+        let first = usize::from(*oid.first()?);
+        let start = if first == 0 {
+            0
+        } else {
+            usize::try_from(read_u32(&self.data, self.fanout + (first - 1) * 4)?).ok()?
+        };
+        let end = usize::try_from(read_u32(&self.data, self.fanout + first * 4)?).ok()?;
+
+        let (mut low, mut high) = (start, end.min(self.len));
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let offset = self.oids + mid * OID_LEN;
+            match self.data[offset..offset + OID_LEN].cmp(oid) {
+                std::cmp::Ordering::Less => low = mid + 1,
+                std::cmp::Ordering::Greater => high = mid,
+                std::cmp::Ordering::Equal => return Some(mid),
+            }
+        }
+
+        None
+    }
+
+    /// Positions of a commit's parents
+    pub fn parents(&self, index: usize) -> Vec<usize> {
+        let offset = self.commits + index * COMMIT_DATA_LEN + OID_LEN;
+        let mut parents = Vec::new();
+
+        let Some(first) = read_u32(&self.data, offset) else {
+            return parents;
+        };
+        if first == PARENT_NONE {
+            return parents;
+        }
+        parents.push(first as usize);
+
+        let Some(second) = read_u32(&self.data, offset + 4) else {
+            return parents;
+        };
+        if second == PARENT_NONE {
+            return parents;
+        }
+
+        if second & PARENT_EXTRA == 0 {
+            parents.push(second as usize);
+            return parents;
+        }
+
+        // Octopus merges keep the second parent onward in the extra edge list
+        let Some(edges) = self.edges else {
+            return parents;
+        };
+        let mut position = edges + (second & !PARENT_EXTRA) as usize * 4;
+        while let Some(edge) = read_u32(&self.data, position) {
+            parents.push((edge & !LAST_EDGE) as usize);
+            if edge & LAST_EDGE != 0 {
+                break;
+            }
+            position += 4;
+        }
+
+        parents
+    }
+
+    /// Commit time in seconds since epoch, stored in the low 34 bits of the generation field
+    pub fn commit_time(&self, index: usize) -> i64 {
+        let offset = self.commits + index * COMMIT_DATA_LEN + OID_LEN + 8;
+        let high = read_u32(&self.data, offset).unwrap_or_default() & 0b11;
+        let low = read_u32(&self.data, offset + 4).unwrap_or_default();
+
+        (i64::from(high) << 32) | i64::from(low)
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_be_bytes(bytes.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::CommitGraph;
+    use crate::{
+        config::Settings,
+        test_utils::{git, temp_dir},
+        utils::get_git_info,
+    };
+
+    #[test]
+    fn counts_match_rev_list() {
+        let dir = temp_dir("commit-graph");
+
+        git(&dir, &["init", "-q", "-b", "main"]);
+        for i in 0..5 {
+            git(
+                &dir,
+                &["commit", "-q", "--allow-empty", "-m", &format!("main {i}")],
+            );
+        }
+        git(&dir, &["checkout", "-q", "-b", "side", "HEAD~2"]);
+        for i in 0..3 {
+            git(
+                &dir,
+                &["commit", "-q", "--allow-empty", "-m", &format!("side {i}")],
+            );
+        }
+        git(&dir, &["checkout", "-q", "main"]);
+        git(&dir, &["merge", "-q", "--no-ff", "-m", "merge", "side"]);
+        git(&dir, &["commit-graph", "write", "--reachable"]);
+
+        // Commits after the graph was written have to be read from the object database
+        for i in 0..2 {
+            git(
+                &dir,
+                &["commit", "-q", "--allow-empty", "-m", &format!("after {i}")],
+            );
+        }
+
+        let graph = CommitGraph::open(&dir.join(".git")).unwrap();
+        assert_eq!(graph.len(), 9);
+
+        let expected: u32 = git(&dir, &["rev-list", "--count", "HEAD"])
+            .trim()
+            .parse()
+            .unwrap();
+        assert_eq!(
+            get_git_info(&dir, &Settings::default())
+                .unwrap()
+                .commit_count,
+            expected
+        );
+    }
+
+    #[test]
+    fn worktrees_share_the_graph() {
+        let dir = temp_dir("commit-graph-worktree");
+        let main = dir.join("main");
+        std::fs::create_dir(&main).unwrap();
+
+        git(&main, &["init", "-q", "-b", "main"]);
+        for i in 0..3 {
+            git(
+                &main,
+                &["commit", "-q", "--allow-empty", "-m", &format!("main {i}")],
+            );
+        }
+        git(&main, &["commit-graph", "write", "--reachable"]);
+        git(&main, &["worktree", "add", "-q", "../feature"]);
+
+        let repo = git2::Repository::open(dir.join("feature")).unwrap();
+        assert!(CommitGraph::open(repo.path()).is_none());
+        let graph = CommitGraph::open(repo.commondir()).unwrap();
+        assert_eq!(graph.len(), 3);
+    }
+}
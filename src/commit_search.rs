@@ -0,0 +1,150 @@
+//! Commits of every repository whose summary matches a query, for `:commits`
+//!
+//! All local branches are walked, a commit on several of them is listed once. The query is
+//! matched ignoring case unless it's written as `/regex/`.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use git2::{Repository, Sort};
+use log::warn;
+use regex::Regex;
+
+/// Matches kept in total, the walk stops once there are this many
+pub const MAX_MATCHES: usize = 500;
+
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    /// Lowercase text the summary has to contain
+    Text(String),
+    Regex(Regex),
+}
+
+impl Pattern {
+    pub fn parse(query: &str) -> anyhow::Result<Self> {
+        let query = query.trim();
+        match query
+            .strip_prefix('/')
+            .and_then(|v| v.strip_suffix('/'))
+            .filter(|v| !v.is_empty())
+        {
+            Some(regex) => Ok(Self::Regex(
+                Regex::new(regex).with_context(|| format!("Invalid regex {regex}"))?,
+            )),
+            None => Ok(Self::Text(query.to_lowercase())),
+        }
+    }
+
+    pub fn matches(&self, summary: &str) -> bool {
+        match self {
+            Self::Text(text) => summary.to_lowercase().contains(text),
+            Self::Regex(regex) => regex.is_match(summary),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitMatch {
+    pub project: PathBuf,
+    /// Abbreviated hash
+    pub id: String,
+    pub summary: String,
+    /// Unix seconds
+    pub time: i64,
+}
+
+/// Matching commits of `projects` in their order, newest first within each
+pub fn search(projects: &[PathBuf], pattern: &Pattern) -> Vec<CommitMatch> {
+    let mut matches = Vec::new();
+    for project in projects {
+        if matches.len() >= MAX_MATCHES {
+            break;
+        }
+        if let Err(err) = search_repo(project, pattern, &mut matches) {
+            warn!("Failed to search commits of {}: {err}", project.display());
+        }
+    }
+    matches
+}
+
+fn search_repo(
+    project: &Path,
+    pattern: &Pattern,
+    matches: &mut Vec<CommitMatch>,
+) -> Result<(), git2::Error> {
+    let repo = Repository::open(project)?;
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push_glob("refs/heads/*")?;
+    revwalk.set_sorting(Sort::TIME)?;
+
+    for oid in revwalk {
+        let commit = repo.find_commit(oid?)?;
+        if !commit.summary().is_some_and(|v| pattern.matches(v)) {
+            continue;
+        }
+
+        matches.push(CommitMatch {
+            project: project.to_path_buf(),
+            id: commit
+                .as_object()
+                .short_id()?
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            summary: commit.summary().unwrap_or_default().to_string(),
+            time: commit.time().seconds(),
+        });
+        if matches.len() >= MAX_MATCHES {
+            break;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{search, Pattern};
+    use crate::test_utils::{git, temp_dir};
+
+    #[test]
+    fn patterns_ignore_case_unless_regex() {
+        assert!(Pattern::parse("TLS").unwrap().matches("Fix tls handshake"));
+        let regex = Pattern::parse("/^Fix [a-z]+$/").unwrap();
+        assert!(regex.matches("Fix tls"));
+        assert!(!regex.matches("fix tls"));
+        assert!(Pattern::parse("/(/").is_err());
+        // A lone slash is text
+        assert!(Pattern::parse("/").unwrap().matches("a/b"));
+    }
+
+    #[test]
+    fn finds_commits_on_every_branch() {
+        let dir = temp_dir("commit-search");
+        git(&dir, &["init", "-q", "-b", "main"]);
+        git(
+            &dir,
+            &["commit", "-q", "--allow-empty", "-m", "Fix tls handshake"],
+        );
+        git(&dir, &["checkout", "-q", "-b", "feature"]);
+        git(
+            &dir,
+            &[
+                "commit",
+                "-q",
+                "--allow-empty",
+                "-m",
+                "Retry TLS on timeout",
+            ],
+        );
+        git(&dir, &["checkout", "-q", "main"]);
+        git(&dir, &["commit", "-q", "--allow-empty", "-m", "Unrelated"]);
+
+        let mut summaries: Vec<_> = search(&[dir.to_path_buf()], &Pattern::parse("tls").unwrap())
+            .into_iter()
+            .map(|v| v.summary)
+            .collect();
+        summaries.sort();
+        assert_eq!(summaries, ["Fix tls handshake", "Retry TLS on timeout"]);
+    }
+}
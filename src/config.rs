@@ -1,40 +1,309 @@
 //! Config for ymir
 
 use std::{
-    fs,
+    collections::HashMap,
+    fs::{self, File},
+    io,
     path::{Path, PathBuf},
+    sync::OnceLock,
 };
 
-use anyhow::bail;
-use serde::Deserialize;
+use memmap2::Mmap;
+use serde::{Deserialize, Serialize};
+use tokei::LanguageType;
 
 use crate::cache::CacheSerializer;
+use crate::error::{Result, YmirError};
 use crate::projects::Project;
+use crate::utils::{parse_size, tilde};
 use log::error;
 
 /// Settings for ymir
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
 pub struct Settings {
     pub ignore_dirs: Vec<String>,
+    /// Also skip directories matched by git's global gitignore, `core.excludesFile`
+    pub global_gitignore: bool,
+    /// Globs like `~/code/*` limiting scans to the directories they match and what's inside
+    /// them, relative ones are under the scanned directory, empty scans everything
+    pub include_paths: Vec<String>,
     pub default_dir: Option<PathBuf>,
+    /// Huffman encode the cache file, disable to make it easier to inspect
+    pub compress_cache: bool,
+    /// Hand over to an already running instance instead of starting a new one
+    pub single_instance: bool,
+    /// Remote names tried in order when picking the primary remote
+    pub remote_priority: Vec<String>,
+    /// Ask the primary remote for its default branch over the network instead of trusting
+    /// remote-tracking refs, slows down scanning and needs ssh-agent for ssh remotes
+    pub fetch_default_branch: bool,
+    /// What projects are labeled by in the list
+    pub label: ProjectLabel,
+    /// Row format of the list like `{name:<24} {size:>8} {modified:relative}`, replaces `label`
+    pub list_template: Option<String>,
+    /// Show paths under the home directory as `~/...`
+    pub tilde_paths: bool,
+    /// How labels too wide for the list are cut
+    pub truncation: Truncation,
+    /// Show Nerd Font glyphs for languages, hosts and repo states, needs a patched font
+    pub use_icons: bool,
+    /// Mark rows with the linguist color of their main language, colors the icon with `use_icons`
+    pub language_markers: bool,
+    /// Draw the languages of the selected project as a donut on terminals with kitty or sixel
+    /// graphics, next to the table
+    pub language_chart: bool,
+    /// Conditional row styles applied in order, the default dims projects without commits
+    pub row_styles: Vec<RowStyleConfig>,
+    /// Extra sortings like `size / loc`, cycled through after the built-in ones
+    pub custom_sorts: Vec<CustomSortConfig>,
+    /// Moving past either end of the list with j/k continues from the other end
+    pub wrap_navigation: bool,
+    /// Digits typed before a movement repeat it, `15j` moves 15 down like in vim. This takes 1, 2
+    /// and 3 from toggling the first panes, F1, F2 and F3 still do
+    pub count_prefix: bool,
+    /// How line and file counts are shown
+    pub number_format: NumberFormat,
+    /// Colors of the TUI, `NO_COLOR` and `--no-color` switch to `no-color`
+    pub theme: Theme,
+    /// Languages left out of totals and LOC sorting, by their tokei name like `JSON`
+    pub ignore_languages: Vec<String>,
+    /// Languages left out for single projects on top of `ignore_languages`, keyed by project path
+    pub project_ignore_languages: HashMap<PathBuf, Vec<String>>,
+    /// Run `direnv allow` before opening a shell in a project with an `.envrc`
+    pub allow_direnv: bool,
+    /// Which actions ask before they're carried out
+    pub confirm: ConfirmPolicy,
+    /// Actions that ask, `true`, or don't, `false`, regardless of `confirm`, like `archive = false`
+    pub confirm_actions: HashMap<ConfirmAction, bool>,
+    /// Set the terminal title to the selected project while ymir runs
+    pub terminal_title: bool,
+    /// Tell the terminal the directory of shells opened in projects with OSC 7, so new tabs and
+    /// panes start there
+    pub report_cwd: bool,
+    /// Command `e` shows the project in, the path is appended, `xdg-open` when unset, `open` on
+    /// macOS and `explorer` on Windows
+    pub file_manager: Option<String>,
+    /// Hours after which the age in a pane's title is called stale, shift and the pane's function
+    /// key refreshes it
+    pub pane_stale_hours: u32,
+    /// Rescans keep the stats of projects whose directory wasn't modified and whose HEAD didn't
+    /// move since they were scanned, `--incremental` turns it on for one run
+    pub incremental: bool,
+    /// Scans note when every file was modified for the F9 pane, that's a stat of every file
+    pub recent_files: bool,
+    /// Ask GitHub about projects hosted there in the background, for their stars, open issues and
+    /// whether they're archived
+    pub github: bool,
+    /// Token for GitHub, raises its limit from 60 requests an hour to 5000 and reaches private
+    /// repositories, `ymir auth login github` keeps it in the system keyring instead which is read
+    /// first
+    pub github_token: Option<String>,
+    /// Days without commits before `ymir digest` calls a project stale
+    pub stale_days: u32,
+    /// Show `ymir digest` as a desktop notification through `notify-send`
+    pub digest_notify: bool,
+    /// Url `ymir digest` posts its summary to as JSON, `{"text": ...}`
+    pub digest_webhook: Option<String>,
+    /// Url `ymir refresh` posts what changed to as JSON once the scan finishes
+    pub refresh_webhook: Option<String>,
+    /// Shell command run after `ymir refresh`, it gets the changes in `YMIR_*` variables
+    pub refresh_command: Option<String>,
+    /// Rank with zoxide's frecency in the Frecency sorting and add opened projects to zoxide
+    pub zoxide: bool,
+    /// Projects larger than this, like `30G`, are listed without counting their lines of code
+    /// until asked to with `c`
+    pub max_project_size: Option<String>,
+    /// Where archived projects are tarred into, `~/archive` when unset
+    pub archive_dir: Option<PathBuf>,
+    /// Projects larger than this, like `100M`, aren't suggested for archiving, unset for any size
+    pub archive_max_size: Option<String>,
+    /// Keep `.git` in archives exported with A
+    pub export_git: bool,
+    /// Show a COCOMO estimate of the effort behind a project in the info pane and `show --json`
+    pub estimate_effort: bool,
+    /// Yearly salary the effort estimate is priced at, in any currency
+    pub effort_salary: u32,
 }
 
-fn pre_config() -> anyhow::Result<String> {
+/// Which actions ask for `y` before they're carried out
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfirmPolicy {
+    Always,
+    /// Only the ones that delete or replace something, see [`ConfirmAction::is_destructive`]
+    #[default]
+    Destructive,
+    Never,
+}
+
+/// Actions [`ConfirmPolicy`] applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfirmAction {
+    /// Opening a shell or the editor in a project
+    Open,
+    /// Running one of the project's tasks
+    Task,
+    /// Showing the project in the file manager
+    Reveal,
+    /// Deleting an extra clone
+    Delete,
+    /// Replacing an extra clone with a worktree of its primary
+    Worktree,
+    /// Tarring a project into `archive_dir` and deleting it
+    Archive,
+    /// Writing an archive, bookmarks or a workspace to a new file, existing ones are refused
+    Export,
+}
+
+impl ConfirmAction {
+    pub const fn is_destructive(self) -> bool {
+        matches!(self, Self::Delete | Self::Worktree | Self::Archive)
+    }
+}
+
+/// How a project is shown in the list
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProjectLabel {
+    /// Full path of the project directory
+    #[default]
+    Path,
+    /// Name of the project directory
+    Name,
+    /// `owner/repo` of the primary remote, the directory name when there is none
+    Remote,
+}
+
+/// Where labels too wide for the list lose characters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Truncation {
+    /// Whatever doesn't fit is cut off
+    #[default]
+    End,
+    /// `~/work/cl…/foo`, keeps the project name at the end visible
+    Middle,
+}
+
+/// How counts are written out
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NumberFormat {
+    /// `1234567`
+    Raw,
+    /// `1,234,567`
+    #[default]
+    Separated,
+    /// `1.2M`
+    Short,
+}
+
+/// Colors of the TUI, see [`crate::theme`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Theme {
+    #[default]
+    Default,
+    /// White on black, what colors marked is bold and states are spelled out
+    HighContrast,
+    /// Terminal colors only, the selection is reversed and states are spelled out
+    NoColor,
+}
+
+/// One rule of `row_styles`, see [`crate::row_style`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RowStyleConfig {
+    /// Condition like `size > 1G`, the rule applies to every row without one
+    pub when: Option<String>,
+    /// Color name or `#rrggbb`
+    pub fg: Option<String>,
+    pub bg: Option<String>,
+    pub bold: bool,
+    pub italic: bool,
+    pub dim: bool,
+    pub gradient: Option<RowGradientConfig>,
+}
+
+/// Sorting by the value of `expr` for each project
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CustomSortConfig {
+    /// Shown above the list while it's selected
+    pub name: String,
+    pub expr: String,
+}
+
+/// Foreground going from `from` at `min` to `to` at `max` of `value`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RowGradientConfig {
+    pub value: String,
+    pub from: String,
+    pub to: String,
+    pub min: Option<String>,
+    pub max: String,
+}
+
+/// Profile given with `--profile`, set once before anything is read
+static PROFILE: OnceLock<String> = OnceLock::new();
+
+/// Keeps the config, cache and history of this run apart in a directory of their own
+pub fn set_profile(name: &str) -> Result<()> {
+    check_profile(name)?;
+    if PROFILE.set(name.to_string()).is_err() {
+        return Err(YmirError::Profile("Profile is already set".to_string()));
+    }
+    Ok(())
+}
+
+/// Fails for names that would put the profile outside of the profiles directory or hide it
+fn check_profile(name: &str) -> Result<()> {
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        return Err(YmirError::Profile(format!("Invalid profile name: {name}")));
+    }
+    Ok(())
+}
+
+/// Profile of this run, `None` for the default one
+pub fn profile() -> Option<&'static str> {
+    PROFILE.get().map(String::as_str)
+}
+
+/// Directory with the config, cache and history of the current profile
+pub fn app_dir() -> Option<PathBuf> {
     let Some(config_dir) = dirs::config_dir() else {
         error!("Failed to find config_directory");
-        bail!("Failed to find config_directory")
+        return None;
     };
 
-    let app_dir = format!("{}/{}", config_dir.display(), env!("CARGO_PKG_NAME"));
+    Some(profile_dir(
+        &config_dir.join(env!("CARGO_PKG_NAME")),
+        profile(),
+    ))
+}
 
-    if !Path::new(&app_dir).exists() {
+/// Directory of `profile` in `app_dir`, `app_dir` itself for the default one
+fn profile_dir(app_dir: &Path, profile: Option<&str>) -> PathBuf {
+    match profile {
+        Some(profile) => app_dir.join("profiles").join(profile),
+        None => app_dir.to_path_buf(),
+    }
+}
+
+fn pre_config() -> Result<String> {
+    let Some(app_dir) = app_dir() else {
+        return Err(YmirError::NoConfigDir);
+    };
+
+    if !app_dir.exists() {
         if let Err(err) = fs::create_dir_all(&app_dir) {
             error!("Failed to create config directory: {err}");
-            bail!("Failed to create config directory")
+            return Err(YmirError::io(app_dir, err));
         }
     }
 
-    Ok(app_dir)
+    Ok(app_dir.display().to_string())
 }
 
 impl Settings {
@@ -64,34 +333,35 @@ impl Settings {
 
     /// Load config
     pub fn new() -> Self {
-        let Some(config_dir) = dirs::config_dir() else {
-            error!("Failed to find config_directory");
+        let Some(app_dir) = app_dir() else {
             return Self::default();
         };
 
-        let config_path = format!(
-            "{}/{}/config.toml",
-            config_dir.display(),
-            env!("CARGO_PKG_NAME")
-        );
-
-        if let Ok(file) = fs::read_to_string(&config_path) {
-            return toml::from_str(&file).unwrap_or_default();
+        let config_path = app_dir.join("config.toml");
+        if !config_path.exists() {
+            return Self::default();
         }
 
-        Self::default()
+        Self::read(&config_path).unwrap_or_else(|err| {
+            error!("{err}");
+            Self::default()
+        })
     }
 
-    pub fn write_config() -> anyhow::Result<()> {
-        let default_config = Self::default();
-        let serialized = format!(
-            "ignore_dirs = {:?}\ndefault_dir = None",
-            default_config.ignore_dirs
-        );
+    /// Settings from the config file at `path`
+    pub fn read(path: &Path) -> Result<Self> {
+        let file = fs::read_to_string(path).map_err(|err| YmirError::io(path, err))?;
+        toml::from_str(&file).map_err(|source| YmirError::Config {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
 
-        let Ok(app_dir) = pre_config() else {
-            bail!("Failed to find config_dir");
-        };
+    pub fn write_config() -> Result<()> {
+        let serialized =
+            toml::to_string_pretty(&Self::default()).expect("Default settings are serializable");
+
+        let app_dir = pre_config()?;
 
         let config_path = format!("{app_dir}/config.toml");
 
@@ -105,6 +375,105 @@ impl Settings {
 
         Ok(())
     }
+
+    /// `path` with `tilde_paths` applied
+    pub fn display_path(&self, path: &Path) -> String {
+        if self.tilde_paths {
+            tilde(path)
+        } else {
+            path.display().to_string()
+        }
+    }
+
+    /// Whether `action` asks before it's carried out
+    pub fn confirms(&self, action: ConfirmAction) -> bool {
+        self.confirm_actions
+            .get(&action)
+            .copied()
+            .unwrap_or(match self.confirm {
+                ConfirmPolicy::Always => true,
+                ConfirmPolicy::Destructive => action.is_destructive(),
+                ConfirmPolicy::Never => false,
+            })
+    }
+
+    /// What the project is labeled by in the list, see `label`
+    pub fn label(&self, project: &Project) -> String {
+        match self.label {
+            ProjectLabel::Path => self.display_path(&project.path),
+            label => project.label(label),
+        }
+    }
+
+    /// `max_project_size` in bytes, an invalid one is logged and ignored
+    pub fn max_project_size(&self) -> Option<u64> {
+        let text = self.max_project_size.as_deref()?;
+        parse_size(text)
+            .inspect_err(|err| error!("Invalid max_project_size: {err:#}"))
+            .ok()
+    }
+
+    /// `archive_max_size` in bytes, an invalid one is logged and ignored
+    pub fn archive_max_size(&self) -> Option<u64> {
+        let text = self.archive_max_size.as_deref()?;
+        parse_size(text)
+            .inspect_err(|err| error!("Invalid archive_max_size: {err:#}"))
+            .ok()
+    }
+
+    /// `archive_dir`, falling back to `~/archive`
+    pub fn archive_dir(&self) -> Option<PathBuf> {
+        self.archive_dir
+            .clone()
+            .or_else(|| dirs::home_dir().map(|v| v.join("archive")))
+    }
+
+    /// Whether `language` is left out of the totals of the project at `project`
+    pub fn ignores_language(&self, project: &Path, language: LanguageType) -> bool {
+        self.ignore_languages
+            .iter()
+            .chain(
+                self.project_ignore_languages
+                    .get(project)
+                    .into_iter()
+                    .flatten(),
+            )
+            .any(|name| name.eq_ignore_ascii_case(language.name()))
+    }
+
+    /// Sets `default_dir` in the config file, leaving everything else in it as it is
+    pub fn save_default_dir(dir: &Path) -> Result<()> {
+        let config_path = PathBuf::from(format!("{}/config.toml", pre_config()?));
+        set_default_dir(&config_path, dir)?;
+        info!("default_dir saved to {}", config_path.display());
+        Ok(())
+    }
+}
+
+/// Writes `default_dir` into the config at `config_path`, editing the document in place so
+/// comments, ordering and formatting survive, a missing config starts out empty
+fn set_default_dir(config_path: &Path, dir: &Path) -> Result<()> {
+    let mut config = match fs::read_to_string(config_path) {
+        Ok(file) => file
+            .parse::<toml_edit::DocumentMut>()
+            .map_err(|err| YmirError::Config {
+                path: config_path.to_path_buf(),
+                source: serde::de::Error::custom(err),
+            })?,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => toml_edit::DocumentMut::new(),
+        Err(err) => return Err(YmirError::io(config_path, err)),
+    };
+    let mut value = toml_edit::Value::from(dir.to_string_lossy().into_owned());
+    // A comment after the old value stays after the new one
+    if let Some(old) = config
+        .get("default_dir")
+        .and_then(toml_edit::Item::as_value)
+    {
+        *value.decor_mut() = old.decor().clone();
+    }
+    config["default_dir"] = toml_edit::Item::Value(value);
+
+    fs::write(config_path, config.to_string()).map_err(|err| YmirError::io(config_path, err))
 }
 
 impl Default for Settings {
@@ -114,7 +483,55 @@ impl Default for Settings {
                 .iter()
                 .map(|&v| (*v).to_string())
                 .collect(),
+            global_gitignore: true,
+            include_paths: Vec::new(),
             default_dir: None,
+            compress_cache: true,
+            single_instance: false,
+            remote_priority: vec!["origin".to_string()],
+            fetch_default_branch: false,
+            label: ProjectLabel::Path,
+            list_template: None,
+            tilde_paths: false,
+            truncation: Truncation::End,
+            use_icons: false,
+            language_markers: true,
+            language_chart: true,
+            row_styles: vec![RowStyleConfig {
+                when: Some("commits == 0".to_string()),
+                fg: Some("#b91c1c".to_string()),
+                ..RowStyleConfig::default()
+            }],
+            custom_sorts: Vec::new(),
+            wrap_navigation: false,
+            count_prefix: false,
+            number_format: NumberFormat::Separated,
+            theme: Theme::Default,
+            ignore_languages: Vec::new(),
+            project_ignore_languages: HashMap::new(),
+            allow_direnv: false,
+            confirm: ConfirmPolicy::Destructive,
+            confirm_actions: HashMap::new(),
+            terminal_title: true,
+            report_cwd: true,
+            file_manager: None,
+            pane_stale_hours: 24,
+            incremental: false,
+            recent_files: true,
+            github: false,
+            github_token: None,
+            stale_days: 90,
+            digest_notify: false,
+            digest_webhook: None,
+            refresh_webhook: None,
+            refresh_command: None,
+            zoxide: false,
+            max_project_size: None,
+            archive_dir: None,
+            archive_max_size: Some("100M".to_string()),
+            export_git: false,
+            estimate_effort: false,
+            effort_salary: 56_286,
         }
     }
 }
@@ -122,56 +539,215 @@ impl Default for Settings {
 #[derive(Default, Debug)]
 pub struct Cache {
     pub projects: Vec<Project>,
+    /// Whether the serialized payload is huffman encoded
+    pub compressed: bool,
 }
 
 impl Cache {
+    /// Location of the cache file
+    pub fn path() -> Option<PathBuf> {
+        app_dir().map(|dir| dir.join("cache"))
+    }
+
+    /// Projects from the cache, empty when it's missing or unreadable
     pub fn read_cache() -> Vec<Project> {
-        let Some(config_dir) = dirs::config_dir() else {
-            error!("Failed to find config_directory");
-            return Vec::new();
-        };
+        Self::try_read_cache().unwrap_or_else(|err| {
+            error!("Failed to read cache: {err:#}");
+            Vec::new()
+        })
+    }
 
-        let cache_path = format!("{}/{}/cache", config_dir.display(), env!("CARGO_PKG_NAME"));
-
-        if let Ok(file) = fs::read(&cache_path) {
-            let mut cursor = std::io::Cursor::new(file.as_slice());
-            let cache: Self = match CacheSerializer::deserialize(&mut cursor) {
-                Ok(cache) => cache,
-                Err(e) => {
-                    eprintln!("{e:#?}");
-                    return Vec::new();
-                }
-            };
-            return cache.projects;
-        }
+    /// Projects from the cache, empty when there is no cache yet
+    pub fn try_read_cache() -> Result<Vec<Project>> {
+        let Some(cache_path) = Self::path() else {
+            return Err(YmirError::NoConfigDir);
+        };
 
-        error!("Failed to find file");
-        Vec::new()
+        let _lock = CacheLock::shared(&cache_path)?;
+        Self::read_locked(&cache_path)
     }
 
-    pub fn create_cache(projects: &[Project]) -> anyhow::Result<Self> {
-        let Ok(app_dir) = pre_config() else {
-            bail!("Failed to find config_dir");
+    /// Projects from the cache at `cache_path`, the caller holds a lock on it
+    fn read_locked(cache_path: &Path) -> Result<Vec<Project>> {
+        let Ok(file) = File::open(cache_path) else {
+            return Ok(Vec::new());
         };
 
+        // SAFETY: Writers hold the exclusive lock and replace the file by renaming, so the
+        // mapped file is never modified in place while a lock is held.
+        let mmap = unsafe { Mmap::map(&file) }.map_err(|err| YmirError::io(cache_path, err))?;
+        let mut cursor = std::io::Cursor::new(&mmap[..]);
+        let cache: Self = CacheSerializer::deserialize(&mut cursor)?;
+
+        Ok(cache.projects)
+    }
+
+    /// Keeps the cached projects `keep` holds for, returns how many were dropped
+    ///
+    /// The cache stays locked from reading it to writing it back, so a scan finishing in between
+    /// isn't lost.
+    pub fn retain(compressed: bool, keep: impl FnMut(&Project) -> bool) -> Result<usize> {
+        let cache_path = PathBuf::from(format!("{}/cache", pre_config()?));
+        let _lock = CacheLock::exclusive(&cache_path)?;
+
+        let mut projects = Self::read_locked(&cache_path)?;
+        let before = projects.len();
+        projects.retain(keep);
+        let dropped = before - projects.len();
+
+        let serialized = CacheSerializer::serialize(&Self {
+            projects,
+            compressed,
+        })?;
+        Self::write_locked(&cache_path, &serialized)
+            .map_err(|err| YmirError::io(&cache_path, err))?;
+        Ok(dropped)
+    }
+
+    /// Replaces the cache at `cache_path`, the caller holds the exclusive lock on it
+    fn write_locked(cache_path: &Path, serialized: &[u8]) -> std::io::Result<()> {
+        // Readers may have the old file mapped, so write next to it and swap it in atomically
+        let tmp_path = cache_path.with_extension("tmp");
+        fs::write(&tmp_path, serialized).and_then(|()| fs::rename(&tmp_path, cache_path))
+    }
+
+    /// Projects from a cache file that isn't the one of this machine, like an exported one
+    pub fn read_file(path: &Path) -> Result<Vec<Project>> {
+        let bytes = fs::read(path).map_err(|err| YmirError::io(path, err))?;
+        let cache: Self = CacheSerializer::deserialize(&mut std::io::Cursor::new(&bytes[..]))?;
+        Ok(cache.projects)
+    }
+
+    pub fn create_cache(projects: &[Project], compressed: bool) -> Result<Self> {
+        let app_dir = pre_config()?;
+
         let config_path = format!("{app_dir}/cache");
 
         let cache = Self {
             projects: projects.to_vec(),
+            compressed,
         };
 
-        let Ok(serialized) = CacheSerializer::serialize(&cache) else {
-            bail!("Failed to serialize cache");
-        };
+        let serialized = CacheSerializer::serialize(&cache)?;
 
-        if !Path::new(&config_path).exists() {
-            if let Err(err) = fs::write(&config_path, serialized) {
-                error!("Failed to write config: {err}");
-            } else {
-                info!("Default config saved to {config_path}");
-            }
+        let config_path = PathBuf::from(config_path);
+        let _lock = CacheLock::exclusive(&config_path)?;
+
+        if let Err(err) = Self::write_locked(&config_path, &serialized) {
+            error!("Failed to write cache: {err}");
+        } else {
+            info!("Cache saved to {}", config_path.display());
         }
 
         Ok(cache)
     }
 }
+
+/// Advisory lock on a file next to the cache, released on drop
+struct CacheLock(File);
+
+impl CacheLock {
+    fn open(cache_path: &Path, lock: impl FnOnce(&File) -> std::io::Result<()>) -> Result<Self> {
+        let lock_path = cache_path.with_extension("lock");
+        File::options()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .and_then(|file| lock(&file).map(|()| Self(file)))
+            .map_err(|err| YmirError::io(lock_path, err))
+    }
+
+    /// Waits until no other instance is writing the cache
+    fn shared(cache_path: &Path) -> Result<Self> {
+        Self::open(cache_path, File::lock_shared)
+    }
+
+    /// Waits until every other instance is done with the cache
+    fn exclusive(cache_path: &Path) -> Result<Self> {
+        Self::open(cache_path, |file| {
+            if file.try_lock().is_err() {
+                // Logged only, this also runs on the TUI's scan thread
+                info!("Cache is in use by another ymir instance, waiting");
+                file.lock()?;
+            }
+            Ok(())
+        })
+    }
+}
+
+impl Drop for CacheLock {
+    fn drop(&mut self) {
+        if let Err(err) = self.0.unlock() {
+            error!("Failed to unlock cache: {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use tokei::LanguageType;
+
+    use super::{check_profile, profile_dir, set_default_dir, Settings};
+    use crate::test_utils::temp_dir;
+
+    #[test]
+    fn saving_default_dir_keeps_the_rest_of_the_config() {
+        let dir = temp_dir("config");
+        let config = dir.join("config.toml");
+
+        set_default_dir(&config, Path::new("/src")).unwrap();
+        assert_eq!(
+            fs::read_to_string(&config).unwrap(),
+            "default_dir = \"/src\"\n"
+        );
+
+        let original = "# Where ymir looks\ndefault_dir = \"/src\" # moved\n\n[[custom_sorts]]\nname = \"Big\"\nexpr = \"size\"\n";
+        fs::write(&config, original).unwrap();
+        set_default_dir(&config, Path::new("/work")).unwrap();
+        assert_eq!(
+            fs::read_to_string(&config).unwrap(),
+            original.replace("\"/src\"", "\"/work\"")
+        );
+
+        fs::write(&config, "default_dir = ").unwrap();
+        assert!(set_default_dir(&config, Path::new("/work")).is_err());
+        assert_eq!(fs::read_to_string(&config).unwrap(), "default_dir = ");
+    }
+
+    #[test]
+    fn ignores_languages_globally_or_per_project() {
+        let settings = Settings {
+            ignore_languages: vec!["json".to_string()],
+            project_ignore_languages: [("/src/site".into(), vec!["HTML".to_string()])].into(),
+            ..Settings::default()
+        };
+        let ignores =
+            |project: &str, language| settings.ignores_language(Path::new(project), language);
+
+        assert!(ignores("/src/ymir", LanguageType::Json));
+        assert!(ignores("/src/site", LanguageType::Json));
+        assert!(ignores("/src/site", LanguageType::Html));
+        assert!(!ignores("/src/ymir", LanguageType::Html));
+        assert!(!ignores("/src/site/docs", LanguageType::Html));
+        assert!(!ignores("/src/site", LanguageType::Rust));
+    }
+
+    #[test]
+    fn profiles_get_a_directory_of_their_own() {
+        let app_dir = Path::new("/home/me/.config/ymir");
+        assert_eq!(profile_dir(app_dir, None), app_dir);
+        assert_eq!(
+            profile_dir(app_dir, Some("work")),
+            Path::new("/home/me/.config/ymir/profiles/work")
+        );
+
+        assert!(check_profile("work").is_ok());
+        assert!(check_profile("client-2").is_ok());
+        for name in ["", ".", "..", ".hidden", "../work", "a/b", "a\\b"] {
+            assert!(check_profile(name).is_err(), "{name:?} was accepted");
+        }
+    }
+}
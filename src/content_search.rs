@@ -0,0 +1,195 @@
+//! Lines of files in every project matching a query, for `#`
+//!
+//! Files are walked the way ripgrep does it, `.gitignore`, hidden files and binaries are skipped
+//! along with what `ignore_dirs` names. Queries are the ones of `:commits`, text matched ignoring
+//! case or a `/regex/`.
+
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+};
+
+use ignore::WalkBuilder;
+
+use crate::commit_search::Pattern;
+
+/// Matches kept in total, the search stops once there are this many
+pub const MAX_MATCHES: usize = 500;
+/// Files with a NUL byte this early are taken for binaries
+const BINARY_PEEK: usize = 8 * 1024;
+/// Characters of a matching line kept
+const MAX_LINE: usize = 200;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentMatch {
+    /// Project the file belongs to, the innermost one for nested projects
+    pub project: PathBuf,
+    pub path: PathBuf,
+    /// Starting at 1
+    pub line: usize,
+    pub text: String,
+}
+
+/// Matching lines in files of `projects`, grouped by project in their order
+pub fn search(
+    projects: &[PathBuf],
+    pattern: &Pattern,
+    ignore_dirs: &[String],
+) -> Vec<ContentMatch> {
+    let mut sorted = projects.to_vec();
+    sorted.sort();
+    // Nested projects are walked as part of the outer one
+    let mut roots: Vec<&PathBuf> = Vec::new();
+    for project in &sorted {
+        if !roots.last().is_some_and(|v| project.starts_with(v)) {
+            roots.push(project);
+        }
+    }
+
+    let mut matches = Vec::new();
+    for root in roots {
+        let ignore_dirs = ignore_dirs.to_vec();
+        let walk = WalkBuilder::new(root)
+            .sort_by_file_name(Ord::cmp)
+            .filter_entry(move |e| {
+                !e.file_type().is_some_and(|v| v.is_dir())
+                    || !ignore_dirs.iter().any(|v| e.file_name() == v.as_str())
+            })
+            .build();
+
+        for entry in walk {
+            let entry = match entry {
+                Ok(v) => v,
+                Err(err) => {
+                    warn!("Failed to read {err}");
+                    continue;
+                }
+            };
+            if !entry.file_type().is_some_and(|v| v.is_file()) {
+                continue;
+            }
+            let Some(project) = projects
+                .iter()
+                .filter(|v| entry.path().starts_with(v))
+                .max_by_key(|v| v.components().count())
+            else {
+                continue;
+            };
+
+            search_file(entry.path(), project, pattern, &mut matches);
+            if matches.len() >= MAX_MATCHES {
+                matches.truncate(MAX_MATCHES);
+                break;
+            }
+        }
+        if matches.len() >= MAX_MATCHES {
+            break;
+        }
+    }
+
+    // Files of nested projects were found while walking the outer one
+    matches.sort_by_key(|v| {
+        let order = projects.iter().position(|p| *p == v.project);
+        (order, v.path.clone(), v.line)
+    });
+    matches
+}
+
+/// Read a line at a time so large files, logs and dumps among them, aren't held in memory whole
+fn search_file(path: &Path, project: &Path, pattern: &Pattern, matches: &mut Vec<ContentMatch>) {
+    let Ok(file) = File::open(path) else {
+        return;
+    };
+    let mut reader = BufReader::with_capacity(BINARY_PEEK, file);
+    match reader.fill_buf() {
+        Ok(start) if !start.contains(&0) => {}
+        _ => return,
+    }
+
+    let mut bytes = Vec::new();
+    for i in 1.. {
+        bytes.clear();
+        match reader.read_until(b'\n', &mut bytes) {
+            Ok(0) => return,
+            Ok(_) => {}
+            Err(err) => {
+                warn!("Failed to read {}: {err}", path.display());
+                return;
+            }
+        }
+        let line = String::from_utf8_lossy(&bytes);
+        if !pattern.matches(line.trim_end_matches(['\n', '\r'])) {
+            continue;
+        }
+        matches.push(ContentMatch {
+            project: project.to_path_buf(),
+            path: path.to_path_buf(),
+            line: i,
+            text: line.trim().chars().take(MAX_LINE).collect(),
+        });
+        if matches.len() >= MAX_MATCHES {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::search;
+    use crate::{commit_search::Pattern, test_utils::temp_dir};
+
+    #[test]
+    fn finds_lines_in_their_innermost_project() {
+        let dir = temp_dir("content-search");
+        let app = dir.join("app");
+        let vendored = app.join("vendor").join("dep");
+        for path in [&vendored, &app.join("node_modules"), &app.join("target")] {
+            fs::create_dir_all(path).unwrap();
+        }
+        fs::write(app.join(".gitignore"), "target\n").unwrap();
+        fs::write(app.join("main.rs"), "fn main() {\n    // TODO: retry\n}\n").unwrap();
+        fs::write(vendored.join("lib.rs"), "// todo\n").unwrap();
+        fs::write(app.join("node_modules").join("index.js"), "// TODO\n").unwrap();
+        fs::write(app.join("target").join("out.rs"), "// TODO\n").unwrap();
+        fs::write(app.join("blob.bin"), b"TODO\0").unwrap();
+        // Ignore files only apply inside repositories, as with git
+        fs::create_dir_all(app.join(".git")).unwrap();
+
+        let projects = [app.clone(), vendored.clone()];
+        let matches = search(
+            &projects,
+            &Pattern::parse("todo").unwrap(),
+            &["node_modules".to_string()],
+        );
+        let found: Vec<_> = matches
+            .iter()
+            .map(|v| (&v.project, &v.path, v.line, v.text.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (&app, &app.join("main.rs"), 2, "// TODO: retry"),
+                (&vendored, &vendored.join("lib.rs"), 1, "// todo"),
+            ]
+        );
+    }
+
+    #[test]
+    fn streams_files_past_the_binary_peek() {
+        let dir = temp_dir("content-search-large");
+        let mut text = "filler\r\n".repeat(super::BINARY_PEEK);
+        text.push_str("needle\r\n");
+        fs::write(dir.join("large.log"), text).unwrap();
+
+        let matches = search(
+            &[dir.to_path_buf()],
+            &Pattern::parse("/^needle$/").unwrap(),
+            &[],
+        );
+        let found: Vec<_> = matches.iter().map(|v| (v.line, v.text.as_str())).collect();
+        assert_eq!(found, [(super::BINARY_PEEK + 1, "needle")]);
+    }
+}
@@ -0,0 +1,157 @@
+//! Tokens kept in the system keyring instead of the config, `ymir auth` manages them
+//!
+//! Every profile has its own entries, under the `ymir` service for the default one and
+//! `ymir-<profile>` for the rest.
+
+use std::{
+    fmt::Display,
+    io::{self, BufRead, IsTerminal, Read, Write},
+};
+
+use anyhow::{bail, Context};
+use keyring::Entry;
+use ratatui::crossterm::terminal;
+
+use crate::config;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Credential {
+    GitHub,
+    GitLab,
+}
+
+impl Credential {
+    pub const ALL: [Self; 2] = [Self::GitHub, Self::GitLab];
+
+    /// Name on the command line and of the keyring entry
+    pub fn key(self) -> &'static str {
+        match self {
+            Self::GitHub => "github",
+            Self::GitLab => "gitlab",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|v| v.key().eq_ignore_ascii_case(name))
+    }
+
+    fn entry(self) -> keyring::Result<Entry> {
+        Entry::new(&service(config::profile()), self.key())
+    }
+
+    /// Token stored for this, `None` when there's none or the keyring can't be reached
+    pub fn token(self) -> Option<String> {
+        match self.entry().and_then(|v| v.get_password()) {
+            Ok(token) => Some(token),
+            Err(keyring::Error::NoEntry) => None,
+            Err(err) => {
+                warn!("Failed to read the {self} token from the keyring: {err}");
+                None
+            }
+        }
+    }
+
+    /// Whether a token is stored, errors when the keyring can't be reached
+    pub fn stored(self) -> anyhow::Result<bool> {
+        match self.entry().and_then(|v| v.get_password()) {
+            Ok(_) => Ok(true),
+            Err(keyring::Error::NoEntry) => Ok(false),
+            Err(err) => Err(err).context("Failed to read the keyring"),
+        }
+    }
+
+    pub fn store(self, token: &str) -> anyhow::Result<()> {
+        self.entry()
+            .and_then(|v| v.set_password(token))
+            .with_context(|| format!("Failed to store the {self} token in the keyring"))
+    }
+
+    /// Removes the stored token, `false` when there was none
+    pub fn remove(self) -> anyhow::Result<bool> {
+        match self.entry().and_then(|v| v.delete_credential()) {
+            Ok(()) => Ok(true),
+            Err(keyring::Error::NoEntry) => Ok(false),
+            Err(err) => Err(err)
+                .with_context(|| format!("Failed to remove the {self} token from the keyring")),
+        }
+    }
+}
+
+impl Display for Credential {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::GitHub => "GitHub",
+            Self::GitLab => "GitLab",
+        })
+    }
+}
+
+/// Keyring service the entries of `profile` are under
+fn service(profile: Option<&str>) -> String {
+    match profile {
+        Some(profile) => format!("{}-{profile}", env!("CARGO_PKG_NAME")),
+        None => env!("CARGO_PKG_NAME").to_string(),
+    }
+}
+
+/// Reads a line from stdin without echoing it when it's a terminal, so tokens can be typed or
+/// piped in without ending up in the shell history
+pub fn read_secret(prompt: &str) -> anyhow::Result<String> {
+    let stdin = io::stdin();
+    if !stdin.is_terminal() {
+        let mut line = String::new();
+        stdin.lock().read_line(&mut line)?;
+        return Ok(line.trim().to_string());
+    }
+
+    eprint!("{prompt}");
+    io::stderr().flush()?;
+    terminal::enable_raw_mode()?;
+    let mut bytes = Vec::new();
+    let mut cancelled = false;
+    for byte in stdin.lock().bytes() {
+        match byte {
+            Ok(b'\r' | b'\n') => break,
+            // Ctrl-c and Ctrl-d
+            Ok(3 | 4) => {
+                cancelled = true;
+                break;
+            }
+            // Backspace
+            Ok(8 | 127) => {
+                bytes.pop();
+            }
+            Ok(byte) => bytes.push(byte),
+            Err(err) => {
+                terminal::disable_raw_mode()?;
+                return Err(err.into());
+            }
+        }
+    }
+    terminal::disable_raw_mode()?;
+    eprintln!();
+
+    if cancelled {
+        bail!("Cancelled");
+    }
+    Ok(String::from_utf8(bytes)
+        .context("Token isn't valid UTF-8")?
+        .trim()
+        .to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{service, Credential};
+
+    #[test]
+    fn names_entries_by_credential_and_profile() {
+        assert_eq!(Credential::parse("GitHub"), Some(Credential::GitHub));
+        assert_eq!(Credential::parse("gitlab"), Some(Credential::GitLab));
+        assert_eq!(Credential::parse("bitbucket"), None);
+        assert_eq!(service(None), "ymir");
+        assert_eq!(service(Some("work")), "ymir-work");
+    }
+}
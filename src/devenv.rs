@@ -0,0 +1,83 @@
+//! Detection of containers and development environments a project is meant to be worked on in
+
+use std::{fmt::Display, path::Path};
+
+use serde::Serialize;
+
+use crate::error::YmirError;
+
+/// Development environments recognized by their configuration files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum DevEnv {
+    Dockerfile,
+    Compose,
+    Devcontainer,
+    Flake,
+    NixShell,
+    Direnv,
+}
+
+impl DevEnv {
+    pub const ALL: [Self; 6] = [
+        Self::Dockerfile,
+        Self::Compose,
+        Self::Devcontainer,
+        Self::Flake,
+        Self::NixShell,
+        Self::Direnv,
+    ];
+
+    /// Files, relative to the project root, that mark the environment as configured
+    const fn markers(self) -> &'static [&'static str] {
+        match self {
+            Self::Dockerfile => &["Dockerfile", "Containerfile"],
+            Self::Compose => &[
+                "compose.yaml",
+                "compose.yml",
+                "docker-compose.yaml",
+                "docker-compose.yml",
+            ],
+            Self::Devcontainer => &[".devcontainer/devcontainer.json", ".devcontainer.json"],
+            Self::Flake => &["flake.nix"],
+            Self::NixShell => &["shell.nix"],
+            Self::Direnv => &[".envrc"],
+        }
+    }
+
+    /// Environments configured in the project at `path`
+    pub fn detect(path: &Path) -> Vec<Self> {
+        Self::ALL
+            .into_iter()
+            .filter(|env| env.markers().iter().any(|v| path.join(v).is_file()))
+            .collect()
+    }
+
+    /// Sets up a dev shell on the host
+    pub const fn is_nix(self) -> bool {
+        matches!(self, Self::Flake | Self::NixShell)
+    }
+}
+
+impl TryFrom<u8> for DevEnv {
+    type Error = YmirError;
+
+    fn try_from(value: u8) -> Result<Self, YmirError> {
+        Self::ALL
+            .get(usize::from(value))
+            .copied()
+            .ok_or_else(|| YmirError::CorruptCache(format!("Invalid dev environment {value}")))
+    }
+}
+
+impl Display for DevEnv {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Dockerfile => write!(f, "Dockerfile"),
+            Self::Compose => write!(f, "Docker Compose"),
+            Self::Devcontainer => write!(f, "Dev Container"),
+            Self::Flake => write!(f, "Nix flake"),
+            Self::NixShell => write!(f, "Nix shell"),
+            Self::Direnv => write!(f, "direnv"),
+        }
+    }
+}
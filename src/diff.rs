@@ -0,0 +1,166 @@
+//! Differences between two sets of scanned projects, usually the cache before and after a scan
+
+use std::{fmt::Display, path::PathBuf};
+
+use serde_json::json;
+
+use crate::{projects::Project, utils::format_bytes};
+
+/// A project in both scans whose size or lines of code moved
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ProjectChange {
+    pub path: PathBuf,
+    pub size_change: i64,
+    pub code_change: i64,
+}
+
+/// What changed between an old and a new scan of the same roots
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ScanDiff {
+    pub added: Vec<PathBuf>,
+    pub removed: Vec<PathBuf>,
+    pub changed: Vec<ProjectChange>,
+    /// Projects in the new scan
+    pub projects: usize,
+    /// Bytes gained by all projects together, negative when they shrank
+    pub size_change: i64,
+}
+
+impl ScanDiff {
+    pub fn new(old: &[Project], new: &[Project]) -> Self {
+        let contains =
+            |projects: &[Project], path: &PathBuf| projects.iter().any(|p| p.path == *path);
+
+        let mut added: Vec<PathBuf> = new
+            .iter()
+            .map(|p| p.path.clone())
+            .filter(|path| !contains(old, path))
+            .collect();
+        let mut removed: Vec<PathBuf> = old
+            .iter()
+            .map(|p| p.path.clone())
+            .filter(|path| !contains(new, path))
+            .collect();
+        added.sort();
+        removed.sort();
+
+        let mut changed: Vec<ProjectChange> = new
+            .iter()
+            .filter_map(|project| {
+                let before = old.iter().find(|p| p.path == project.path)?;
+                let change = ProjectChange {
+                    path: project.path.clone(),
+                    size_change: signed(project.size) - signed(before.size),
+                    code_change: i64::from(project.languages_total.code)
+                        - i64::from(before.languages_total.code),
+                };
+                (change.size_change != 0 || change.code_change != 0).then_some(change)
+            })
+            .collect();
+        changed.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let size = |projects: &[Project]| signed(projects.iter().map(|p| p.size).sum());
+
+        Self {
+            added,
+            removed,
+            changed,
+            projects: new.len(),
+            size_change: size(new) - size(old),
+        }
+    }
+
+    /// Every added, removed and changed project under the summary line
+    pub fn report(&self) -> String {
+        let mut lines = vec![self.to_string()];
+        lines.extend(self.added.iter().map(|v| format!("+ {}", v.display())));
+        lines.extend(self.removed.iter().map(|v| format!("- {}", v.display())));
+        lines.extend(self.changed.iter().map(|v| {
+            format!(
+                "~ {} (size {}, code {:+} lines)",
+                v.path.display(),
+                format_size_change(v.size_change),
+                v.code_change
+            )
+        }));
+        lines.join("\n")
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "projects": self.projects,
+            "size_change": self.size_change,
+            "added": self.added,
+            "removed": self.removed,
+            "changed": self
+                .changed
+                .iter()
+                .map(|v| json!({
+                    "path": v.path,
+                    "size_change": v.size_change,
+                    "code_change": v.code_change,
+                }))
+                .collect::<Vec<_>>(),
+        })
+    }
+}
+
+fn signed(value: u64) -> i64 {
+    i64::try_from(value).unwrap_or(i64::MAX)
+}
+
+fn format_size_change(change: i64) -> String {
+    let sign = if change < 0 { "-" } else { "+" };
+    format!("{sign}{}", format_bytes(change.unsigned_abs()))
+}
+
+impl Display for ScanDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} projects, {} new, {} removed, {} changed, total size {}",
+            self.projects,
+            self.added.len(),
+            self.removed.len(),
+            self.changed.len(),
+            format_size_change(self.size_change)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{ProjectChange, ScanDiff};
+    use crate::test_utils::project;
+
+    #[test]
+    fn counts_added_and_removed() {
+        let old = [project("/a").with_size(4096), project("/b").with_size(1024)];
+        let new = [project("/a").with_size(2048), project("/c").with_size(1024)];
+
+        let diff = ScanDiff::new(&old, &new);
+        assert_eq!(
+            diff,
+            ScanDiff {
+                added: vec![PathBuf::from("/c")],
+                removed: vec![PathBuf::from("/b")],
+                changed: vec![ProjectChange {
+                    path: PathBuf::from("/a"),
+                    size_change: -2048,
+                    code_change: 0,
+                }],
+                projects: 2,
+                size_change: -2048,
+            }
+        );
+        assert_eq!(
+            diff.report(),
+            "2 projects, 1 new, 1 removed, 1 changed, total size -2.0K\n\
+             + /c\n\
+             - /b\n\
+             ~ /a (size -2.0K, code +0 lines)"
+        );
+    }
+}
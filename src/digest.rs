@@ -0,0 +1,204 @@
+//! Summary of repositories that need attention, for `ymir digest`
+//!
+//! Dirty worktrees and unpushed commits aren't cached since they change all the time, they're
+//! read from every cached repository when the digest is made. Staleness comes from the cache.
+
+use std::{fmt::Display, path::PathBuf, process::Command};
+
+use anyhow::bail;
+use git2::{Repository, StatusOptions};
+use log::warn;
+
+use crate::{
+    config::Settings,
+    error::YmirError,
+    projects::Project,
+    utils::{post_json, run_status},
+};
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// Repositories with something left to do
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Digest {
+    /// Repositories with uncommitted changes and how many files they touch
+    pub dirty: Vec<(PathBuf, usize)>,
+    /// Branches ahead of their upstream and by how many commits
+    pub unpushed: Vec<(PathBuf, usize)>,
+    /// Projects without commits for `stale_days` and how many days it has been
+    pub stale: Vec<(PathBuf, i64)>,
+}
+
+impl Digest {
+    /// Looks at every project, `now` is a unix timestamp
+    pub fn collect(projects: &[Project], stale_days: u32, now: i64) -> Self {
+        let mut digest = Self::default();
+
+        for project in projects {
+            if project.git_info.state.has_commits() {
+                let days = (now - i64::from(project.git_info.last_commit_date)) / SECONDS_PER_DAY;
+                if days >= i64::from(stale_days) {
+                    digest.stale.push((project.path.clone(), days));
+                }
+            }
+
+            let Ok(repo) = Repository::open(&project.path) else {
+                continue;
+            };
+            match changed_files(&repo) {
+                Ok(0) => {}
+                Ok(count) => digest.dirty.push((project.path.clone(), count)),
+                Err(err) => warn!("Failed to read status of {}: {err}", project.path.display()),
+            }
+            if let Some(ahead) = unpushed_commits(&repo).filter(|&v| v > 0) {
+                digest.unpushed.push((project.path.clone(), ahead));
+            }
+        }
+
+        digest.stale.sort_by_key(|(_, days)| -days);
+        digest
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dirty.is_empty() && self.unpushed.is_empty() && self.stale.is_empty()
+    }
+
+    /// Hands the digest to `notify-send` and posts it to `webhook`, whichever are set
+    pub fn send(&self, notify: bool, webhook: Option<&str>) -> Result<(), YmirError> {
+        let text = self.to_string();
+
+        if notify {
+            run_status(Command::new("notify-send").args(["ymir digest", &text]))?;
+        }
+
+        if let Some(webhook) = webhook {
+            // `text` is what Slack and Mattermost hooks read, `content` is Discord's
+            post_json(
+                webhook,
+                &serde_json::json!({ "text": text, "content": text }),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for Digest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return write!(f, "Nothing to do, every repository is clean and pushed");
+        }
+
+        let mut sections = Vec::new();
+        if !self.dirty.is_empty() {
+            sections.push(section("Uncommitted changes", &self.dirty, |v| {
+                format!("{v} files")
+            }));
+        }
+        if !self.unpushed.is_empty() {
+            sections.push(section("Unpushed commits", &self.unpushed, |v| {
+                format!("{v} commits")
+            }));
+        }
+        if !self.stale.is_empty() {
+            sections.push(section("Stale", &self.stale, |v| format!("{v} days")));
+        }
+        write!(f, "{}", sections.join("\n\n"))
+    }
+}
+
+fn section<T: Copy>(title: &str, entries: &[(PathBuf, T)], count: impl Fn(T) -> String) -> String {
+    let mut text = format!("{title} ({}):", entries.len());
+    for (path, value) in entries {
+        text.push_str(&format!("\n  {} ({})", path.display(), count(*value)));
+    }
+    text
+}
+
+/// Modified, staged and untracked files, ignored ones left out
+pub(crate) fn changed_files(repo: &Repository) -> Result<usize, YmirError> {
+    if repo.is_bare() {
+        return Ok(0);
+    }
+
+    let mut options = StatusOptions::new();
+    options
+        .include_untracked(true)
+        .include_ignored(false)
+        .exclude_submodules(true);
+    Ok(repo.statuses(Some(&mut options))?.len())
+}
+
+/// Commits on the checked out branch its upstream doesn't have, `None` without an upstream
+fn unpushed_commits(repo: &Repository) -> Option<usize> {
+    let head = repo.head().ok()?;
+    if !head.is_branch() {
+        return None;
+    }
+
+    let local = head.target()?;
+    let remote = git2::Branch::wrap(head).upstream().ok()?.get().target()?;
+    repo.graph_ahead_behind(local, remote)
+        .ok()
+        .map(|(ahead, _)| ahead)
+}
+
+/// `ymir digest`, prints the digest and sends it wherever the config asks
+pub fn run(projects: &[Project], settings: &Settings) -> anyhow::Result<()> {
+    if projects.is_empty() {
+        bail!("The cache is empty, run ymir once to scan for projects");
+    }
+
+    let digest = Digest::collect(
+        projects,
+        settings.stale_days,
+        chrono::Local::now().timestamp(),
+    );
+    println!("{digest}");
+
+    digest.send(settings.digest_notify, settings.digest_webhook.as_deref())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::Digest;
+    use crate::test_utils::{git, project, temp_dir};
+
+    const NOW: u32 = 40 * 24 * 60 * 60;
+
+    #[test]
+    fn finds_dirty_unpushed_and_stale() {
+        let root = temp_dir("digest");
+        let (upstream, clone) = (root.join("upstream"), root.join("clone"));
+        fs::create_dir_all(&upstream).unwrap();
+
+        git(&upstream, &["init", "--quiet", "--bare"]);
+        git(&root, &["clone", "--quiet", "upstream", "clone"]);
+        fs::write(clone.join("a"), "a").unwrap();
+        git(&clone, &["add", "a"]);
+        git(&clone, &["commit", "--quiet", "-m", "first"]);
+        git(&clone, &["push", "--quiet", "-u", "origin", "HEAD"]);
+        git(
+            &clone,
+            &["commit", "--quiet", "--allow-empty", "-m", "second"],
+        );
+        fs::write(clone.join("b"), "b").unwrap();
+
+        let projects = [
+            project(&clone).with_last_commit(NOW),
+            project(root.join("old")),
+        ];
+        let digest = Digest::collect(&projects, 30, i64::from(NOW));
+        assert_eq!(
+            digest,
+            Digest {
+                dirty: vec![(clone.clone(), 1)],
+                unpushed: vec![(clone, 1)],
+                stale: vec![(root.join("old"), 40)],
+            }
+        );
+    }
+}
@@ -0,0 +1,240 @@
+//! Directory browser for picking the scan root when none is given
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use ratatui::{
+    buffer::Buffer,
+    crossterm::event::{self, Event, KeyCode, KeyEvent},
+    layout::{Constraint, Layout, Rect},
+    style::{Style, Stylize},
+    symbols,
+    text::{Line, Span},
+    widgets::{
+        Block, Borders, HighlightSpacing, List, ListItem, ListState, Paragraph, StatefulWidget,
+        Widget,
+    },
+    DefaultTerminal,
+};
+
+use crate::{app::TEXT_FG_COLOR, config::Theme};
+
+/// Root picked in the browser
+pub struct PickedDir {
+    pub path: PathBuf,
+    /// Save it as `default_dir` so the browser isn't shown next time
+    pub save: bool,
+}
+
+pub struct DirPicker {
+    dir: PathBuf,
+    entries: Vec<PathBuf>,
+    state: ListState,
+    show_hidden: bool,
+    /// Picked directory waiting for the answer to saving it
+    confirm: Option<PathBuf>,
+    result: Option<Option<PickedDir>>,
+    theme: Theme,
+}
+
+impl DirPicker {
+    /// Starts browsing at `dir`
+    pub fn new(dir: PathBuf) -> Self {
+        let mut picker = Self {
+            dir,
+            entries: Vec::new(),
+            state: ListState::default(),
+            show_hidden: false,
+            confirm: None,
+            result: None,
+            theme: Theme::Default,
+        };
+        picker.load();
+        picker
+    }
+
+    pub const fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Runs until a directory is picked, `None` when the browser was closed
+    pub fn run(mut self, mut terminal: DefaultTerminal) -> anyhow::Result<Option<PickedDir>> {
+        loop {
+            terminal.draw(|frame| frame.render_widget(&mut self, frame.area()))?;
+
+            if let Event::Key(key) = event::read()? {
+                self.handle_key(key);
+            }
+
+            if let Some(result) = self.result.take() {
+                return Ok(result);
+            }
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) {
+        if key.kind != event::KeyEventKind::Press {
+            return;
+        }
+
+        if let Some(path) = self.confirm.take() {
+            match key.code {
+                KeyCode::Char('y' | 'Y') | KeyCode::Enter => {
+                    self.result = Some(Some(PickedDir { path, save: true }));
+                }
+                KeyCode::Char('n' | 'N') => {
+                    self.result = Some(Some(PickedDir { path, save: false }));
+                }
+                // Back to browsing
+                _ => {}
+            }
+            return;
+        }
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => self.result = Some(None),
+            KeyCode::Char('j') | KeyCode::Down => self.state.select_next(),
+            KeyCode::Char('k') | KeyCode::Up => self.state.select_previous(),
+            KeyCode::Char('g') | KeyCode::Home => self.state.select_first(),
+            KeyCode::Char('G') | KeyCode::End => self.state.select_last(),
+            KeyCode::Char('l') | KeyCode::Right | KeyCode::Enter => {
+                if let Some(entry) = self.state.selected().and_then(|i| self.entries.get(i)) {
+                    self.dir = entry.clone();
+                    self.load();
+                }
+            }
+            KeyCode::Char('h') | KeyCode::Left | KeyCode::Backspace => {
+                if let Some(parent) = self.dir.parent() {
+                    let child = self.dir.clone();
+                    self.dir = parent.to_path_buf();
+                    self.load();
+                    // Keep the directory we came from selected
+                    if let Some(index) = self.entries.iter().position(|v| *v == child) {
+                        self.state.select(Some(index));
+                    }
+                }
+            }
+            KeyCode::Char('.') => {
+                self.show_hidden = !self.show_hidden;
+                self.load();
+            }
+            KeyCode::Char(' ' | 's') => self.confirm = Some(self.dir.clone()),
+            _ => {}
+        }
+    }
+
+    fn load(&mut self) {
+        self.entries = subdirectories(&self.dir, self.show_hidden);
+        self.state = ListState::default();
+        if !self.entries.is_empty() {
+            self.state.select_first();
+        }
+    }
+}
+
+/// Sorted directories inside `dir`, dot directories only when `hidden` is set
+fn subdirectories(dir: &Path, hidden: bool) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut dirs: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .filter(|entry| hidden || !entry.file_name().to_string_lossy().starts_with('.'))
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+impl Widget for &mut DirPicker {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let [header_area, main_area, footer_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ])
+        .areas(area);
+
+        Paragraph::new("Pick the directory to look for projects in")
+            .bold()
+            .centered()
+            .render(header_area, buf);
+
+        let block = Block::new()
+            .title(Line::from(self.dir.display().to_string()).left_aligned())
+            .borders(Borders::ALL)
+            .border_set(symbols::border::ROUNDED);
+
+        let items: Vec<ListItem> = self
+            .entries
+            .iter()
+            .map(|path| {
+                let name = path.file_name().map_or_else(
+                    || path.display().to_string(),
+                    |v| v.to_string_lossy().into_owned(),
+                );
+                ListItem::new(format!("{name}/"))
+            })
+            .collect();
+
+        let list = List::new(items)
+            .block(block)
+            .fg(TEXT_FG_COLOR)
+            .highlight_style(Style::new().bold().reversed())
+            .highlight_symbol(">")
+            .highlight_spacing(HighlightSpacing::Always);
+        StatefulWidget::render(list, main_area, buf, &mut self.state);
+
+        let key = |key| Span::styled(key, Style::default().cyan());
+        let footer = match &self.confirm {
+            Some(path) => Line::from(vec![
+                Span::from(format!("Save {} as default_dir? ", path.display())),
+                key("y"),
+                Span::from("/"),
+                key("n"),
+            ]),
+            None => Line::from(vec![
+                key("space"),
+                Span::from(" use this directory, "),
+                key("l"),
+                Span::from("/"),
+                key("h"),
+                Span::from(" open/go up, "),
+                key("."),
+                Span::from(" hidden, "),
+                key("q"),
+                Span::from(" quit"),
+            ]),
+        };
+        Paragraph::new(footer).centered().render(footer_area, buf);
+        self.theme.apply(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::subdirectories;
+    use crate::test_utils::temp_dir;
+
+    #[test]
+    fn lists_only_directories() {
+        let dir = temp_dir("dir-picker");
+        for name in ["b", "a", ".hidden"] {
+            fs::create_dir_all(dir.join(name)).unwrap();
+        }
+        fs::write(dir.join("file"), "").unwrap();
+
+        assert_eq!(subdirectories(&dir, false), [dir.join("a"), dir.join("b")]);
+        assert_eq!(
+            subdirectories(&dir, true),
+            [dir.join(".hidden"), dir.join("a"), dir.join("b")]
+        );
+    }
+}
@@ -0,0 +1,404 @@
+//! Clones of the same remote, listed by the Duplicates filter
+//!
+//! Extra clones can be deleted or turned into worktrees of the primary clone, both refuse while
+//! the clone has anything the primary doesn't: uncommitted, untracked or ignored files, stashes,
+//! refs pointing at objects missing from it, remotes, hooks, or submodules with any of those.
+
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context};
+use git2::{BranchType, Repository, Status, StatusOptions, WorktreeAddOptions};
+
+use crate::{
+    projects::Project,
+    utils::{find_hooks, RemoteUrl},
+};
+
+/// How many paths a line of [`losses`] names before counting the rest
+const LISTED: usize = 3;
+
+/// Where a project stands among the clones of its remote
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Duplicate {
+    /// `host/owner/repo` the clones share
+    pub remote: String,
+    /// Clone the others are consolidated into, it's the project itself for the primary
+    pub primary: PathBuf,
+    /// How many clones of the remote there are, the primary included
+    pub clones: usize,
+}
+
+/// `host/owner/repo` in lowercase, the same for the ssh and https urls of a repository
+pub fn remote_key(url: &str) -> Option<String> {
+    let url = RemoteUrl::parse(url)?;
+    Some(format!("{}/{}/{}", url.host, url.owner, url.repo).to_lowercase())
+}
+
+/// Projects sharing their primary remote with another one, by path
+///
+/// The primary is the clone committed to last, the shortest path on a tie. Linked worktrees
+/// already share their repository so they're left out.
+pub fn find(projects: &[Project]) -> HashMap<PathBuf, Duplicate> {
+    let mut by_remote: HashMap<String, Vec<&Project>> = HashMap::new();
+    for project in projects {
+        let Some(remote) = project.git_info.remote_url.as_deref().and_then(remote_key) else {
+            continue;
+        };
+        if project.path.join(".git").is_file() {
+            continue;
+        }
+        by_remote.entry(remote).or_default().push(project);
+    }
+
+    let mut duplicates = HashMap::new();
+    for (remote, clones) in by_remote.into_iter().filter(|(_, v)| v.len() > 1) {
+        let Some(primary) = clones.iter().min_by_key(|v| {
+            (
+                std::cmp::Reverse(v.git_info.last_commit_date),
+                v.path.as_os_str().len(),
+            )
+        }) else {
+            continue;
+        };
+        for clone in &clones {
+            duplicates.insert(
+                clone.path.clone(),
+                Duplicate {
+                    remote: remote.clone(),
+                    primary: primary.path.clone(),
+                    clones: clones.len(),
+                },
+            );
+        }
+    }
+    duplicates
+}
+
+/// What to do with an extra clone
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Consolidation {
+    Delete,
+    /// Recreate it as a worktree of the primary, on the branch it has checked out
+    Worktree,
+}
+
+impl Consolidation {
+    pub fn apply(self, clone: &Path, primary: &Path) -> anyhow::Result<()> {
+        if clone == primary {
+            bail!("{} is the primary clone", clone.display());
+        }
+        let clone_repo = Repository::open(clone)
+            .with_context(|| format!("Failed to open {}", clone.display()))?;
+        let primary_repo = Repository::open(primary)
+            .with_context(|| format!("Failed to open {}", primary.display()))?;
+        ensure_contained(&clone_repo, &primary_repo, clone, primary)?;
+
+        match self {
+            Self::Delete => {
+                drop(clone_repo);
+                fs::remove_dir_all(clone)
+                    .with_context(|| format!("Failed to delete {}", clone.display()))
+            }
+            Self::Worktree => into_worktree(&clone_repo, &primary_repo, clone),
+        }
+    }
+}
+
+impl Display for Consolidation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Delete => write!(f, "Delete"),
+            Self::Worktree => write!(f, "Convert to a worktree"),
+        }
+    }
+}
+
+/// Fails unless losing the clone's directory would lose nothing, naming what would be lost
+fn ensure_contained(
+    clone_repo: &Repository,
+    primary_repo: &Repository,
+    clone: &Path,
+    primary: &Path,
+) -> anyhow::Result<()> {
+    let losses = losses(clone_repo, primary_repo, primary)?;
+    if !losses.is_empty() {
+        bail!(
+            "{} has what {} doesn't: {}",
+            clone.display(),
+            primary.display(),
+            losses.join("; ")
+        );
+    }
+    Ok(())
+}
+
+/// Everything of the clone missing from the primary, submodules included under their path
+fn losses(
+    clone_repo: &Repository,
+    primary_repo: &Repository,
+    primary: &Path,
+) -> anyhow::Result<Vec<String>> {
+    let mut lost = Vec::new();
+
+    let mut changed = Vec::new();
+    let mut ignored = Vec::new();
+    if !clone_repo.is_bare() {
+        let mut options = StatusOptions::new();
+        options
+            .include_untracked(true)
+            .include_ignored(true)
+            .exclude_submodules(false);
+        for entry in clone_repo.statuses(Some(&mut options))?.iter() {
+            let path = entry.path().unwrap_or_default().to_string();
+            if entry.status().contains(Status::IGNORED) {
+                ignored.push(path);
+            } else {
+                changed.push(path);
+            }
+        }
+    }
+    if !changed.is_empty() {
+        lost.push(format!("uncommitted changes in {}", listing(&changed)));
+    }
+    if !ignored.is_empty() {
+        lost.push(format!(
+            "ignored files {}, `git clean -fdX` removes them",
+            listing(&ignored)
+        ));
+    }
+
+    if clone_repo.find_reference("refs/stash").is_ok() {
+        lost.push("stashed changes".to_string());
+    }
+    let odb = primary_repo.odb()?;
+    let mut refs = Vec::new();
+    for reference in clone_repo.references()? {
+        let reference = reference?;
+        let Some(target) = reference.target() else {
+            continue;
+        };
+        let name = reference.name().unwrap_or_default();
+        if name != "refs/stash" && !odb.exists(target) {
+            refs.push(name.to_string());
+        }
+    }
+    if !refs.is_empty() {
+        lost.push(format!(
+            "objects missing from the primary in {}",
+            listing(&refs)
+        ));
+    }
+
+    for name in clone_repo.remotes()?.iter().flatten() {
+        let url = clone_repo
+            .find_remote(name)?
+            .url()
+            .unwrap_or_default()
+            .to_string();
+        if !has_remote(primary_repo, primary, &url) {
+            lost.push(format!("remote {name} ({url})"));
+        }
+    }
+
+    let hooks: Vec<String> = find_hooks(clone_repo.commondir(), None, None)
+        .into_iter()
+        .filter(|name| {
+            let read = |repo: &Repository| fs::read(repo.commondir().join("hooks").join(name)).ok();
+            read(clone_repo) != read(primary_repo)
+        })
+        .collect();
+    if !hooks.is_empty() {
+        lost.push(format!("hooks {}", listing(&hooks)));
+    }
+
+    for submodule in clone_repo.submodules()? {
+        // Never checked out, there's nothing of it in the clone
+        let Ok(sub_clone) = submodule.open() else {
+            continue;
+        };
+        let path = submodule.path();
+        let sub_primary = primary.join(path);
+        let Ok(sub_primary_repo) = Repository::open(&sub_primary) else {
+            lost.push(format!(
+                "submodule {}, it isn't checked out in the primary",
+                path.display()
+            ));
+            continue;
+        };
+        lost.extend(
+            losses(&sub_clone, &sub_primary_repo, &sub_primary)?
+                .into_iter()
+                .map(|v| format!("{v} of submodule {}", path.display())),
+        );
+    }
+
+    Ok(lost)
+}
+
+/// Whether the primary has a remote for `url`, or is what it points at
+fn has_remote(primary_repo: &Repository, primary: &Path, url: &str) -> bool {
+    let same =
+        |other: &str| other == url || remote_key(other).is_some_and(|v| Some(v) == remote_key(url));
+    let points_at_primary = Path::new(url)
+        .canonicalize()
+        .is_ok_and(|v| primary.canonicalize().is_ok_and(|primary| v == primary));
+    points_at_primary
+        || primary_repo.remotes().is_ok_and(|names| {
+            names
+                .iter()
+                .flatten()
+                .filter_map(|v| primary_repo.find_remote(v).ok())
+                .any(|v| v.url().is_some_and(same))
+        })
+}
+
+/// First few of `paths` and how many more there are
+fn listing(paths: &[String]) -> String {
+    let mut text = paths[..paths.len().min(LISTED)].join(", ");
+    if paths.len() > LISTED {
+        text.push_str(&format!(" and {} more", paths.len() - LISTED));
+    }
+    text
+}
+
+/// Replaces the clone with a worktree of the primary checking out the same branch, the clone is
+/// put back if creating the worktree fails
+fn into_worktree(
+    clone_repo: &Repository,
+    primary_repo: &Repository,
+    clone: &Path,
+) -> anyhow::Result<()> {
+    let head = clone_repo.head()?;
+    if !head.is_branch() {
+        bail!(
+            "HEAD of {} is detached, check out a branch first",
+            clone.display()
+        );
+    }
+    let (Some(name), Some(target)) = (head.shorthand(), head.target()) else {
+        bail!("Failed to read HEAD of {}", clone.display());
+    };
+
+    let created = match primary_repo.find_branch(name, BranchType::Local) {
+        Ok(branch) if branch.get().target() == Some(target) => false,
+        Ok(_) => bail!("{name} points elsewhere in the primary, merge it first"),
+        Err(_) => {
+            primary_repo.branch(name, &primary_repo.find_commit(target)?, false)?;
+            true
+        }
+    };
+
+    let Some(file_name) = clone.file_name() else {
+        bail!("{} has no name", clone.display());
+    };
+    let mut backup = file_name.to_os_string();
+    backup.push(".ymir-backup");
+    let backup = clone.with_file_name(backup);
+    fs::rename(clone, &backup)
+        .with_context(|| format!("Failed to move {} aside", clone.display()))?;
+
+    let reference = primary_repo.find_reference(&format!("refs/heads/{name}"))?;
+    let added = primary_repo.worktree(
+        &file_name.to_string_lossy(),
+        clone,
+        Some(WorktreeAddOptions::new().reference(Some(&reference))),
+    );
+    if let Err(err) = added {
+        if created {
+            let _ = primary_repo
+                .find_branch(name, BranchType::Local)
+                .and_then(|mut v| v.delete());
+        }
+        let _ = fs::remove_dir_all(clone);
+        fs::rename(&backup, clone)
+            .with_context(|| format!("Failed to restore {}", clone.display()))?;
+        return Err(err).context("Failed to add worktree");
+    }
+
+    fs::remove_dir_all(&backup).with_context(|| format!("Failed to delete {}", backup.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use super::{find, remote_key, Consolidation};
+    use crate::test_utils::{git, project, temp_dir};
+
+    #[test]
+    fn groups_clones_of_a_remote() {
+        assert_eq!(
+            remote_key("git@github.com:Ponurakk/Ymir.git"),
+            remote_key("https://github.com/ponurakk/ymir")
+        );
+
+        let duplicates = find(&[
+            project("/src/ymir")
+                .with_remote("git@github.com:ponurakk/ymir.git")
+                .with_last_commit(10),
+            project("/tmp/ymir-old")
+                .with_remote("https://github.com/ponurakk/ymir")
+                .with_last_commit(5),
+            project("/src/other")
+                .with_remote("https://github.com/ponurakk/other")
+                .with_last_commit(5),
+        ]);
+        assert_eq!(duplicates.len(), 2);
+        let old = &duplicates[Path::new("/tmp/ymir-old")];
+        assert_eq!(old.remote, "github.com/ponurakk/ymir");
+        assert_eq!(old.primary, Path::new("/src/ymir"));
+        assert_eq!(old.clones, 2);
+    }
+
+    #[test]
+    fn converts_clean_clones_into_worktrees() {
+        let dir = temp_dir("duplicates");
+        let primary = dir.join("primary");
+        fs::create_dir_all(&primary).unwrap();
+        git(&primary, &["init", "-q", "-b", "main"]);
+        git(&primary, &["commit", "-q", "--allow-empty", "-m", "init"]);
+        git(&dir, &["clone", "-q", "primary", "clone"]);
+        let clone = dir.join("clone");
+        git(&clone, &["checkout", "-q", "-b", "feature"]);
+
+        fs::write(clone.join("wip"), "").unwrap();
+        assert!(Consolidation::Delete.apply(&clone, &primary).is_err());
+        fs::remove_file(clone.join("wip")).unwrap();
+
+        // Ignored files, tags and remotes are lost with the clone too
+        fs::write(clone.join(".gitignore"), ".env\n").unwrap();
+        git(&clone, &["add", ".gitignore"]);
+        git(&clone, &["commit", "-q", "-m", "Ignore .env"]);
+        git(&primary, &["fetch", "-q", "../clone", "feature:feature"]);
+        fs::write(clone.join(".env"), "TOKEN=secret").unwrap();
+        let err = Consolidation::Delete.apply(&clone, &primary).unwrap_err();
+        assert!(err.to_string().contains("ignored files .env"), "{err}");
+        fs::remove_file(clone.join(".env")).unwrap();
+
+        git(&clone, &["tag", "-a", "v1", "-m", "Release"]);
+        git(
+            &clone,
+            &["remote", "add", "fork", "https://example.com/fork/ymir"],
+        );
+        let err = Consolidation::Delete.apply(&clone, &primary).unwrap_err();
+        assert!(err.to_string().contains("refs/tags/v1"), "{err}");
+        assert!(err.to_string().contains("remote fork"), "{err}");
+        git(&primary, &["fetch", "-q", "../clone", "tag", "v1"]);
+        git(
+            &primary,
+            &["remote", "add", "fork", "https://example.com/fork/ymir"],
+        );
+
+        Consolidation::Worktree.apply(&clone, &primary).unwrap();
+        assert!(clone.join(".git").is_file());
+        assert!(!dir.join("clone.ymir-backup").exists());
+        let repo = git2::Repository::open(&clone).unwrap();
+        assert!(repo.is_worktree());
+        assert_eq!(repo.head().unwrap().shorthand(), Some("feature"));
+    }
+}
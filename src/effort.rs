@@ -0,0 +1,73 @@
+//! What it would take to write a project from scratch, estimated from its lines of code with the
+//! basic COCOMO model for organic projects, the same one scc uses
+//!
+//! The figures are rough, they know nothing about the code besides its length. Enabled with
+//! `estimate_effort`.
+
+use std::fmt::Display;
+
+use serde::Serialize;
+
+use crate::{config::NumberFormat, projects::Project, utils::format_number};
+
+/// Cost on top of salaries, like offices and management, as a multiple of them
+const OVERHEAD: f64 = 2.4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Effort {
+    pub person_months: f64,
+    /// How long it would take with the number of people the model suggests
+    pub schedule_months: f64,
+    /// Salaries of everyone over the schedule with overhead, in `effort_salary`'s currency
+    pub cost: f64,
+}
+
+impl Effort {
+    /// Estimate for `project` paying `salary` a year, `None` without code
+    pub fn of(project: &Project, salary: u32) -> Option<Self> {
+        let code = project.languages_total.code;
+        if code == 0 {
+            return None;
+        }
+
+        let kloc = f64::from(code) / 1000.0;
+        let person_months = 2.4 * kloc.powf(1.05);
+        Some(Self {
+            person_months,
+            schedule_months: 2.5 * person_months.powf(0.38),
+            cost: person_months * f64::from(salary) / 12.0 * OVERHEAD,
+        })
+    }
+}
+
+impl Display for Effort {
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:.1} person-months over {:.1} months, costing {}",
+            self.person_months,
+            self.schedule_months,
+            format_number(self.cost.round() as u64, NumberFormat::Separated)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Effort;
+    use crate::projects::Project;
+
+    #[test]
+    fn follows_organic_cocomo() {
+        let mut project = Project::default();
+        assert_eq!(Effort::of(&project, 56_286), None);
+
+        project.languages_total.code = 10_000;
+        let effort = Effort::of(&project, 56_286).unwrap();
+        assert_eq!(
+            effort.to_string(),
+            "26.9 person-months over 8.7 months, costing 303,139"
+        );
+    }
+}
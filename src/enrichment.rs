@@ -0,0 +1,470 @@
+//! What GitHub knows about projects hosted there, like stars and whether they're archived,
+//! fetched in the background when `github` is set
+//!
+//! Repositories wait in a queue kept in `enrichment.json` next to the history, so a run that's
+//! quit halfway or runs out of requests picks up where it stopped. Requests go one at a time
+//! through curl, the rate limit GitHub reports pauses the queue until it resets and failed ones
+//! are retried with exponential backoff before the project is marked as failed.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::mpsc::{Receiver, RecvTimeoutError},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config,
+    error::{Result, YmirError},
+    projects::Project,
+    utils::RemoteUrl,
+};
+
+/// Pause between two requests, even with requests left
+const INTERVAL: Duration = Duration::from_secs(1);
+/// Wait before the first retry, doubled for every one after it
+const BACKOFF: u64 = 60;
+/// Failed requests before a project is marked as failed
+const MAX_ATTEMPTS: u32 = 5;
+/// Age after which details, and failures, are fetched again
+const REFRESH_AFTER: u64 = 7 * 24 * 60 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Details {
+    pub stars: u32,
+    pub open_issues: u32,
+    pub archived: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Status {
+    /// Waiting for its turn, a retry or the rate limit
+    Pending,
+    Done(Details),
+    /// Gave up after retrying, or GitHub doesn't know the repository
+    Failed {
+        error: String,
+    },
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Pending => write!(f, "enrichment pending"),
+            Self::Done(details) => {
+                write!(
+                    f,
+                    "{} stars, {} open issues",
+                    details.stars, details.open_issues
+                )?;
+                if details.archived {
+                    write!(f, ", archived")?;
+                }
+                Ok(())
+            }
+            Self::Failed { error } => write!(f, "enrichment failed, {error}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Entry {
+    /// `owner/name` on GitHub
+    repo: String,
+    #[serde(flatten)]
+    status: Status,
+    /// Failed requests since the last one that worked
+    attempts: u32,
+    /// Not requested before this, unix seconds
+    next_try: u64,
+    /// When it was done or failed for good, unix seconds
+    settled_at: u64,
+}
+
+/// Projects to ask GitHub about and what it said, by path
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Queue {
+    entries: BTreeMap<PathBuf, Entry>,
+    /// No requests before this, unix seconds, set when the rate limit ran out
+    paused_until: u64,
+}
+
+/// Answer to a request as curl got it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reply {
+    pub status: u16,
+    /// Requests left until the rate limit resets
+    pub remaining: Option<u32>,
+    /// When the rate limit resets, unix seconds
+    pub reset: Option<u64>,
+    /// Seconds to wait before trying again, sent instead of the above when requests come too fast
+    pub retry_after: Option<u64>,
+    pub body: String,
+}
+
+/// `owner/name` of the project's primary remote, if it's on GitHub
+pub fn github_repo(project: &Project) -> Option<String> {
+    let url = RemoteUrl::parse(project.git_info.remote_url.as_deref()?)?;
+    (url.host == "github.com").then(|| format!("{}/{}", url.owner, url.repo))
+}
+
+impl Queue {
+    pub fn path() -> Option<PathBuf> {
+        config::app_dir().map(|dir| dir.join("enrichment.json"))
+    }
+
+    /// Reads the queue, empty when there is none yet
+    pub fn load() -> Self {
+        Self::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|v| serde_json::from_str(&v).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path().ok_or(YmirError::NoConfigDir)?;
+        fs::write(&path, serde_json::to_string(self)?).map_err(|err| YmirError::io(&path, err))
+    }
+
+    /// Queues `repos`, projects and their GitHub repository, that aren't queued yet or were
+    /// settled long enough ago, returning where each of them stands
+    ///
+    /// Projects missing from `repos` are kept, they may be under another scan root.
+    pub fn sync(&mut self, repos: &[(PathBuf, String)], now: u64) -> Vec<(PathBuf, Status)> {
+        repos
+            .iter()
+            .map(|(path, repo)| {
+                let entry = self.entries.entry(path.clone()).or_insert_with(|| Entry {
+                    repo: repo.clone(),
+                    status: Status::Pending,
+                    attempts: 0,
+                    next_try: 0,
+                    settled_at: 0,
+                });
+                let settled = entry.status != Status::Pending;
+                if entry.repo != *repo || (settled && now >= entry.settled_at + REFRESH_AFTER) {
+                    entry.repo.clone_from(repo);
+                    entry.status = Status::Pending;
+                    entry.attempts = 0;
+                    entry.next_try = 0;
+                }
+                (path.clone(), entry.status.clone())
+            })
+            .collect()
+    }
+
+    /// Project and repository to request next, if any is due at `now`
+    pub fn next_due(&self, now: u64) -> Option<(PathBuf, String)> {
+        if now < self.paused_until {
+            return None;
+        }
+        self.entries
+            .iter()
+            .filter(|(_, v)| v.status == Status::Pending && v.next_try <= now)
+            .min_by_key(|(_, v)| v.next_try)
+            .map(|(path, v)| (path.clone(), v.repo.clone()))
+    }
+
+    /// When the next request becomes due, `None` with nothing pending
+    pub fn next_wake(&self) -> Option<u64> {
+        self.entries
+            .values()
+            .filter(|v| v.status == Status::Pending)
+            .map(|v| v.next_try.max(self.paused_until))
+            .min()
+    }
+
+    /// Takes in how requesting the project at `path` went, returning where it stands now
+    pub fn record(&mut self, path: &Path, reply: Result<Reply, String>, now: u64) -> Status {
+        let Some(entry) = self.entries.get_mut(path) else {
+            return Status::Pending;
+        };
+        if let Ok(Reply {
+            remaining: Some(0),
+            reset: Some(reset),
+            ..
+        }) = &reply
+        {
+            self.paused_until = *reset;
+        }
+
+        let failure = match reply {
+            Ok(reply) if reply.status == 200 => match details(&reply.body) {
+                Ok(details) => {
+                    entry.status = Status::Done(details);
+                    entry.attempts = 0;
+                    entry.settled_at = now;
+                    return entry.status.clone();
+                }
+                Err(err) => format!("{err:#}"),
+            },
+            Ok(reply) if reply.status == 404 => {
+                entry.status = Status::Failed {
+                    error: "Not found, private repositories need github_token".to_string(),
+                };
+                entry.settled_at = now;
+                return entry.status.clone();
+            }
+            // Out of requests, the project isn't to blame
+            Ok(reply)
+                if matches!(reply.status, 403 | 429)
+                    && (reply.remaining == Some(0) || reply.retry_after.is_some()) =>
+            {
+                self.paused_until = self
+                    .paused_until
+                    .max(reply.retry_after.map_or(now + BACKOFF, |v| now + v));
+                return entry.status.clone();
+            }
+            Ok(reply) => format!("GitHub answered {}", reply.status),
+            Err(err) => err,
+        };
+
+        entry.attempts += 1;
+        if entry.attempts >= MAX_ATTEMPTS {
+            entry.status = Status::Failed { error: failure };
+            entry.settled_at = now;
+        } else {
+            entry.next_try = now + BACKOFF * 2u64.pow(entry.attempts - 1);
+        }
+        entry.status.clone()
+    }
+}
+
+fn details(body: &str) -> Result<Details> {
+    #[derive(Deserialize)]
+    struct Repository {
+        stargazers_count: u32,
+        open_issues_count: u32,
+        archived: bool,
+    }
+
+    let repository: Repository = serde_json::from_str(body)?;
+    Ok(Details {
+        stars: repository.stargazers_count,
+        open_issues: repository.open_issues_count,
+        archived: repository.archived,
+    })
+}
+
+/// Asks GitHub about `repo` through curl, the token is handed over on stdin to keep it out of
+/// the process list
+pub fn fetch(repo: &str, token: Option<&str>) -> Result<Reply, String> {
+    let mut command = Command::new("curl");
+    command
+        .args(["--silent", "--show-error", "--include"])
+        .args(["--header", "Accept: application/vnd.github+json"])
+        .args(["--header", "User-Agent: ymir"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if token.is_some() {
+        command.args(["--header", "@-"]);
+    }
+    command.arg(format!("https://api.github.com/repos/{repo}"));
+
+    let mut child = command
+        .spawn()
+        .map_err(|err| format!("Failed to run curl: {err}"))?;
+    if let (Some(token), Some(mut stdin)) = (token, child.stdin.take()) {
+        writeln!(stdin, "Authorization: Bearer {token}")
+            .map_err(|err| format!("Failed to hand the token to curl: {err}"))?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|err| format!("Failed to run curl: {err}"))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    parse_reply(&String::from_utf8_lossy(&output.stdout))
+        .ok_or_else(|| "Failed to read GitHub's answer".to_string())
+}
+
+/// Status, rate limit headers and body of what `curl --include` printed
+fn parse_reply(text: &str) -> Option<Reply> {
+    // Proxies and redirects put more header blocks in front, the last one is the answer
+    let mut head = "";
+    let mut body = text;
+    while body.starts_with("HTTP/") {
+        let (next_head, rest) = body.split_once("\r\n\r\n")?;
+        (head, body) = (next_head, rest);
+    }
+
+    let mut lines = head.lines();
+    let status = lines.next()?.split_whitespace().nth(1)?.parse().ok()?;
+    let mut reply = Reply {
+        status,
+        remaining: None,
+        reset: None,
+        retry_after: None,
+        body: body.to_string(),
+    };
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match name.trim().to_lowercase().as_str() {
+            "x-ratelimit-remaining" => reply.remaining = value.parse().ok(),
+            "x-ratelimit-reset" => reply.reset = value.parse().ok(),
+            "retry-after" => reply.retry_after = value.parse().ok(),
+            _ => {}
+        }
+    }
+    Some(reply)
+}
+
+/// Works through the queue until `repos` is dropped, taking in every list of projects it sends
+/// and handing where each project stands to `report`, which returns false to stop
+pub fn run(
+    repos: &Receiver<Vec<(PathBuf, String)>>,
+    token: Option<&str>,
+    mut report: impl FnMut(PathBuf, Status) -> bool,
+) {
+    let mut queue = Queue::load();
+    loop {
+        let wait = if let Some((path, repo)) = queue.next_due(now()) {
+            let reply = fetch(&repo, token);
+            if let Err(err) = &reply {
+                warn!("Failed to ask GitHub about {repo}: {err}");
+            }
+            let status = queue.record(&path, reply, now());
+            save(&queue);
+            if !report(path, status) {
+                return;
+            }
+            Some(INTERVAL)
+        } else {
+            queue
+                .next_wake()
+                .map(|v| Duration::from_secs(v.saturating_sub(now())))
+        };
+
+        let received = match wait {
+            Some(wait) => repos.recv_timeout(wait),
+            None => repos.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        match received {
+            Ok(list) => {
+                for (path, status) in queue.sync(&list, now()) {
+                    if !report(path, status) {
+                        return;
+                    }
+                }
+                save(&queue);
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+    }
+}
+
+fn save(queue: &Queue) {
+    if let Err(err) = queue.save() {
+        warn!("Failed to save the enrichment queue: {err:#}");
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |v| v.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{parse_reply, Details, Queue, Reply, Status, BACKOFF, MAX_ATTEMPTS};
+
+    fn reply(status: u16, remaining: u32, body: &str) -> Reply {
+        Reply {
+            status,
+            remaining: Some(remaining),
+            reset: Some(5000),
+            retry_after: None,
+            body: body.to_string(),
+        }
+    }
+
+    #[test]
+    fn reads_the_last_header_block() {
+        let text = "HTTP/1.1 200 Connection established\r\n\r\nHTTP/2 200 \r\n\
+                    x-ratelimit-remaining: 59\r\nX-RateLimit-Reset: 1700000000\r\n\r\n{}";
+        let reply = parse_reply(text).unwrap();
+        assert_eq!(
+            (
+                reply.status,
+                reply.remaining,
+                reply.reset,
+                reply.body.as_str()
+            ),
+            (200, Some(59), Some(1_700_000_000), "{}")
+        );
+    }
+
+    #[test]
+    fn retries_with_backoff_and_waits_out_the_rate_limit() {
+        let (alpha, beta) = (PathBuf::from("/src/alpha"), PathBuf::from("/src/beta"));
+        let mut queue = Queue::default();
+        let statuses = queue.sync(
+            &[
+                (alpha.clone(), "me/alpha".to_string()),
+                (beta.clone(), "me/beta".to_string()),
+            ],
+            100,
+        );
+        assert!(statuses.iter().all(|(_, v)| *v == Status::Pending));
+
+        // A failure waits, the other project goes first
+        assert_eq!(
+            queue.record(&alpha, Err("offline".into()), 100),
+            Status::Pending
+        );
+        assert_eq!(queue.next_due(100).unwrap().0, beta);
+
+        // Out of requests pauses everything until the reset
+        let body = r#"{"stargazers_count": 12, "open_issues_count": 3, "archived": true}"#;
+        let details = Details {
+            stars: 12,
+            open_issues: 3,
+            archived: true,
+        };
+        assert_eq!(
+            queue.record(&beta, Ok(reply(200, 0, body)), 100),
+            Status::Done(details)
+        );
+        assert_eq!(queue.next_due(100 + BACKOFF), None);
+        assert_eq!(queue.next_wake(), Some(5000));
+        assert_eq!(queue.next_due(5000).unwrap().0, alpha);
+
+        for _ in 2..MAX_ATTEMPTS {
+            let status = queue.record(&alpha, Ok(reply(500, 10, "")), 5000);
+            assert_eq!(status, Status::Pending);
+        }
+        assert_eq!(
+            queue.record(&alpha, Ok(reply(500, 10, "")), 5000),
+            Status::Failed {
+                error: "GitHub answered 500".to_string()
+            }
+        );
+        assert_eq!(queue.next_wake(), None);
+
+        // Survives a restart and isn't asked about again until it's old
+        let mut queue: Queue =
+            serde_json::from_str(&serde_json::to_string(&queue).unwrap()).unwrap();
+        let statuses = queue.sync(&[(beta.clone(), "me/beta".to_string())], 6000);
+        assert_eq!(statuses, [(beta.clone(), Status::Done(details))]);
+        let statuses = queue.sync(
+            &[(beta.clone(), "me/beta".to_string())],
+            100 + 8 * 24 * 60 * 60,
+        );
+        assert_eq!(statuses, [(beta, Status::Pending)]);
+    }
+}
@@ -0,0 +1,120 @@
+//! Errors of the cache, config, scan and git paths, of the state kept next to the cache and of
+//! the helper programs ymir runs, the binary wraps them in `anyhow`
+//!
+//! Actions on projects and what only ever ends up in front of the user still return `anyhow`,
+//! nothing tells their errors apart: `archive`, `duplicates`, `tasks`, `serve`, `digest::run`,
+//! the `commit_search`, `row_style`, `sort_expr` and `template` parsers, `credentials`, `ipc`,
+//! `commands`, `menu` and the TUIs in `app` and `dir_picker`.
+
+use std::{io, num::TryFromIntError, path::PathBuf, process::ExitStatus};
+
+pub type Result<T, E = YmirError> = std::result::Result<T, E>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum YmirError {
+    /// Cache bytes that can't be decoded, rescanning replaces them
+    #[error("Corrupt cache: {0}")]
+    CorruptCache(String),
+    /// Cache written by another version of ymir
+    #[error("Cache version {found} doesn't match {expected}")]
+    CacheVersion { found: u8, expected: u8 },
+    /// A length doesn't fit in its field of the cache format
+    #[error("Value too large for the cache: {0}")]
+    CacheOverflow(#[from] TryFromIntError),
+    /// Reading or writing a file failed
+    #[error("Failed to access {}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("Failed to find config directory")]
+    NoConfigDir,
+    #[error("Failed to find home directory")]
+    NoHomeDir,
+    #[error("Invalid config {}: {source}", path.display())]
+    Config {
+        path: PathBuf,
+        #[source]
+        source: toml::de::Error,
+    },
+    #[error("Invalid profile: {0}")]
+    Profile(String),
+    /// Repository that libgit2 or gitoxide couldn't read
+    #[error("Failed to read repository: {0}")]
+    Git(#[source] Box<dyn std::error::Error + Send + Sync>),
+    /// State kept as JSON next to the cache that can't be written, or an answer from GitHub
+    /// that can't be read
+    #[error("Invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    /// Helper program that isn't installed or can't be started
+    #[error("Failed to run {program}: {source}")]
+    Spawn {
+        program: String,
+        #[source]
+        source: io::Error,
+    },
+    #[error("{program} exited with {status}")]
+    Exit { program: String, status: ExitStatus },
+    /// Size in the config that isn't a number with an optional `K`, `M`, `G` or `T` suffix
+    #[error("Invalid size {0}")]
+    InvalidSize(String),
+    #[error("Invalid host name: {0}")]
+    InvalidHost(String),
+    #[error("Unknown source {0}, expected ghq, projectile or vscode")]
+    UnknownSource(String),
+}
+
+impl YmirError {
+    pub fn io(path: impl Into<PathBuf>, source: io::Error) -> Self {
+        Self::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    /// Cache data that ends before the value being read
+    pub(crate) fn truncated(_: io::Error) -> Self {
+        Self::CorruptCache("Unexpected end of data".to_string())
+    }
+
+    /// Whether the cache can't be read back, which a fresh scan fixes
+    pub const fn is_corrupt_cache(&self) -> bool {
+        matches!(self, Self::CorruptCache(_) | Self::CacheVersion { .. })
+    }
+
+    /// Whether it failed for lack of permissions, which rescanning doesn't fix
+    pub fn is_permission_denied(&self) -> bool {
+        matches!(self, Self::Io { source, .. } if source.kind() == io::ErrorKind::PermissionDenied)
+    }
+}
+
+impl From<git2::Error> for YmirError {
+    fn from(err: git2::Error) -> Self {
+        Self::Git(Box::new(err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::YmirError;
+
+    #[test]
+    fn tells_corrupt_cache_from_permission_denied() {
+        let corrupt = YmirError::CorruptCache("Invalid magic value".to_string());
+        assert!(corrupt.is_corrupt_cache());
+        assert!(!corrupt.is_permission_denied());
+
+        let version = YmirError::CacheVersion {
+            found: 1,
+            expected: 2,
+        };
+        assert!(version.is_corrupt_cache());
+
+        let denied = YmirError::io("/cache", io::Error::from(io::ErrorKind::PermissionDenied));
+        assert!(denied.is_permission_denied());
+        assert!(!denied.is_corrupt_cache());
+    }
+}
@@ -0,0 +1,243 @@
+//! Exporting the project list for use outside ymir
+
+use std::{collections::BTreeMap, fmt::Write, fs::OpenOptions, io::Write as _, path::Path};
+
+use crate::{
+    config::Settings, effort::Effort, error::YmirError, history::History, projects::Project,
+    utils::RemoteUrl,
+};
+
+/// Writes `contents` to a new file at `path`, an existing file is never replaced
+pub fn write_new(path: &Path, contents: &[u8]) -> Result<(), YmirError> {
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(contents))
+        .map_err(|err| YmirError::io(path, err))
+}
+
+/// Everything known about `project` for `ymir show --json`, with the times it was opened from
+/// ymir under `opened` and the effort estimate under `effort` with `estimate_effort`
+pub fn project_json(
+    project: &Project,
+    history: &History,
+    settings: &Settings,
+) -> serde_json::Result<serde_json::Value> {
+    let mut json = serde_json::to_value(project)?;
+    if settings.estimate_effort {
+        json["effort"] = serde_json::to_value(Effort::of(project, settings.effort_salary))?;
+    }
+    json["opened"] = history
+        .entries
+        .iter()
+        .filter(|(_, path)| *path == project.path)
+        .map(|(time, _)| *time)
+        .collect::<Vec<_>>()
+        .into();
+    Ok(json)
+}
+
+/// Netscape bookmark file linking to the web page of every project with a remote
+///
+/// Browsers import it as folders per host and owner, opened directly it reads as a plain index
+/// page. Projects whose remote isn't a recognizable url are left out.
+pub fn bookmarks_html(projects: &[Project]) -> String {
+    let mut hosts: BTreeMap<String, BTreeMap<String, Vec<(String, String)>>> = BTreeMap::new();
+    for project in projects {
+        let Some(remote) = project
+            .git_info
+            .remote_url
+            .as_deref()
+            .and_then(RemoteUrl::parse)
+        else {
+            continue;
+        };
+
+        let url = format!("https://{}/{}/{}", remote.host, remote.owner, remote.repo);
+        let links = hosts
+            .entry(remote.host)
+            .or_default()
+            .entry(remote.owner)
+            .or_default();
+        if !links.iter().any(|(v, _)| *v == url) {
+            links.push((url, remote.repo));
+        }
+    }
+
+    let mut html = String::from(
+        "<!DOCTYPE NETSCAPE-Bookmark-file-1>\n\
+         <META HTTP-EQUIV=\"Content-Type\" CONTENT=\"text/html; charset=UTF-8\">\n\
+         <TITLE>Projects</TITLE>\n\
+         <H1>Projects</H1>\n\
+         <DL><p>\n",
+    );
+    for (host, owners) in hosts {
+        let _ = writeln!(html, "    <DT><H3>{}</H3>\n    <DL><p>", escape(&host));
+        for (owner, mut links) in owners {
+            links.sort_by(|a, b| a.1.cmp(&b.1));
+            let _ = writeln!(
+                html,
+                "        <DT><H3>{}</H3>\n        <DL><p>",
+                escape(&owner)
+            );
+            for (url, repo) in links {
+                let _ = writeln!(
+                    html,
+                    "            <DT><A HREF=\"{}\">{}</A>",
+                    escape(&url),
+                    escape(&repo)
+                );
+            }
+            html.push_str("        </DL><p>\n");
+        }
+        html.push_str("    </DL><p>\n");
+    }
+    html.push_str("</DL><p>\n");
+    html
+}
+
+/// VS Code `.code-workspace` file with every project as a folder named by its label
+///
+/// Paths are absolute so the file works from anywhere. Zed opens the same set of folders with
+/// `zed <paths>` and has no workspace file of its own.
+pub fn code_workspace(projects: &[Project], settings: &Settings) -> serde_json::Result<String> {
+    let folders: Vec<serde_json::Value> = projects
+        .iter()
+        .map(|v| serde_json::json!({ "name": settings.label(v), "path": v.path }))
+        .collect();
+    let mut json = serde_json::to_string_pretty(&serde_json::json!({
+        "folders": folders,
+        "settings": {},
+    }))?;
+    json.push('\n');
+    Ok(json)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io::ErrorKind, path::PathBuf};
+
+    use super::{bookmarks_html, code_workspace, project_json, write_new};
+    use crate::{
+        config::{ProjectLabel, Settings},
+        error::YmirError,
+        history::History,
+        projects::{Project, ProjectLanguage},
+        test_utils::{project, temp_dir},
+    };
+
+    #[test]
+    fn exports_dont_replace_files() {
+        let dir = temp_dir("export");
+        let path = dir.join("bookmarks.html");
+
+        write_new(&path, b"first").unwrap();
+        let err = write_new(&path, b"second").unwrap_err();
+        assert!(
+            matches!(&err, YmirError::Io { source, .. } if source.kind() == ErrorKind::AlreadyExists)
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), "first");
+    }
+
+    #[test]
+    fn groups_by_host_and_owner() {
+        let html = bookmarks_html(&[
+            project("/src/project").with_remote("git@github.com:acme/web.git"),
+            project("/src/project").with_remote("https://gitlab.com/group/sub/tool"),
+            project("/src/project").with_remote("https://github.com/acme/api"),
+            project("/src/project").with_remote("git@github.com:acme/web.git"),
+            project("/src/project").with_remote("/srv/git/local.git"),
+            project("/src/project"),
+        ]);
+
+        let links: Vec<&str> = html
+            .lines()
+            .map(str::trim)
+            .filter(|line| line.starts_with("<DT>"))
+            .collect();
+        assert_eq!(
+            links,
+            [
+                "<DT><H3>github.com</H3>",
+                "<DT><H3>acme</H3>",
+                "<DT><A HREF=\"https://github.com/acme/api\">api</A>",
+                "<DT><A HREF=\"https://github.com/acme/web\">web</A>",
+                "<DT><H3>gitlab.com</H3>",
+                "<DT><H3>group/sub</H3>",
+                "<DT><A HREF=\"https://gitlab.com/group/sub/tool\">tool</A>",
+            ]
+        );
+    }
+
+    #[test]
+    fn dumps_a_project_with_its_opens() {
+        let mut project = project("/src/project").with_remote("git@github.com:acme/web.git");
+        let rust = tokei::LanguageType::list()
+            .iter()
+            .position(|v| *v == tokei::LanguageType::Rust)
+            .unwrap();
+        project.languages.insert(
+            u8::try_from(rust).unwrap(),
+            ProjectLanguage {
+                code: 10,
+                ..ProjectLanguage::default()
+            },
+        );
+        let history = History {
+            entries: vec![
+                (1, PathBuf::from("/src/project")),
+                (2, PathBuf::from("/src/other")),
+                (3, PathBuf::from("/src/project")),
+            ],
+        };
+
+        let settings = Settings {
+            estimate_effort: true,
+            ..Settings::default()
+        };
+        let json = project_json(&project, &history, &settings).unwrap();
+        assert_eq!(json["path"], "/src/project");
+        assert_eq!(
+            json["git_info"]["remote_url"],
+            "git@github.com:acme/web.git"
+        );
+        assert_eq!(json["git_info"]["state"], "Ok");
+        assert_eq!(json["languages"]["Rust"]["code"], 10);
+        assert_eq!(json["opened"], serde_json::json!([1, 3]));
+        // Only the per language counts are set
+        assert_eq!(json["effort"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn lists_projects_as_workspace_folders() {
+        let projects = [PathBuf::from("/src/web"), PathBuf::from("/src/api")].map(|path| Project {
+            path,
+            ..Project::default()
+        });
+        let settings = Settings {
+            label: ProjectLabel::Name,
+            ..Settings::default()
+        };
+
+        let json: serde_json::Value =
+            serde_json::from_str(&code_workspace(&projects, &settings).unwrap()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "folders": [
+                    { "name": "web", "path": "/src/web" },
+                    { "name": "api", "path": "/src/api" },
+                ],
+                "settings": {},
+            })
+        );
+    }
+}
@@ -0,0 +1,249 @@
+//! Detection of generated and vendored files, left out of line counts
+//!
+//! Follows what GitHub linguist does in spirit: well known file names and directories, a marker
+//! comment at the top of the file, and `linguist-generated` / `linguist-vendored` attributes in
+//! the project's `.gitattributes` which win over everything else.
+
+use std::{
+    fs::{self, File},
+    io::Read,
+    path::Path,
+};
+
+/// File name endings of generated or vendored files
+const SUFFIXES: [&str; 14] = [
+    "_pb2.py",
+    "_pb2_grpc.py",
+    ".pb.go",
+    ".pb.cc",
+    ".pb.h",
+    ".min.js",
+    ".min.css",
+    ".js.map",
+    ".css.map",
+    ".g.dart",
+    ".freezed.dart",
+    ".designer.cs",
+    ".generated.ts",
+    ".generated.cs",
+];
+
+/// Lockfiles and other whole files written by tools
+const FILE_NAMES: [&str; 9] = [
+    "package-lock.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "Cargo.lock",
+    "poetry.lock",
+    "composer.lock",
+    "Gemfile.lock",
+    "go.sum",
+    "flake.lock",
+];
+
+/// Directories holding nothing but generated or vendored code
+const DIRECTORIES: [&str; 7] = [
+    "generated",
+    "__generated__",
+    "vendor",
+    "vendored",
+    "third_party",
+    "third-party",
+    "Pods",
+];
+
+/// Comments tools leave at the top of what they write
+const MARKERS: [&str; 3] = ["DO NOT EDIT", "@generated", "<auto-generated"];
+
+/// How much of a file is looked at for a marker
+const MARKER_BYTES: usize = 512;
+
+/// Rules for one project
+#[derive(Debug, Default)]
+pub struct Generated {
+    /// Patterns from `.gitattributes`, `true` when they mark files as generated
+    attributes: Vec<(String, bool)>,
+}
+
+impl Generated {
+    /// Reads the `.gitattributes` of the project at `root`
+    pub fn load(root: &Path) -> Self {
+        let attributes = fs::read_to_string(root.join(".gitattributes"))
+            .map(|v| parse_attributes(&v))
+            .unwrap_or_default();
+
+        Self { attributes }
+    }
+
+    /// Whether the file at `path` inside `root` is generated or vendored
+    pub fn is_generated(&self, root: &Path, path: &Path) -> bool {
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        let relative = relative.to_string_lossy();
+
+        // The last matching line wins, same as git
+        if let Some(&(_, generated)) = self
+            .attributes
+            .iter()
+            .rev()
+            .find(|(pattern, _)| attribute_matches(pattern, &relative))
+        {
+            return generated;
+        }
+
+        is_generated_path(&relative) || has_marker(path)
+    }
+}
+
+/// Whether the name or location of `relative` marks it as generated
+fn is_generated_path(relative: &str) -> bool {
+    let mut components = relative.split('/').collect::<Vec<_>>();
+    let Some(name) = components.pop() else {
+        return false;
+    };
+
+    SUFFIXES.iter().any(|suffix| name.ends_with(suffix))
+        || FILE_NAMES.contains(&name)
+        || components.iter().any(|v| DIRECTORIES.contains(v))
+}
+
+fn has_marker(path: &Path) -> bool {
+    let mut head = Vec::with_capacity(MARKER_BYTES);
+    let Ok(file) = File::open(path) else {
+        return false;
+    };
+    if file
+        .take(MARKER_BYTES as u64)
+        .read_to_end(&mut head)
+        .is_err()
+    {
+        return false;
+    }
+
+    let head = String::from_utf8_lossy(&head);
+    MARKERS.iter().any(|marker| head.contains(marker))
+}
+
+/// Patterns setting or unsetting `linguist-generated` or `linguist-vendored`
+fn parse_attributes(text: &str) -> Vec<(String, bool)> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let pattern = parts.next()?;
+
+            let generated = parts.rev().find_map(|attribute| {
+                let (negated, attribute) = match attribute.strip_prefix('-') {
+                    Some(rest) => (true, rest),
+                    None => (false, attribute),
+                };
+                let (name, value) = attribute.split_once('=').unwrap_or((attribute, "true"));
+
+                matches!(name, "linguist-generated" | "linguist-vendored")
+                    .then_some(!negated && value != "false")
+            })?;
+
+            Some((pattern.to_string(), generated))
+        })
+        .collect()
+}
+
+/// Matches a gitattributes pattern against a path relative to the project root
+///
+/// Patterns without a slash match the file name anywhere, others are anchored at the root.
+fn attribute_matches(pattern: &str, relative: &str) -> bool {
+    let pattern = pattern.strip_prefix('/').unwrap_or(pattern);
+
+    if pattern.contains('/') {
+        glob(pattern.as_bytes(), relative.as_bytes())
+    } else {
+        relative
+            .rsplit('/')
+            .next()
+            .is_some_and(|name| glob(pattern.as_bytes(), name.as_bytes()))
+            // `dir/**` style patterns are rare without a slash, a bare directory name still
+            // covers everything inside it
+            || relative
+                .split('/')
+                .rev()
+                .skip(1)
+                .any(|dir| glob(pattern.as_bytes(), dir.as_bytes()))
+    }
+}
+
+/// `*` and `?` stop at slashes, `**` doesn't
+pub(crate) fn glob(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        [b'*', b'*', rest @ ..] => {
+            let rest = rest.strip_prefix(b"/").unwrap_or(rest);
+            (0..=text.len()).any(|i| glob(rest, &text[i..]))
+        }
+        [b'*', rest @ ..] => (0..=text.len())
+            .take_while(|&i| i == 0 || text[i - 1] != b'/')
+            .any(|i| glob(rest, &text[i..])),
+        [b'?', rest @ ..] => text
+            .split_first()
+            .is_some_and(|(&c, text)| c != b'/' && glob(rest, text)),
+        [c, rest @ ..] => text
+            .split_first()
+            .is_some_and(|(t, text)| t == c && glob(rest, text)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{attribute_matches, glob, is_generated_path, parse_attributes, Generated};
+
+    #[test]
+    fn globs_match_like_git() {
+        assert!(glob(b"*.json", b"data.json"));
+        assert!(!glob(b"*.json", b"dir/data.json"));
+        assert!(glob(b"docs/**", b"docs/a/b.md"));
+        assert!(glob(b"**/gen/*.rs", b"src/gen/a.rs"));
+        assert!(glob(b"a?c", b"abc"));
+        assert!(!glob(b"a?c", b"a/c"));
+    }
+
+    #[test]
+    fn attribute_patterns() {
+        assert!(attribute_matches("*.pb.rs", "src/api.pb.rs"));
+        assert!(attribute_matches("/schema/*.sql", "schema/init.sql"));
+        assert!(!attribute_matches("/schema/*.sql", "src/schema/init.sql"));
+        assert!(attribute_matches("assets", "assets/app.js"));
+    }
+
+    #[test]
+    fn reads_linguist_attributes() {
+        let attributes = parse_attributes(
+            "# comment\n*.json linguist-generated\nfixtures/** linguist-vendored=true\n\
+             src/keep.json -linguist-generated\n*.rs text eol=lf\n",
+        );
+        assert_eq!(
+            attributes,
+            [
+                ("*.json".to_string(), true),
+                ("fixtures/**".to_string(), true),
+                ("src/keep.json".to_string(), false),
+            ]
+        );
+
+        let generated = Generated { attributes };
+        let root = Path::new("/nonexistent");
+        assert!(generated.is_generated(root, &root.join("data.json")));
+        assert!(!generated.is_generated(root, &root.join("src/keep.json")));
+        assert!(generated.is_generated(root, &root.join("fixtures/a/b.rs")));
+    }
+
+    #[test]
+    fn known_paths() {
+        assert!(is_generated_path("proto/api_pb2.py"));
+        assert!(is_generated_path("static/app.min.js"));
+        assert!(is_generated_path("Cargo.lock"));
+        assert!(is_generated_path("src/generated/schema.rs"));
+        assert!(!is_generated_path("src/main.rs"));
+        assert!(!is_generated_path("generated.rs"));
+    }
+}
@@ -0,0 +1,66 @@
+//! Directories the user's global gitignore names, skipped on top of `ignore_dirs`
+//!
+//! The file is git's `core.excludesFile`, `~/.config/git/ignore` when that isn't set. Git applies
+//! it relative to each repository, a scan has no repository to be relative to so only patterns
+//! without a leading slash, like `.cache/` or `*.tmp/`, match reliably. Files are never skipped,
+//! scans only use it to stay out of caches and scratch directories.
+
+use std::path::Path;
+
+use ignore::gitignore::Gitignore;
+
+use crate::config::Settings;
+
+#[derive(Debug, Default)]
+pub struct GlobalIgnore(Option<Gitignore>);
+
+impl GlobalIgnore {
+    /// Reads the global gitignore, nothing is skipped when `global_gitignore` is off or there is
+    /// none
+    pub fn new(settings: &Settings) -> Self {
+        if !settings.global_gitignore {
+            return Self(None);
+        }
+
+        let (gitignore, err) = Gitignore::global();
+        if let Some(err) = err {
+            warn!("Failed to read global gitignore: {err}");
+        }
+        Self((!gitignore.is_empty()).then_some(gitignore))
+    }
+
+    /// Whether the directory at `dir` is ignored, `.git` never is since it's how projects are found
+    pub fn skips(&self, dir: &Path) -> bool {
+        let Some(gitignore) = &self.0 else {
+            return false;
+        };
+        dir.file_name().is_some_and(|v| v != ".git") && gitignore.matched(dir, true).is_ignore()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use ignore::gitignore::GitignoreBuilder;
+
+    use super::GlobalIgnore;
+
+    #[test]
+    fn skips_matching_directories() {
+        let mut builder = GitignoreBuilder::new("");
+        for line in [".cache/", "scratch-*/", ".*", "!.config/"] {
+            builder.add_line(None, line).unwrap();
+        }
+        let ignore = GlobalIgnore(Some(builder.build().unwrap()));
+
+        assert!(ignore.skips(Path::new("/home/me/.cache")));
+        assert!(ignore.skips(Path::new("/home/me/code/scratch-2024")));
+        assert!(!ignore.skips(Path::new("/home/me/code/ymir")));
+        assert!(!ignore.skips(Path::new("/home/me/.config")));
+        // Matched by `.*`, but it's what makes a project
+        assert!(!ignore.skips(Path::new("/home/me/code/ymir/.git")));
+
+        assert!(!GlobalIgnore::default().skips(Path::new("/home/me/.cache")));
+    }
+}
@@ -0,0 +1,262 @@
+//! Language donut drawn with the kitty or sixel graphics protocol
+//!
+//! Ratatui only knows about text, so the chart is written to the terminal after each frame over
+//! cells the languages pane leaves blank for it. Cells are assumed to be twice as tall as they are
+//! wide, which holds for most fonts.
+
+use std::{env, f64::consts::TAU, fmt::Write};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+/// Pixels of a cell row, sixel images aren't scaled so this sets their size
+const CELL_HEIGHT: u32 = 20;
+/// Kitty takes escape sequences of at most this many bytes of payload
+const KITTY_CHUNK: usize = 4096;
+/// Inner radius of the donut relative to the outer one
+const HOLE: f64 = 0.55;
+
+/// Color and weight of a slice of the chart
+pub type Slice = ([u8; 3], u32);
+
+/// Graphics protocol of the terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    Kitty,
+    Sixel,
+}
+
+impl Protocol {
+    /// Protocol the terminal supports going by its environment, `None` if it's unknown
+    pub fn detect() -> Option<Self> {
+        let var = |name| env::var(name).unwrap_or_default();
+        let term = var("TERM");
+        let program = var("TERM_PROGRAM");
+
+        if env::var_os("KITTY_WINDOW_ID").is_some()
+            || term.contains("kitty")
+            || term.contains("ghostty")
+            || matches!(program.as_str(), "WezTerm" | "ghostty")
+        {
+            Some(Self::Kitty)
+        } else if term.contains("sixel")
+            || term.starts_with("foot")
+            || term.starts_with("mlterm")
+            || matches!(program.as_str(), "iTerm.app" | "mintty")
+        {
+            Some(Self::Sixel)
+        } else {
+            None
+        }
+    }
+
+    /// Escape sequence drawing a donut of `slices` at the cursor, `rows` cells high
+    pub fn encode(self, slices: &[Slice], rows: u16) -> String {
+        let side = u32::from(rows) * CELL_HEIGHT;
+        let image = Image::donut(slices, side);
+        match self {
+            Self::Kitty => image.kitty(rows * 2, rows),
+            Self::Sixel => image.sixel(),
+        }
+    }
+
+    /// Escape sequence removing what [`Self::encode`] drew, sixels are replaced by redrawing
+    pub const fn clear(self) -> &'static str {
+        match self {
+            Self::Kitty => "\x1b_Ga=d,d=A,q=2\x1b\\",
+            Self::Sixel => "",
+        }
+    }
+}
+
+/// RGBA pixels, row by row
+#[derive(Debug, PartialEq, Eq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<[u8; 4]>,
+}
+
+impl Image {
+    /// Square donut with a slice for every `(color, weight)` clockwise from the top, the rest is
+    /// transparent
+    pub fn donut(slices: &[Slice], side: u32) -> Self {
+        let total: u64 = slices.iter().map(|(_, v)| u64::from(*v)).sum();
+        let center = f64::from(side) / 2.0;
+        let outer = center - 1.0;
+        let inner = outer * HOLE;
+
+        // Where each slice ends, as a fraction of the whole
+        let mut sum = 0;
+        let ends: Vec<([u8; 3], f64)> = slices
+            .iter()
+            .map(|&(color, weight)| {
+                sum += u64::from(weight);
+                #[allow(clippy::cast_precision_loss)]
+                (color, sum as f64 / total.max(1) as f64)
+            })
+            .collect();
+
+        let mut pixels = Vec::with_capacity((side * side) as usize);
+        for y in 0..side {
+            for x in 0..side {
+                let dx = f64::from(x) + 0.5 - center;
+                let dy = f64::from(y) + 0.5 - center;
+                let distance = dx.hypot(dy);
+                if total == 0 || distance > outer || distance < inner {
+                    pixels.push([0; 4]);
+                    continue;
+                }
+
+                let turn = dx.atan2(-dy).rem_euclid(TAU) / TAU;
+                let [r, g, b] = ends
+                    .iter()
+                    .find(|(_, end)| turn < *end)
+                    .or(ends.last())
+                    .map_or([0; 3], |(color, _)| *color);
+                pixels.push([r, g, b, u8::MAX]);
+            }
+        }
+
+        Self {
+            width: side,
+            height: side,
+            pixels,
+        }
+    }
+
+    /// Kitty escape sequences transmitting the image and scaling it to `cols`×`rows` cells
+    fn kitty(&self, cols: u16, rows: u16) -> String {
+        let bytes: Vec<u8> = self.pixels.iter().flatten().copied().collect();
+        let payload = STANDARD.encode(&bytes);
+        let chunks: Vec<&[u8]> = payload.as_bytes().chunks(KITTY_CHUNK).collect();
+
+        let mut out = String::new();
+        for (i, chunk) in chunks.iter().enumerate() {
+            let more = u8::from(i + 1 < chunks.len());
+            let chunk = std::str::from_utf8(chunk).unwrap_or_default();
+            if i == 0 {
+                let _ = write!(
+                    out,
+                    "\x1b_Ga=T,f=32,s={},v={},c={cols},r={rows},C=1,q=2,m={more};{chunk}\x1b\\",
+                    self.width, self.height
+                );
+            } else {
+                let _ = write!(out, "\x1b_Gm={more};{chunk}\x1b\\");
+            }
+        }
+        out
+    }
+
+    /// Sixel escape sequence, transparent pixels leave what's behind them
+    fn sixel(&self) -> String {
+        let mut palette: Vec<[u8; 3]> = Vec::new();
+        let indexes: Vec<Option<usize>> = self
+            .pixels
+            .iter()
+            .map(|&[r, g, b, a]| {
+                (a > 0).then(|| {
+                    palette
+                        .iter()
+                        .position(|v| *v == [r, g, b])
+                        .unwrap_or_else(|| {
+                            palette.push([r, g, b]);
+                            palette.len() - 1
+                        })
+                })
+            })
+            .collect();
+
+        let mut out = format!("\x1bP0;1;0q\"1;1;{};{}", self.width, self.height);
+        let percent = |v: u8| u32::from(v) * 100 / 255;
+        for (i, [r, g, b]) in palette.iter().enumerate() {
+            let _ = write!(
+                out,
+                "#{i};2;{};{};{}",
+                percent(*r),
+                percent(*g),
+                percent(*b)
+            );
+        }
+
+        let width = self.width as usize;
+        for band in 0..self.height.div_ceil(6) as usize {
+            for color in 0..palette.len() {
+                let sixels: Vec<u8> = (0..width)
+                    .map(|x| {
+                        (0..6)
+                            .filter(|row| {
+                                indexes.get((band * 6 + row) * width + x).copied().flatten()
+                                    == Some(color)
+                            })
+                            .fold(0, |bits, row| bits | 1 << row)
+                    })
+                    .collect();
+                if sixels.iter().all(|v| *v == 0) {
+                    continue;
+                }
+
+                let _ = write!(out, "#{color}");
+                for run in sixels.chunk_by(|a, b| a == b) {
+                    let char = char::from(63 + run[0]);
+                    if run.len() > 3 {
+                        let _ = write!(out, "!{}{char}", run.len());
+                    } else {
+                        out.extend(std::iter::repeat_n(char, run.len()));
+                    }
+                }
+                out.push('$');
+            }
+            out.push('-');
+        }
+
+        out.push_str("\x1b\\");
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Image, Protocol};
+
+    const RED: [u8; 3] = [255, 0, 0];
+    const BLUE: [u8; 3] = [0, 0, 255];
+
+    #[test]
+    fn donut_slices_go_clockwise_from_the_top() {
+        let image = Image::donut(&[(RED, 1), (BLUE, 3)], 40);
+        let pixel = |x: u32, y: u32| image.pixels[(y * image.width + x) as usize];
+
+        // Center is the hole, corners are outside
+        assert_eq!(pixel(20, 20)[3], 0);
+        assert_eq!(pixel(0, 0)[3], 0);
+        // A quarter turn covers the top right, the rest is the second slice
+        assert_eq!(pixel(30, 8), [255, 0, 0, 255]);
+        assert_eq!(pixel(30, 32), [0, 0, 255, 255]);
+        assert_eq!(pixel(8, 20), [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn nothing_to_draw_is_transparent() {
+        let image = Image::donut(&[], 8);
+        assert!(image.pixels.iter().all(|v| v[3] == 0));
+    }
+
+    #[test]
+    fn kitty_is_chunked() {
+        let encoded = Protocol::Kitty.encode(&[(RED, 1)], 4);
+        let chunks: Vec<&str> = encoded.split_terminator("\x1b\\").collect();
+        assert!(chunks.len() > 1);
+        assert!(chunks[0].starts_with("\x1b_Ga=T,f=32,s=80,v=80,c=8,r=4,"));
+        assert!(chunks[0].contains("m=1;"));
+        assert!(chunks.last().unwrap().starts_with("\x1b_Gm=0;"));
+    }
+
+    #[test]
+    fn sixel_has_a_register_per_color() {
+        let encoded = Protocol::Sixel.encode(&[(RED, 1), (BLUE, 1)], 1);
+        assert!(encoded.starts_with("\x1bP0;1;0q\"1;1;20;20"));
+        assert!(encoded.contains(";2;100;0;0"));
+        assert!(encoded.contains(";2;0;0;100"));
+        assert!(encoded.ends_with("-\x1b\\"));
+    }
+}
@@ -0,0 +1,238 @@
+//! Projects opened from ymir, newest last
+//!
+//! Kept as plain text next to the cache, one `<unix seconds>\t<path>` line per open, so it
+//! survives rescans and cache version bumps. How many times each project was opened is kept in
+//! a file of its own, the history forgets old opens.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    config,
+    error::{Result, YmirError},
+};
+
+/// Lines kept when the file is rewritten, older opens are forgotten
+const MAX_ENTRIES: usize = 1000;
+
+/// Times projects were opened, oldest first
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct History {
+    pub entries: Vec<(u64, PathBuf)>,
+}
+
+impl History {
+    /// Location of the history file
+    pub fn path() -> Option<PathBuf> {
+        config::app_dir().map(|dir| dir.join("history"))
+    }
+
+    /// Reads the history, empty when there is none yet
+    pub fn load() -> Self {
+        Self::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|v| Self::parse(&v))
+            .unwrap_or_default()
+    }
+
+    fn parse(text: &str) -> Self {
+        let entries = text
+            .lines()
+            .filter_map(|line| {
+                let (time, path) = line.split_once('\t')?;
+                Some((time.parse().ok()?, PathBuf::from(path)))
+            })
+            .collect();
+
+        Self { entries }
+    }
+
+    /// Appends an open of `project` to the history file
+    pub fn record(project: &Path) -> Result<()> {
+        let path = Self::path().ok_or(YmirError::NoConfigDir)?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |v| v.as_secs());
+
+        let mut history = Self::load();
+        if history.entries.len() >= MAX_ENTRIES {
+            history
+                .entries
+                .drain(..=history.entries.len() - MAX_ENTRIES);
+            history.entries.push((now, project.to_path_buf()));
+            return history.save();
+        }
+
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| writeln!(file, "{now}\t{}", project.display()))
+            .map_err(|err| YmirError::io(&path, err))?;
+        OpenCounts::record(project)
+    }
+
+    /// Replaces the history file with these entries
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path().ok_or(YmirError::NoConfigDir)?;
+
+        let text: String = self
+            .entries
+            .iter()
+            .map(|(time, path)| format!("{time}\t{}\n", path.display()))
+            .collect();
+        fs::write(&path, text).map_err(|err| YmirError::io(&path, err))
+    }
+
+    /// Forgets projects that no longer exist and merges every open of a project into the latest
+    /// one, returns how many entries were dropped
+    pub fn compact(&mut self) -> usize {
+        let before = self.entries.len();
+        let mut latest: HashMap<PathBuf, u64> = HashMap::new();
+        for (time, path) in self.entries.drain(..) {
+            let kept = latest.entry(path).or_default();
+            *kept = (*kept).max(time);
+        }
+        self.entries = latest
+            .into_iter()
+            .filter(|(path, _)| path.is_dir())
+            .map(|(path, time)| (time, path))
+            .collect();
+        self.entries.sort();
+        self.entries
+            .drain(..self.entries.len().saturating_sub(MAX_ENTRIES));
+        before - self.entries.len()
+    }
+
+    /// Most recently opened project that still exists
+    pub fn last(&self) -> Option<&Path> {
+        self.entries
+            .iter()
+            .rev()
+            .map(|(_, path)| path.as_path())
+            .find(|path| path.is_dir())
+    }
+}
+
+/// How many times each project was opened, one `<count>\t<path>` line per project
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OpenCounts {
+    pub counts: BTreeMap<PathBuf, u64>,
+}
+
+impl OpenCounts {
+    /// Location of the counts file
+    pub fn path() -> Option<PathBuf> {
+        config::app_dir().map(|dir| dir.join("opens"))
+    }
+
+    /// Reads the counts, empty when nothing was opened yet
+    pub fn load() -> Self {
+        Self::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|v| Self::parse(&v))
+            .unwrap_or_default()
+    }
+
+    fn parse(text: &str) -> Self {
+        let counts = text
+            .lines()
+            .filter_map(|line| {
+                let (count, path) = line.split_once('\t')?;
+                Some((PathBuf::from(path), count.parse().ok()?))
+            })
+            .collect();
+
+        Self { counts }
+    }
+
+    /// Counts another open of `project`
+    pub fn record(project: &Path) -> Result<()> {
+        let mut counts = Self::load();
+        *counts.counts.entry(project.to_path_buf()).or_default() += 1;
+        counts.save()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path().ok_or(YmirError::NoConfigDir)?;
+
+        let text: String = self
+            .counts
+            .iter()
+            .map(|(path, count)| format!("{count}\t{}\n", path.display()))
+            .collect();
+        fs::write(&path, text).map_err(|err| YmirError::io(&path, err))
+    }
+
+    /// Forgets projects that no longer exist, returns how many were dropped
+    pub fn compact(&mut self) -> usize {
+        let before = self.counts.len();
+        self.counts.retain(|path, _| path.is_dir());
+        before - self.counts.len()
+    }
+
+    /// The `n` most opened projects, most opens first
+    pub fn top(&self, n: usize) -> Vec<(&Path, u64)> {
+        let mut top: Vec<(&Path, u64)> = self
+            .counts
+            .iter()
+            .map(|(path, count)| (path.as_path(), *count))
+            .collect();
+        top.sort_by_key(|(path, count)| (std::cmp::Reverse(*count), *path));
+        top.truncate(n);
+        top
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::{History, OpenCounts};
+
+    #[test]
+    fn last_skips_missing_projects() {
+        let tmp = std::env::temp_dir();
+        let history = History::parse(&format!(
+            "10\t{}\nbroken line\n20\t/nonexistent/ymir-project\n",
+            tmp.display()
+        ));
+
+        assert_eq!(
+            history.entries,
+            [
+                (10, tmp.clone()),
+                (20, PathBuf::from("/nonexistent/ymir-project"))
+            ]
+        );
+        assert_eq!(history.last(), Some(tmp.as_path()));
+    }
+
+    #[test]
+    fn compact_merges_repeated_opens() {
+        let tmp = std::env::temp_dir();
+        let mut history = History::parse(&format!(
+            "10\t{0}\n20\t/nonexistent/ymir-project\n30\t{0}\n70\t/\n50\t/\n60\t{0}\n40\t{0}\n",
+            tmp.display()
+        ));
+
+        assert_eq!(history.compact(), 5);
+        assert_eq!(history.entries, [(60, tmp), (70, PathBuf::from("/"))]);
+    }
+
+    #[test]
+    fn top_orders_by_opens() {
+        let counts = OpenCounts::parse("3\t/src/a\n10\t/src/b\nbroken\n3\t/src/c\nx\t/src/d\n");
+
+        assert_eq!(counts.counts.len(), 3);
+        assert_eq!(
+            counts.top(2),
+            [(Path::new("/src/b"), 10), (Path::new("/src/a"), 3)]
+        );
+    }
+}
@@ -0,0 +1,201 @@
+//! Caches imported from other machines
+//!
+//! `ymir cache export` on one machine writes its cache as it is, `ymir cache import` on another
+//! keeps it under `hosts/` next to the local cache. Repositories are matched across machines by
+//! their primary remote, projects without one only ever exist on their own machine.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    config::{self, Cache},
+    error::{Result, YmirError},
+    projects::Project,
+    utils::RemoteUrl,
+};
+
+/// Directory the imported caches are kept in, one file per host
+pub fn dir() -> Option<PathBuf> {
+    config::app_dir().map(|dir| dir.join("hosts"))
+}
+
+/// Name of this machine, used for the local cache in merged lists
+pub fn local_name() -> String {
+    fs::read_to_string("/etc/hostname")
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| "local".to_string())
+}
+
+/// Stores the cache at `file` as the cache of `host`, replacing an earlier import
+pub fn import(file: &Path, host: &str) -> Result<usize> {
+    if host.is_empty() || host.starts_with('.') || host.contains(['/', '\\']) {
+        return Err(YmirError::InvalidHost(host.to_string()));
+    }
+    let dir = dir().ok_or(YmirError::NoConfigDir)?;
+
+    // Fails on files from other versions before they end up next to the others
+    let projects = Cache::read_file(file)?;
+    fs::create_dir_all(&dir).map_err(|err| YmirError::io(&dir, err))?;
+    fs::copy(file, dir.join(host)).map_err(|err| YmirError::io(file, err))?;
+    Ok(projects.len())
+}
+
+/// Every imported host with its projects, sorted by name
+///
+/// Caches that can't be read, from another version of ymir say, are logged and left out so
+/// the other hosts still show.
+pub fn imported() -> Result<Vec<(String, Vec<Project>)>> {
+    let dir = dir().ok_or(YmirError::NoConfigDir)?;
+    Ok(imported_from(&dir))
+}
+
+fn imported_from(dir: &Path) -> Vec<(String, Vec<Project>)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut hosts: Vec<_> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            match Cache::read_file(&entry.path()) {
+                Ok(projects) => Some((name, projects)),
+                Err(err) => {
+                    warn!("Skipped the cache of {name}: {err}");
+                    None
+                }
+            }
+        })
+        .collect();
+    hosts.sort_by(|a, b| a.0.cmp(&b.0));
+    hosts
+}
+
+/// One repository of the merged list
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergedProject {
+    /// `host/owner/repo` of the remote, `machine:path` without one
+    pub label: String,
+    /// Machines the repository is checked out on
+    pub hosts: Vec<String>,
+    /// Machines it's missing on, always empty for projects without a remote
+    pub missing: Vec<String>,
+}
+
+/// Merges the projects of every host, sorted by label
+pub fn merge(hosts: &[(String, Vec<Project>)]) -> Vec<MergedProject> {
+    let mut merged: BTreeMap<String, (bool, Vec<String>)> = BTreeMap::new();
+    for (host, projects) in hosts {
+        for project in projects {
+            let remote = project
+                .git_info
+                .remote_url
+                .as_deref()
+                .and_then(RemoteUrl::parse);
+            let (label, shared) = remote.map_or_else(
+                || (format!("{host}:{}", project.path.display()), false),
+                |v| (format!("{}/{}/{}", v.host, v.owner, v.repo), true),
+            );
+
+            let entry = merged.entry(label).or_insert((shared, Vec::new()));
+            if !entry.1.contains(host) {
+                entry.1.push(host.clone());
+            }
+        }
+    }
+
+    merged
+        .into_iter()
+        .map(|(label, (shared, on))| {
+            let missing = if shared {
+                hosts
+                    .iter()
+                    .map(|(host, _)| host)
+                    .filter(|host| !on.contains(host))
+                    .cloned()
+                    .collect()
+            } else {
+                Vec::new()
+            };
+            MergedProject {
+                label,
+                hosts: on,
+                missing,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{imported_from, merge, MergedProject};
+    use crate::{
+        cache::CacheSerializer,
+        config::Cache,
+        test_utils::{project, temp_dir},
+    };
+
+    #[test]
+    fn skips_unreadable_host_caches() {
+        let dir = temp_dir("hosts");
+        let cache = Cache {
+            projects: vec![project("/src/ymir")],
+            compressed: false,
+        };
+        fs::write(dir.join("desktop"), cache.serialize().unwrap()).unwrap();
+        fs::write(dir.join("laptop"), "not a cache").unwrap();
+
+        let hosts = imported_from(&dir);
+        assert_eq!(hosts.len(), 1);
+        assert_eq!(hosts[0].0, "desktop");
+        assert_eq!(hosts[0].1[0].path, cache.projects[0].path);
+    }
+
+    #[test]
+    fn finds_repos_missing_on_a_host() {
+        let hosts = [
+            (
+                "desktop".to_string(),
+                vec![
+                    project("/src/ymir").with_remote("git@github.com:ponurakk/ymir.git"),
+                    project("/src/notes"),
+                ],
+            ),
+            (
+                "laptop".to_string(),
+                vec![
+                    project("/home/me/ymir").with_remote("https://github.com/ponurakk/ymir"),
+                    project("/home/me/dots").with_remote("https://github.com/me/dots"),
+                ],
+            ),
+        ];
+
+        assert_eq!(
+            merge(&hosts),
+            [
+                MergedProject {
+                    label: "desktop:/src/notes".to_string(),
+                    hosts: vec!["desktop".to_string()],
+                    missing: Vec::new(),
+                },
+                MergedProject {
+                    label: "github.com/me/dots".to_string(),
+                    hosts: vec!["laptop".to_string()],
+                    missing: vec!["desktop".to_string()],
+                },
+                MergedProject {
+                    label: "github.com/ponurakk/ymir".to_string(),
+                    hosts: vec!["desktop".to_string(), "laptop".to_string()],
+                    missing: Vec::new(),
+                },
+            ]
+        );
+    }
+}
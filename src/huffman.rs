@@ -3,6 +3,17 @@ use std::{
     io::Read,
 };
 
+use crate::error::{Result, YmirError};
+
+/// Size of a single table entry: byte, code length and packed code
+const TABLE_ENTRY_LEN: usize = 4;
+/// There can't be more codes than distinct byte values
+const MAX_TABLE_LEN: usize = 256 * TABLE_ENTRY_LEN;
+/// Codes are packed into a `u16`, so none can be longer than this
+const MAX_CODE_LEN: usize = 16;
+/// Upper bound on decoded output, far above any real cache
+const MAX_DECODED_LEN: usize = 256 * 1024 * 1024;
+
 #[derive(Eq)]
 pub struct MinHeapNode {
     data: Option<u8>,
@@ -36,7 +47,7 @@ impl Ord for MinHeapNode {
 
 impl PartialOrd for MinHeapNode {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(other.freq.cmp(&self.freq))
+        Some(self.cmp(other))
     }
 }
 
@@ -96,7 +107,7 @@ pub fn get_frequencies(data: &[u8]) -> (Vec<u8>, Vec<u32>) {
     }
 
     let mut freq_vec: Vec<(u8, u32)> = freq.into_iter().collect();
-    freq_vec.sort_by(|a, b| b.1.cmp(&a.1));
+    freq_vec.sort_by_key(|a| std::cmp::Reverse(a.1));
 
     let (sorted_chars, sorted_freqs): (Vec<u8>, Vec<u32>) =
         freq_vec.iter().map(|(c, f)| (*c, *f)).unzip();
@@ -105,16 +116,32 @@ pub fn get_frequencies(data: &[u8]) -> (Vec<u8>, Vec<u32>) {
 }
 
 pub fn huffman_encode(buffer: &[u8]) -> Vec<u8> {
-    let (arr, freq) = get_frequencies(buffer);
-    let mut heap = huffman_table(&arr, &freq).unwrap_or_default();
-    let mut table = HashMap::new();
-    lookup_table(heap.pop().map(Box::new), Vec::new(), &mut table);
+    let (arr, mut freq) = get_frequencies(buffer);
+    let mut table = loop {
+        let mut heap = huffman_table(&arr, &freq).unwrap_or_default();
+        let mut table = HashMap::new();
+        lookup_table(heap.pop().map(Box::new), Vec::new(), &mut table);
+
+        if table.values().all(|code| code.len() <= MAX_CODE_LEN) {
+            break table;
+        }
+
+        // Flatten very skewed distributions until the deepest code fits in the packed `u16`
+        for f in &mut freq {
+            *f = *f / 2 + 1;
+        }
+    };
+
+    // A tree with a single leaf assigns it an empty code, which can't be decoded
+    if let Some(code) = table.values_mut().find(|code| code.is_empty()) {
+        code.push(0);
+    }
 
     let mut table_bytes = Vec::new();
     for (char, code) in &table {
         table_bytes.push(*char);
 
-        // There is very very low probability that this will fail unwraping
+        // Code lengths are capped at `MAX_CODE_LEN` above
         table_bytes.push(u8::try_from(code.len()).unwrap_or_default());
 
         let mut packed_code: u16 = 0;
@@ -133,6 +160,9 @@ pub fn huffman_encode(buffer: &[u8]) -> Vec<u8> {
         }
     }
 
+    // Stored so the decoder knows where the padding of the last byte starts
+    let bit_len = u32::try_from(bit_stream.len()).unwrap_or(u32::MAX);
+
     let mut buffer: Vec<u8> = Vec::new();
     let mut byte = 0u8;
     let mut bit_count = 0;
@@ -160,61 +190,168 @@ pub fn huffman_encode(buffer: &[u8]) -> Vec<u8> {
             .to_le_bytes(),
     );
     new_buffer.extend_from_slice(&table_bytes);
+    new_buffer.extend_from_slice(&bit_len.to_le_bytes());
     new_buffer.extend_from_slice(&buffer);
 
     new_buffer
 }
 
-pub fn huffman_decode(buffer: &[u8]) -> anyhow::Result<Vec<u8>> {
+pub fn huffman_decode(buffer: &[u8]) -> Result<Vec<u8>> {
     let mut cursor = std::io::Cursor::new(buffer);
 
     let mut table_size_len = u16::MAX.to_le_bytes();
-    cursor.read_exact(&mut table_size_len)?;
+    cursor
+        .read_exact(&mut table_size_len)
+        .map_err(YmirError::truncated)?;
     let table_size = u16::from_le_bytes(table_size_len) as usize;
 
-    let mut table = HashMap::new();
+    if table_size > MAX_TABLE_LEN || !table_size.is_multiple_of(TABLE_ENTRY_LEN) {
+        return Err(YmirError::CorruptCache(format!(
+            "Invalid huffman table size {table_size}"
+        )));
+    }
+
+    // Every 16 bit window maps straight to the symbol its prefix encodes and that code's length
+    let mut table = vec![(0u8, 0u8); 1 << MAX_CODE_LEN];
 
     while usize::try_from(cursor.position())? < table_size + table_size_len.len() {
         let mut char_byte = [0u8; 1];
-        cursor.read_exact(&mut char_byte)?;
+        cursor
+            .read_exact(&mut char_byte)
+            .map_err(YmirError::truncated)?;
         let char_byte = char_byte[0];
 
         let mut packed_code_len = [0u8; 1];
-        cursor.read_exact(&mut packed_code_len)?;
+        cursor
+            .read_exact(&mut packed_code_len)
+            .map_err(YmirError::truncated)?;
         let packed_code_len = u8::from_le_bytes(packed_code_len);
-
-        let mut packed_code_bytes = [0u8; 2];
-        cursor.read_exact(&mut packed_code_bytes)?;
-        let packed_code = u16::from_le_bytes(packed_code_bytes);
-
-        let mut code = Vec::new();
-        for i in 0..packed_code_len {
-            let bit = (packed_code >> (15 - i)) & 1;
-            code.push(bit as u8);
+        if packed_code_len == 0 || usize::from(packed_code_len) > MAX_CODE_LEN {
+            return Err(YmirError::CorruptCache(format!(
+                "Invalid code length {packed_code_len} for byte {char_byte}"
+            )));
         }
 
-        table.insert(code, char_byte);
+        let mut packed_code_bytes = [0u8; 2];
+        cursor
+            .read_exact(&mut packed_code_bytes)
+            .map_err(YmirError::truncated)?;
+        let packed_code = usize::from(u16::from_le_bytes(packed_code_bytes));
+
+        let span = 1 << (MAX_CODE_LEN - usize::from(packed_code_len));
+        let first = packed_code & !(span - 1);
+        table[first..first + span].fill((char_byte, packed_code_len));
     }
 
+    let mut bit_len = [0u8; 4];
+    cursor
+        .read_exact(&mut bit_len)
+        .map_err(YmirError::truncated)?;
+    let bit_len = usize::try_from(u32::from_le_bytes(bit_len))?;
+
     let mut decoded_bytes = Vec::new();
-    let mut bit_stream = Vec::new();
 
     let remaining_data = cursor.get_ref();
-    for &byte in &remaining_data[usize::try_from(cursor.position())?..] {
-        for i in (0..8).rev() {
-            // bit_stream.push(if (byte >> i) & 1 == 1 { 1 } else { 0 });
-            bit_stream.push(u8::from((byte >> i) & 1 == 1));
-        }
+    let mut reader = BitReader::new(&remaining_data[usize::try_from(cursor.position())?..]);
+
+    if bit_len > reader.remaining() {
+        return Err(YmirError::CorruptCache(format!(
+            "Encoded length of {bit_len} bits exceeds remaining {} bits",
+            reader.remaining()
+        )));
     }
+    reader.truncate(bit_len);
 
-    let mut current_code = Vec::new();
-    for bit in bit_stream {
-        current_code.push(bit);
-        if let Some(&byte) = table.get(&current_code) {
-            decoded_bytes.push(byte);
-            current_code.clear();
+    while reader.remaining() > 0 {
+        let (byte, len) = table[usize::from(reader.peek_u16())];
+        let len = usize::from(len);
+
+        if len == 0 || len > reader.remaining() {
+            return Err(YmirError::CorruptCache(
+                "Bit sequence doesn't match any huffman code".to_string(),
+            ));
+        }
+
+        if decoded_bytes.len() == MAX_DECODED_LEN {
+            return Err(YmirError::CorruptCache(format!(
+                "Decoded data exceeds limit of {MAX_DECODED_LEN} bytes"
+            )));
         }
+
+        decoded_bytes.push(byte);
+        reader.consume(len);
     }
 
     Ok(decoded_bytes)
 }
+
+/// Reads a byte slice as a stream of bits, most significant bit first
+pub struct BitReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    len: usize,
+}
+
+impl<'a> BitReader<'a> {
+    pub const fn new(bytes: &'a [u8]) -> Self {
+        Self {
+            bytes,
+            position: 0,
+            len: bytes.len() * 8,
+        }
+    }
+
+    /// Stops reading after `len` bits, ignoring the padding of the last byte
+    pub fn truncate(&mut self, len: usize) {
+        self.len = self.len.min(len);
+    }
+
+    /// Number of bits left to read
+    pub const fn remaining(&self) -> usize {
+        self.len.saturating_sub(self.position)
+    }
+
+    /// Next 16 bits without advancing, missing bits past the end read as zero
+    pub fn peek_u16(&self) -> u16 {
+        let index = self.position / 8;
+        let byte = |offset: usize| u32::from(self.bytes.get(index + offset).copied().unwrap_or(0));
+        let window = (byte(0) << 16) | (byte(1) << 8) | byte(2);
+
+        ((window >> (8 - self.position % 8)) & 0xFFFF) as u16
+    }
+
+    pub fn consume(&mut self, bits: usize) {
+        self.position += bits;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{huffman_decode, huffman_encode};
+
+    #[test]
+    fn round_trips_text() {
+        let data = b"ymir finds projects and keeps their stats in a cache".repeat(32);
+        assert_eq!(huffman_decode(&huffman_encode(&data)).unwrap(), data);
+    }
+
+    #[test]
+    fn padding_is_not_decoded() {
+        // A single symbol gets the 1 bit code `0`, so zero padding used to decode as extra data
+        let data = [7u8; 3];
+        assert_eq!(huffman_decode(&huffman_encode(&data)).unwrap(), data);
+    }
+
+    #[test]
+    fn round_trips_skewed_distribution() {
+        // Fibonacci frequencies produce a maximally deep tree, well past 16 bit codes
+        let (mut a, mut b) = (1usize, 1usize);
+        let mut data = Vec::new();
+        for symbol in 0..24u8 {
+            data.extend(std::iter::repeat_n(symbol, a));
+            (a, b) = (b, a + b);
+        }
+
+        assert_eq!(huffman_decode(&huffman_encode(&data)).unwrap(), data);
+    }
+}
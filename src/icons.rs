@@ -0,0 +1,102 @@
+//! Nerd Font glyphs shown with `use_icons`, every caller keeps a plain text fallback
+//!
+//! Codepoints are from Nerd Fonts 3, written as escapes since most editors can't show them.
+
+use tokei::LanguageType;
+
+use crate::utils::RepoState;
+
+/// Shown for languages without a glyph of their own
+const FILE: &str = "\u{f15b}";
+
+pub const CI: &str = "\u{f013}";
+pub const DOCKER: &str = "\u{f308}";
+pub const NIX: &str = "\u{f313}";
+pub const DIRENV: &str = "\u{f120}";
+
+pub const fn language(language: LanguageType) -> &'static str {
+    match language {
+        LanguageType::Rust => "\u{e7a8}",
+        LanguageType::Python => "\u{e73c}",
+        LanguageType::JavaScript | LanguageType::Jsx => "\u{e74e}",
+        LanguageType::TypeScript | LanguageType::Tsx => "\u{e628}",
+        LanguageType::Go => "\u{e627}",
+        LanguageType::C | LanguageType::CHeader => "\u{e61e}",
+        LanguageType::Cpp | LanguageType::CppHeader => "\u{e61d}",
+        LanguageType::CSharp => "\u{f031b}",
+        LanguageType::Java => "\u{e738}",
+        LanguageType::Kotlin => "\u{e634}",
+        LanguageType::Scala => "\u{e737}",
+        LanguageType::Swift => "\u{e755}",
+        LanguageType::Dart => "\u{e798}",
+        LanguageType::Ruby => "\u{e739}",
+        LanguageType::Php => "\u{e73d}",
+        LanguageType::Haskell => "\u{e777}",
+        LanguageType::Elixir => "\u{e62d}",
+        LanguageType::Lua => "\u{e620}",
+        LanguageType::Zig => "\u{e6a9}",
+        LanguageType::Nix => NIX,
+        LanguageType::Sh | LanguageType::Bash | LanguageType::Zsh | LanguageType::Fish => {
+            "\u{e795}"
+        }
+        LanguageType::VimScript => "\u{e62b}",
+        LanguageType::Html => "\u{e736}",
+        LanguageType::Css | LanguageType::Sass | LanguageType::Less => "\u{e749}",
+        LanguageType::Vue => "\u{e6a0}",
+        LanguageType::Svelte => "\u{e697}",
+        LanguageType::Markdown => "\u{e73e}",
+        LanguageType::Json => "\u{e60b}",
+        LanguageType::Toml | LanguageType::Yaml | LanguageType::Makefile => "\u{e615}",
+        LanguageType::Dockerfile => DOCKER,
+        _ => FILE,
+    }
+}
+
+/// Forge of a remote by its host name, self-hosted GitLab and Gitea instances included when
+/// their name says so
+pub fn host(host: &str) -> &'static str {
+    if host.contains("github") {
+        "\u{f09b}"
+    } else if host.contains("gitlab") {
+        "\u{f296}"
+    } else if host.contains("bitbucket") {
+        "\u{f171}"
+    } else if host.contains("gitea") || host.contains("codeberg") || host.contains("forgejo") {
+        "\u{f339}"
+    } else {
+        "\u{e702}"
+    }
+}
+
+pub const fn state(state: RepoState) -> &'static str {
+    match state {
+        RepoState::Ok => "\u{e725}",
+        RepoState::Empty | RepoState::Unborn => "\u{f10c}",
+        RepoState::Detached => "\u{f417}",
+        RepoState::Error => "\u{f071}",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokei::LanguageType;
+
+    use super::{host, language, FILE};
+
+    #[test]
+    fn hosts_and_languages_fall_back_to_generic_glyphs() {
+        assert_eq!(host("github.com"), host("github.example.com"));
+        assert_eq!(host("gitlab.gnome.org"), host("gitlab.com"));
+        assert_eq!(host("codeberg.org"), host("gitea.example.com"));
+        assert_ne!(host("github.com"), host("gitlab.com"));
+        assert_ne!(host("git.sr.ht"), host("github.com"));
+        assert_eq!(host("git.sr.ht"), host("example.com"));
+
+        assert_eq!(
+            language(LanguageType::Tsx),
+            language(LanguageType::TypeScript)
+        );
+        assert_ne!(language(LanguageType::Rust), FILE);
+        assert_eq!(language(LanguageType::Cobol), FILE);
+    }
+}
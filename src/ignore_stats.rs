@@ -0,0 +1,172 @@
+//! What the last scan skipped, to check `ignore_dirs` against
+//!
+//! Saved next to the cache after every scan that writes it, `ymir scan --explain` prints the
+//! same figures for a fresh scan without touching either. Only explaining looks for the
+//! repositories the skipped directories hide, that reads every one of them.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config,
+    error::{Result, YmirError},
+};
+
+/// Directories one entry of `ignore_dirs` kept out of a scan
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct IgnoredDirs {
+    pub dirs: usize,
+    /// Repositories in the skipped directories or right below them, likely real projects, only
+    /// looked for by [`IgnoreStats::explaining`]
+    pub projects: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct IgnoreStats {
+    /// Keyed by the entry of `ignore_dirs`
+    pub rules: BTreeMap<String, IgnoredDirs>,
+    /// Entries the walk couldn't read, their paths are in the scan errors
+    pub unreadable: usize,
+    /// Whether skipped directories are read to find the projects they hide
+    #[serde(skip)]
+    explain: bool,
+}
+
+impl IgnoreStats {
+    /// Stats that also list the projects in skipped directories
+    pub fn explaining() -> Self {
+        Self {
+            explain: true,
+            ..Self::default()
+        }
+    }
+
+    /// Location of the stats of the last scan
+    pub fn path() -> Option<PathBuf> {
+        config::app_dir().map(|dir| dir.join("ignore_stats.json"))
+    }
+
+    /// Stats of the last scan, empty when nothing was scanned yet
+    pub fn load() -> Self {
+        Self::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|v| serde_json::from_str(&v).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path().ok_or(YmirError::NoConfigDir)?;
+        fs::write(&path, serde_json::to_string(self)?).map_err(|err| YmirError::io(&path, err))
+    }
+
+    /// Counts `dir` as skipped by `rule`
+    ///
+    /// When explaining, `dir` and its children are checked for repositories, going any deeper
+    /// would be the walk that was skipped.
+    pub fn skip(&mut self, rule: &str, dir: &Path) {
+        let ignored = self.rules.entry(rule.to_string()).or_default();
+        ignored.dirs += 1;
+        if !self.explain {
+            return;
+        }
+
+        let children = fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .filter_map(Result::ok)
+            .map(|v| v.path());
+        ignored.projects.extend(
+            std::iter::once(dir.to_path_buf())
+                .chain(children)
+                .filter(|v| v.join(".git").exists()),
+        );
+    }
+
+    /// Adds the stats of a scan of another root
+    pub fn merge(&mut self, other: Self) {
+        for (rule, dirs) in other.rules {
+            let ignored = self.rules.entry(rule).or_default();
+            ignored.dirs += dirs.dirs;
+            ignored.projects.extend(dirs.projects);
+        }
+        self.unreadable += other.unreadable;
+    }
+
+    /// Repositories kept out of the scan by any rule
+    pub fn hidden_projects(&self) -> usize {
+        self.rules.values().map(|v| v.projects.len()).sum()
+    }
+}
+
+impl Display for IgnoreStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.rules.is_empty() {
+            writeln!(f, "Nothing was skipped by ignore_dirs")?;
+        }
+        for (rule, ignored) in &self.rules {
+            writeln!(
+                f,
+                "{rule}: {} {}",
+                ignored.dirs,
+                if ignored.dirs == 1 { "dir" } else { "dirs" }
+            )?;
+            for project in &ignored.projects {
+                writeln!(f, "  hides project {}", project.display())?;
+            }
+        }
+        write!(f, "Unreadable: {}", self.unreadable)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::IgnoreStats;
+    use crate::test_utils::temp_dir;
+
+    #[test]
+    fn counts_skipped_dirs_and_hidden_projects() {
+        let tmp = temp_dir("ignore-stats");
+        let project = tmp.join("vendor/lib");
+        fs::create_dir_all(project.join(".git")).unwrap();
+        fs::create_dir_all(tmp.join("vendor/plain")).unwrap();
+
+        let mut stats = IgnoreStats::explaining();
+        stats.skip("target", &tmp.join("target"));
+        stats.skip("vendor", &tmp.join("vendor"));
+        let mut other = IgnoreStats::explaining();
+        other.skip("target", &tmp.join("other/target"));
+        other.unreadable = 2;
+        stats.merge(other);
+
+        assert_eq!(stats.rules["target"].dirs, 2);
+        assert!(stats.rules["target"].projects.is_empty());
+        assert_eq!(stats.rules["vendor"].projects, [project]);
+        assert_eq!(stats.hidden_projects(), 1);
+        assert_eq!(
+            stats.to_string(),
+            format!(
+                "target: 2 dirs\nvendor: 1 dir\n  hides project {}\nUnreadable: 2",
+                tmp.join("vendor/lib").display()
+            )
+        );
+    }
+
+    #[test]
+    fn only_explaining_reads_skipped_dirs() {
+        let tmp = temp_dir("ignore-stats-quiet");
+        fs::create_dir_all(tmp.join("vendor/lib/.git")).unwrap();
+
+        let mut stats = IgnoreStats::default();
+        stats.skip("vendor", &tmp.join("vendor"));
+        assert_eq!(stats.rules["vendor"].dirs, 1);
+        assert_eq!(stats.hidden_projects(), 0);
+    }
+}
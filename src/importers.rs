@@ -0,0 +1,222 @@
+//! Project lists of other tools, used to seed the cache without crawling the whole disk
+//!
+//! Only the lists themselves are read, every project found is then scanned like any other.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::error::YmirError;
+
+/// Tool to import the projects of
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    /// Repositories cloned with ghq, laid out as `<root>/<host>/<owner>/<repo>`
+    Ghq,
+    /// Known projects of Emacs projectile
+    Projectile,
+    /// Recently opened folders of VS Code and VSCodium
+    VsCode,
+}
+
+impl Source {
+    pub fn parse(name: &str) -> Result<Self, YmirError> {
+        match name {
+            "ghq" => Ok(Self::Ghq),
+            "projectile" => Ok(Self::Projectile),
+            "vscode" => Ok(Self::VsCode),
+            _ => Err(YmirError::UnknownSource(name.to_string())),
+        }
+    }
+
+    /// Existing directories listed by the tool, nested ones are left to the scan of their parent
+    pub fn paths(self) -> Result<Vec<PathBuf>, YmirError> {
+        let home = dirs::home_dir().ok_or(YmirError::NoHomeDir)?;
+
+        let mut paths: Vec<PathBuf> = match self {
+            Self::Ghq => ghq_roots(&home)
+                .iter()
+                .flat_map(|root| children(root, 3))
+                .collect(),
+            Self::Projectile => [
+                ".emacs.d/projectile-bookmarks.eld",
+                ".config/emacs/projectile-bookmarks.eld",
+                ".emacs.d/.local/cache/projectile.projects",
+            ]
+            .iter()
+            .filter_map(|file| fs::read_to_string(home.join(file)).ok())
+            .flat_map(|text| eld_strings(&text, &home))
+            .collect(),
+            Self::VsCode => dirs::config_dir()
+                .into_iter()
+                .flat_map(|dir| {
+                    ["Code", "Code - Insiders", "VSCodium"]
+                        .map(|app| dir.join(app).join("User/globalStorage/storage.json"))
+                })
+                .filter_map(|file| fs::read_to_string(file).ok())
+                .filter_map(|text| serde_json::from_str(&text).ok())
+                .flat_map(|json| vscode_folders(&json))
+                .collect(),
+        };
+
+        paths.retain(|path| path.is_dir());
+        Ok(outermost(paths))
+    }
+}
+
+/// Roots ghq clones into, `~/ghq` when ghq isn't installed to ask
+fn ghq_roots(home: &Path) -> Vec<PathBuf> {
+    let roots: Vec<PathBuf> = Command::new("ghq")
+        .args(["root", "--all"])
+        .output()
+        .ok()
+        .filter(|v| v.status.success())
+        .map(|v| {
+            String::from_utf8_lossy(&v.stdout)
+                .lines()
+                .map(PathBuf::from)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    if roots.is_empty() {
+        vec![home.join("ghq")]
+    } else {
+        roots
+    }
+}
+
+/// Directories exactly `depth` levels below `root`
+fn children(root: &Path, depth: usize) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(root) else {
+        return Vec::new();
+    };
+    let dirs = entries
+        .filter_map(Result::ok)
+        .map(|v| v.path())
+        .filter(|v| v.is_dir());
+
+    if depth <= 1 {
+        dirs.collect()
+    } else {
+        dirs.flat_map(|dir| children(&dir, depth - 1)).collect()
+    }
+}
+
+/// Strings of an elisp list like `("~/src/ymir/" "/opt/tool/")`, with `~` expanded
+fn eld_strings(text: &str, home: &Path) -> Vec<PathBuf> {
+    text.split('"')
+        .skip(1)
+        .step_by(2)
+        .map(|path| match path.strip_prefix("~/") {
+            Some(rest) => home.join(rest),
+            None => PathBuf::from(path),
+        })
+        .collect()
+}
+
+/// Every `file://` folder anywhere in VS Code's `storage.json`, the layout changed between
+/// versions so keys and values are both looked at
+fn vscode_folders(json: &serde_json::Value) -> Vec<PathBuf> {
+    let mut folders = Vec::new();
+    let mut stack = vec![json];
+    while let Some(value) = stack.pop() {
+        match value {
+            serde_json::Value::String(text) => folders.extend(file_uri(text)),
+            serde_json::Value::Array(values) => stack.extend(values),
+            serde_json::Value::Object(map) => {
+                folders.extend(map.keys().filter_map(|key| file_uri(key)));
+                stack.extend(map.values());
+            }
+            _ => {}
+        }
+    }
+    folders
+}
+
+/// Path of a `file:///home/me/my%20project` uri
+fn file_uri(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+    let mut bytes = Vec::with_capacity(path.len());
+    let mut rest = path.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let hex = tail
+            .get(..2)
+            .and_then(|v| std::str::from_utf8(v).ok())
+            .and_then(|v| u8::from_str_radix(v, 16).ok());
+        match (byte, hex) {
+            (b'%', Some(decoded)) => {
+                bytes.push(decoded);
+                rest = &tail[2..];
+            }
+            _ => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+    Some(PathBuf::from(String::from_utf8_lossy(&bytes).to_string()))
+}
+
+/// Drops duplicates and paths inside other paths, the scan of those finds them anyway
+fn outermost(mut paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    paths.sort();
+    let mut kept: Vec<PathBuf> = Vec::new();
+    for path in paths {
+        if !kept.last().is_some_and(|last| path.starts_with(last)) {
+            kept.push(path);
+        }
+    }
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use serde_json::json;
+
+    use super::{eld_strings, outermost, vscode_folders};
+
+    #[test]
+    fn reads_projectile_bookmarks() {
+        assert_eq!(
+            eld_strings(r#"("~/src/ymir/" "/opt/tool/")"#, Path::new("/home/me")),
+            [
+                PathBuf::from("/home/me/src/ymir/"),
+                PathBuf::from("/opt/tool/")
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_vscode_storage() {
+        let storage = json!({
+            "backupWorkspaces": { "folders": [{ "folderUri": "file:///src/my%20project" }] },
+            "profileAssociations": { "workspaces": { "file:///src/ymir": "__default__profile__" } },
+            "windowsState": { "lastActiveWindow": { "folder": "vscode-remote://ssh/src/far" } },
+        });
+
+        let mut folders = vscode_folders(&storage);
+        folders.sort();
+        assert_eq!(
+            folders,
+            [PathBuf::from("/src/my project"), PathBuf::from("/src/ymir")]
+        );
+    }
+
+    #[test]
+    fn keeps_outermost_paths() {
+        assert_eq!(
+            outermost(vec![
+                PathBuf::from("/src/ymir/crates/core"),
+                PathBuf::from("/src/ymir"),
+                PathBuf::from("/src/ymir"),
+                PathBuf::from("/src/ymir-web"),
+            ]),
+            [PathBuf::from("/src/ymir"), PathBuf::from("/src/ymir-web")]
+        );
+    }
+}
@@ -0,0 +1,99 @@
+//! `include_paths`, globs limiting a scan to the subtrees they match
+//!
+//! The walk still has to pass through the directories leading to a match, like `~` and `~/code`
+//! for `~/code/*`, but doesn't look anywhere else in them.
+
+use std::path::Path;
+
+use log::error;
+
+use crate::{generated::glob, utils::expand_home};
+
+pub struct IncludePaths {
+    /// Absolute patterns without trailing slashes
+    patterns: Vec<String>,
+}
+
+impl IncludePaths {
+    /// Patterns with `~` expanded, relative ones are taken to be under `root`
+    pub fn new(root: &Path, patterns: &[String]) -> Self {
+        let patterns = patterns
+            .iter()
+            .filter_map(|pattern| {
+                let path = expand_home(pattern)
+                    .inspect_err(|err| error!("Invalid include_paths entry {pattern}: {err}"))
+                    .ok()?;
+                let path = root.join(path);
+                let text = path.to_str()?.trim_end_matches('/');
+                Some(text.to_string())
+            })
+            .collect();
+        Self { patterns }
+    }
+
+    /// Whether the walk enters `dir`, everything is when there are no patterns
+    pub fn allows(&self, dir: &Path) -> bool {
+        let Some(text) = dir.to_str() else {
+            return false;
+        };
+        let text = text.trim_end_matches('/');
+
+        self.patterns.is_empty()
+            || self
+                .patterns
+                .iter()
+                .any(|pattern| covers(pattern, dir) || leads_to(pattern, text))
+    }
+}
+
+/// `dir` or one of its parents matches `pattern`
+fn covers(pattern: &str, dir: &Path) -> bool {
+    dir.ancestors()
+        .filter_map(Path::to_str)
+        .any(|v| glob(pattern.as_bytes(), v.trim_end_matches('/').as_bytes()))
+}
+
+/// Something below `dir` could match `pattern`, its leading components match those of `dir`
+/// up to the first `**`
+fn leads_to(pattern: &str, dir: &str) -> bool {
+    let dir: Vec<&str> = dir.split('/').collect();
+    let mut leading: Vec<&str> = pattern.split('/').take(dir.len()).collect();
+    if let Some(i) = leading.iter().position(|v| v.contains("**")) {
+        leading.truncate(i);
+    }
+    glob(
+        leading.join("/").as_bytes(),
+        dir[..leading.len()].join("/").as_bytes(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::IncludePaths;
+
+    #[test]
+    fn allows_matches_and_the_way_to_them() {
+        let include = IncludePaths::new(
+            Path::new("/home/me"),
+            &["code/*/".to_string(), "/srv/**/repos".to_string()],
+        );
+
+        for dir in [
+            "/",
+            "/home",
+            "/home/me",
+            "/home/me/code",
+            "/home/me/code/ymir",
+            "/home/me/code/ymir/src",
+            "/srv/a/b",
+        ] {
+            assert!(include.allows(Path::new(dir)), "{dir}");
+        }
+        for dir in ["/home/me/Downloads", "/home/other", "/opt"] {
+            assert!(!include.allows(Path::new(dir)), "{dir}");
+        }
+        assert!(IncludePaths::new(Path::new("/"), &[]).allows(Path::new("/opt")));
+    }
+}
@@ -0,0 +1,196 @@
+//! Single instance support over a unix socket
+//!
+//! The running TUI listens on a socket in the runtime directory, a second invocation connects to it
+//! and sends one line per message instead of starting its own scan.
+
+use std::{
+    fs,
+    io::{BufRead, BufReader, ErrorKind, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    path::{Path, PathBuf},
+    process::Command,
+    sync::mpsc::{self, Receiver},
+    thread,
+};
+
+use anyhow::{bail, Context};
+
+use crate::config;
+
+/// Messages a second instance can send to the running one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IpcMessage {
+    /// Bring the running instance to the foreground
+    Focus,
+    /// Reload projects from the cache
+    Refresh,
+    /// Select the project at or containing the path
+    Select(PathBuf),
+}
+
+impl IpcMessage {
+    fn encode(&self) -> String {
+        match self {
+            Self::Focus => "focus\n".to_string(),
+            Self::Refresh => "refresh\n".to_string(),
+            Self::Select(path) => format!("select {}\n", path.display()),
+        }
+    }
+
+    fn decode(line: &str) -> Option<Self> {
+        match line.split_once(' ') {
+            Some(("select", path)) => Some(Self::Select(PathBuf::from(path))),
+            None if line == "focus" => Some(Self::Focus),
+            None if line == "refresh" => Some(Self::Refresh),
+            _ => None,
+        }
+    }
+}
+
+/// Location of the socket, prefers the runtime directory since it's cleaned on logout
+///
+/// Every profile gets a socket of its own so instances only hand over within one.
+pub fn socket_path() -> Option<PathBuf> {
+    dirs::runtime_dir().or_else(dirs::config_dir).map(|dir| {
+        dir.join(env!("CARGO_PKG_NAME"))
+            .join(socket_name(config::profile()))
+    })
+}
+
+fn socket_name(profile: Option<&str>) -> String {
+    profile.map_or_else(|| "ymir.sock".to_string(), |v| format!("ymir-{v}.sock"))
+}
+
+/// Sends a message to the running instance, fails if there is none
+pub fn send(message: &IpcMessage) -> anyhow::Result<()> {
+    let Some(path) = socket_path() else {
+        bail!("Failed to find socket directory");
+    };
+    send_to(&path, message)
+}
+
+fn send_to(path: &Path, message: &IpcMessage) -> anyhow::Result<()> {
+    let mut stream = UnixStream::connect(path)
+        .with_context(|| format!("No running instance at {}", path.display()))?;
+    stream.write_all(message.encode().as_bytes())?;
+
+    Ok(())
+}
+
+/// Listening side of the socket, removes the socket file when dropped
+pub struct IpcServer {
+    path: PathBuf,
+    pub receiver: Receiver<IpcMessage>,
+}
+
+impl IpcServer {
+    /// Binds the socket and forwards incoming messages to `receiver` from a background thread,
+    /// fails while another instance listens on it
+    pub fn bind() -> anyhow::Result<Self> {
+        let Some(path) = socket_path() else {
+            bail!("Failed to find socket directory");
+        };
+        Self::bind_at(path)
+    }
+
+    fn bind_at(path: PathBuf) -> anyhow::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        match UnixStream::connect(&path) {
+            Ok(_) => bail!("Another instance is listening on {}", path.display()),
+            // Nothing answered on it, so the socket was left behind by an instance that crashed
+            Err(err) if err.kind() == ErrorKind::ConnectionRefused => fs::remove_file(&path)?,
+            Err(err) if err.kind() == ErrorKind::NotFound => {}
+            Err(err) => {
+                return Err(err).with_context(|| format!("Failed to check {}", path.display()))
+            }
+        }
+
+        let listener = UnixListener::bind(&path)
+            .with_context(|| format!("Failed to bind {}", path.display()))?;
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            for stream in listener.incoming().filter_map(Result::ok) {
+                for line in BufReader::new(stream).lines().map_while(Result::ok) {
+                    let Some(message) = IpcMessage::decode(&line) else {
+                        warn!("Unknown ipc message: {line}");
+                        continue;
+                    };
+
+                    if sender.send(message).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+
+        Ok(Self { path, receiver })
+    }
+}
+
+impl Drop for IpcServer {
+    fn drop(&mut self) {
+        if let Err(err) = fs::remove_file(&self.path) {
+            error!("Failed to remove socket: {err}");
+        }
+    }
+}
+
+/// Switches tmux to the pane ymir runs in, there is no portable way to raise a terminal window
+pub fn focus_terminal() {
+    let Ok(pane) = std::env::var("TMUX_PANE") else {
+        return;
+    };
+
+    for args in [["select-window", "-t"], ["select-pane", "-t"]] {
+        if let Err(err) = Command::new("tmux").args(args).arg(&pane).status() {
+            error!("Failed to focus tmux pane: {err}");
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{os::unix::net::UnixListener, path::PathBuf, time::Duration};
+
+    use super::{send_to, socket_name, IpcMessage, IpcServer};
+    use crate::test_utils::temp_dir;
+
+    #[test]
+    fn messages_reach_the_running_instance() {
+        let dir = temp_dir("ipc");
+        let path = dir.join("ymir.sock");
+
+        // Left behind by an instance that crashed
+        drop(UnixListener::bind(&path).unwrap());
+        let server = IpcServer::bind_at(path.clone()).unwrap();
+        assert!(IpcServer::bind_at(path.clone()).is_err());
+
+        let messages = [
+            IpcMessage::Focus,
+            IpcMessage::Refresh,
+            IpcMessage::Select(PathBuf::from("/src/my project")),
+        ];
+        for message in &messages {
+            send_to(&path, message).unwrap();
+        }
+        for message in messages {
+            let received = server.receiver.recv_timeout(Duration::from_secs(5));
+            assert_eq!(received, Ok(message));
+        }
+
+        drop(server);
+        assert!(!path.exists());
+        assert!(send_to(&path, &IpcMessage::Focus).is_err());
+    }
+
+    #[test]
+    fn profiles_have_their_own_socket() {
+        assert_eq!(socket_name(None), "ymir.sock");
+        assert_eq!(socket_name(Some("work")), "ymir-work.sock");
+    }
+}
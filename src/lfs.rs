@@ -0,0 +1,89 @@
+//! Git LFS, the patterns `.gitattributes` hands to it and the objects it keeps under `.git/lfs`
+
+use std::{fs, path::Path};
+
+use serde::Serialize;
+
+use crate::utils::get_size_skipping;
+
+/// Share of the project size above which LFS objects are called out in the info pane
+const DOMINANT: f64 = 0.5;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Lfs {
+    /// Patterns of the top-level `.gitattributes` with `filter=lfs`
+    pub patterns: Vec<String>,
+    /// Bytes of the local object store, part of the project size
+    pub size: u64,
+}
+
+impl Lfs {
+    pub fn detect(root: &Path) -> Self {
+        let patterns = fs::read_to_string(root.join(".gitattributes"))
+            .map(|v| patterns(&v))
+            .unwrap_or_default();
+        let objects = root.join(".git").join("lfs").join("objects");
+        let size = if objects.is_dir() {
+            get_size_skipping(&objects, &|_| false).map_or(0, |(size, _)| size)
+        } else {
+            0
+        };
+        Self { patterns, size }
+    }
+
+    pub fn is_used(&self) -> bool {
+        !self.patterns.is_empty() || self.size > 0
+    }
+
+    /// Whether the objects take up most of `total`, the size of the whole project
+    #[allow(clippy::cast_precision_loss)]
+    pub fn dominates(&self, total: u64) -> bool {
+        total > 0 && self.size as f64 / total as f64 > DOMINANT
+    }
+}
+
+fn patterns(gitattributes: &str) -> Vec<String> {
+    gitattributes
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let pattern = fields.next()?;
+            fields
+                .any(|v| v == "filter=lfs")
+                .then(|| pattern.to_string())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::Lfs;
+    use crate::test_utils::temp_dir;
+
+    #[test]
+    fn reads_patterns_and_objects() {
+        let dir = temp_dir("lfs");
+        let objects = dir.join(".git/lfs/objects/ab/cd");
+        fs::create_dir_all(&objects).unwrap();
+        fs::write(objects.join("abcd1234"), vec![0; 3000]).unwrap();
+        fs::write(
+            dir.join(".gitattributes"),
+            "# Design files\n*.psd filter=lfs diff=lfs merge=lfs -text\n\
+             *.sh text eol=lf\n  assets/** filter=lfs diff=lfs merge=lfs -text\n",
+        )
+        .unwrap();
+
+        let lfs = Lfs::detect(&dir);
+        assert_eq!(lfs.patterns, ["*.psd", "assets/**"]);
+        assert_eq!(lfs.size, 3000);
+        assert!(lfs.is_used());
+        assert!(lfs.dominates(4000));
+        assert!(!lfs.dominates(8000));
+
+        assert_eq!(Lfs::detect(&dir.join("missing")), Lfs::default());
+    }
+}
@@ -0,0 +1,58 @@
+//! Library side of ymir, shared by the binary, benchmarks and fuzz targets
+
+#[macro_use]
+extern crate log;
+
+pub mod app;
+pub mod archive;
+pub mod audit;
+pub mod cache;
+pub mod ci;
+pub mod commands;
+pub mod commit_graph;
+pub mod commit_search;
+pub mod config;
+pub mod content_search;
+pub mod credentials;
+pub mod devenv;
+pub mod diff;
+pub mod digest;
+pub mod dir_picker;
+pub mod duplicates;
+pub mod effort;
+pub mod enrichment;
+pub mod error;
+pub mod export;
+pub mod generated;
+pub mod global_ignore;
+pub mod graphics;
+pub mod history;
+pub mod hosts;
+pub mod huffman;
+pub mod icons;
+pub mod ignore_stats;
+pub mod importers;
+pub mod include_paths;
+pub mod ipc;
+pub mod lfs;
+pub mod linguist;
+pub mod manifest;
+pub mod menu;
+pub mod palette;
+pub mod projects;
+pub mod recent_files;
+pub mod row_style;
+pub mod serve;
+pub mod signature;
+pub mod sort_expr;
+pub mod sorting;
+pub mod submodules;
+pub mod tasks;
+pub mod template;
+pub mod terminal;
+#[cfg(test)]
+mod test_utils;
+pub mod theme;
+pub mod trend;
+pub mod utils;
+pub mod zoxide;
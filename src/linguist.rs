@@ -0,0 +1,85 @@
+//! Language colors of GitHub linguist, shown as list markers with `language_markers`
+//!
+//! Copied from linguist's `languages.yml`, languages linguist has no color for are left out.
+
+use ratatui::style::Color;
+use tokei::LanguageType;
+
+const fn rgb(hex: u32) -> Color {
+    let [_, r, g, b] = hex.to_be_bytes();
+    Color::Rgb(r, g, b)
+}
+
+pub const fn color(language: LanguageType) -> Option<Color> {
+    Some(rgb(match language {
+        LanguageType::Rust => 0xdea584,
+        LanguageType::Python => 0x3572a5,
+        LanguageType::JavaScript | LanguageType::Jsx => 0xf1e05a,
+        LanguageType::TypeScript | LanguageType::Tsx => 0x3178c6,
+        LanguageType::Go => 0x00add8,
+        LanguageType::C | LanguageType::CHeader => 0x555555,
+        LanguageType::Cpp | LanguageType::CppHeader => 0xf34b7d,
+        LanguageType::CSharp => 0x178600,
+        LanguageType::ObjectiveC => 0x438eff,
+        LanguageType::Java => 0xb07219,
+        LanguageType::Kotlin => 0xa97bff,
+        LanguageType::Scala => 0xc22d40,
+        LanguageType::Groovy => 0x4298b8,
+        LanguageType::Clojure => 0xdb5855,
+        LanguageType::Swift => 0xf05138,
+        LanguageType::Dart => 0x00b4ab,
+        LanguageType::Ruby => 0x701516,
+        LanguageType::Crystal => 0x000100,
+        LanguageType::Php => 0x4f5d95,
+        LanguageType::Perl => 0x0298c3,
+        LanguageType::Haskell => 0x5e5086,
+        LanguageType::OCaml => 0xef7a08,
+        LanguageType::FSharp => 0xb845fc,
+        LanguageType::Elm => 0x60b5cc,
+        LanguageType::Elixir => 0x6e4a7e,
+        LanguageType::Erlang => 0xb83998,
+        LanguageType::Julia => 0xa270ba,
+        LanguageType::R => 0x198ce7,
+        LanguageType::Lua => 0x000080,
+        LanguageType::Nim => 0xffc200,
+        LanguageType::Zig => 0xec915c,
+        LanguageType::Solidity => 0xaa6746,
+        LanguageType::Nix => 0x7e7eff,
+        LanguageType::Sh | LanguageType::Bash | LanguageType::Zsh | LanguageType::Fish => 0x89e051,
+        LanguageType::PowerShell => 0x012456,
+        LanguageType::VimScript => 0x199f4b,
+        LanguageType::Html => 0xe34c26,
+        LanguageType::Css => 0x563d7c,
+        LanguageType::Sass => 0xa53b70,
+        LanguageType::Less => 0x1d365d,
+        LanguageType::Vue => 0x41b883,
+        LanguageType::Svelte => 0xff3e00,
+        LanguageType::Tex => 0x3d6117,
+        LanguageType::Markdown => 0x083fa1,
+        LanguageType::Json => 0x292929,
+        LanguageType::Toml => 0x9c4221,
+        LanguageType::Yaml => 0xcb171e,
+        LanguageType::Makefile => 0x427819,
+        LanguageType::CMake => 0xda3434,
+        LanguageType::Dockerfile => 0x384d54,
+        _ => return None,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::style::Color;
+    use tokei::LanguageType;
+
+    use super::color;
+
+    #[test]
+    fn colors_are_linguist_hex_codes() {
+        assert_eq!(
+            color(LanguageType::Rust),
+            Some(Color::Rgb(0xde, 0xa5, 0x84))
+        );
+        assert_eq!(color(LanguageType::Tsx), color(LanguageType::TypeScript));
+        assert_eq!(color(LanguageType::Cobol), None);
+    }
+}
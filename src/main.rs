@@ -1,56 +1,125 @@
 //! Ymir is a tool for finding projects
 #![warn(missing_docs)]
 
-#[macro_use]
-extern crate log;
-
-mod app;
-mod cache;
-mod config;
-mod huffman;
-mod projects;
-mod sorting;
-mod utils;
-
-use std::{env, fs::File, path::PathBuf};
+use std::{
+    env,
+    fs::{self, File},
+    path::PathBuf,
+};
 
 use anyhow::bail;
-use app::App;
-use config::{Cache, Settings};
 use getopts::Options;
-use log::LevelFilter;
+use log::{debug, error, warn, LevelFilter};
 use simplelog::ConfigBuilder;
+use ymir::{
+    app::App,
+    commands,
+    config::{self, Cache, Settings, Theme},
+    digest,
+    dir_picker::DirPicker,
+    history::History,
+    ipc::{self, IpcMessage, IpcServer},
+    menu, serve,
+    tasks::Task,
+    terminal, zoxide,
+};
 
 fn print_usage(opts: &Options) {
-    let brief = format!("Usage: {} [PATH] [OPTIONS]", env!("CARGO_PKG_NAME"));
+    let brief = format!(
+        "Usage: {0} [PATH] [OPTIONS]\n       {0} cache info|compact|hosts\n       {0} cache export|import FILE [HOST]\n       {0} refresh [PATH]\n       {0} scan [PATH] [--explain]\n       {0} diff [PATH] [--json]\n       {0} show [PATH] [--json]\n       {0} trend OLD [NEW] [--json]\n       {0} digest\n       {0} current [PATH]\n       {0} menu [CHOICE|-] [--format dmenu|paths]\n       {0} import ghq|projectile|vscode\n       {0} export FILE\n       {0} auth login|logout github|gitlab\n       {0} auth status\n       {0} serve [ADDRESS]",
+        env!("CARGO_PKG_NAME")
+    );
     print!("{}", opts.usage(&brief));
 }
 
-fn main() -> anyhow::Result<()> {
-    let Some(config_dir) = dirs::config_dir() else {
-        bail!("Failed to find config_directory")
-    };
+/// Lets the user browse to a root when none is configured, `None` if they gave up
+fn pick_dir(theme: Theme) -> anyhow::Result<Option<PathBuf>> {
+    let start = env::current_dir()
+        .ok()
+        .or_else(dirs::home_dir)
+        .unwrap_or_else(|| PathBuf::from("/"));
 
-    let log_path = config_dir
-        .join(env!("CARGO_PKG_NAME"))
-        .join(format!("{}.log", env!("CARGO_PKG_NAME")));
+    let terminal = ratatui::init();
+    let picked = DirPicker::new(start).with_theme(theme).run(terminal);
+    ratatui::restore();
 
-    let Ok(log_file) = File::create(log_path) else {
-        bail!("Failed to create log file");
+    let Some(picked) = picked? else {
+        return Ok(None);
     };
+    if picked.save {
+        if let Err(err) = Settings::save_default_dir(&picked.path) {
+            eprintln!("Failed to save default_dir: {err:#}");
+            error!("Failed to save default_dir: {err:#}");
+        }
+    }
+    Ok(Some(picked.path))
+}
 
-    simplelog::WriteLogger::init(
-        LevelFilter::Info,
-        ConfigBuilder::new().add_filter_ignore_str("tokei").build(),
-        log_file,
-    )?;
-
+fn main() -> anyhow::Result<()> {
     let args: Vec<String> = env::args().collect();
 
     let mut opts = Options::new();
     opts.optflag("", "gen-config", "Saves config in config directory");
     opts.optflag("", "no-cache", "Don't create cache file");
-    opts.optflag("f", "fresh", "Recreate cache file from scratch");
+    opts.optflag(
+        "f",
+        "fresh",
+        "Rescan, replacing what the cache has for the directory",
+    );
+    opts.optflag(
+        "",
+        "incremental",
+        "Rescan in the background, keeping the stats of projects that didn't change",
+    );
+    opts.optflag(
+        "",
+        "no-compress",
+        "Write the cache file without huffman encoding",
+    );
+    opts.optflag(
+        "",
+        "single-instance",
+        "Hand over to a running instance if there is one",
+    );
+    opts.optflag(
+        "",
+        "last",
+        "Open a shell in the most recently opened project without the TUI",
+    );
+    opts.optflag(
+        "",
+        "json",
+        "Print the output of diff, show and trend as JSON",
+    );
+    opts.optflag("", "explain", "Print what ignore_dirs skipped during scan");
+    opts.optopt(
+        "",
+        "format",
+        "Entries of menu as dmenu lines or only paths",
+        "dmenu|paths",
+    );
+    opts.optflag(
+        "",
+        "no-color",
+        "Draw without colors, same as setting NO_COLOR",
+    );
+    opts.optopt(
+        "",
+        "select",
+        "Start with the project matching QUERY best selected",
+        "QUERY",
+    );
+    opts.optflag(
+        "",
+        "open",
+        "Open a shell in the project chosen with --select right away",
+    );
+    opts.optopt(
+        "",
+        "profile",
+        "Use a separate config, cache and history",
+        "NAME",
+    );
     opts.optflag("h", "help", "Print help");
 
     let matches = match opts.parse(&args[1..]) {
@@ -58,48 +127,215 @@ fn main() -> anyhow::Result<()> {
         Err(f) => bail!("{}", f),
     };
 
+    if let Some(profile) = matches.opt_str("profile") {
+        config::set_profile(&profile)?;
+    }
+
+    let Some(app_dir) = config::app_dir() else {
+        bail!("Failed to find config_directory")
+    };
+    fs::create_dir_all(&app_dir)?;
+    let log_path = app_dir.join(format!("{}.log", env!("CARGO_PKG_NAME")));
+
+    let Ok(log_file) = File::create(log_path) else {
+        bail!("Failed to create log file");
+    };
+
+    simplelog::WriteLogger::init(
+        LevelFilter::Info,
+        ConfigBuilder::new().add_filter_ignore_str("tokei").build(),
+        log_file,
+    )?;
+
     if matches.opt_present("h") {
         print_usage(&opts);
         return Ok(());
     }
 
+    let select = matches.opt_str("select");
+    if matches.opt_present("open") && select.is_none() {
+        bail!("--open needs a project to open, pick one with --select");
+    }
+
     if matches.opt_present("gen-config") {
         Settings::write_config()?;
         return Ok(());
     }
 
+    if matches.free.first().is_some_and(|v| v == "export") {
+        return commands::export(&matches.free[1..]);
+    }
+
+    if matches.free.first().is_some_and(|v| v == "serve") {
+        return serve::run(
+            matches
+                .free
+                .get(1)
+                .map_or(serve::DEFAULT_ADDRESS, String::as_str),
+        );
+    }
+
     let path = matches.free.first().map(PathBuf::from);
-    let settings = Settings::new();
+    let mut settings = Settings::new();
+    settings.incremental |= matches.opt_present("incremental");
 
-    let Some(find_dir) = path.or(settings.default_dir) else {
-        bail!("You must specify the directory");
+    if matches.free.first().is_some_and(|v| v == "show") {
+        return commands::show(&matches.free[1..], &settings, matches.opt_present("json"));
+    }
+
+    if matches.free.first().is_some_and(|v| v == "cache") {
+        return commands::cache(&matches.free[1..], &settings);
+    }
+
+    if matches.free.first().is_some_and(|v| v == "refresh") {
+        return commands::refresh(&matches.free[1..], &settings);
+    }
+
+    if matches.free.first().is_some_and(|v| v == "import") {
+        return commands::import(&matches.free[1..], &settings);
+    }
+
+    if matches.free.first().is_some_and(|v| v == "scan") {
+        return commands::scan(
+            &matches.free[1..],
+            &settings,
+            matches.opt_present("explain"),
+        );
+    }
+
+    if matches.free.first().is_some_and(|v| v == "diff") {
+        return commands::diff(&matches.free[1..], &settings, matches.opt_present("json"));
+    }
+
+    if matches.free.first().is_some_and(|v| v == "auth") {
+        return commands::auth(&matches.free[1..], &settings);
+    }
+
+    if matches.free.first().is_some_and(|v| v == "trend") {
+        return commands::trend(&matches.free[1..], matches.opt_present("json"));
+    }
+
+    if matches.free.first().is_some_and(|v| v == "current") {
+        return commands::current(&matches.free[1..]);
+    }
+
+    if matches.free.first().is_some_and(|v| v == "menu") {
+        let format = matches
+            .opt_str("format")
+            .map_or(Ok(menu::Format::Dmenu), |v| menu::Format::parse(&v))?;
+        return menu::run(&matches.free[1..], &settings, format);
+    }
+
+    if matches.free.first().is_some_and(|v| v == "digest") {
+        return digest::run(&Cache::try_read_cache()?, &settings);
+    }
+
+    if matches.opt_present("last") {
+        let history = History::load();
+        let Some(project) = history.last() else {
+            bail!("No project has been opened yet");
+        };
+        History::record(project)?;
+        if settings.zoxide {
+            if let Err(err) = zoxide::add(project) {
+                error!("Failed to add to zoxide: {err:#}");
+            }
+        }
+        if settings.report_cwd {
+            terminal::emit(&terminal::cwd(project));
+        }
+        return Task::shell(project, settings.allow_direnv).run(project);
+    }
+    settings.compress_cache = settings.compress_cache && !matches.opt_present("no-compress");
+    settings.theme = settings
+        .theme
+        .with_no_color(matches.opt_present("no-color"));
+    let single_instance = settings.single_instance || matches.opt_present("single-instance");
+
+    if single_instance {
+        let message = if matches.opt_present("fresh") {
+            IpcMessage::Refresh
+        } else if let Some(path) = &path {
+            IpcMessage::Select(path.canonicalize().unwrap_or_else(|_| path.clone()))
+        } else {
+            IpcMessage::Focus
+        };
+
+        if ipc::send(&message).is_ok() {
+            eprintln!("Handed over to running instance");
+            return Ok(());
+        }
+    }
+
+    let Some(find_dir) = path
+        .or_else(|| settings.default_dir.clone())
+        .map_or_else(|| pick_dir(settings.theme), |v| Ok(Some(v)))?
+    else {
+        return Ok(());
     };
+    // The cache keeps projects from every root scanned, they're told apart by absolute paths
+    let find_dir = find_dir.canonicalize().unwrap_or(find_dir);
 
-    let projects = if matches.opt_present("no-cache") {
+    // Scanning happens in the app, projects show up as they're found, `Some(false)` leaves the
+    // cache alone
+    let (projects, scan) = if matches.opt_present("no-cache") {
         eprintln!("Loading fresh data");
         debug!("Loading fresh data");
-        projects::find(&find_dir, &settings.ignore_dirs)
+        (Vec::new(), Some(false))
     } else if matches.opt_present("fresh") {
         eprintln!("Refreshing cache");
         debug!("Refreshing cache");
-        Cache::create_cache(&projects::find(&find_dir, &settings.ignore_dirs))
-            .unwrap_or_default()
-            .projects
+        (Vec::new(), Some(true))
     } else {
         eprintln!("Loading data from cache");
         debug!("Loading data from cache");
-        let cache = Cache::read_cache();
-        if cache.is_empty() {
-            Cache::create_cache(&projects::find(&find_dir, &settings.ignore_dirs))
-                .unwrap_or_default()
-                .projects
-        } else {
+        let cache = Cache::try_read_cache().map(|cache| {
             cache
+                .into_iter()
+                .filter(|p| p.path.starts_with(&find_dir))
+                .collect::<Vec<_>>()
+        });
+        match cache {
+            // Cached projects show while the incremental scan checks them
+            Ok(cache) if !cache.is_empty() => {
+                (cache, matches.opt_present("incremental").then_some(true))
+            }
+            Ok(_) => (Vec::new(), Some(true)),
+            Err(err) if err.is_corrupt_cache() => {
+                eprintln!("Cache is unreadable, rescanning: {err}");
+                error!("Cache is unreadable: {err}");
+                (Vec::new(), Some(true))
+            }
+            // Writing would fail the same way, so scan without touching the cache
+            Err(err) => {
+                eprintln!("Failed to read cache, scanning without it: {err}");
+                warn!("Failed to read cache: {err}");
+                (Vec::new(), Some(false))
+            }
         }
     };
 
+    let ipc = if single_instance {
+        IpcServer::bind()
+            .inspect_err(|err| error!("Failed to start ipc server: {err}"))
+            .ok()
+    } else {
+        None
+    };
+
+    let mut app = App::new(projects)
+        .with_ipc(ipc)
+        .with_settings(&settings)
+        .with_scan_root(find_dir);
+    if let Some(cache) = scan {
+        app = app.with_scan(cache);
+    }
+    if let Some(query) = &select {
+        app = app.with_selection(query, matches.opt_present("open"));
+    }
+
     let terminal = ratatui::init();
-    let app_result = App::new(projects).run(terminal);
+    let app_result = app.run(terminal);
     ratatui::restore();
     app_result
 }
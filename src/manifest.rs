@@ -0,0 +1,317 @@
+//! Metadata read from package manifests checked into a project
+
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+use toml::{Table, Value};
+
+use crate::generated::glob;
+
+/// Values read from `package.json`, `pyproject.toml` and `go.mod`, keyed like `npm.name`
+pub type ProjectMetadata = BTreeMap<String, String>;
+
+/// A package from `Cargo.toml`, one per workspace member
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RustCrate {
+    pub name: String,
+    /// `None` when the manifest doesn't set one, which cargo reads as 2015
+    pub edition: Option<String>,
+    pub lib: bool,
+    pub bin: bool,
+}
+
+impl RustCrate {
+    /// `lib`, `bin` or both
+    pub const fn kind(&self) -> &'static str {
+        match (self.lib, self.bin) {
+            (true, true) => "lib, bin",
+            (true, false) => "lib",
+            (false, true) => "bin",
+            (false, false) => "-",
+        }
+    }
+}
+
+impl Display for RustCrate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ({}, edition {})",
+            self.name,
+            self.kind(),
+            self.edition.as_deref().unwrap_or("2015")
+        )
+    }
+}
+
+fn read_toml(path: &Path) -> Option<Table> {
+    toml::from_str(&fs::read_to_string(path).ok()?).ok()
+}
+
+/// Crates of the project at `root`, the root package first followed by workspace members
+pub fn rust_crates(root: &Path) -> Vec<RustCrate> {
+    let Some(manifest) = read_toml(&root.join("Cargo.toml")) else {
+        return Vec::new();
+    };
+
+    let workspace = manifest.get("workspace").and_then(Value::as_table);
+    let workspace_edition = workspace
+        .and_then(|v| v.get("package"))
+        .and_then(|v| v.get("edition"))
+        .and_then(Value::as_str);
+
+    let mut crates: Vec<RustCrate> = rust_crate(root, &manifest, workspace_edition)
+        .into_iter()
+        .collect();
+
+    let members = |key| {
+        workspace
+            .and_then(|v| v.get(key))
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .flat_map(|member| expand_member(root, member))
+    };
+    let excluded: Vec<PathBuf> = members("exclude").collect();
+
+    let mut seen = vec![root.to_path_buf()];
+    for dir in members("members") {
+        if excluded.contains(&dir) || seen.contains(&dir) {
+            continue;
+        }
+        if let Some(member) = read_toml(&dir.join("Cargo.toml"))
+            .and_then(|manifest| rust_crate(&dir, &manifest, workspace_edition))
+        {
+            crates.push(member);
+        }
+        seen.push(dir);
+    }
+
+    crates
+}
+
+fn rust_crate(dir: &Path, manifest: &Table, workspace_edition: Option<&str>) -> Option<RustCrate> {
+    let package = manifest.get("package")?.as_table()?;
+    let name = package.get("name")?.as_str()?.to_string();
+
+    let edition = match package.get("edition") {
+        Some(Value::String(edition)) => Some(edition.clone()),
+        // `edition.workspace = true`
+        Some(Value::Table(table)) if table.get("workspace") == Some(&Value::Boolean(true)) => {
+            workspace_edition.map(String::from)
+        }
+        _ => None,
+    };
+
+    Some(RustCrate {
+        name,
+        edition,
+        lib: manifest.contains_key("lib") || dir.join("src/lib.rs").is_file(),
+        bin: manifest.contains_key("bin")
+            || dir.join("src/main.rs").is_file()
+            || dir.join("src/bin").is_dir(),
+    })
+}
+
+/// Every key [`metadata`] fills, searching `key:text` looks in only these
+pub const METADATA_KEYS: [&str; 6] = [
+    "go.module",
+    "go.version",
+    "npm.name",
+    "npm.scripts",
+    "python.name",
+    "python.tooling",
+];
+
+/// Metadata of the project at `root` from every manifest it has
+pub fn metadata(root: &Path) -> ProjectMetadata {
+    let mut metadata = ProjectMetadata::new();
+    npm_metadata(root, &mut metadata);
+    python_metadata(root, &mut metadata);
+    go_metadata(root, &mut metadata);
+    metadata
+}
+
+/// `npm.name` and `npm.scripts` from `package.json`
+fn npm_metadata(root: &Path, metadata: &mut ProjectMetadata) {
+    let Some(package) = fs::read_to_string(root.join("package.json"))
+        .ok()
+        .and_then(|v| serde_json::from_str::<serde_json::Value>(&v).ok())
+    else {
+        return;
+    };
+
+    if let Some(name) = package.get("name").and_then(serde_json::Value::as_str) {
+        metadata.insert("npm.name".to_string(), name.to_string());
+    }
+    if let Some(scripts) = package
+        .get("scripts")
+        .and_then(serde_json::Value::as_object)
+        .filter(|v| !v.is_empty())
+    {
+        let names: Vec<&str> = scripts.keys().map(String::as_str).collect();
+        metadata.insert("npm.scripts".to_string(), names.join(", "));
+    }
+}
+
+/// `python.name` and `python.tooling` from `pyproject.toml`, tooling being the `[tool.*]` tables
+fn python_metadata(root: &Path, metadata: &mut ProjectMetadata) {
+    let Some(pyproject) = read_toml(&root.join("pyproject.toml")) else {
+        return;
+    };
+    let tool = pyproject.get("tool").and_then(Value::as_table);
+
+    // Poetry before 2.0 kept the name in its own table
+    let name = pyproject
+        .get("project")
+        .or_else(|| tool.and_then(|v| v.get("poetry")))
+        .and_then(|v| v.get("name"))
+        .and_then(Value::as_str);
+    if let Some(name) = name {
+        metadata.insert("python.name".to_string(), name.to_string());
+    }
+
+    if let Some(tool) = tool.filter(|v| !v.is_empty()) {
+        let names: Vec<&str> = tool.keys().map(String::as_str).collect();
+        metadata.insert("python.tooling".to_string(), names.join(", "));
+    }
+}
+
+/// `go.module` and `go.version` from `go.mod`
+fn go_metadata(root: &Path, metadata: &mut ProjectMetadata) {
+    let Ok(go_mod) = fs::read_to_string(root.join("go.mod")) else {
+        return;
+    };
+
+    for line in go_mod.lines().map(str::trim) {
+        let key = match line.split_once(char::is_whitespace) {
+            Some(("module", _)) => "go.module",
+            Some(("go", _)) => "go.version",
+            _ => continue,
+        };
+        let value = line.split_whitespace().nth(1).unwrap_or_default();
+        metadata.insert(key.to_string(), value.trim_matches('"').to_string());
+    }
+}
+
+/// Directories a workspace member entry like `crates/*` stands for
+fn expand_member(root: &Path, member: &str) -> Vec<PathBuf> {
+    let member = member.trim_end_matches('/');
+    let (parent, pattern) = member.rsplit_once('/').unwrap_or(("", member));
+    if !pattern.contains(['*', '?']) {
+        return vec![root.join(member)];
+    }
+
+    let Ok(entries) = fs::read_dir(root.join(parent)) else {
+        return Vec::new();
+    };
+
+    let mut dirs: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .filter(|entry| glob(pattern.as_bytes(), entry.file_name().as_encoded_bytes()))
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{metadata, rust_crates, RustCrate, METADATA_KEYS};
+    use crate::test_utils::{temp_dir, TempDir};
+
+    fn fixture(name: &str) -> (TempDir, impl Fn(&str, &str)) {
+        let dir = temp_dir(name);
+
+        let root = dir.to_path_buf();
+        let write = move |path: &str, text: &str| {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, text).unwrap();
+        };
+        (dir, write)
+    }
+
+    #[test]
+    fn reads_workspace_members() {
+        let (dir, write) = fixture("manifest-cargo");
+        write(
+            "Cargo.toml",
+            "[package]\nname = \"app\"\nedition = \"2021\"\n\n\
+             [workspace]\nmembers = [\"crates/*\", \"tools/cli\"]\nexclude = [\"crates/old\"]\n\n\
+             [workspace.package]\nedition = \"2024\"\n",
+        );
+        write("src/main.rs", "");
+        write(
+            "crates/core/Cargo.toml",
+            "[package]\nname = \"core\"\nedition.workspace = true\n",
+        );
+        write("crates/core/src/lib.rs", "");
+        write("crates/old/Cargo.toml", "[package]\nname = \"old\"\n");
+        write("tools/cli/Cargo.toml", "[package]\nname = \"cli\"\n");
+        write("tools/cli/src/lib.rs", "");
+        write("tools/cli/src/bin/cli.rs", "");
+
+        assert_eq!(
+            rust_crates(&dir),
+            [
+                RustCrate {
+                    name: "app".to_string(),
+                    edition: Some("2021".to_string()),
+                    lib: false,
+                    bin: true,
+                },
+                RustCrate {
+                    name: "core".to_string(),
+                    edition: Some("2024".to_string()),
+                    lib: true,
+                    bin: false,
+                },
+                RustCrate {
+                    name: "cli".to_string(),
+                    edition: None,
+                    lib: true,
+                    bin: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_other_manifests() {
+        let (dir, write) = fixture("manifest-other");
+        write(
+            "package.json",
+            r#"{ "name": "web", "scripts": { "build": "vite build", "dev": "vite" } }"#,
+        );
+        write(
+            "pyproject.toml",
+            "[project]\nname = \"tool\"\n\n[tool.ruff]\n\n[tool.mypy]\nstrict = true\n",
+        );
+        write("go.mod", "module github.com/acme/tool\n\ngo 1.22\n");
+
+        let metadata: Vec<(String, String)> = metadata(&dir).into_iter().collect();
+        let expected = [
+            ("go.module", "github.com/acme/tool"),
+            ("go.version", "1.22"),
+            ("npm.name", "web"),
+            ("npm.scripts", "build, dev"),
+            ("python.name", "tool"),
+            ("python.tooling", "mypy, ruff"),
+        ]
+        .map(|(k, v)| (k.to_string(), v.to_string()));
+        assert_eq!(metadata, expected);
+        assert!(metadata
+            .iter()
+            .all(|(key, _)| METADATA_KEYS.contains(&key.as_str())));
+    }
+}
@@ -0,0 +1,170 @@
+//! `ymir menu`, cached projects for dmenu, rofi and other launchers
+//!
+//! Without arguments every project is printed one per line, most recently opened first. Passing
+//! the chosen line back, as an argument or on stdin with `-`, opens a shell in the project like
+//! Enter does in the TUI:
+//!
+//! ```sh
+//! $TERMINAL -e ymir menu "$(ymir menu | dmenu -l 20)"
+//! ```
+
+use std::{
+    collections::HashMap,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use anyhow::bail;
+
+use crate::{
+    config::{Cache, Settings},
+    history::History,
+    projects::Project,
+    tasks::Task,
+    terminal, zoxide,
+};
+
+/// How entries are printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// The label, a tab and the path, or just the path when the label is the path
+    Dmenu,
+    /// Only the path
+    Paths,
+}
+
+impl Format {
+    pub fn parse(name: &str) -> anyhow::Result<Self> {
+        match name {
+            "dmenu" | "rofi" => Ok(Self::Dmenu),
+            "paths" => Ok(Self::Paths),
+            _ => bail!("Unknown menu format {name}, expected dmenu or paths"),
+        }
+    }
+
+    fn entry(self, project: &Project, settings: &Settings) -> String {
+        let path = project.path.display().to_string();
+        let label = settings.label(project);
+        if self == Self::Paths || label == path {
+            path
+        } else {
+            format!("{label}\t{path}")
+        }
+    }
+}
+
+/// `ymir menu [CHOICE|-]`
+pub fn run(args: &[String], settings: &Settings, format: Format) -> anyhow::Result<()> {
+    let projects = Cache::try_read_cache()?;
+    let choice = match args.first().map(String::as_str) {
+        None => {
+            for line in entries(&projects, &History::load(), settings, format) {
+                println!("{line}");
+            }
+            return Ok(());
+        }
+        Some("-") => {
+            let mut input = String::new();
+            io::stdin().read_to_string(&mut input)?;
+            input
+        }
+        Some(choice) => choice.to_string(),
+    };
+
+    let Some(project) = chosen(&projects, &choice) else {
+        bail!("{} isn't a cached project", choice.trim());
+    };
+    History::record(project)?;
+    if settings.zoxide {
+        if let Err(err) = zoxide::add(project) {
+            error!("Failed to add to zoxide: {err:#}");
+        }
+    }
+    if settings.report_cwd {
+        terminal::emit(&terminal::cwd(project));
+    }
+    Task::shell(project, settings.allow_direnv).run(project)
+}
+
+/// Lines for `projects`, most recently opened first and the rest by path
+fn entries(
+    projects: &[Project],
+    history: &History,
+    settings: &Settings,
+    format: Format,
+) -> Vec<String> {
+    let opened: HashMap<&Path, u64> = history
+        .entries
+        .iter()
+        .map(|(time, path)| (path.as_path(), *time))
+        .collect();
+
+    let mut projects: Vec<&Project> = projects.iter().collect();
+    projects.sort_by(|a, b| {
+        let time = |v: &Project| opened.get(v.path.as_path()).copied();
+        time(b).cmp(&time(a)).then_with(|| a.path.cmp(&b.path))
+    });
+    projects
+        .into_iter()
+        .map(|v| format.entry(v, settings))
+        .collect()
+}
+
+/// Project a line printed by [`entries`] stands for, the path is after the last tab
+fn chosen<'a>(projects: &'a [Project], line: &str) -> Option<&'a PathBuf> {
+    let line = line.trim_end_matches(['\n', '\r']);
+    let path = Path::new(line.rsplit_once('\t').map_or(line, |(_, path)| path));
+    projects.iter().map(|v| &v.path).find(|v| *v == path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::{chosen, entries, Format};
+    use crate::{
+        config::{ProjectLabel, Settings},
+        history::History,
+        projects::Project,
+    };
+
+    #[test]
+    fn entries_round_trip() {
+        let projects: Vec<Project> = ["/src/beta", "/src/alpha", "/src/gamma"]
+            .into_iter()
+            .map(|path| Project {
+                path: path.into(),
+                ..Project::default()
+            })
+            .collect();
+        let history = History {
+            entries: vec![
+                (10, PathBuf::from("/src/gamma")),
+                (20, PathBuf::from("/src/beta")),
+            ],
+        };
+        let settings = Settings {
+            label: ProjectLabel::Name,
+            ..Settings::default()
+        };
+
+        let lines = entries(&projects, &history, &settings, Format::Dmenu);
+        assert_eq!(
+            lines,
+            ["beta\t/src/beta", "gamma\t/src/gamma", "alpha\t/src/alpha"]
+        );
+        assert_eq!(
+            chosen(&projects, "gamma\t/src/gamma\n").map(PathBuf::as_path),
+            Some(Path::new("/src/gamma"))
+        );
+        assert_eq!(chosen(&projects, "gamma"), None);
+
+        let paths = entries(&projects, &history, &Settings::default(), Format::Dmenu);
+        assert_eq!(paths[0], "/src/beta");
+        assert_eq!(
+            chosen(&projects, &paths[0]).map(PathBuf::as_path),
+            Some(Path::new("/src/beta"))
+        );
+        assert!(Format::parse("fzf").is_err());
+    }
+}
@@ -0,0 +1,196 @@
+//! Every action of the list by name, `Ctrl-p` searches them so they can be found without knowing
+//! their keys
+
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::sorting::{Filter, Sorting};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Does what pressing the key does
+    Key(KeyEvent),
+    Sort(Sorting),
+    Filter(Filter),
+    /// Starts a `:` command with this text, for commands that need an argument
+    Command(&'static str),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub name: String,
+    /// Key or command doing the same, shown next to the name
+    pub key: String,
+    pub action: Action,
+}
+
+impl Entry {
+    fn key(name: &str, key: &str, code: KeyCode) -> Self {
+        Self {
+            name: name.to_string(),
+            key: key.to_string(),
+            action: Action::Key(KeyEvent::from(code)),
+        }
+    }
+
+    fn command(name: &str, command: &'static str) -> Self {
+        Self {
+            name: name.to_string(),
+            key: format!(":{}", command.trim_end()),
+            action: Action::Command(command),
+        }
+    }
+}
+
+/// Every action, with `custom` sorts of the config after the built-in ones and the `frecency`
+/// one only when zoxide is enabled
+pub fn entries(custom: usize, frecency: bool) -> Vec<Entry> {
+    let mut entries = vec![
+        Entry::key("Open a shell in the project", "Enter", KeyCode::Enter),
+        Entry::key("Run a task", "t", KeyCode::Char('t')),
+        Entry::key("Show in the file manager", "e", KeyCode::Char('e')),
+        Entry::key("Show as JSON", "J", KeyCode::Char('J')),
+        Entry::key("Search the list", "/", KeyCode::Char('/')),
+        Entry::key("Jump to a labeled row", "s", KeyCode::Char('s')),
+        Entry::key("Go to the top", "g", KeyCode::Char('g')),
+        Entry::key("Go to the bottom", "G", KeyCode::Char('G')),
+        Entry::key("Go to the next group", "}", KeyCode::Char('}')),
+        Entry::key("Go to the previous group", "{", KeyCode::Char('{')),
+    ];
+
+    let mut sorting = Sorting::Name;
+    loop {
+        entries.push(Entry {
+            name: format!("Sort by {sorting}"),
+            key: "h/l".to_string(),
+            action: Action::Sort(sorting),
+        });
+        sorting = sorting.next(custom, frecency);
+        if sorting == Sorting::Name {
+            break;
+        }
+    }
+    entries.push(Entry::key("Invert the sorting", "i", KeyCode::Char('i')));
+
+    entries.push(Entry::key("Pick a filter", "f", KeyCode::Char('f')));
+    entries.extend(Filter::all().into_iter().map(|filter| Entry {
+        name: format!("Filter {filter}"),
+        key: "y/o".to_string(),
+        action: Action::Filter(filter),
+    }));
+    entries.extend([
+        Entry::key("Show the next organization", "O", KeyCode::Char('O')),
+        Entry::key("Show the previous organization", "Y", KeyCode::Char('Y')),
+        Entry::key("Undo the view change", "U", KeyCode::Char('U')),
+        Entry::key("Redo the view change", "R", KeyCode::Char('R')),
+        Entry::key("Toggle the project info", "F1", KeyCode::F(1)),
+        Entry::key("Toggle the languages", "F2", KeyCode::F(2)),
+        Entry::key("Toggle git", "F3", KeyCode::F(3)),
+        Entry::key("Toggle the crates", "F4", KeyCode::F(4)),
+        Entry::key("Toggle the metadata", "F5", KeyCode::F(5)),
+        Entry::key("Toggle the errors", "F6", KeyCode::F(6)),
+        Entry::key("Toggle what was ignored", "F7", KeyCode::F(7)),
+        Entry::key("Toggle the most opened", "F8", KeyCode::F(8)),
+        Entry::key("Toggle the recently modified files", "F9", KeyCode::F(9)),
+        // Shift-F1 reaches ymir as F13 without modifiers on some terminals
+        Entry::key("Refresh the project", "S-F1", KeyCode::F(13)),
+        Entry::key("Refresh what was ignored", "S-F7", KeyCode::F(19)),
+        Entry::key("Refresh the most opened", "S-F8", KeyCode::F(20)),
+        Entry::key(
+            "Refresh the recently modified files",
+            "S-F9",
+            KeyCode::F(21),
+        ),
+        Entry::key("Count deferred lines of code", "c", KeyCode::Char('c')),
+        Entry {
+            name: "Rescan".to_string(),
+            key: "Ctrl-r".to_string(),
+            action: Action::Key(KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL)),
+        },
+        Entry::command("Change the directory", "cd "),
+        Entry::command("Add a directory", "add "),
+        Entry::command("Export bookmarks", "export "),
+        Entry::command("Export a workspace", "workspace "),
+        Entry::key("Export an archive", "A", KeyCode::Char('A')),
+        Entry::command("Search commits", "commits "),
+        Entry::key("Search files", "#", KeyCode::Char('#')),
+        Entry::key("Delete the duplicate", "D", KeyCode::Char('D')),
+        Entry::key(
+            "Turn the duplicate into a worktree",
+            "W",
+            KeyCode::Char('W'),
+        ),
+        Entry::key("Archive and delete", "X", KeyCode::Char('X')),
+        Entry::key("Show the audit log", "L", KeyCode::Char('L')),
+        Entry::key("Quit", "q", KeyCode::Char('q')),
+    ]);
+    entries
+}
+
+/// Entries whose name has the characters of `query` in order, tightest matches first
+pub fn search<'a>(entries: &'a [Entry], query: &str) -> Vec<&'a Entry> {
+    let mut matches: Vec<(usize, &Entry)> = entries
+        .iter()
+        .filter_map(|entry| Some((score(&entry.name, query)?, entry)))
+        .collect();
+    // Stable, equal scores keep the order of `entries`
+    matches.sort_by_key(|(score, _)| *score);
+    matches.into_iter().map(|(_, entry)| entry).collect()
+}
+
+/// How far apart the characters of `query` are in `name`, ignoring case, `None` when they aren't
+/// all there
+fn score(name: &str, query: &str) -> Option<usize> {
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+    let mut position = 0;
+    let mut score = 0;
+    let mut first = true;
+    for c in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let skipped = name[position..].iter().position(|v| *v == c)?;
+        // Where the match starts counts less than gaps inside it
+        score += if first { skipped.min(1) } else { skipped * 2 };
+        first = false;
+        position += skipped + 1;
+    }
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{entries, search, Action};
+    use crate::sorting::{Filter, Sorting};
+
+    #[test]
+    fn finds_actions_by_abbreviation() {
+        let entries = entries(1, false);
+        let names = |query| -> Vec<String> {
+            search(&entries, query)
+                .into_iter()
+                .map(|v| v.name.clone())
+                .take(2)
+                .collect()
+        };
+
+        assert_eq!(names("sort size"), ["Sort by Size"]);
+        assert_eq!(
+            names("sort co"),
+            ["Sort by Commits", "Sort by Commits per Month"]
+        );
+        assert_eq!(names("HASREM"), ["Filter Has Remote"]);
+        assert_eq!(names("json"), ["Show as JSON"]);
+        assert!(names("zzz").is_empty());
+        assert_eq!(search(&entries, "").len(), entries.len());
+
+        assert!(entries
+            .iter()
+            .any(|v| v.action == Action::Sort(Sorting::Custom(0))));
+        assert!(!entries
+            .iter()
+            .any(|v| v.action == Action::Sort(Sorting::Frecency)));
+        assert!(super::entries(0, true)
+            .iter()
+            .any(|v| v.action == Action::Sort(Sorting::Frecency)));
+        assert!(entries
+            .iter()
+            .any(|v| v.action == Action::Filter(Filter::Duplicates)));
+    }
+}
@@ -1,27 +1,71 @@
 //! Functions for finding projects
 
-use std::{collections::HashMap, ffi::OsStr, fmt::Display, path::PathBuf};
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use chrono::{Local, TimeZone};
-use log::{error, info};
-use tokei::{Config, Languages};
+use log::{debug, error, info, warn};
+use serde::{Serialize, Serializer};
+use tokei::{Config, LanguageType, Languages};
 use walkdir::{DirEntry, WalkDir};
 
 use crate::{
-    config::Settings,
-    utils::{format_bytes, get_git_info, get_size, GitInfo},
+    ci::CiProvider,
+    config::{Cache, ProjectLabel, Settings},
+    devenv::DevEnv,
+    generated::Generated,
+    global_ignore::GlobalIgnore,
+    ignore_stats::IgnoreStats,
+    include_paths::IncludePaths,
+    lfs::Lfs,
+    manifest::{self, ProjectMetadata, RustCrate},
+    recent_files::{self, RecentFiles},
+    submodules::Submodules,
+    utils::{
+        format_bytes, get_git_info, get_size_skipping, GitInfo, Ownership, RemoteUrl, RepoState,
+    },
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
 pub struct Project {
     pub path: PathBuf,
     pub size: u64,
+    /// Unix seconds of when the stats were computed, 0 if unknown
+    pub scanned_at: u64,
+    /// Commit HEAD pointed to when the stats were computed, `None` without one
+    pub head: Option<String>,
+    /// Larger than `max_project_size`, lines of code weren't counted
+    pub deferred: bool,
     pub git_info: GitInfo,
+    #[serde(serialize_with = "serialize_languages")]
     pub languages: HashMap<u8, ProjectLanguage>,
     pub languages_total: ProjectLanguage,
+    /// CI providers with configuration in the project
+    pub ci: Vec<CiProvider>,
+    /// Generated and vendored files, not part of `languages` or `languages_total`
+    pub generated: ProjectLanguage,
+    /// Packages in `Cargo.toml`, workspace members included
+    pub crates: Vec<RustCrate>,
+    pub metadata: ProjectMetadata,
+    /// Containers and dev shells the project is set up for
+    pub dev_envs: Vec<DevEnv>,
+    /// What couldn't be read while scanning, the values it would have filled are defaults
+    pub scan_errors: Vec<String>,
+    /// Owner and permissions of the project directory, empty if they couldn't be read
+    pub ownership: Ownership,
+    /// Some directories couldn't be read for lack of permissions, size and lines leave them out
+    pub partial: bool,
+    pub lfs: Lfs,
+    pub submodules: Submodules,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
 pub struct ProjectLanguage {
     pub files: u32,
     pub lines: u32,
@@ -30,25 +74,45 @@ pub struct ProjectLanguage {
     pub blanks: u32,
 }
 
+/// Languages keyed by their tokei name instead of the index they're stored by
+fn serialize_languages<S: Serializer>(
+    languages: &HashMap<u8, ProjectLanguage>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(languages.iter().filter_map(|(id, stats)| {
+        let language = LanguageType::list().get(usize::from(*id))?;
+        Some((language.name(), stats))
+    }))
+}
+
+impl ProjectLanguage {
+    fn add(&mut self, other: &Self) {
+        self.files = self.files.saturating_add(other.files);
+        self.lines = self.lines.saturating_add(other.lines);
+        self.code = self.code.saturating_add(other.code);
+        self.comments = self.comments.saturating_add(other.comments);
+        self.blanks = self.blanks.saturating_add(other.blanks);
+    }
+
+    /// Takes the counts of `other` out of these
+    fn remove(&mut self, other: &Self) {
+        self.files = self.files.saturating_sub(other.files);
+        self.lines = self.lines.saturating_sub(other.lines);
+        self.code = self.code.saturating_sub(other.code);
+        self.comments = self.comments.saturating_sub(other.comments);
+        self.blanks = self.blanks.saturating_sub(other.blanks);
+    }
+}
+
 impl Display for Project {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let init_date = Local
-            .timestamp_opt(i64::from(self.git_info.init_date), 0)
-            .single()
-            .map_or("Invalid date".to_string(), |dt| {
-                dt.format("%Y-%m-%d %H:%M:%S").to_string()
-            });
-
-        let last_commit_date = Local
-            .timestamp_opt(i64::from(self.git_info.last_commit_date), 0)
-            .single()
-            .map_or("Invalid date".to_string(), |dt| {
-                dt.format("%Y-%m-%d %H:%M:%S").to_string()
-            });
+        let init_date = self.format_date(self.git_info.init_date);
+        let last_commit_date = self.format_date(self.git_info.last_commit_date);
+        let partial = if self.partial { PARTIAL } else { "" };
 
         write!(
             f,
-            "Project Name: {}\nPath: {}\nSize: {}\nCreated At: {}\nModified At: {}\n\n# Git:\nLast Commit: {}\nCommits: {}\nRemote: {}",
+            "Project Name: {}\nPath: {}\nSize: {}{partial}\nCreated At: {}\nModified At: {}\nCI: {}\nEnvironment: {}",
             self.path
                 .file_name()
                 .map_or("Failed to get file name", |v| v
@@ -58,94 +122,649 @@ impl Display for Project {
             format_bytes(self.size),
             init_date,
             last_commit_date,
-            self.git_info.last_commit_msg.as_ref().map_or("Unknown", |v| v),
-            self.git_info.commit_count,
-            self.git_info.remote_url.as_ref().map_or("Unknown", |v| v),
-        )
+            list_or_none(&self.ci),
+            list_or_none(&self.dev_envs),
+        )?;
+        if !self.ownership.owner.is_empty() {
+            write!(f, "\nOwner: {}", self.ownership)?;
+        }
+        if self.submodules.count > 0 {
+            write!(f, "\nSubmodules: {}", self.submodules)?;
+        }
+        if self.lfs.is_used() {
+            write!(
+                f,
+                "\nLFS: {} of objects, tracking {}",
+                format_bytes(self.lfs.size),
+                if self.lfs.patterns.is_empty() {
+                    "nothing".to_string()
+                } else {
+                    self.lfs.patterns.join(", ")
+                }
+            )?;
+        }
+        Ok(())
+    }
+}
+
+const SECONDS_PER_MONTH: i64 = 30 * 24 * 60 * 60;
+
+/// Appended to stats that leave out what couldn't be read
+pub const PARTIAL: &str = " (partial: permission denied)";
+
+fn list_or_none<T: Display>(values: &[T]) -> String {
+    if values.is_empty() {
+        "None".to_string()
+    } else {
+        values
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ")
     }
 }
 
 impl Project {
+    /// Text the project is listed as
+    pub fn label(&self, label: ProjectLabel) -> String {
+        let name = || {
+            self.path.file_name().map_or_else(
+                || self.path.display().to_string(),
+                |v| v.to_string_lossy().to_string(),
+            )
+        };
+
+        match label {
+            ProjectLabel::Path => self.path.display().to_string(),
+            ProjectLabel::Name => name(),
+            ProjectLabel::Remote => self
+                .git_info
+                .remote_url
+                .as_deref()
+                .and_then(RemoteUrl::parse)
+                .map_or_else(name, |v| format!("{}/{}", v.owner, v.repo)),
+        }
+    }
+
+    /// Seconds since the first commit at `now`, `None` without commits
+    pub fn age(&self, now: i64) -> Option<i64> {
+        self.git_info
+            .state
+            .has_commits()
+            .then(|| (now - i64::from(self.git_info.init_date)).max(0))
+    }
+
+    /// Seconds from the first commit to the last one, `None` without commits
+    pub fn activity_span(&self) -> Option<i64> {
+        let info = &self.git_info;
+        info.state
+            .has_commits()
+            .then(|| (i64::from(info.last_commit_date) - i64::from(info.init_date)).max(0))
+    }
+
+    /// Average commits per 30 days over the activity span, a shorter span counts as a month
+    #[allow(clippy::cast_precision_loss)]
+    pub fn commits_per_month(&self) -> Option<f64> {
+        let months = self.activity_span()?.max(SECONDS_PER_MONTH) as f64 / SECONDS_PER_MONTH as f64;
+        Some(f64::from(self.git_info.commit_count) / months)
+    }
+
+    /// Language with the most lines of code
+    pub fn top_language(&self) -> Option<LanguageType> {
+        self.languages
+            .iter()
+            .max_by_key(|(id, v)| (v.code, std::cmp::Reverse(**id)))
+            .and_then(|(id, _)| LanguageType::list().get(usize::from(*id)).copied())
+    }
+
+    /// Formats a commit timestamp, repos without commits have no meaningful dates
+    fn format_date(&self, timestamp: u32) -> String {
+        if !self.git_info.state.has_commits() {
+            return "No commits".to_string();
+        }
+
+        Local
+            .timestamp_opt(i64::from(timestamp), 0)
+            .single()
+            .map_or("Invalid date".to_string(), |dt| {
+                dt.format("%Y-%m-%d %H:%M:%S").to_string()
+            })
+    }
+
     pub fn new(
         path: PathBuf,
         size: u64,
         languages: HashMap<u8, ProjectLanguage>,
         languages_total: ProjectLanguage,
+        generated: ProjectLanguage,
+        mut scan_errors: Vec<String>,
+        settings: &Settings,
     ) -> Self {
-        let git_info = get_git_info(&path).unwrap_or_default();
+        let git_info = get_git_info(&path, settings).unwrap_or_else(|err| {
+            warn!("Failed to read git info of {}: {err}", path.display());
+            scan_errors.push(format!("Failed to read git data: {err}"));
+            GitInfo {
+                state: RepoState::Error,
+                ..Default::default()
+            }
+        });
+        let ci = CiProvider::detect(&path);
+        let crates = manifest::rust_crates(&path);
+        let metadata = manifest::metadata(&path);
+        let dev_envs = DevEnv::detect(&path);
+        let lfs = Lfs::detect(&path);
+        let submodules = Submodules::detect(&path);
+        let ownership = Ownership::of(&path).unwrap_or_else(|err| {
+            warn!("Failed to read owner of {}: {err}", path.display());
+            Ownership::default()
+        });
 
         Self {
+            head: head_commit(&path),
             path,
             size,
+            scanned_at: now(),
+            deferred: false,
             git_info,
             languages,
             languages_total,
+            ci,
+            generated,
+            crates,
+            metadata,
+            dev_envs,
+            scan_errors,
+            ownership,
+            partial: false,
+            lfs,
+            submodules,
+        }
+    }
+
+    /// Whether the size and lines still hold, the directory wasn't modified and HEAD didn't move
+    /// since they were computed
+    ///
+    /// Only the top directory's mtime is looked at, edits to files deeper in go unnoticed until
+    /// they're committed.
+    pub fn unchanged_on_disk(&self) -> bool {
+        if self.scanned_at == 0 || self.partial {
+            return false;
+        }
+        let Some(modified) = fs::metadata(&self.path)
+            .and_then(|v| v.modified())
+            .ok()
+            .and_then(|v| v.duration_since(UNIX_EPOCH).ok())
+        else {
+            return false;
+        };
+        // Modified in the second the stats were computed may have been after them
+        modified.as_secs() < self.scanned_at && head_commit(&self.path) == self.head
+    }
+
+    /// Directory the scan couldn't read, listed so it doesn't vanish while it may be a project
+    pub fn unreadable(path: PathBuf, error: String) -> Self {
+        Self {
+            path,
+            scanned_at: now(),
+            git_info: GitInfo {
+                state: RepoState::Error,
+                ..Default::default()
+            },
+            scan_errors: vec![error],
+            partial: true,
+            ..Self::default()
         }
     }
+
+    /// Project over `max_project_size`, lines of code wait for a scan without the limit
+    pub fn deferred(
+        path: PathBuf,
+        size: u64,
+        scan_errors: Vec<String>,
+        settings: &Settings,
+    ) -> Self {
+        Self {
+            deferred: true,
+            ..Self::new(
+                path,
+                size,
+                HashMap::new(),
+                ProjectLanguage::default(),
+                ProjectLanguage::default(),
+                scan_errors,
+                settings,
+            )
+        }
+    }
+}
+
+/// Id of the commit HEAD points to
+fn head_commit(path: &Path) -> Option<String> {
+    let repo = git2::Repository::open(path).ok()?;
+    let id = repo.head().ok()?.target()?;
+    Some(id.to_string())
+}
+
+/// Unix seconds, stored as when a project was scanned
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |v| v.as_secs())
 }
 
-/// Checks if the entry is a build directory
-fn is_build(entry: &DirEntry, ignore_dirs: &[String]) -> bool {
-    entry
-        .file_name()
-        .to_str()
-        .is_some_and(|s| ignore_dirs.contains(&s.to_string()))
+/// What [`IgnoreStats`] lists directories skipped by the global gitignore under
+const GLOBAL_GITIGNORE: &str = "global gitignore";
+
+/// Entry of `ignore_dirs` the directory is skipped by
+fn ignore_rule<'a>(entry: &DirEntry, ignore_dirs: &'a [String]) -> Option<&'a str> {
+    let name = entry.file_name().to_str()?;
+    ignore_dirs.iter().find(|v| *v == name).map(String::as_str)
+}
+
+/// Scans `root` and writes the result to the cache, the projects are kept even if writing fails
+///
+/// `progress` is called with every project found and the number found so far.
+pub fn scan_and_cache(
+    root: &PathBuf,
+    settings: &Settings,
+    progress: impl FnMut(usize, &Project),
+) -> (Vec<Project>, Option<String>) {
+    let (projects, stats, recent) = find_with_stats(root, settings, progress);
+    save_ignore_stats(&stats);
+    if settings.recent_files {
+        save_recent_files(&recent);
+    }
+    let error = Cache::create_cache(&projects, settings.compress_cache)
+        .err()
+        .map(|err| format!("Failed to write cache: {err}"));
+
+    (projects, error)
+}
+
+/// Scans `roots` and merges the result into the cache, cached projects from other roots are kept
+/// so switching back to them doesn't need another scan
+///
+/// Returns the cached projects under any of `visible`.
+pub fn scan_into_cache(
+    roots: &[PathBuf],
+    visible: &[PathBuf],
+    settings: &Settings,
+    mut progress: impl FnMut(usize, &Project),
+) -> (Vec<Project>, Option<String>) {
+    let (cached, mut projects): (Vec<Project>, Vec<Project>) = Cache::try_read_cache()
+        .unwrap_or_default()
+        .into_iter()
+        .partition(|p| in_roots(&p.path, roots));
+    let cached: HashMap<PathBuf, Project> = if settings.incremental {
+        cached.into_iter().map(|v| (v.path.clone(), v)).collect()
+    } else {
+        HashMap::new()
+    };
+
+    let mut found = 0;
+    let mut stats = IgnoreStats::default();
+    let mut recent = RecentFiles::load();
+    for root in roots {
+        let (scanned, root_stats, root_recent) =
+            find_reusing(root, settings, &cached, |count, project| {
+                progress(found + count, project);
+            });
+        found += scanned.len();
+        projects.extend(scanned);
+        stats.merge(root_stats);
+        recent.merge(std::slice::from_ref(root), root_recent);
+    }
+    save_ignore_stats(&stats);
+    if settings.recent_files {
+        save_recent_files(&recent);
+    }
+
+    let error = Cache::create_cache(&projects, settings.compress_cache)
+        .err()
+        .map(|err| format!("Failed to write cache: {err}"));
+
+    projects.retain(|p| in_roots(&p.path, visible));
+    (projects, error)
+}
+
+fn save_ignore_stats(stats: &IgnoreStats) {
+    if let Err(err) = stats.save() {
+        warn!("Failed to save ignore stats: {err:#}");
+    }
+}
+
+fn save_recent_files(recent: &RecentFiles) {
+    if let Err(err) = recent.save() {
+        warn!("Failed to save recent files: {err:#}");
+    }
+}
+
+/// Whether `path` is inside any of `roots`
+pub fn in_roots(path: &Path, roots: &[PathBuf]) -> bool {
+    roots.iter().any(|root| path.starts_with(root))
+}
+
+/// Project `query` fits best, matched against paths ignoring case
+///
+/// A directory named like the query beats one whose name contains it, which beats a match
+/// anywhere else in the path. Shorter paths win ties.
+pub fn best_match<'a>(projects: &'a [Project], query: &str) -> Option<&'a Project> {
+    let query = query.to_lowercase();
+    projects
+        .iter()
+        .filter_map(|project| {
+            let path = project.path.to_string_lossy().to_lowercase();
+            let name = project
+                .path
+                .file_name()
+                .map(|v| v.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            let rank = if name == query {
+                0
+            } else if name.contains(&query) {
+                1
+            } else if path.contains(&query) {
+                2
+            } else {
+                return None;
+            };
+            Some((rank, path.len(), project))
+        })
+        .min_by_key(|(rank, len, _)| (*rank, *len))
+        .map(|(_, _, project)| project)
 }
 
 /// Returns a list of directories that contain a `.git` directory
-pub fn find(path: &PathBuf, ignore_dirs: &[String]) -> Vec<Project> {
+pub fn find(path: &PathBuf, settings: &Settings) -> Vec<Project> {
+    find_with_progress(path, settings, |_, _| {})
+}
+
+/// Projects in any of `roots`, calling `progress` with every project found and the number found
+/// so far
+pub fn find_all(
+    roots: &[PathBuf],
+    settings: &Settings,
+    mut progress: impl FnMut(usize, &Project),
+) -> Vec<Project> {
+    let mut projects = Vec::new();
+    for root in roots {
+        let found = projects.len();
+        projects.extend(find_with_progress(root, settings, |count, project| {
+            progress(found + count, project);
+        }));
+    }
+    projects
+}
+
+/// Same as [`find`], calling `progress` with every project found
+pub fn find_with_progress(
+    path: &PathBuf,
+    settings: &Settings,
+    progress: impl FnMut(usize, &Project),
+) -> Vec<Project> {
+    find_with_stats(path, settings, progress).0
+}
+
+/// Same as [`find_with_progress`], also telling what `ignore_dirs` skipped and which files were
+/// modified last
+pub fn find_with_stats(
+    path: &PathBuf,
+    settings: &Settings,
+    progress: impl FnMut(usize, &Project),
+) -> (Vec<Project>, IgnoreStats, RecentFiles) {
+    find_reusing(path, settings, &HashMap::new(), progress)
+}
+
+/// Same as [`find_with_stats`], the stats also listing the projects skipped directories hide
+pub fn find_explained(path: &PathBuf, settings: &Settings) -> (Vec<Project>, IgnoreStats) {
+    let (projects, stats, _) = walk(
+        path,
+        settings,
+        &HashMap::new(),
+        IgnoreStats::explaining(),
+        |_, _| {},
+    );
+    (projects, stats)
+}
+
+/// Same as [`find_with_stats`], taking the size and lines from `cached` instead of walking the
+/// project again when it's [unchanged](Project::unchanged_on_disk), git info is always read anew
+pub fn find_reusing(
+    path: &PathBuf,
+    settings: &Settings,
+    cached: &HashMap<PathBuf, Project>,
+    progress: impl FnMut(usize, &Project),
+) -> (Vec<Project>, IgnoreStats, RecentFiles) {
+    walk(path, settings, cached, IgnoreStats::default(), progress)
+}
+
+fn walk(
+    path: &PathBuf,
+    settings: &Settings,
+    cached: &HashMap<PathBuf, Project>,
+    mut stats: IgnoreStats,
+    mut progress: impl FnMut(usize, &Project),
+) -> (Vec<Project>, IgnoreStats, RecentFiles) {
     let mut paths: Vec<Project> = Vec::new();
+    let mut recent = recent_files::Collector::default();
+    let mut unreadable = Vec::new();
+    let max_size = settings.max_project_size();
+    let include = IncludePaths::new(path, &settings.include_paths);
+    let global = GlobalIgnore::new(settings);
 
-    for entry in WalkDir::new(path)
-        .into_iter()
-        .filter_entry(|e| !is_build(e, ignore_dirs))
-        .filter_map(Result::ok)
-    {
-        if entry.path().file_name() != Some(OsStr::new(".git")) {
+    let mut walk = WalkDir::new(path).into_iter().filter_entry(|e| {
+        if e.file_type().is_dir() && !include.allows(e.path()) {
+            stats.skip("include_paths", e.path());
+            return false;
+        }
+        let rule = ignore_rule(e, &settings.ignore_dirs);
+        if let Some(rule) = rule {
+            stats.skip(rule, e.path());
+        }
+        if rule.is_none() && e.depth() > 0 && e.file_type().is_dir() && global.skips(e.path()) {
+            stats.skip(GLOBAL_GITIGNORE, e.path());
+            return false;
+        }
+        rule.is_none()
+    });
+    while let Some(entry) = walk.next() {
+        // Projects can't be told apart from other directories before they're entered, these
+        // are given to the project they're in once the walk is done
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(err) => {
+                warn!("Skipped while scanning: {err}");
+                let error = err
+                    .io_error()
+                    .map_or_else(|| err.to_string(), ToString::to_string);
+                unreadable.push((err.path().map(Path::to_path_buf), error));
+                continue;
+            }
+        };
+
+        if entry.file_name() != OsStr::new(".git") {
+            if !settings.recent_files || !entry.file_type().is_file() {
+                continue;
+            }
+            if let Some(modified) = entry
+                .metadata()
+                .ok()
+                .and_then(|v| v.modified().ok())
+                .and_then(|v| v.duration_since(UNIX_EPOCH).ok())
+            {
+                recent.add(entry.path(), modified.as_secs());
+            }
             continue;
         }
+        // Worktree and submodule checkouts have a .git file pointing at their git directory
+        if entry.file_type().is_dir() {
+            // Nothing in it is a project or a recent file, its size is measured with the project
+            walk.skip_current_dir();
+        }
 
         let Some(parent) = entry.path().parent() else {
             error!("Failed to get parent of directory");
             continue;
         };
 
-        let mut languages = Languages::new();
-        languages.get_statistics(&[parent], &Settings::ignore_dirs(), &Config::default());
-
-        let total = languages.total();
-        let total: ProjectLanguage = ProjectLanguage {
-            files: u32::try_from(total.reports.len()).unwrap_or_default(),
-            lines: u32::try_from(total.lines()).unwrap_or_default(),
-            code: u32::try_from(total.code).unwrap_or_default(),
-            comments: u32::try_from(total.comments).unwrap_or_default(),
-            blanks: u32::try_from(total.blanks).unwrap_or_default(),
-        };
-
-        let languages: HashMap<u8, ProjectLanguage> = languages
-            .into_iter()
-            .map(|(key, value)| {
-                (
-                    key as u8,
-                    ProjectLanguage {
-                        files: u32::try_from(value.reports.len()).unwrap_or_default(),
-                        lines: u32::try_from(value.lines()).unwrap_or_default(),
-                        code: u32::try_from(value.code).unwrap_or_default(),
-                        comments: u32::try_from(value.comments).unwrap_or_default(),
-                        blanks: u32::try_from(value.blanks).unwrap_or_default(),
-                    },
+        if let Some(cached) = cached.get(parent).filter(|v| v.unchanged_on_disk()) {
+            debug!(
+                "Reusing size and lines of {}, it didn't change",
+                parent.display()
+            );
+            // Branches, remotes and hooks change without moving HEAD, only the walks are skipped
+            let project = Project {
+                scanned_at: cached.scanned_at,
+                deferred: cached.deferred,
+                ..Project::new(
+                    parent.to_path_buf(),
+                    cached.size,
+                    cached.languages.clone(),
+                    cached.languages_total.clone(),
+                    cached.generated.clone(),
+                    Vec::new(),
+                    settings,
                 )
-            })
-            .collect();
+            };
+            progress(paths.len() + 1, &project);
+            paths.push(project);
+            continue;
+        }
 
-        let size = get_size(parent).unwrap_or(0);
-        paths.push(Project::new(parent.to_path_buf(), size, languages, total));
-        let paths_len = paths.len();
+        let mut scan_errors = Vec::new();
+        let (size, partial) = match get_size_skipping(parent, &|v| global.skips(v)) {
+            Ok(size) => size,
+            Err(err) if err.is_permission_denied() => (0, true),
+            Err(err) => {
+                warn!("Failed to measure {}: {err}", parent.display());
+                scan_errors.push(format!("Failed to measure size: {err}"));
+                (0, false)
+            }
+        };
+        if partial {
+            info!("Partial stats of {}, permission denied", parent.display());
+        }
+        let project = if max_size.is_some_and(|max| size > max) {
+            info!(
+                "Deferring stats of {}, it has {size} bytes",
+                parent.display()
+            );
+            Project::deferred(parent.to_path_buf(), size, scan_errors, settings)
+        } else {
+            let (languages, total, generated) = language_stats(parent, settings);
+            Project::new(
+                parent.to_path_buf(),
+                size,
+                languages,
+                total,
+                generated,
+                scan_errors,
+                settings,
+            )
+        };
+        let paths_len = paths.len() + 1;
         let parent_display = parent.display();
         info!("{paths_len} - {parent_display}");
+        let project = Project { partial, ..project };
+        progress(paths_len, &project);
+        paths.push(project);
     }
 
-    paths
+    stats.unreadable = unreadable.len();
+    record_unreadable(&mut paths, unreadable);
+    let recent = recent.finish(&paths);
+    (paths, stats, recent)
+}
+
+/// Adds what the walk couldn't read to the scan errors of the innermost project it's in, lists
+/// directories outside of any project on their own
+fn record_unreadable(projects: &mut Vec<Project>, unreadable: Vec<(Option<PathBuf>, String)>) {
+    for (path, error) in unreadable {
+        let Some(path) = path else {
+            continue;
+        };
+        let message = format!("Failed to read {}: {error}", path.display());
+        match projects
+            .iter_mut()
+            .filter(|v| path.starts_with(&v.path))
+            .max_by_key(|v| v.path.components().count())
+        {
+            Some(project) => {
+                project.partial = true;
+                project.scan_errors.push(message);
+            }
+            None => projects.push(Project::unreadable(path, message)),
+        }
+    }
+}
+
+/// Lines of code of the project at `path` by language, in total and in generated files
+fn language_stats(
+    parent: &Path,
+    settings: &Settings,
+) -> (
+    HashMap<u8, ProjectLanguage>,
+    ProjectLanguage,
+    ProjectLanguage,
+) {
+    let mut languages = Languages::new();
+    languages.get_statistics(&[parent], &Settings::ignore_dirs(), &Config::default());
+
+    let total = languages.total();
+    let mut total: ProjectLanguage = ProjectLanguage {
+        files: u32::try_from(total.reports.len()).unwrap_or_default(),
+        lines: u32::try_from(total.lines()).unwrap_or_default(),
+        code: u32::try_from(total.code).unwrap_or_default(),
+        comments: u32::try_from(total.comments).unwrap_or_default(),
+        blanks: u32::try_from(total.blanks).unwrap_or_default(),
+    };
+
+    // Generated files are taken out of their language and counted on their own
+    let rules = Generated::load(parent);
+    let mut generated = ProjectLanguage::default();
+
+    let languages: HashMap<u8, ProjectLanguage> = languages
+        .into_iter()
+        .filter_map(|(key, value)| {
+            let mut language = ProjectLanguage {
+                files: u32::try_from(value.reports.len()).unwrap_or_default(),
+                lines: u32::try_from(value.lines()).unwrap_or_default(),
+                code: u32::try_from(value.code).unwrap_or_default(),
+                comments: u32::try_from(value.comments).unwrap_or_default(),
+                blanks: u32::try_from(value.blanks).unwrap_or_default(),
+            };
+
+            for report in &value.reports {
+                if rules.is_generated(parent, &report.name) {
+                    let stats = ProjectLanguage {
+                        files: 1,
+                        lines: u32::try_from(report.stats.lines()).unwrap_or_default(),
+                        code: u32::try_from(report.stats.code).unwrap_or_default(),
+                        comments: u32::try_from(report.stats.comments).unwrap_or_default(),
+                        blanks: u32::try_from(report.stats.blanks).unwrap_or_default(),
+                    };
+                    language.remove(&stats);
+                    generated.add(&stats);
+                }
+            }
+
+            // Nothing left when every file was generated
+            (value.reports.is_empty() || language.files > 0).then_some((key as u8, language))
+        })
+        .collect();
+    total.remove(&generated);
+
+    // Ignored languages are still listed, they only don't count
+    for (&key, language) in &languages {
+        if LanguageType::list()
+            .get(usize::from(key))
+            .is_some_and(|&v| settings.ignores_language(parent, v))
+        {
+            total.remove(language);
+        }
+    }
+
+    (languages, total, generated)
 }
 
 // pub fn find_from_cache(projects: Vec<PathBuf>) -> Vec<Project> {
@@ -187,3 +806,88 @@ pub fn find(path: &PathBuf, ignore_dirs: &[String]) -> Vec<Project> {
 //
 //     paths
 // }
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use tokei::LanguageType;
+
+    use super::{in_roots, record_unreadable};
+    use crate::{config::ProjectLabel, test_utils::project, utils::RepoState};
+
+    #[test]
+    fn unreadable_paths_become_scan_errors() {
+        let mut projects = vec![project("/src/ymir"), project("/src/ymir/vendor/dep")];
+        record_unreadable(
+            &mut projects,
+            vec![
+                (
+                    Some(PathBuf::from("/src/ymir/vendor/dep/private")),
+                    "Permission denied".to_string(),
+                ),
+                (
+                    Some(PathBuf::from("/src/locked")),
+                    "Permission denied".to_string(),
+                ),
+                (None, "Loop".to_string()),
+            ],
+        );
+
+        assert!(projects[0].scan_errors.is_empty());
+        assert!(projects[1].partial);
+        assert_eq!(
+            projects[1].scan_errors,
+            ["Failed to read /src/ymir/vendor/dep/private: Permission denied"]
+        );
+        let [_, _, locked] = &projects[..] else {
+            panic!("{projects:?}");
+        };
+        assert_eq!(locked.path, PathBuf::from("/src/locked"));
+        assert_eq!(locked.git_info.state, RepoState::Error);
+        assert_eq!(
+            locked.scan_errors,
+            ["Failed to read /src/locked: Permission denied"]
+        );
+    }
+
+    #[test]
+    fn labels_by_name_or_remote() {
+        let local = project("/src/ymir");
+        let cloned = project("/src/fork").with_remote("git@github.com:ponurakk/ymir.git");
+
+        assert_eq!(local.label(ProjectLabel::Path), "/src/ymir");
+        assert_eq!(local.label(ProjectLabel::Name), "ymir");
+        assert_eq!(cloned.label(ProjectLabel::Name), "fork");
+        assert_eq!(cloned.label(ProjectLabel::Remote), "ponurakk/ymir");
+        // Without a remote the directory name stands in
+        assert_eq!(local.label(ProjectLabel::Remote), "ymir");
+        assert_eq!(project("/").label(ProjectLabel::Name), "/");
+    }
+
+    #[test]
+    fn roots_contain_whole_components() {
+        let roots = [PathBuf::from("/src"), PathBuf::from("/work/tool")];
+        assert!(in_roots(Path::new("/src"), &roots));
+        assert!(in_roots(Path::new("/src/ymir"), &roots));
+        assert!(in_roots(Path::new("/work/tool/vendor"), &roots));
+        assert!(!in_roots(Path::new("/srcs/ymir"), &roots));
+        assert!(!in_roots(Path::new("/work"), &roots));
+        assert!(!in_roots(Path::new("/src"), &[]));
+    }
+
+    #[test]
+    fn top_language_has_the_most_code() {
+        assert_eq!(project("/src/empty").top_language(), None);
+
+        let mixed = project("/src/ymir")
+            .with_code(LanguageType::Toml, 40)
+            .with_code(LanguageType::Rust, 900);
+        assert_eq!(mixed.top_language(), Some(LanguageType::Rust));
+        // Ties go to the language listed first by tokei
+        let tied = project("/src/web")
+            .with_code(LanguageType::TypeScript, 50)
+            .with_code(LanguageType::JavaScript, 50);
+        assert_eq!(tied.top_language(), Some(LanguageType::JavaScript));
+    }
+}
@@ -0,0 +1,162 @@
+//! Files modified last across every project, for the F9 pane
+//!
+//! Scans already walk every file of a project, the newest ones they see are kept next to the
+//! cache so the pane doesn't walk anything. Files in `.git` and outside of projects are left out.
+
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config,
+    error::{Result, YmirError},
+    projects::Project,
+};
+
+/// Files kept in the index, older ones are forgotten
+const MAX_FILES: usize = 500;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RecentFile {
+    pub path: PathBuf,
+    /// Project the file belongs to, the innermost one for nested projects
+    pub project: PathBuf,
+    /// Unix seconds
+    pub modified: u64,
+}
+
+/// Newest files first
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RecentFiles {
+    pub files: Vec<RecentFile>,
+}
+
+impl RecentFiles {
+    /// Location of the index
+    pub fn path() -> Option<PathBuf> {
+        config::app_dir().map(|dir| dir.join("recent_files.json"))
+    }
+
+    /// Files of the last scans, empty when nothing was scanned yet
+    pub fn load() -> Self {
+        Self::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|v| serde_json::from_str(&v).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path().ok_or(YmirError::NoConfigDir)?;
+        fs::write(&path, serde_json::to_string(self)?).map_err(|err| YmirError::io(&path, err))
+    }
+
+    /// Replaces the files under `roots` with the ones a scan of them found
+    pub fn merge(&mut self, roots: &[PathBuf], scanned: Self) {
+        self.files
+            .retain(|v| !roots.iter().any(|root| v.path.starts_with(root)));
+        self.files.extend(scanned.files);
+        self.files.sort_by_key(|v| Reverse(v.modified));
+        self.files.truncate(MAX_FILES);
+    }
+}
+
+/// Newest files seen by a walk, their projects are only known once it's done
+#[derive(Debug, Default)]
+pub struct Collector {
+    newest: BinaryHeap<Reverse<(u64, PathBuf)>>,
+}
+
+impl Collector {
+    pub fn add(&mut self, path: &Path, modified: u64) {
+        if self.newest.len() == MAX_FILES
+            && self
+                .newest
+                .peek()
+                .is_some_and(|Reverse((v, _))| *v >= modified)
+        {
+            return;
+        }
+        if path.components().any(|v| v.as_os_str() == ".git") {
+            return;
+        }
+
+        self.newest.push(Reverse((modified, path.to_path_buf())));
+        if self.newest.len() > MAX_FILES {
+            self.newest.pop();
+        }
+    }
+
+    /// Files in `projects`, newest first
+    pub fn finish(self, projects: &[Project]) -> RecentFiles {
+        let mut files: Vec<RecentFile> = self
+            .newest
+            .into_iter()
+            .filter_map(|Reverse((modified, path))| {
+                let project = projects
+                    .iter()
+                    .filter(|v| path.starts_with(&v.path))
+                    .max_by_key(|v| v.path.components().count())?;
+                Some(RecentFile {
+                    project: project.path.clone(),
+                    path,
+                    modified,
+                })
+            })
+            .collect();
+        files.sort_by_key(|v| Reverse(v.modified));
+        RecentFiles { files }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::{Collector, RecentFiles};
+    use crate::projects::Project;
+
+    #[test]
+    fn files_go_to_their_innermost_project() {
+        let project = |path: &str| Project {
+            path: path.into(),
+            ..Project::default()
+        };
+        let mut collector = Collector::default();
+        collector.add(Path::new("/src/ymir/src/main.rs"), 30);
+        collector.add(Path::new("/src/ymir/.git/index"), 40);
+        collector.add(Path::new("/src/ymir/vendor/dep/lib.rs"), 20);
+        collector.add(Path::new("/src/notes.txt"), 50);
+
+        let recent = collector.finish(&[project("/src/ymir"), project("/src/ymir/vendor/dep")]);
+        let files: Vec<_> = recent
+            .files
+            .iter()
+            .map(|v| (v.path.to_str().unwrap(), v.project.to_str().unwrap()))
+            .collect();
+        assert_eq!(
+            files,
+            [
+                ("/src/ymir/src/main.rs", "/src/ymir"),
+                ("/src/ymir/vendor/dep/lib.rs", "/src/ymir/vendor/dep"),
+            ]
+        );
+
+        // Rescanning a root replaces only what's under it
+        let mut index = RecentFiles::default();
+        let mut other = Collector::default();
+        other.add(Path::new("/work/api/main.go"), 10);
+        index.merge(
+            &[PathBuf::from("/work")],
+            other.finish(&[project("/work/api")]),
+        );
+        index.merge(&[PathBuf::from("/src")], recent);
+        let paths: Vec<_> = index.files.iter().map(|v| v.path.clone()).collect();
+        assert_eq!(paths.len(), 3);
+        assert_eq!(paths[2], Path::new("/work/api/main.go"));
+    }
+}
@@ -0,0 +1,345 @@
+//! Conditional styling of list rows, set with `row_styles`
+//!
+//! Every rule whose `when` holds is applied in order, so later rules win. A condition compares
+//! one value of the project against a number, `size` takes `K`, `M`, `G` and `T` suffixes and
+//! `age`, the time since the last commit, takes `m`, `h`, `d`, `w` and `y`.
+//!
+//! ```toml
+//! [[row_styles]]
+//! when = "size > 1G"
+//! bold = true
+//!
+//! [[row_styles]]
+//! gradient = { value = "age", from = "#22c55e", to = "#ef4444", max = "365d" }
+//! ```
+
+use std::str::FromStr;
+
+use anyhow::{bail, Context};
+use ratatui::style::{Color, Modifier, Style};
+
+use crate::{
+    config::{RowGradientConfig, RowStyleConfig},
+    projects::Project,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Value {
+    Size,
+    Loc,
+    Files,
+    Commits,
+    /// Seconds since the last commit, projects without commits have none
+    Age,
+}
+
+impl Value {
+    fn parse(name: &str) -> anyhow::Result<Self> {
+        Ok(match name {
+            "size" => Self::Size,
+            "loc" => Self::Loc,
+            "files" => Self::Files,
+            "commits" => Self::Commits,
+            "age" => Self::Age,
+            _ => bail!("Unknown value {name}, expected size, loc, files, commits or age"),
+        })
+    }
+
+    fn of(self, project: &Project, now: i64) -> Option<i64> {
+        Some(match self {
+            Self::Size => i64::try_from(project.size).unwrap_or(i64::MAX),
+            Self::Loc => i64::from(project.languages_total.code),
+            Self::Files => i64::from(project.languages_total.files),
+            Self::Commits => i64::from(project.git_info.commit_count),
+            Self::Age => {
+                if !project.git_info.state.has_commits() {
+                    return None;
+                }
+                now - i64::from(project.git_info.last_commit_date)
+            }
+        })
+    }
+
+    /// Reads a number with the suffixes this value takes
+    fn amount(self, text: &str) -> anyhow::Result<i64> {
+        let text = text.trim();
+        let split = text
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(text.len());
+        let (number, suffix) = text.split_at(split);
+        let number: i64 = number
+            .parse()
+            .with_context(|| format!("Invalid number {text}"))?;
+
+        let multiplier: i64 = match (self, suffix.trim()) {
+            (_, "") => 1,
+            (Self::Size, "K") => 1 << 10,
+            (Self::Size, "M") => 1 << 20,
+            (Self::Size, "G") => 1 << 30,
+            (Self::Size, "T") => 1 << 40,
+            (Self::Age, "s") => 1,
+            (Self::Age, "m") => 60,
+            (Self::Age, "h") => 60 * 60,
+            (Self::Age, "d") => 24 * 60 * 60,
+            (Self::Age, "w") => 7 * 24 * 60 * 60,
+            (Self::Age, "y") => 365 * 24 * 60 * 60,
+            (_, suffix) => bail!("Unknown suffix {suffix} in {text}"),
+        };
+        Ok(number.saturating_mul(multiplier))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Equal,
+    NotEqual,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Condition {
+    value: Value,
+    op: Op,
+    amount: i64,
+}
+
+impl Condition {
+    fn parse(text: &str) -> anyhow::Result<Self> {
+        // Longer operators first so `<=` isn't read as `<`
+        let ops = [
+            ("<=", Op::LessEqual),
+            (">=", Op::GreaterEqual),
+            ("==", Op::Equal),
+            ("!=", Op::NotEqual),
+            ("<", Op::Less),
+            (">", Op::Greater),
+        ];
+        let Some((value, op, amount)) = ops.iter().find_map(|(token, op)| {
+            text.split_once(token)
+                .map(|(value, amount)| (value, *op, amount))
+        }) else {
+            bail!("Expected a comparison like `size > 1G` in {text}");
+        };
+
+        let value = Value::parse(value.trim())?;
+        Ok(Self {
+            value,
+            op,
+            amount: value.amount(amount)?,
+        })
+    }
+
+    fn holds(&self, project: &Project, now: i64) -> bool {
+        let Some(value) = self.value.of(project, now) else {
+            return false;
+        };
+        match self.op {
+            Op::Less => value < self.amount,
+            Op::LessEqual => value <= self.amount,
+            Op::Greater => value > self.amount,
+            Op::GreaterEqual => value >= self.amount,
+            Op::Equal => value == self.amount,
+            Op::NotEqual => value != self.amount,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Gradient {
+    value: Value,
+    from: (u8, u8, u8),
+    to: (u8, u8, u8),
+    min: i64,
+    max: i64,
+}
+
+impl Gradient {
+    fn parse(config: &RowGradientConfig) -> anyhow::Result<Self> {
+        let value = Value::parse(&config.value)?;
+        let rgb = |text: &str| match Color::from_str(text) {
+            Ok(Color::Rgb(r, g, b)) => Ok((r, g, b)),
+            _ => bail!("Gradients need colors like #22c55e, got {text}"),
+        };
+        let min = config.min.as_deref().map_or(Ok(0), |v| value.amount(v))?;
+        let max = value.amount(&config.max)?;
+        if max <= min {
+            bail!("Gradient max has to be above min");
+        }
+
+        Ok(Self {
+            value,
+            from: rgb(&config.from)?,
+            to: rgb(&config.to)?,
+            min,
+            max,
+        })
+    }
+
+    fn color(&self, project: &Project, now: i64) -> Option<Color> {
+        let value = self.value.of(project, now)?.clamp(self.min, self.max);
+        #[allow(clippy::cast_precision_loss)]
+        let t = (value - self.min) as f64 / (self.max - self.min) as f64;
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let mix = |a: u8, b: u8| (f64::from(a) + (f64::from(b) - f64::from(a)) * t).round() as u8;
+
+        Some(Color::Rgb(
+            mix(self.from.0, self.to.0),
+            mix(self.from.1, self.to.1),
+            mix(self.from.2, self.to.2),
+        ))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Rule {
+    when: Option<Condition>,
+    style: Style,
+    gradient: Option<Gradient>,
+}
+
+/// Parsed `row_styles`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RowStyles {
+    rules: Vec<Rule>,
+}
+
+impl RowStyles {
+    pub fn parse(configs: &[RowStyleConfig]) -> anyhow::Result<Self> {
+        let rules = configs
+            .iter()
+            .enumerate()
+            .map(|(i, config)| parse_rule(config).with_context(|| format!("In row_styles[{i}]")))
+            .collect::<anyhow::Result<_>>()?;
+
+        Ok(Self { rules })
+    }
+
+    /// Style of the row of `project`, `now` is a unix timestamp for ages
+    pub fn style(&self, project: &Project, now: i64) -> Style {
+        let mut style = Style::default();
+        for rule in &self.rules {
+            if rule.when.as_ref().is_some_and(|v| !v.holds(project, now)) {
+                continue;
+            }
+            style = style.patch(rule.style);
+            if let Some(color) = rule.gradient.as_ref().and_then(|v| v.color(project, now)) {
+                style = style.fg(color);
+            }
+        }
+        style
+    }
+}
+
+fn parse_rule(config: &RowStyleConfig) -> anyhow::Result<Rule> {
+    let color = |text: &Option<String>| {
+        text.as_deref()
+            .map(|v| Color::from_str(v).map_err(|_| anyhow::anyhow!("Unknown color {v}")))
+            .transpose()
+    };
+
+    let mut style = Style::default();
+    if let Some(fg) = color(&config.fg)? {
+        style = style.fg(fg);
+    }
+    if let Some(bg) = color(&config.bg)? {
+        style = style.bg(bg);
+    }
+    for (set, modifier) in [
+        (config.bold, Modifier::BOLD),
+        (config.italic, Modifier::ITALIC),
+        (config.dim, Modifier::DIM),
+    ] {
+        if set {
+            style = style.add_modifier(modifier);
+        }
+    }
+
+    Ok(Rule {
+        when: config.when.as_deref().map(Condition::parse).transpose()?,
+        style,
+        gradient: config.gradient.as_ref().map(Gradient::parse).transpose()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::style::{Color, Modifier, Style};
+
+    use super::RowStyles;
+    use crate::{
+        config::{RowGradientConfig, RowStyleConfig, Settings},
+        test_utils::project,
+    };
+
+    const DAY: i64 = 24 * 60 * 60;
+
+    #[test]
+    fn default_marks_projects_without_commits() {
+        let styles = RowStyles::parse(&Settings::default().row_styles).unwrap();
+        assert_eq!(
+            styles.style(&project(""), 0),
+            Style::default().fg(Color::Rgb(0xb9, 0x1c, 0x1c))
+        );
+        assert_eq!(
+            styles.style(&project("").with_commits(1, 0, 0), 0),
+            Style::default()
+        );
+    }
+
+    #[test]
+    fn conditions_and_gradients() {
+        let styles = RowStyles::parse(&[
+            RowStyleConfig {
+                gradient: Some(RowGradientConfig {
+                    value: "age".to_string(),
+                    from: "#000000".to_string(),
+                    to: "#c8c8c8".to_string(),
+                    min: None,
+                    max: "10d".to_string(),
+                }),
+                ..RowStyleConfig::default()
+            },
+            RowStyleConfig {
+                when: Some("size >= 1G".to_string()),
+                bold: true,
+                ..RowStyleConfig::default()
+            },
+        ])
+        .unwrap();
+
+        let now = 20 * DAY;
+        let recent = project("").with_size(1 << 30).with_commits(
+            1,
+            0,
+            u32::try_from(now - 5 * DAY).unwrap(),
+        );
+        assert_eq!(
+            styles.style(&recent, now),
+            Style::default()
+                .fg(Color::Rgb(100, 100, 100))
+                .add_modifier(Modifier::BOLD)
+        );
+        assert_eq!(
+            styles.style(&project("").with_size(1024).with_commits(1, 0, 0), now),
+            Style::default().fg(Color::Rgb(200, 200, 200))
+        );
+    }
+
+    #[test]
+    fn rejects_bad_rules() {
+        let rule = |when: &str| {
+            RowStyles::parse(&[RowStyleConfig {
+                when: Some(when.to_string()),
+                ..RowStyleConfig::default()
+            }])
+        };
+        assert!(rule("size > 1G").is_ok());
+        assert!(rule("age <= 2w").is_ok());
+        assert!(rule("size > 1d").is_err());
+        assert!(rule("stars > 5").is_err());
+        assert!(rule("size").is_err());
+    }
+}
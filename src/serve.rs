@@ -0,0 +1,309 @@
+//! `ymir serve`, a small HTTP server exposing the cache to other tools
+//!
+//! Only `GET /metrics` is served, in the Prometheus text format. The cache is read again on every
+//! request so a refresh from another instance shows up without restarting the server.
+//!
+//! Every connection gets a thread of its own and a few seconds to send its request, so a slow or
+//! idle client can't hold up the scrapers.
+
+use std::{
+    fmt::Write as _,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{Shutdown, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use anyhow::Context;
+use log::{error, info};
+
+use crate::{config::Cache, projects::Project};
+
+/// Where the server listens when no address is given
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:9898";
+
+/// How long a client may take to send its request or read the response
+const TIMEOUT: Duration = Duration::from_secs(10);
+/// Longest request line or header line accepted
+const MAX_LINE: usize = 8 * 1024;
+/// Most bytes of request line and headers together
+const MAX_HEAD: usize = 32 * 1024;
+/// Connections handled at once, more are turned away until one finishes
+const MAX_CONNECTIONS: usize = 32;
+
+/// Serves requests on `address` until the process is stopped
+pub fn run(address: &str) -> anyhow::Result<()> {
+    let listener =
+        TcpListener::bind(address).with_context(|| format!("Failed to listen on {address}"))?;
+    info!("Serving metrics on http://{address}/metrics");
+    eprintln!("Serving metrics on http://{address}/metrics");
+    serve(&listener);
+    Ok(())
+}
+
+/// Accepts connections on `listener`, handling each on a thread of its own
+fn serve(listener: &TcpListener) {
+    let open = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming() {
+        let mut stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                error!("Failed to accept connection: {err}");
+                continue;
+            }
+        };
+        if let Err(err) = stream
+            .set_read_timeout(Some(TIMEOUT))
+            .and_then(|()| stream.set_write_timeout(Some(TIMEOUT)))
+        {
+            error!("Failed to set timeouts: {err}");
+            continue;
+        }
+        if open.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+            open.fetch_sub(1, Ordering::SeqCst);
+            let _ = respond(
+                &mut stream,
+                "503 Service Unavailable",
+                "Too many requests\n",
+            );
+            continue;
+        }
+
+        let open = Arc::clone(&open);
+        thread::spawn(move || {
+            if let Err(err) = handle_connection(stream) {
+                error!("Failed to handle request: {err:#}");
+            }
+            open.fetch_sub(1, Ordering::SeqCst);
+        });
+    }
+}
+
+fn handle_connection(mut stream: TcpStream) -> anyhow::Result<()> {
+    let request_line = match read_head(&stream) {
+        Ok(line) => line,
+        Err(err) if err.kind() == io::ErrorKind::InvalidData => {
+            respond(
+                &mut stream,
+                "431 Request Header Fields Too Large",
+                "Request too large\n",
+            )?;
+            // Closing with the rest unread would reset the connection before the response is read
+            stream.shutdown(Shutdown::Write)?;
+            io::copy(&mut (&stream).take(MAX_HEAD as u64), &mut io::sink())?;
+            return Ok(());
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut parts = request_line.split_whitespace();
+    let (status, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => match Cache::try_read_cache() {
+            Ok(projects) => (
+                "200 OK",
+                metrics(&projects, chrono::Local::now().timestamp()),
+            ),
+            Err(err) => (
+                "500 Internal Server Error",
+                format!("Failed to read cache: {err:#}\n"),
+            ),
+        },
+        (Some("GET"), _) => ("404 Not Found", "Not found\n".to_string()),
+        _ => ("405 Method Not Allowed", "Method not allowed\n".to_string()),
+    };
+
+    respond(&mut stream, status, &body)?;
+    Ok(())
+}
+
+/// Reads the request line and drops the headers after it, closing with them unread can reset the
+/// connection
+///
+/// Lines over [`MAX_LINE`] or a head over [`MAX_HEAD`] fail with `InvalidData`.
+fn read_head(stream: impl Read) -> io::Result<String> {
+    let mut reader = BufReader::new(stream.take(MAX_HEAD as u64));
+    let mut request_line = String::new();
+    read_line(&mut reader, &mut request_line)?;
+    let mut header = String::new();
+    while read_line(&mut reader, &mut header)? > 2 {
+        header.clear();
+    }
+    Ok(request_line)
+}
+
+/// `read_line` failing on lines over [`MAX_LINE`] and ones the head limit cut short
+fn read_line(reader: &mut impl BufRead, line: &mut String) -> io::Result<usize> {
+    let read = reader.take(MAX_LINE as u64).read_line(line)?;
+    if read > 0 && !line.ends_with('\n') {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "Line too long"));
+    }
+    Ok(read)
+}
+
+fn respond(stream: &mut TcpStream, status: &str, body: &str) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: text/plain; version=0.0.4; charset=utf-8\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
+
+/// Value of a per-project gauge given the current time, `None` leaves the project out
+type ProjectGauge = fn(&Project, i64) -> Option<i64>;
+
+/// Per-project and aggregate gauges, `now` is a unix timestamp for commit ages
+pub fn metrics(projects: &[Project], now: i64) -> String {
+    let total = |value: fn(&Project) -> u64| projects.iter().map(value).sum::<u64>();
+
+    let mut text = String::new();
+    gauge(&mut text, "ymir_projects", "Number of cached projects");
+    let _ = writeln!(text, "ymir_projects {}", projects.len());
+    gauge(&mut text, "ymir_size_bytes", "Size of every project");
+    let _ = writeln!(text, "ymir_size_bytes {}", total(|p| p.size));
+    gauge(
+        &mut text,
+        "ymir_lines_of_code",
+        "Lines of code of every project",
+    );
+    let _ = writeln!(
+        text,
+        "ymir_lines_of_code {}",
+        total(|p| u64::from(p.languages_total.code))
+    );
+    gauge(&mut text, "ymir_commits", "Commits of every project");
+    let _ = writeln!(
+        text,
+        "ymir_commits {}",
+        total(|p| u64::from(p.git_info.commit_count))
+    );
+
+    let per_project: [(&str, &str, ProjectGauge); 4] = [
+        (
+            "ymir_project_size_bytes",
+            "Size of the project directory",
+            |p, _| i64::try_from(p.size).ok(),
+        ),
+        (
+            "ymir_project_lines_of_code",
+            "Lines of code, generated files and ignored languages left out",
+            |p, _| Some(i64::from(p.languages_total.code)),
+        ),
+        (
+            "ymir_project_commits",
+            "Commits reachable from HEAD",
+            |p, _| Some(i64::from(p.git_info.commit_count)),
+        ),
+        (
+            "ymir_project_last_commit_age_seconds",
+            "Seconds since the last commit",
+            |p, now| {
+                p.git_info
+                    .state
+                    .has_commits()
+                    .then(|| now - i64::from(p.git_info.last_commit_date))
+            },
+        ),
+    ];
+    for (name, help, value) in per_project {
+        gauge(&mut text, name, help);
+        for project in projects {
+            if let Some(value) = value(project, now) {
+                let path = escape_label(&project.path.to_string_lossy());
+                let _ = writeln!(text, "{name}{{path=\"{path}\"}} {value}");
+            }
+        }
+    }
+
+    text
+}
+
+fn gauge(text: &mut String, name: &str, help: &str) {
+    let _ = writeln!(text, "# HELP {name} {help}\n# TYPE {name} gauge");
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::{TcpListener, TcpStream},
+        path::PathBuf,
+        thread,
+    };
+
+    use super::{metrics, serve, MAX_LINE};
+    use crate::{projects::Project, utils::RepoState};
+
+    fn request(address: &str, text: &str) -> String {
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.write_all(text.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn idle_clients_dont_block_others() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        thread::spawn(move || serve(&listener));
+
+        let _idle = TcpStream::connect(&address).unwrap();
+        let response = request(&address, "GET /other HTTP/1.1\r\nHost: ymir\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 404 Not Found"), "{response}");
+
+        let long = format!(
+            "GET /other HTTP/1.1\r\nX-Long: {}\r\n\r\n",
+            "a".repeat(MAX_LINE)
+        );
+        let response = request(&address, &long);
+        assert!(response.starts_with("HTTP/1.1 431"), "{response}");
+    }
+
+    #[test]
+    fn writes_project_gauges() {
+        let mut project = Project {
+            path: PathBuf::from("/src/a \"b\""),
+            size: 2048,
+            ..Project::default()
+        };
+        project.languages_total.code = 120;
+        project.git_info.commit_count = 7;
+        project.git_info.last_commit_date = 1000;
+        let mut empty = Project {
+            path: PathBuf::from("/src/empty"),
+            ..Project::default()
+        };
+        empty.git_info.state = RepoState::Empty;
+
+        let text = metrics(&[project, empty], 4600);
+        let samples: Vec<&str> = text.lines().filter(|v| !v.starts_with('#')).collect();
+        assert_eq!(
+            samples,
+            [
+                "ymir_projects 2",
+                "ymir_size_bytes 2048",
+                "ymir_lines_of_code 120",
+                "ymir_commits 7",
+                "ymir_project_size_bytes{path=\"/src/a \\\"b\\\"\"} 2048",
+                "ymir_project_size_bytes{path=\"/src/empty\"} 0",
+                "ymir_project_lines_of_code{path=\"/src/a \\\"b\\\"\"} 120",
+                "ymir_project_lines_of_code{path=\"/src/empty\"} 0",
+                "ymir_project_commits{path=\"/src/a \\\"b\\\"\"} 7",
+                "ymir_project_commits{path=\"/src/empty\"} 0",
+                "ymir_project_last_commit_age_seconds{path=\"/src/a \\\"b\\\"\"} 3600",
+            ]
+        );
+    }
+}
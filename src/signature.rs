@@ -0,0 +1,372 @@
+//! Key lookup for signed commits
+//!
+//! Only the armored `gpgsig` header is looked at, signatures aren't verified. Verifying needs the
+//! signer's public key and a keyring, what's recorded here is enough to spot unsigned commits and
+//! commits signed with an unexpected key.
+
+use std::fmt::Display;
+
+use base64::{
+    alphabet,
+    engine::{
+        general_purpose::STANDARD_NO_PAD, DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig,
+    },
+    Engine,
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::error::YmirError;
+
+const PGP_HEADER: &str = "-----BEGIN PGP SIGNATURE-----";
+const SSH_HEADER: &str = "-----BEGIN SSH SIGNATURE-----";
+const X509_HEADER: &str = "-----BEGIN SIGNED MESSAGE-----";
+
+/// Armor bodies, some tools leave out the padding
+const ARMOR: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum SignatureKind {
+    Gpg = 1,
+    Ssh = 2,
+    X509 = 3,
+}
+
+impl TryFrom<u8> for SignatureKind {
+    type Error = YmirError;
+
+    fn try_from(value: u8) -> Result<Self, YmirError> {
+        match value {
+            1 => Ok(Self::Gpg),
+            2 => Ok(Self::Ssh),
+            3 => Ok(Self::X509),
+            _ => Err(YmirError::CorruptCache(format!(
+                "Invalid signature kind {value}"
+            ))),
+        }
+    }
+}
+
+impl Display for SignatureKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Gpg => write!(f, "GPG"),
+            Self::Ssh => write!(f, "SSH"),
+            Self::X509 => write!(f, "X.509"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CommitSignature {
+    pub kind: SignatureKind,
+    /// Issuer fingerprint or key id for gpg, `SHA256:` fingerprint for ssh
+    pub key: Option<String>,
+}
+
+impl CommitSignature {
+    /// Reads the kind and key from an armored signature, `None` if it isn't in a known format
+    pub fn parse(armored: &str) -> Option<Self> {
+        let armored = armored.trim_start();
+        let (kind, key) = if armored.starts_with(PGP_HEADER) {
+            (SignatureKind::Gpg, pgp_issuer(&dearmor(armored)?))
+        } else if armored.starts_with(SSH_HEADER) {
+            (SignatureKind::Ssh, ssh_fingerprint(&dearmor(armored)?))
+        } else if armored.starts_with(X509_HEADER) {
+            (SignatureKind::X509, None)
+        } else {
+            return None;
+        };
+
+        Some(Self { kind, key })
+    }
+}
+
+impl Display for CommitSignature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.key {
+            Some(key) => write!(f, "{} {key}", self.kind),
+            None => write!(f, "{} (unknown key)", self.kind),
+        }
+    }
+}
+
+/// Decodes the body of an ascii armored block, skipping armor headers and the checksum
+fn dearmor(armored: &str) -> Option<Vec<u8>> {
+    let body: String = armored
+        .lines()
+        .skip(1)
+        .take_while(|line| !line.starts_with("-----END"))
+        .map(str::trim)
+        .filter(|line| !line.contains(':') && !line.starts_with('='))
+        .collect();
+
+    ARMOR.decode(body).ok()
+}
+
+/// Issuer of the first signature packet, taken from the hashed or unhashed subpackets
+fn pgp_issuer(data: &[u8]) -> Option<String> {
+    let (tag, body) = pgp_packet(data)?;
+    if tag != 2 {
+        return None;
+    }
+
+    match *body.first()? {
+        // Version 3 keeps the key id at a fixed offset
+        3 => Some(hex(body.get(7..15)?)),
+        version @ (4 | 6) => {
+            let len_size = if version == 4 { 2 } else { 4 };
+            let mut offset = 4;
+            let mut key_id = None;
+
+            for _ in 0..2 {
+                let len = read_be(body.get(offset..offset + len_size)?);
+                offset += len_size;
+                let subpackets = body.get(offset..offset + len)?;
+                offset += len;
+
+                for (kind, data) in pgp_subpackets(subpackets) {
+                    match kind {
+                        // Issuer fingerprint, prefixed with the key version
+                        33 if data.len() > 1 => return Some(hex(&data[1..])),
+                        16 if data.len() == 8 => key_id = Some(hex(data)),
+                        _ => {}
+                    }
+                }
+            }
+
+            key_id
+        }
+        _ => None,
+    }
+}
+
+/// Tag and body of the first packet, both old and new packet formats
+fn pgp_packet(data: &[u8]) -> Option<(u8, &[u8])> {
+    let header = *data.first()?;
+    if header & 0x80 == 0 {
+        return None;
+    }
+
+    let (tag, start, len) = if header & 0x40 == 0 {
+        let size = match header & 0b11 {
+            0 => 1,
+            1 => 2,
+            2 => 4,
+            _ => return Some(((header >> 2) & 0x0f, &data[1..])),
+        };
+        ((header >> 2) & 0x0f, 1 + size, read_be(data.get(1..=size)?))
+    } else {
+        let (len, size) = pgp_length(data.get(1..)?)?;
+        (header & 0x3f, 1 + size, len)
+    };
+
+    Some((tag, data.get(start..start + len)?))
+}
+
+fn pgp_subpackets(mut data: &[u8]) -> impl Iterator<Item = (u8, &[u8])> {
+    std::iter::from_fn(move || {
+        let (len, size) = pgp_length(data)?;
+        let packet = data.get(size..size + len)?;
+        data = &data[size + len..];
+
+        // The high bit marks critical subpackets
+        let (&kind, body) = packet.split_first()?;
+        Some((kind & 0x7f, body))
+    })
+}
+
+/// Length and number of bytes used to encode it
+fn pgp_length(data: &[u8]) -> Option<(usize, usize)> {
+    match *data.first()? {
+        len @ 0..192 => Some((usize::from(len), 1)),
+        first @ 192..255 => {
+            let second = usize::from(*data.get(1)?);
+            Some((((usize::from(first) - 192) << 8) + second + 192, 2))
+        }
+        255 => Some((read_be(data.get(1..5)?), 5)),
+    }
+}
+
+/// Fingerprint of the public key embedded in an `SSHSIG` blob, the same one `ssh-keygen -l` shows
+fn ssh_fingerprint(data: &[u8]) -> Option<String> {
+    let rest = data.strip_prefix(b"SSHSIG")?;
+    let len = read_be(rest.get(4..8)?);
+    let key = rest.get(8..8 + len)?;
+
+    Some(format!(
+        "SHA256:{}",
+        STANDARD_NO_PAD.encode(Sha256::digest(key))
+    ))
+}
+
+fn read_be(bytes: &[u8]) -> usize {
+    bytes
+        .iter()
+        .fold(0, |acc, &byte| (acc << 8) | usize::from(byte))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|v| format!("{v:02X}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::Path, process::Command};
+
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use proptest::{collection, prelude::*};
+
+    use super::{CommitSignature, SignatureKind};
+    use crate::{
+        config::Settings,
+        test_utils::{git, temp_dir},
+        utils::get_git_info,
+    };
+
+    fn run(dir: &Path, program: &str, args: &[&str]) -> String {
+        let output = Command::new(program)
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{program} {args:?} failed");
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    /// Armored v4 signature packet with `subpackets` in its hashed area
+    fn pgp_armored(subpackets: &[Vec<u8>]) -> String {
+        let hashed: Vec<u8> = subpackets.concat();
+
+        let mut body = vec![4, 0, 1, 8];
+        body.extend_from_slice(&u16::try_from(hashed.len()).unwrap().to_be_bytes());
+        body.extend_from_slice(&hashed);
+        body.extend_from_slice(&[0, 0]);
+
+        // New format header, two byte length past 191
+        let mut packet = vec![0xC2];
+        match body.len() {
+            len @ 0..192 => packet.push(u8::try_from(len).unwrap()),
+            len => {
+                let len = len - 192;
+                packet.extend_from_slice(&[u8::try_from((len >> 8) + 192).unwrap(), len as u8]);
+            }
+        }
+        packet.extend_from_slice(&body);
+
+        format!(
+            "-----BEGIN PGP SIGNATURE-----\n\n{}\n=abcd\n-----END PGP SIGNATURE-----\n",
+            STANDARD.encode(&packet)
+        )
+    }
+
+    /// Subpacket of `kind` holding `data`, its length fitting in one byte
+    fn subpacket(kind: u8, data: &[u8]) -> Vec<u8> {
+        let mut subpacket = vec![u8::try_from(data.len() + 1).unwrap(), kind];
+        subpacket.extend_from_slice(data);
+        subpacket
+    }
+
+    #[test]
+    fn reads_pgp_issuer_fingerprint() {
+        let mut fingerprint = vec![4];
+        fingerprint.extend_from_slice(&[0xAB; 20]);
+        let armored = pgp_armored(&[subpacket(33, &fingerprint)]);
+
+        let signature = CommitSignature::parse(&armored).unwrap();
+        assert_eq!(signature.kind, SignatureKind::Gpg);
+        assert_eq!(signature.key, Some("AB".repeat(20)));
+
+        let unpadded = armored.replace("=\n=abcd", "\n=abcd");
+        assert_eq!(CommitSignature::parse(&unpadded), Some(signature));
+    }
+
+    proptest! {
+        #[test]
+        fn finds_the_issuer_among_other_subpackets(
+            before in collection::vec((1..100_u8, collection::vec(any::<u8>(), 0..16)), 0..8),
+            fingerprint in collection::vec(any::<u8>(), 20..=32),
+            key_id in proptest::option::of(collection::vec(any::<u8>(), 8)),
+        ) {
+            let mut subpackets: Vec<Vec<u8>> = before
+                .iter()
+                .filter(|(kind, _)| !matches!(kind, 16 | 33))
+                .map(|(kind, data)| subpacket(*kind, data))
+                .collect();
+            if let Some(key_id) = &key_id {
+                subpackets.push(subpacket(16, key_id));
+            }
+            let mut issuer = vec![4];
+            issuer.extend_from_slice(&fingerprint);
+            subpackets.push(subpacket(33, &issuer));
+
+            let expected: String = fingerprint.iter().map(|v| format!("{v:02X}")).collect();
+            let signature = CommitSignature::parse(&pgp_armored(&subpackets)).unwrap();
+            prop_assert_eq!(signature.key, Some(expected));
+        }
+
+        #[test]
+        fn arbitrary_packets_do_not_panic(bytes in collection::vec(any::<u8>(), 0..512)) {
+            for header in ["-----BEGIN PGP SIGNATURE-----", "-----BEGIN SSH SIGNATURE-----"] {
+                let armored = format!("{header}\n\n{}\n", STANDARD.encode(&bytes));
+                let _ = CommitSignature::parse(&armored);
+            }
+        }
+
+        #[test]
+        fn arbitrary_text_does_not_panic(text in "\\PC{0,256}") {
+            let _ = CommitSignature::parse(&format!("-----BEGIN PGP SIGNATURE-----\n{text}"));
+        }
+    }
+
+    #[test]
+    fn reads_ssh_fingerprint_from_commit() {
+        let dir = temp_dir("signature");
+
+        let key = dir.join("key");
+        run(
+            &dir,
+            "ssh-keygen",
+            &["-q", "-t", "ed25519", "-N", "", "-f", key.to_str().unwrap()],
+        );
+        git(&dir, &["init", "-q"]);
+        git(
+            &dir,
+            &[
+                "-c",
+                "gpg.format=ssh",
+                "-c",
+                &format!("user.signingkey={}", key.display()),
+                "commit",
+                "-q",
+                "-S",
+                "--allow-empty",
+                "-m",
+                "signed",
+            ],
+        );
+
+        // `256 SHA256:... comment (ED25519)`
+        let listing = run(
+            &dir,
+            "ssh-keygen",
+            &["-l", "-f", &format!("{}.pub", key.display())],
+        );
+        let expected = listing.split_whitespace().nth(1).unwrap().to_string();
+
+        let signature = get_git_info(&dir, &Settings::default())
+            .unwrap()
+            .signature
+            .unwrap();
+        assert_eq!(signature.kind, SignatureKind::Ssh);
+        assert_eq!(signature.key, Some(expected));
+    }
+
+    #[test]
+    fn unknown_formats_are_ignored() {
+        assert_eq!(CommitSignature::parse("not a signature"), None);
+    }
+}
@@ -0,0 +1,289 @@
+//! Sortings defined in the config as arithmetic over project values, set with `custom_sorts`
+//!
+//! Expressions take numbers, `+ - * /` and parentheses over `size` in bytes, `loc`, `lines`,
+//! `files`, `commits` and the days `age_days` since the first commit, `span_days` from the first
+//! to the last commit and `idle_days` since the last one. Projects an expression has no value
+//! for, like ones without commits for the day counts or a division by zero, sort first.
+//!
+//! ```toml
+//! [[custom_sorts]]
+//! name = "Bytes per Line"
+//! expr = "size / loc"
+//! ```
+
+use anyhow::{bail, Context};
+
+use crate::{config::CustomSortConfig, projects::Project};
+
+const SECONDS_PER_DAY: f64 = 24.0 * 60.0 * 60.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Size,
+    Loc,
+    Lines,
+    Files,
+    Commits,
+    AgeDays,
+    SpanDays,
+    IdleDays,
+}
+
+impl Field {
+    fn parse(name: &str) -> anyhow::Result<Self> {
+        Ok(match name {
+            "size" => Self::Size,
+            "loc" => Self::Loc,
+            "lines" => Self::Lines,
+            "files" => Self::Files,
+            "commits" => Self::Commits,
+            "age_days" => Self::AgeDays,
+            "span_days" => Self::SpanDays,
+            "idle_days" => Self::IdleDays,
+            _ => bail!(
+                "Unknown value {name}, expected size, loc, lines, files, commits, age_days, \
+                 span_days or idle_days"
+            ),
+        })
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn of(self, project: &Project, now: i64) -> Option<f64> {
+        let days = |seconds: i64| seconds as f64 / SECONDS_PER_DAY;
+        let total = &project.languages_total;
+        Some(match self {
+            Self::Size => project.size as f64,
+            Self::Loc => f64::from(total.code),
+            Self::Lines => f64::from(total.lines),
+            Self::Files => f64::from(total.files),
+            Self::Commits => f64::from(project.git_info.commit_count),
+            Self::AgeDays => days(project.age(now)?),
+            Self::SpanDays => days(project.activity_span()?),
+            Self::IdleDays => {
+                if !project.git_info.state.has_commits() {
+                    return None;
+                }
+                days(now - i64::from(project.git_info.last_commit_date))
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Number(f64),
+    Field(Field),
+    Neg(Box<Expr>),
+    Binary(char, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    fn eval(&self, project: &Project, now: i64) -> Option<f64> {
+        let value = match self {
+            Self::Number(v) => *v,
+            Self::Field(field) => field.of(project, now)?,
+            Self::Neg(expr) => -expr.eval(project, now)?,
+            Self::Binary(op, left, right) => {
+                let (left, right) = (left.eval(project, now)?, right.eval(project, now)?);
+                match op {
+                    '+' => left + right,
+                    '-' => left - right,
+                    '*' => left * right,
+                    _ => left / right,
+                }
+            }
+        };
+        value.is_finite().then_some(value)
+    }
+}
+
+/// Recursive descent over the characters of an expression, lowest precedence first
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&mut self) -> Option<char> {
+        self.text = self.text.trim_start();
+        self.text.chars().next()
+    }
+
+    fn bump(&mut self, c: char) {
+        self.text = &self.text[c.len_utf8()..];
+        self.pos += c.len_utf8();
+    }
+
+    fn sum(&mut self) -> anyhow::Result<Expr> {
+        let mut expr = self.product()?;
+        while let Some(op @ ('+' | '-')) = self.peek() {
+            self.bump(op);
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.product()?));
+        }
+        Ok(expr)
+    }
+
+    fn product(&mut self) -> anyhow::Result<Expr> {
+        let mut expr = self.unary()?;
+        while let Some(op @ ('*' | '/')) = self.peek() {
+            self.bump(op);
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> anyhow::Result<Expr> {
+        match self.peek() {
+            Some('-') => {
+                self.bump('-');
+                Ok(Expr::Neg(Box::new(self.unary()?)))
+            }
+            Some('(') => {
+                self.bump('(');
+                let expr = self.sum()?;
+                if self.peek() != Some(')') {
+                    bail!("Missing ) at {}", self.pos);
+                }
+                self.bump(')');
+                Ok(expr)
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                let len = self
+                    .text
+                    .find(|v: char| !(v.is_ascii_digit() || v == '.'))
+                    .unwrap_or(self.text.len());
+                let number = &self.text[..len];
+                let value = number
+                    .parse()
+                    .with_context(|| format!("Invalid number {number}"))?;
+                self.text = &self.text[len..];
+                self.pos += len;
+                Ok(Expr::Number(value))
+            }
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+                let len = self
+                    .text
+                    .find(|v: char| !(v.is_ascii_alphanumeric() || v == '_'))
+                    .unwrap_or(self.text.len());
+                let field = Field::parse(&self.text[..len])?;
+                self.text = &self.text[len..];
+                self.pos += len;
+                Ok(Expr::Field(field))
+            }
+            Some(c) => bail!("Unexpected {c} at {}", self.pos),
+            None => bail!("Unexpected end"),
+        }
+    }
+}
+
+/// Parsed `custom_sorts` entry
+#[derive(Debug, Clone, PartialEq)]
+pub struct SortExpr {
+    pub name: String,
+    expr: Expr,
+}
+
+impl SortExpr {
+    pub fn parse(name: &str, text: &str) -> anyhow::Result<Self> {
+        let mut parser = Parser { text, pos: 0 };
+        let expr = parser
+            .sum()
+            .and_then(|expr| match parser.peek() {
+                Some(c) => bail!("Unexpected {c} at {}", parser.pos),
+                None => Ok(expr),
+            })
+            .with_context(|| format!("Invalid sort {name}: {text}"))?;
+        Ok(Self {
+            name: name.to_string(),
+            expr,
+        })
+    }
+
+    /// Every entry of `custom_sorts`, failing on the first invalid one
+    pub fn parse_all(configs: &[CustomSortConfig]) -> anyhow::Result<Vec<Self>> {
+        configs
+            .iter()
+            .map(|v| Self::parse(&v.name, &v.expr))
+            .collect()
+    }
+
+    /// Value of `project` at `now`, `None` when the expression has none
+    pub fn eval(&self, project: &Project, now: i64) -> Option<f64> {
+        self.expr.eval(project, now)
+    }
+
+    /// Sorts `items` by their value, ascending
+    pub fn sort(&self, items: &mut [Project], now: i64) {
+        items.sort_by_cached_key(|v| self.eval(v, now).map(OrderedValue));
+    }
+}
+
+/// `f64` ordered with `total_cmp`, expressions never give NaN
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct OrderedValue(f64);
+
+impl Eq for OrderedValue {}
+
+impl PartialOrd for OrderedValue {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OrderedValue {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokei::LanguageType;
+
+    use super::SortExpr;
+    use crate::test_utils::project;
+
+    const DAY: u32 = 24 * 60 * 60;
+
+    #[test]
+    fn follows_precedence() {
+        let eval = |text| {
+            SortExpr::parse("test", text).unwrap().eval(
+                &project("")
+                    .with_size(1000)
+                    .with_code(LanguageType::Rust, 10)
+                    .with_commits(4, DAY, 11 * DAY),
+                i64::from(21 * DAY),
+            )
+        };
+        assert_eq!(eval("size / loc"), Some(100.0));
+        assert_eq!(eval("1 + 2 * 3"), Some(7.0));
+        assert_eq!(eval("(1 + 2) * 3"), Some(9.0));
+        assert_eq!(eval("-commits - -1"), Some(-3.0));
+        assert_eq!(eval("commits / span_days"), Some(0.4));
+        assert_eq!(eval("age_days + idle_days"), Some(30.0));
+        assert_eq!(eval("size / (loc - 10)"), None);
+    }
+
+    #[test]
+    fn rejects_invalid_expressions() {
+        for text in [
+            "", "size +", "(size", "size loc", "stars", "1..2", "size % 2",
+        ] {
+            assert!(SortExpr::parse("test", text).is_err(), "{text}");
+        }
+    }
+
+    #[test]
+    fn values_it_lacks_sort_first() {
+        let expr = SortExpr::parse("test", "commits / age_days").unwrap();
+        let mut items = vec![
+            project("").with_commits(40, DAY, 11 * DAY),
+            project(""),
+            project("").with_commits(10, DAY, 11 * DAY),
+        ];
+        expr.sort(&mut items, i64::from(21 * DAY));
+        let commits: Vec<_> = items.iter().map(|v| v.git_info.commit_count).collect();
+        assert_eq!(commits, [0, 10, 40]);
+    }
+}
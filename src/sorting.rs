@@ -1,33 +1,62 @@
 use std::fmt::Display;
 
+use crate::ci::CiProvider;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Sorting {
     Name,
     Size,
     Commits,
     CreationDate,
     ModificationDate,
+    /// Time since the first commit
+    Age,
+    /// Time between the first and last commit
+    ActivitySpan,
+    /// Commits per month over the activity span
+    CommitRate,
     Loc,
+    /// Most visited first by zoxide's score
+    Frecency,
+    /// Entry of `custom_sorts` by its index
+    Custom(usize),
 }
 
 impl Sorting {
-    pub const fn next(&self) -> Self {
+    /// Following sorting, going through the `custom` sorts of the config after the built-in ones,
+    /// `Frecency` only with `frecency`, when zoxide is enabled
+    pub const fn next(&self, custom: usize, frecency: bool) -> Self {
         match *self {
             Self::Name => Self::Size,
             Self::Size => Self::Commits,
             Self::Commits => Self::CreationDate,
             Self::CreationDate => Self::ModificationDate,
-            Self::ModificationDate => Self::Loc,
-            Self::Loc => Self::Name,
+            Self::ModificationDate => Self::Age,
+            Self::Age => Self::ActivitySpan,
+            Self::ActivitySpan => Self::CommitRate,
+            Self::CommitRate => Self::Loc,
+            Self::Loc if frecency => Self::Frecency,
+            Self::Loc | Self::Frecency if custom > 0 => Self::Custom(0),
+            Self::Custom(i) if i + 1 < custom => Self::Custom(i + 1),
+            Self::Loc | Self::Frecency | Self::Custom(_) => Self::Name,
         }
     }
 
-    pub const fn previous(&self) -> Self {
+    pub const fn previous(&self, custom: usize, frecency: bool) -> Self {
         match *self {
-            Self::Loc => Self::ModificationDate,
+            Self::Custom(i) if i > 0 && i <= custom => Self::Custom(i - 1),
+            Self::Custom(_) if frecency => Self::Frecency,
+            Self::Custom(_) | Self::Frecency => Self::Loc,
+            Self::Loc => Self::CommitRate,
+            Self::CommitRate => Self::ActivitySpan,
+            Self::ActivitySpan => Self::Age,
+            Self::Age => Self::ModificationDate,
             Self::ModificationDate => Self::CreationDate,
             Self::CreationDate => Self::Commits,
             Self::Commits => Self::Size,
             Self::Size => Self::Name,
+            Self::Name if custom > 0 => Self::Custom(custom - 1),
+            Self::Name if frecency => Self::Frecency,
             Self::Name => Self::Loc,
         }
     }
@@ -41,37 +70,102 @@ impl Display for Sorting {
             Self::Commits => write!(f, "Commits"),
             Self::CreationDate => write!(f, "Creation Date"),
             Self::ModificationDate => write!(f, "Modification Date"),
+            Self::Age => write!(f, "Age"),
+            Self::ActivitySpan => write!(f, "Activity Span"),
+            Self::CommitRate => write!(f, "Commits per Month"),
             Self::Loc => write!(f, "Lines of Code"),
+            Self::Frecency => write!(f, "Frecency"),
+            Self::Custom(i) => write!(f, "Custom {}", i + 1),
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Filter {
     All,
     Owned,
     NotOwned,
     HasRemote,
     NoRemote,
+    /// Last commit isn't signed
+    Unsigned,
+    /// No pre-commit hook would run
+    NoPreCommit,
+    HasCi,
+    /// Has configuration for this CI provider
+    Ci(CiProvider),
+    NoCi,
+    /// Checked out branch isn't the remote's default branch
+    OffDefaultBranch,
+    /// Some submodule is uninitialized or checked out at another commit than the recorded one
+    SubmodulesOutOfDate,
+    /// No commits to count, empty, unborn or unreadable
+    Inactive,
+    /// Something couldn't be read while scanning, usually permissions
+    ScanErrors,
+    /// Shares its remote with another clone
+    Duplicates,
+    /// Stale, small and without a remote, see [`crate::archive`]
+    ArchiveCandidates,
 }
 
 impl Filter {
+    /// Every filter in the order `next` goes through them
+    pub fn all() -> Vec<Self> {
+        let mut filters = vec![Self::All];
+        let mut filter = Self::All.next();
+        while filter != Self::All {
+            filters.push(filter);
+            filter = filter.next();
+        }
+        filters
+    }
+
     pub const fn next(&self) -> Self {
         match self {
             Self::All => Self::Owned,
             Self::Owned => Self::NotOwned,
             Self::NotOwned => Self::HasRemote,
             Self::HasRemote => Self::NoRemote,
-            Self::NoRemote => Self::All,
+            Self::NoRemote => Self::Unsigned,
+            Self::Unsigned => Self::NoPreCommit,
+            Self::NoPreCommit => Self::HasCi,
+            Self::HasCi => Self::Ci(CiProvider::ALL[0]),
+            Self::Ci(provider) => match provider.next() {
+                Some(provider) => Self::Ci(provider),
+                None => Self::NoCi,
+            },
+            Self::NoCi => Self::OffDefaultBranch,
+            Self::OffDefaultBranch => Self::SubmodulesOutOfDate,
+            Self::SubmodulesOutOfDate => Self::Inactive,
+            Self::Inactive => Self::ScanErrors,
+            Self::ScanErrors => Self::Duplicates,
+            Self::Duplicates => Self::ArchiveCandidates,
+            Self::ArchiveCandidates => Self::All,
         }
     }
 
     pub const fn previous(&self) -> Self {
         match self {
+            Self::ArchiveCandidates => Self::Duplicates,
+            Self::Duplicates => Self::ScanErrors,
+            Self::ScanErrors => Self::Inactive,
+            Self::Inactive => Self::SubmodulesOutOfDate,
+            Self::SubmodulesOutOfDate => Self::OffDefaultBranch,
+            Self::OffDefaultBranch => Self::NoCi,
+            Self::NoCi => Self::Ci(CiProvider::ALL[CiProvider::ALL.len() - 1]),
+            Self::Ci(provider) => match provider.previous() {
+                Some(provider) => Self::Ci(provider),
+                None => Self::HasCi,
+            },
+            Self::HasCi => Self::NoPreCommit,
+            Self::NoPreCommit => Self::Unsigned,
+            Self::Unsigned => Self::NoRemote,
             Self::NoRemote => Self::HasRemote,
             Self::HasRemote => Self::NotOwned,
             Self::NotOwned => Self::Owned,
             Self::Owned => Self::All,
-            Self::All => Self::NoRemote,
+            Self::All => Self::ArchiveCandidates,
         }
     }
 }
@@ -84,6 +178,35 @@ impl Display for Filter {
             Self::NotOwned => write!(f, "Not Owned"),
             Self::HasRemote => write!(f, "Has Remote"),
             Self::NoRemote => write!(f, "No Remote"),
+            Self::Unsigned => write!(f, "Unsigned"),
+            Self::NoPreCommit => write!(f, "No pre-commit"),
+            Self::HasCi => write!(f, "Has CI"),
+            Self::Ci(provider) => write!(f, "CI: {provider}"),
+            Self::NoCi => write!(f, "No CI"),
+            Self::OffDefaultBranch => write!(f, "Off Default Branch"),
+            Self::SubmodulesOutOfDate => write!(f, "Submodules Out of Date"),
+            Self::Inactive => write!(f, "Inactive"),
+            Self::ScanErrors => write!(f, "Scan Errors"),
+            Self::Duplicates => write!(f, "Duplicates"),
+            Self::ArchiveCandidates => write!(f, "Archive Candidates"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Filter;
+    use crate::ci::CiProvider;
+
+    #[test]
+    fn all_filters_in_picker_order() {
+        let filters = Filter::all();
+        assert_eq!(filters[0], Filter::All);
+        assert!(filters.contains(&Filter::Ci(CiProvider::Woodpecker)));
+        for (i, filter) in filters.iter().enumerate() {
+            assert!(!filters[..i].contains(filter), "{filter} is listed twice");
+            assert_eq!(filter.next(), filters[(i + 1) % filters.len()]);
+            assert_eq!(filters[(i + 1) % filters.len()].previous(), *filter);
         }
     }
 }
@@ -0,0 +1,102 @@
+//! Submodules of a repository and whether their checkouts match the commits it records
+
+use std::{fmt::Display, path::Path};
+
+use git2::{Repository, SubmoduleIgnore, SubmoduleStatus};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Submodules {
+    pub count: u32,
+    /// Listed in `.gitmodules` but never checked out, `git submodule update --init` fixes them
+    pub uninitialized: u32,
+    /// Checked out at another commit than the one the index records
+    pub out_of_sync: u32,
+}
+
+impl Submodules {
+    pub fn detect(root: &Path) -> Self {
+        let Ok(repo) = Repository::open(root) else {
+            return Self::default();
+        };
+        let Ok(submodules) = repo.submodules() else {
+            return Self::default();
+        };
+
+        let mut result = Self::default();
+        for submodule in submodules {
+            result.count += 1;
+            let Some(status) = submodule
+                .name()
+                .and_then(|name| repo.submodule_status(name, SubmoduleIgnore::None).ok())
+            else {
+                continue;
+            };
+            if status.contains(SubmoduleStatus::WD_UNINITIALIZED) {
+                result.uninitialized += 1;
+            } else if status.contains(SubmoduleStatus::WD_MODIFIED) {
+                result.out_of_sync += 1;
+            }
+        }
+        result
+    }
+
+    /// Some submodule isn't checked out at the recorded commit, builds may not match the history
+    pub fn out_of_date(&self) -> bool {
+        self.uninitialized > 0 || self.out_of_sync > 0
+    }
+}
+
+impl Display for Submodules {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.count)?;
+        if self.uninitialized > 0 {
+            write!(f, ", {} uninitialized", self.uninitialized)?;
+        }
+        if self.out_of_sync > 0 {
+            write!(f, ", {} out of sync", self.out_of_sync)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::Submodules;
+    use crate::test_utils::{git, temp_dir};
+
+    #[test]
+    fn tells_uninitialized_and_out_of_sync() {
+        let root = temp_dir("submodules");
+        let (lib, app) = (root.join("lib"), root.join("app"));
+        fs::create_dir_all(&lib).unwrap();
+        fs::create_dir_all(&app).unwrap();
+        git(&lib, &["init", "-q"]);
+        git(&lib, &["commit", "-q", "--allow-empty", "-m", "First"]);
+        git(&app, &["init", "-q"]);
+        for name in ["one", "two"] {
+            git(
+                &app,
+                &["submodule", "-q", "add", lib.to_str().unwrap(), name],
+            );
+        }
+        git(&app, &["commit", "-q", "-m", "Add submodules"]);
+
+        let submodules = Submodules::detect(&app);
+        assert_eq!(submodules.to_string(), "2");
+        assert!(!submodules.out_of_date());
+
+        git(
+            &app.join("one"),
+            &["commit", "-q", "--allow-empty", "-m", "Second"],
+        );
+        git(&app, &["submodule", "-q", "deinit", "-f", "two"]);
+        let submodules = Submodules::detect(&app);
+        assert_eq!(submodules.to_string(), "2, 1 uninitialized, 1 out of sync");
+        assert!(submodules.out_of_date());
+
+        assert_eq!(Submodules::detect(&lib), Submodules::default());
+    }
+}
@@ -0,0 +1,422 @@
+//! Runnable tasks found in a project's manifests and build files
+
+use std::{
+    fmt::Display,
+    fs,
+    io::{self, BufRead, Write},
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use anyhow::Context;
+use toml::{Table, Value};
+
+use crate::devenv::DevEnv;
+
+/// A command that can be run from the project root
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Task {
+    /// Where the task came from, `npm`, `make` and such
+    pub source: &'static str,
+    pub name: String,
+    pub program: String,
+    pub args: Vec<String>,
+    /// Wait for Enter after it exits so the output can be read, not needed for shells
+    pub pause: bool,
+    /// Written to the task's stdin instead of handing it the terminal's
+    pub input: Option<String>,
+}
+
+impl Task {
+    fn new(source: &'static str, name: &str, program: &str, args: &[&str]) -> Self {
+        Self {
+            source,
+            name: name.to_string(),
+            program: program.to_string(),
+            args: args.iter().map(ToString::to_string).collect(),
+            pause: true,
+            input: None,
+        }
+    }
+
+    /// Interactive shell in the project, inside its dev shell when it has one
+    ///
+    /// direnv wins over nix since an `.envrc` usually loads the flake itself. It refuses to run a
+    /// new or changed `.envrc` until it's allowed, `allow_direnv` does that first.
+    pub fn shell(root: &Path, allow_direnv: bool) -> Self {
+        let envs = DevEnv::detect(root);
+        let script = if envs.contains(&DevEnv::Direnv) {
+            if allow_direnv {
+                "direnv allow . && exec direnv exec . \"$SHELL\""
+            } else {
+                "exec direnv exec . \"$SHELL\""
+            }
+        } else if envs.contains(&DevEnv::Flake) {
+            "exec nix develop --command \"$SHELL\""
+        } else if envs.contains(&DevEnv::NixShell) {
+            "exec nix-shell --command \"$SHELL\""
+        } else {
+            "exec \"$SHELL\""
+        };
+
+        Self {
+            pause: false,
+            ..Self::new("shell", "shell", "sh", &["-c", script])
+        }
+    }
+
+    /// `$PAGER`, `less` when it isn't set, showing `text`
+    pub fn pager(text: String) -> Self {
+        Self {
+            pause: false,
+            input: Some(text),
+            ..Self::new("ymir", "pager", "sh", &["-c", "exec ${PAGER:-less}"])
+        }
+    }
+
+    /// `$VISUAL` or `$EDITOR`, `vi` when neither is set, at `line` of `file`
+    pub fn editor(file: &Path, line: usize) -> Self {
+        let file = file.to_string_lossy();
+        let line = format!("+{line}");
+        Self {
+            pause: false,
+            ..Self::new(
+                "ymir",
+                "editor",
+                "sh",
+                &[
+                    "-c",
+                    "exec ${VISUAL:-${EDITOR:-vi}} \"$1\" \"$2\"",
+                    "sh",
+                    &line,
+                    &file,
+                ],
+            )
+        }
+    }
+
+    /// Runs the task in `dir` on the current terminal and waits for Enter before returning, so
+    /// the output can be read before the TUI takes over again
+    pub fn run(&self, dir: &Path) -> anyhow::Result<()> {
+        println!("$ {self}");
+
+        let mut command = Command::new(&self.program);
+        command.args(&self.args).current_dir(dir);
+        let status = match &self.input {
+            Some(input) => command.stdin(Stdio::piped()).spawn().and_then(|mut child| {
+                if let Some(mut stdin) = child.stdin.take() {
+                    // A pager quit before reading everything closes the pipe early
+                    let _ = stdin.write_all(input.as_bytes());
+                }
+                child.wait()
+            }),
+            None => command.status(),
+        };
+        match status {
+            Ok(_) if !self.pause => return Ok(()),
+            Ok(status) => println!("\n{} exited with {status}", self.program),
+            Err(err) => println!("\nFailed to run {}: {err}", self.program),
+        }
+
+        print!("Press Enter to return to ymir");
+        io::stdout().flush()?;
+        io::stdin().lock().read_line(&mut String::new())?;
+        Ok(())
+    }
+}
+
+impl Display for Task {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.program)?;
+        for arg in &self.args {
+            write!(f, " {arg}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Shows `dir` in `file_manager`, or the platform's default opener when it's `None`, without
+/// waiting for it to close
+pub fn reveal(dir: &Path, file_manager: Option<&str>) -> anyhow::Result<()> {
+    let default = if cfg!(target_os = "macos") {
+        "open"
+    } else if cfg!(windows) {
+        "explorer"
+    } else {
+        "xdg-open"
+    };
+    let mut words = file_manager.unwrap_or(default).split_whitespace();
+    let Some(program) = words.next() else {
+        anyhow::bail!("file_manager is empty");
+    };
+
+    Command::new(program)
+        .args(words)
+        .arg(dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("Failed to run {program}"))?;
+    Ok(())
+}
+
+/// Tag `docker build` gives the image of the project at `root`, its directory name reduced to
+/// lowercase letters and digits joined by single dashes
+fn image_name(root: &Path) -> String {
+    let name = root
+        .file_name()
+        .map(|v| v.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let name = name
+        .split(|v: char| !v.is_ascii_alphanumeric())
+        .filter(|v| !v.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    if name.is_empty() {
+        "project".to_string()
+    } else {
+        name
+    }
+}
+
+/// Every task of the project at `root`
+pub fn find(root: &Path) -> Vec<Task> {
+    let mut tasks = npm_tasks(root);
+    tasks.extend(cargo_tasks(root));
+    tasks.extend(make_tasks(root));
+    tasks.extend(just_tasks(root));
+    tasks.extend(container_tasks(root));
+    tasks
+}
+
+/// Bringing up whatever containers the project defines
+fn container_tasks(root: &Path) -> Vec<Task> {
+    let name = image_name(root);
+
+    DevEnv::detect(root)
+        .into_iter()
+        .flat_map(|env| match env {
+            DevEnv::Dockerfile => vec![Task::new(
+                "docker",
+                "build",
+                "docker",
+                &["build", "-t", &name, "."],
+            )],
+            DevEnv::Compose => vec![Task::new(
+                "docker",
+                "compose up",
+                "docker",
+                &["compose", "up"],
+            )],
+            DevEnv::Devcontainer => vec![
+                Task::new(
+                    "devc",
+                    "up",
+                    "devcontainer",
+                    &["up", "--workspace-folder", "."],
+                ),
+                Task::new(
+                    "devc",
+                    "shell",
+                    "devcontainer",
+                    &[
+                        "exec",
+                        "--workspace-folder",
+                        ".",
+                        "sh",
+                        "-c",
+                        "exec ${SHELL:-sh}",
+                    ],
+                ),
+            ],
+            // Entered through the shell instead, see `Task::shell`
+            DevEnv::Flake | DevEnv::NixShell | DevEnv::Direnv => Vec::new(),
+        })
+        .collect()
+}
+
+/// `scripts` of `package.json`, run with whichever package manager the lockfile belongs to
+fn npm_tasks(root: &Path) -> Vec<Task> {
+    let Some(package) = fs::read_to_string(root.join("package.json"))
+        .ok()
+        .and_then(|v| serde_json::from_str::<serde_json::Value>(&v).ok())
+    else {
+        return Vec::new();
+    };
+
+    let runner = [
+        ("pnpm-lock.yaml", "pnpm"),
+        ("yarn.lock", "yarn"),
+        ("bun.lockb", "bun"),
+        ("bun.lock", "bun"),
+    ]
+    .into_iter()
+    .find(|(lockfile, _)| root.join(lockfile).is_file())
+    .map_or("npm", |(_, runner)| runner);
+
+    package
+        .get("scripts")
+        .and_then(serde_json::Value::as_object)
+        .into_iter()
+        .flatten()
+        .map(|(name, _)| Task::new("npm", name, runner, &["run", name]))
+        .collect()
+}
+
+/// Aliases from `.cargo/config.toml`
+fn cargo_tasks(root: &Path) -> Vec<Task> {
+    let config = ["config.toml", "config"]
+        .into_iter()
+        .find_map(|name| fs::read_to_string(root.join(".cargo").join(name)).ok())
+        .and_then(|v| toml::from_str::<Table>(&v).ok());
+
+    config
+        .as_ref()
+        .and_then(|v| v.get("alias"))
+        .and_then(Value::as_table)
+        .into_iter()
+        .flatten()
+        .map(|(name, _)| Task::new("cargo", name, "cargo", &[name]))
+        .collect()
+}
+
+/// Explicit targets of the `Makefile`, pattern and special targets left out
+fn make_tasks(root: &Path) -> Vec<Task> {
+    let Some(makefile) = ["GNUmakefile", "makefile", "Makefile"]
+        .into_iter()
+        .find_map(|name| fs::read_to_string(root.join(name)).ok())
+    else {
+        return Vec::new();
+    };
+
+    let mut names: Vec<&str> = Vec::new();
+    for line in makefile.lines() {
+        if line.starts_with(|c: char| c.is_whitespace() || c == '#') {
+            continue;
+        }
+        let Some((targets, rest)) = line.split_once(':') else {
+            continue;
+        };
+        // `VAR := value` and `VAR ::= value` are assignments
+        if rest.starts_with('=') || rest.starts_with(":=") || targets.contains('=') {
+            continue;
+        }
+
+        for target in targets.split_whitespace() {
+            if !target.starts_with('.')
+                && !target.contains(['%', '$', '('])
+                && !names.contains(&target)
+            {
+                names.push(target);
+            }
+        }
+    }
+
+    names
+        .into_iter()
+        .map(|name| Task::new("make", name, "make", &[name]))
+        .collect()
+}
+
+/// Public recipes of the `justfile`
+fn just_tasks(root: &Path) -> Vec<Task> {
+    let Some(justfile) = ["justfile", "Justfile", ".justfile"]
+        .into_iter()
+        .find_map(|name| fs::read_to_string(root.join(name)).ok())
+    else {
+        return Vec::new();
+    };
+
+    let keywords = ["set", "alias", "import", "mod", "export"];
+    justfile
+        .lines()
+        .filter(|line| !line.starts_with(|c: char| c.is_whitespace() || c == '#' || c == '['))
+        .filter(|line| line.contains(':') && !line.contains(":="))
+        .filter_map(|line| {
+            let line = line.trim_start_matches('@');
+            let name = line.split([' ', ':']).next()?;
+            (!name.is_empty() && !name.starts_with('_') && !keywords.contains(&name))
+                .then_some(name)
+        })
+        .map(|name| Task::new("just", name, "just", &[name]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use super::{find, image_name, Task};
+    use crate::test_utils::temp_dir;
+
+    #[test]
+    fn finds_tasks_in_every_format() {
+        let dir = temp_dir("tasks");
+        fs::create_dir_all(dir.join(".cargo")).unwrap();
+
+        fs::write(
+            dir.join("package.json"),
+            r#"{ "scripts": { "build": "vite build" } }"#,
+        )
+        .unwrap();
+        fs::write(dir.join("yarn.lock"), "").unwrap();
+        fs::write(
+            dir.join(".cargo/config.toml"),
+            "[alias]\nxtask = \"run -p xtask --\"\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("Makefile"),
+            "CC := gcc\n.PHONY: all test\nall test: main.o\n\tcc main.o\n%.o: %.c\n\tcc -c $<\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("justfile"),
+            "set shell := [\"bash\", \"-c\"]\nversion := \"1\"\n\n# Deploy it\n[confirm]\n\
+             deploy env='prod': build\n    ./deploy {{env}}\n@build:\n    cargo build\n_hidden:\n",
+        )
+        .unwrap();
+
+        let tasks: Vec<String> = find(&dir)
+            .iter()
+            .map(|task| format!("{}: {}", task.name, task))
+            .collect();
+        assert_eq!(
+            tasks,
+            [
+                "build: yarn run build",
+                "xtask: cargo xtask",
+                "all: make all",
+                "test: make test",
+                "deploy: just deploy",
+                "build: just build",
+            ]
+        );
+    }
+
+    #[test]
+    fn image_names_are_valid_tags() {
+        let name = |path: &str| image_name(Path::new(path));
+        assert_eq!(name("/src/ymir"), "ymir");
+        assert_eq!(name("/src/My App (old)"), "my-app-old");
+        assert_eq!(name("/src/_x__y.z-"), "x-y-z");
+        assert_eq!(name("/src/日本"), "project");
+        assert_eq!(name("/"), "project");
+    }
+
+    #[test]
+    fn shell_enters_dev_environment() {
+        let dir = temp_dir("shell");
+
+        let script = |allow| Task::shell(&dir, allow).args[1].clone();
+        assert_eq!(script(true), "exec \"$SHELL\"");
+
+        fs::write(dir.join("flake.nix"), "{}").unwrap();
+        assert_eq!(script(false), "exec nix develop --command \"$SHELL\"");
+
+        fs::write(dir.join(".envrc"), "use flake\n").unwrap();
+        assert_eq!(script(false), "exec direnv exec . \"$SHELL\"");
+        assert!(script(true).starts_with("direnv allow . && "));
+    }
+}
@@ -0,0 +1,276 @@
+//! Templates for list rows, set with `list_template`
+//!
+//! Text is copied as it is and `{field}` is replaced by a value of the project. A field can be
+//! followed by specs separated by colons, either an alignment like `>8`, `<20` or `^10`, or a
+//! modifier like `relative` for dates. `{{` and `}}` are literal braces.
+//!
+//! ```text
+//! {name:<24}  {size:>8}  {loc:>8}  {modified:relative}
+//! ```
+
+use anyhow::bail;
+use chrono::{Local, TimeZone};
+
+use crate::{
+    config::{ProjectLabel, Settings},
+    projects::Project,
+    utils::{format_bytes, format_number, format_relative},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    /// Whatever `label` is set to
+    Label,
+    Name,
+    Path,
+    Remote,
+    Branch,
+    Size,
+    Loc,
+    Files,
+    Commits,
+    /// Language with the most lines of code
+    Language,
+    Modified,
+    Created,
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "label" => Self::Label,
+            "name" => Self::Name,
+            "path" => Self::Path,
+            "remote" => Self::Remote,
+            "branch" => Self::Branch,
+            "size" => Self::Size,
+            "loc" => Self::Loc,
+            "files" => Self::Files,
+            "commits" => Self::Commits,
+            "language" => Self::Language,
+            "modified" => Self::Modified,
+            "created" => Self::Created,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Field {
+        field: Field,
+        align: Option<(Align, usize)>,
+        /// Dates as `3 days ago` instead of `2024-01-31`
+        relative: bool,
+    },
+}
+
+/// A parsed `list_template`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListTemplate {
+    parts: Vec<Part>,
+}
+
+impl ListTemplate {
+    pub fn parse(template: &str) -> anyhow::Result<Self> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = template.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut spec = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => spec.push(c),
+                            None => bail!("Unclosed {{ in list_template"),
+                        }
+                    }
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(parse_field(&spec)?);
+                }
+                '}' => bail!("Unmatched }} in list_template, write }}}} for a literal one"),
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+
+        Ok(Self { parts })
+    }
+
+    /// The row for `project`, `now` is a unix timestamp for relative dates
+    pub fn render(&self, project: &Project, settings: &Settings, now: i64) -> String {
+        let mut row = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => row.push_str(text),
+                Part::Field {
+                    field,
+                    align,
+                    relative,
+                } => {
+                    let value = field_value(*field, *relative, project, settings, now);
+                    match align {
+                        Some((align, width)) => row.push_str(&pad(&value, *align, *width)),
+                        None => row.push_str(&value),
+                    }
+                }
+            }
+        }
+        row
+    }
+}
+
+fn parse_field(spec: &str) -> anyhow::Result<Part> {
+    let mut specs = spec.split(':');
+    let name = specs.next().unwrap_or_default().trim();
+    let Some(field) = Field::parse(name) else {
+        bail!("Unknown field {{{name}}} in list_template");
+    };
+
+    let mut align = None;
+    let mut relative = false;
+    for spec in specs {
+        let (kind, width) = match spec.chars().next() {
+            Some('<') => (Align::Left, &spec[1..]),
+            Some('>') => (Align::Right, &spec[1..]),
+            Some('^') => (Align::Center, &spec[1..]),
+            _ => (Align::Left, spec),
+        };
+        if let Ok(width) = width.parse() {
+            align = Some((kind, width));
+        } else if spec == "relative" && matches!(field, Field::Modified | Field::Created) {
+            relative = true;
+        } else {
+            bail!("Unknown spec {spec} for {{{name}}} in list_template");
+        }
+    }
+
+    Ok(Part::Field {
+        field,
+        align,
+        relative,
+    })
+}
+
+fn field_value(
+    field: Field,
+    relative: bool,
+    project: &Project,
+    settings: &Settings,
+    now: i64,
+) -> String {
+    let number = |value: u32| format_number(u64::from(value), settings.number_format);
+    let date = |timestamp: u32| {
+        if !project.git_info.state.has_commits() {
+            "-".to_string()
+        } else if relative {
+            format_relative(now - i64::from(timestamp))
+        } else {
+            Local
+                .timestamp_opt(i64::from(timestamp), 0)
+                .single()
+                .map_or_else(|| "-".to_string(), |v| v.format("%Y-%m-%d").to_string())
+        }
+    };
+
+    match field {
+        Field::Label => settings.label(project),
+        Field::Name => project.label(ProjectLabel::Name),
+        Field::Path => settings.display_path(&project.path),
+        Field::Remote => project.label(ProjectLabel::Remote),
+        Field::Branch => project.git_info.branch.clone().unwrap_or_default(),
+        Field::Size => format_bytes(project.size),
+        Field::Loc => number(project.languages_total.code),
+        Field::Files => number(project.languages_total.files),
+        Field::Commits => number(project.git_info.commit_count),
+        Field::Language => project
+            .top_language()
+            .map(|v| v.name().to_string())
+            .unwrap_or_default(),
+        Field::Modified => date(project.git_info.last_commit_date),
+        Field::Created => date(project.git_info.init_date),
+    }
+}
+
+/// Pads `value` to `width` characters, longer values are cut with an ellipsis
+fn pad(value: &str, align: Align, width: usize) -> String {
+    let len = value.chars().count();
+    if len > width {
+        let mut cut: String = value.chars().take(width.saturating_sub(1)).collect();
+        if width > 0 {
+            cut.push('…');
+        }
+        return cut;
+    }
+
+    let fill = width - len;
+    match align {
+        Align::Left => format!("{value}{}", " ".repeat(fill)),
+        Align::Right => format!("{}{value}", " ".repeat(fill)),
+        Align::Center => format!(
+            "{}{value}{}",
+            " ".repeat(fill / 2),
+            " ".repeat(fill - fill / 2)
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::ListTemplate;
+    use crate::{config::Settings, projects::Project};
+
+    #[test]
+    fn renders_fields() {
+        let mut project = Project {
+            path: PathBuf::from("/src/ymir"),
+            size: 2048,
+            ..Project::default()
+        };
+        project.languages_total.code = 12_345;
+        project.git_info.last_commit_date = 1000;
+
+        let template =
+            ListTemplate::parse("{{{name:<6}}} {size:>6}|{loc:^8}|{modified:relative}").unwrap();
+        assert_eq!(
+            template.render(&project, &Settings::default(), 1000 + 3 * 24 * 60 * 60),
+            "{ymir  }   2.0K| 12,345 |3 days ago"
+        );
+
+        let template = ListTemplate::parse("{path:5}").unwrap();
+        assert_eq!(template.render(&project, &Settings::default(), 0), "/src…");
+    }
+
+    #[test]
+    fn rejects_bad_templates() {
+        assert!(ListTemplate::parse("{nme}").is_err());
+        assert!(ListTemplate::parse("{size:relative}").is_err());
+        assert!(ListTemplate::parse("{name").is_err());
+        assert!(ListTemplate::parse("name}").is_err());
+    }
+}
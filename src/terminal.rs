@@ -0,0 +1,68 @@
+//! Escape sequences telling the terminal emulator about ymir, its window title and the directory
+//! of the shell a project opens in
+
+use std::{
+    fmt::Write as _,
+    io::{self, Write},
+    path::Path,
+};
+
+use crate::hosts;
+
+/// Saves the current title on xterm's title stack, most terminals support it and the rest ignore it
+pub const PUSH_TITLE: &str = "\x1b[22;2t";
+/// Restores the title saved with [`PUSH_TITLE`]
+pub const POP_TITLE: &str = "\x1b[23;2t";
+
+/// Sets the window title to `text`
+pub fn title(text: &str) -> String {
+    // Control characters would end the sequence early
+    let text: String = text.chars().filter(|c| !c.is_control()).collect();
+    format!("\x1b]2;{text}\x07")
+}
+
+/// OSC 7, the working directory as a `file://` url, terminals and multiplexers open new tabs and
+/// panes there
+pub fn cwd(path: &Path) -> String {
+    let mut url = format!("\x1b]7;file://{}", hosts::local_name());
+    for byte in path.to_string_lossy().bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-._~".contains(&byte) {
+            url.push(char::from(byte));
+        } else {
+            let _ = write!(url, "%{byte:02X}");
+        }
+    }
+    url.push_str("\x1b\\");
+    url
+}
+
+/// Writes `sequence` to the terminal right away
+pub fn emit(sequence: &str) {
+    let mut stdout = io::stdout();
+    if let Err(err) = stdout
+        .write_all(sequence.as_bytes())
+        .and_then(|()| stdout.flush())
+    {
+        warn!("Failed to write to the terminal: {err}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{cwd, title};
+    use crate::hosts;
+
+    #[test]
+    fn escapes_what_would_break_the_sequence() {
+        assert_eq!(title("ymir — ~/src/a\x07b"), "\x1b]2;ymir — ~/src/ab\x07");
+        assert_eq!(
+            cwd(Path::new("/home/me/my project/ząb")),
+            format!(
+                "\x1b]7;file://{}/home/me/my%20project/z%C4%85b\x1b\\",
+                hosts::local_name()
+            )
+        );
+    }
+}
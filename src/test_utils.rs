@@ -0,0 +1,110 @@
+//! Fixtures shared by the unit tests
+
+use std::{
+    ops::Deref,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use tokei::LanguageType;
+
+use crate::projects::{Project, ProjectLanguage};
+
+/// Empty directory removed with everything in it on drop, panics included
+pub struct TempDir(tempfile::TempDir);
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        self.0.path()
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        self.0.path()
+    }
+}
+
+/// [`TempDir`] named after `name`, to tell which test left it when removing fails
+pub fn temp_dir(name: &str) -> TempDir {
+    TempDir(
+        tempfile::Builder::new()
+            .prefix(&format!("ymir-{name}-"))
+            .tempdir()
+            .unwrap(),
+    )
+}
+
+/// Runs git in `dir` with a fixed identity, panicking when it fails, returns what it printed
+pub fn git(dir: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .args(["-c", "user.name=ymir", "-c", "user.email=ymir@localhost"])
+        .args([
+            "-c",
+            "protocol.file.allow=always",
+            "-c",
+            "init.defaultBranch=main",
+        ])
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "git {args:?} failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Project at `path` with nothing else known, the `with_` methods fill in what a test looks at
+pub fn project(path: impl Into<PathBuf>) -> Project {
+    Project {
+        path: path.into(),
+        ..Project::default()
+    }
+}
+
+impl Project {
+    pub fn with_size(mut self, size: u64) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn with_remote(mut self, url: &str) -> Self {
+        self.git_info.remote_url = Some(url.to_string());
+        self
+    }
+
+    pub fn with_last_commit(mut self, date: u32) -> Self {
+        self.git_info.last_commit_date = date;
+        self
+    }
+
+    /// `count` commits, the first and last at those unix seconds
+    pub fn with_commits(mut self, count: u32, first: u32, last: u32) -> Self {
+        self.git_info.commit_count = count;
+        self.git_info.init_date = first;
+        self.git_info.last_commit_date = last;
+        self
+    }
+
+    /// `code` lines of `language`, added to the total too
+    pub fn with_code(mut self, language: LanguageType, code: u32) -> Self {
+        let id = LanguageType::list()
+            .iter()
+            .position(|v| *v == language)
+            .unwrap();
+        self.languages.insert(
+            u8::try_from(id).unwrap(),
+            ProjectLanguage {
+                code,
+                ..ProjectLanguage::default()
+            },
+        );
+        self.languages_total.code += code;
+        self
+    }
+}
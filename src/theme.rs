@@ -0,0 +1,119 @@
+//! Accessible alternatives to the default colors
+//!
+//! Frames are drawn with the default colors and restyled afterwards, so no render function has
+//! to know about themes. What only color told apart, like inactive repositories, is spelled out
+//! in the list instead when [`Theme::is_accessible`].
+
+use std::env;
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier},
+};
+
+use crate::{app::TEXT_FG_COLOR, config::Theme};
+
+impl Theme {
+    /// [`Theme::NoColor`] when asked for with `--no-color` or a non-empty `NO_COLOR`
+    pub fn with_no_color(self, flag: bool) -> Self {
+        if flag || env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+            Self::NoColor
+        } else {
+            self
+        }
+    }
+
+    /// Whether states have to be shown as text instead of by color
+    pub const fn is_accessible(self) -> bool {
+        !matches!(self, Self::Default)
+    }
+
+    /// Restyles what was rendered to `area`, backgrounds like the selection turn into reversed
+    /// text
+    pub fn apply(self, area: Rect, buf: &mut Buffer) {
+        if self == Self::Default {
+            return;
+        }
+
+        for y in area.top()..area.bottom() {
+            for x in area.left()..area.right() {
+                let Some(cell) = buf.cell_mut((x, y)) else {
+                    continue;
+                };
+
+                if cell.bg != Color::Reset {
+                    cell.modifier.insert(Modifier::REVERSED);
+                }
+                match self {
+                    Self::Default => {}
+                    Self::HighContrast => {
+                        if cell.bg != Color::Reset
+                            || !matches!(cell.fg, Color::Reset | TEXT_FG_COLOR)
+                        {
+                            cell.modifier.insert(Modifier::BOLD);
+                        }
+                        cell.modifier.remove(Modifier::DIM);
+                        cell.set_fg(Color::White).set_bg(Color::Black);
+                    }
+                    Self::NoColor => {
+                        cell.set_fg(Color::Reset).set_bg(Color::Reset);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{
+        buffer::Buffer,
+        layout::Rect,
+        style::{Color, Modifier, Style},
+    };
+
+    use crate::{app::TEXT_FG_COLOR, config::Theme};
+
+    fn buffer() -> Buffer {
+        let mut buf = Buffer::empty(Rect::new(0, 0, 3, 1));
+        buf.set_string(0, 0, "a", Style::new().fg(TEXT_FG_COLOR));
+        buf.set_string(1, 0, "b", Style::new().fg(Color::Red));
+        buf.set_string(2, 0, "c", Style::new().bg(Color::DarkGray));
+        buf
+    }
+
+    #[test]
+    fn no_color_drops_colors_and_reverses_backgrounds() {
+        let mut buf = buffer();
+        Theme::NoColor.apply(buf.area, &mut buf);
+
+        for cell in &buf.content {
+            assert_eq!((cell.fg, cell.bg), (Color::Reset, Color::Reset));
+        }
+        assert!(!buf[(1, 0)].modifier.contains(Modifier::REVERSED));
+        assert!(buf[(2, 0)].modifier.contains(Modifier::REVERSED));
+    }
+
+    #[test]
+    fn high_contrast_bolds_what_color_marked() {
+        let mut buf = buffer();
+        Theme::HighContrast.apply(buf.area, &mut buf);
+
+        for cell in &buf.content {
+            assert_eq!((cell.fg, cell.bg), (Color::White, Color::Black));
+        }
+        assert!(!buf[(0, 0)].modifier.contains(Modifier::BOLD));
+        assert!(buf[(1, 0)].modifier.contains(Modifier::BOLD));
+        assert!(buf[(2, 0)]
+            .modifier
+            .contains(Modifier::BOLD | Modifier::REVERSED));
+    }
+
+    #[test]
+    fn default_leaves_the_frame_alone() {
+        let mut buf = buffer();
+        Theme::Default.apply(buf.area, &mut buf);
+        assert_eq!(buf, buffer());
+    }
+}
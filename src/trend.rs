@@ -0,0 +1,237 @@
+//! Lines of code per language at two points in time, from cache files saved with
+//! `ymir cache export`
+//!
+//! Meant for following a migration across many repositories, like JavaScript to TypeScript.
+//! Projects are matched by path, one only in either snapshot counts as having no code in the other.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use serde_json::json;
+use tokei::LanguageType;
+
+use crate::projects::Project;
+
+/// Lines of code of a language before and after
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LanguageChange {
+    pub language: &'static str,
+    pub before: u64,
+    pub after: u64,
+}
+
+impl LanguageChange {
+    pub fn change(&self) -> i64 {
+        signed(self.after) - signed(self.before)
+    }
+}
+
+/// Languages whose code moved in one project
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectTrend {
+    pub path: PathBuf,
+    pub languages: Vec<LanguageChange>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct LanguageTrend {
+    /// Every language of either snapshot summed over all projects, growth first
+    pub languages: Vec<LanguageChange>,
+    /// Projects where any language moved, by path
+    pub projects: Vec<ProjectTrend>,
+}
+
+impl LanguageTrend {
+    pub fn new(old: &[Project], new: &[Project]) -> Self {
+        let paths: BTreeSet<&Path> = old.iter().chain(new).map(|v| v.path.as_path()).collect();
+        let find = |projects: &'_ [Project], path| -> BTreeMap<&'static str, u64> {
+            projects
+                .iter()
+                .find(|v| v.path == path)
+                .map(code_per_language)
+                .unwrap_or_default()
+        };
+
+        let mut totals: BTreeMap<&'static str, (u64, u64)> = BTreeMap::new();
+        let mut projects = Vec::new();
+        for path in paths {
+            let (before, after) = (find(old, path), find(new, path));
+            let mut languages = changes(&before, &after);
+            for v in &languages {
+                let total = totals.entry(v.language).or_default();
+                total.0 += v.before;
+                total.1 += v.after;
+            }
+
+            languages.retain(|v| v.change() != 0);
+            if !languages.is_empty() {
+                projects.push(ProjectTrend {
+                    path: path.to_path_buf(),
+                    languages,
+                });
+            }
+        }
+
+        let mut languages: Vec<LanguageChange> = totals
+            .into_iter()
+            .map(|(language, (before, after))| LanguageChange {
+                language,
+                before,
+                after,
+            })
+            .collect();
+        languages.sort_by_key(|v| std::cmp::Reverse(v.change()));
+
+        Self {
+            languages,
+            projects,
+        }
+    }
+
+    /// The totals under a summary line, then every project that moved
+    pub fn report(&self) -> String {
+        let mut lines = vec![self.to_string()];
+        lines.extend(self.languages.iter().map(|v| format!("  {}", line(v))));
+        for project in &self.projects {
+            lines.push(format!("\n{}", project.path.display()));
+            lines.extend(project.languages.iter().map(|v| format!("  {}", line(v))));
+        }
+        lines.join("\n")
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let languages = |changes: &[LanguageChange]| {
+            changes
+                .iter()
+                .map(|v| {
+                    json!({
+                        "language": v.language,
+                        "before": v.before,
+                        "after": v.after,
+                        "change": v.change(),
+                    })
+                })
+                .collect::<Vec<_>>()
+        };
+        json!({
+            "languages": languages(&self.languages),
+            "projects": self
+                .projects
+                .iter()
+                .map(|v| json!({ "path": v.path, "languages": languages(&v.languages) }))
+                .collect::<Vec<_>>(),
+        })
+    }
+}
+
+impl Display for LanguageTrend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let grew = self.languages.iter().filter(|v| v.change() > 0).count();
+        let shrank = self.languages.iter().filter(|v| v.change() < 0).count();
+        write!(
+            f,
+            "{} languages, {grew} grew, {shrank} shrank, in {} changed projects",
+            self.languages.len(),
+            self.projects.len()
+        )
+    }
+}
+
+/// `+ TypeScript +1200 (300 -> 1500)`, marked by whether it grew or shrank
+fn line(change: &LanguageChange) -> String {
+    let mark = match change.change() {
+        0 => ' ',
+        v if v > 0 => '+',
+        _ => '-',
+    };
+    format!(
+        "{mark} {} {:+} ({} -> {})",
+        change.language,
+        change.change(),
+        change.before,
+        change.after
+    )
+}
+
+fn code_per_language(project: &Project) -> BTreeMap<&'static str, u64> {
+    project
+        .languages
+        .iter()
+        .filter_map(|(id, stats)| {
+            let language = LanguageType::list().get(usize::from(*id))?;
+            Some((language.name(), u64::from(stats.code)))
+        })
+        .collect()
+}
+
+/// Every language of either side, by name
+fn changes(
+    before: &BTreeMap<&'static str, u64>,
+    after: &BTreeMap<&'static str, u64>,
+) -> Vec<LanguageChange> {
+    let languages: BTreeSet<&'static str> = before.keys().chain(after.keys()).copied().collect();
+    languages
+        .into_iter()
+        .map(|language| LanguageChange {
+            language,
+            before: before.get(language).copied().unwrap_or(0),
+            after: after.get(language).copied().unwrap_or(0),
+        })
+        .collect()
+}
+
+fn signed(value: u64) -> i64 {
+    i64::try_from(value).unwrap_or(i64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use tokei::LanguageType;
+
+    use super::LanguageTrend;
+    use crate::test_utils::project;
+
+    #[test]
+    fn follows_a_migration() {
+        use LanguageType::{JavaScript, Rust, TypeScript};
+
+        let old = [
+            project("/src/web").with_code(JavaScript, 900),
+            project("/src/api")
+                .with_code(JavaScript, 300)
+                .with_code(Rust, 50),
+            project("/src/old").with_code(JavaScript, 100),
+        ];
+        let new = [
+            project("/src/web")
+                .with_code(JavaScript, 100)
+                .with_code(TypeScript, 1000),
+            project("/src/api")
+                .with_code(TypeScript, 250)
+                .with_code(Rust, 50),
+            project("/src/cli").with_code(Rust, 200),
+        ];
+
+        assert_eq!(
+            LanguageTrend::new(&old, &new).report(),
+            "3 languages, 2 grew, 1 shrank, in 4 changed projects\n  \
+             + TypeScript +1250 (0 -> 1250)\n  \
+             + Rust +200 (50 -> 250)\n  \
+             - JavaScript -1200 (1300 -> 100)\n\
+             \n/src/api\n  \
+             - JavaScript -300 (300 -> 0)\n  \
+             + TypeScript +250 (0 -> 250)\n\
+             \n/src/cli\n  \
+             + Rust +200 (0 -> 200)\n\
+             \n/src/old\n  \
+             - JavaScript -100 (100 -> 0)\n\
+             \n/src/web\n  \
+             - JavaScript -800 (900 -> 100)\n  \
+             + TypeScript +1000 (0 -> 1000)"
+        );
+    }
+}
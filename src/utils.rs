@@ -1,7 +1,23 @@
-use std::{fs::read_dir, path::Path};
+use std::{
+    fmt::Display,
+    fs::{self, read_dir},
+    os::unix::fs::{MetadataExt, PermissionsExt},
+    path::{Path, PathBuf},
+    process::Command,
+};
 
 use chrono::{DateTime, Local};
+#[cfg(not(feature = "gix"))]
 use git2::Repository;
+use serde::Serialize;
+
+#[cfg(not(feature = "gix"))]
+use crate::commit_graph::CommitGraph;
+use crate::{
+    config::{NumberFormat, Settings},
+    error::YmirError,
+    signature::CommitSignature,
+};
 
 pub fn format_bytes(bytes: u64) -> String {
     let sizes = ["B", "K", "M", "G", "T", "P", "E"];
@@ -17,89 +33,861 @@ pub fn format_bytes(bytes: u64) -> String {
     format!("{:.1}{}", size, sizes[index])
 }
 
-pub fn get_size<P>(path: P) -> anyhow::Result<u64>
+/// Runs `command` and waits for it, erroring when it can't start or fails
+pub fn run_status(command: &mut Command) -> Result<(), YmirError> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    match command.status() {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(YmirError::Exit { program, status }),
+        Err(source) => Err(YmirError::Spawn { program, source }),
+    }
+}
+
+/// Posts `body` to `url` through curl, which keeps an HTTP client out of the dependencies
+pub fn post_json(url: &str, body: &serde_json::Value) -> Result<(), YmirError> {
+    run_status(
+        Command::new("curl")
+            .args([
+                "--silent",
+                "--show-error",
+                "--fail",
+                "--output",
+                "/dev/null",
+            ])
+            .args(["--header", "Content-Type: application/json"])
+            .args(["--data", &body.to_string(), url]),
+    )
+}
+
+/// `12 days ago` for an age in seconds
+pub fn format_relative(seconds: i64) -> String {
+    if seconds < 60 {
+        "just now".to_string()
+    } else {
+        format!("{} ago", format_duration(seconds))
+    }
+}
+
+/// `12 days` for a duration in seconds, in its largest whole unit down to minutes
+pub fn format_duration(seconds: i64) -> String {
+    let units = [
+        (365 * 24 * 60 * 60, "year"),
+        (30 * 24 * 60 * 60, "month"),
+        (7 * 24 * 60 * 60, "week"),
+        (24 * 60 * 60, "day"),
+        (60 * 60, "hour"),
+        (60, "minute"),
+    ];
+
+    let (unit, name) = units
+        .iter()
+        .find(|(unit, _)| seconds >= *unit)
+        .unwrap_or(&(60, "minute"));
+    let count = seconds / unit;
+    format!("{count} {name}{}", if count == 1 { "" } else { "s" })
+}
+
+/// Bytes an unprivileged user can still write to the filesystem holding `path`
+pub fn free_space(path: &Path) -> Result<u64, YmirError> {
+    let stat = rustix::fs::statvfs(path).map_err(|err| YmirError::io(path, err.into()))?;
+    Ok(stat.f_bavail.saturating_mul(stat.f_frsize))
+}
+
+/// `input` with a leading `~` replaced by the home directory
+pub fn expand_home(input: &str) -> Result<PathBuf, YmirError> {
+    match input.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => Ok(dirs::home_dir()
+            .ok_or(YmirError::NoHomeDir)?
+            .join(rest.trim_start_matches('/'))),
+        _ => Ok(PathBuf::from(input)),
+    }
+}
+
+/// `path` with the home directory replaced by `~`
+pub fn tilde(path: &Path) -> String {
+    match dirs::home_dir().and_then(|home| path.strip_prefix(home).ok().map(Path::to_path_buf)) {
+        Some(rest) if rest.as_os_str().is_empty() => "~".to_string(),
+        Some(rest) => format!("~/{}", rest.display()),
+        None => path.display().to_string(),
+    }
+}
+
+/// `text` cut to `width` characters by replacing its middle with `…`, as much of the part after
+/// the last `/` as fits is kept
+pub fn truncate_middle(text: &str, width: usize) -> String {
+    let len = text.chars().count();
+    if len <= width {
+        return text.to_string();
+    }
+    if width == 0 {
+        return String::new();
+    }
+
+    let name = text.rfind('/').map_or(0, |i| text[i..].chars().count());
+    let tail = name.min(width - 1);
+    let head = width - 1 - tail;
+    text.chars()
+        .take(head)
+        .chain(std::iter::once('…'))
+        .chain(text.chars().skip(len - tail))
+        .collect()
+}
+
+/// Reads sizes like `512`, `64K` or `30G`, suffixes are powers of 1024
+pub fn parse_size(text: &str) -> Result<u64, YmirError> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    let (number, suffix) = text.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| YmirError::InvalidSize(text.to_string()))?;
+
+    let shift = match suffix.trim() {
+        "" | "B" => 0,
+        "K" => 10,
+        "M" => 20,
+        "G" => 30,
+        "T" => 40,
+        _ => return Err(YmirError::InvalidSize(text.to_string())),
+    };
+    Ok(number.saturating_mul(1 << shift))
+}
+
+pub fn format_number(value: u64, format: NumberFormat) -> String {
+    match format {
+        NumberFormat::Raw => value.to_string(),
+        NumberFormat::Separated => {
+            let digits = value.to_string();
+            let mut text = String::with_capacity(digits.len() + digits.len() / 3);
+            for (i, digit) in digits.chars().enumerate() {
+                if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                    text.push(',');
+                }
+                text.push(digit);
+            }
+            text
+        }
+        NumberFormat::Short => {
+            let units = ["", "K", "M", "B", "T"];
+            #[allow(clippy::cast_precision_loss)]
+            let mut size = value as f64;
+            let mut index = 0;
+
+            while size >= 1000.0 && index < units.len() - 1 {
+                size /= 1000.0;
+                index += 1;
+            }
+
+            if index == 0 {
+                value.to_string()
+            } else {
+                format!("{:.1}{}", size, units[index])
+            }
+        }
+    }
+}
+
+pub fn get_size<P>(path: P) -> Result<u64, YmirError>
 where
     P: AsRef<Path>,
 {
-    let path_metadata = path.as_ref().symlink_metadata()?;
+    get_size_skipping(path.as_ref(), &|_| false).map(|(size, _)| size)
+}
+
+/// Same as [`get_size`], leaving out directories `skip` is true for
+///
+/// Directories below `path` that can't be read for lack of permissions are left out too, the
+/// flag tells if any were.
+pub fn get_size_skipping(
+    path: &Path,
+    skip: &dyn Fn(&Path) -> bool,
+) -> Result<(u64, bool), YmirError> {
+    let io_error = |err| YmirError::io(path, err);
+    let path_metadata = path.symlink_metadata().map_err(io_error)?;
 
     let mut size_in_bytes = 0;
+    let mut denied = false;
 
     if path_metadata.is_dir() {
-        for entry in read_dir(&path)? {
-            let entry = entry?;
-            let entry_metadata = entry.metadata()?;
+        for entry in read_dir(path).map_err(io_error)? {
+            let entry = entry.map_err(io_error)?;
+            let entry_metadata = entry.metadata().map_err(io_error)?;
 
-            if entry_metadata.is_dir() {
-                size_in_bytes += get_size(entry.path())?;
-            } else {
+            if !entry_metadata.is_dir() {
                 size_in_bytes += entry_metadata.len();
+                continue;
+            }
+            if skip(&entry.path()) {
+                continue;
+            }
+            match get_size_skipping(&entry.path(), skip) {
+                Ok((size, partial)) => {
+                    size_in_bytes += size;
+                    denied |= partial;
+                }
+                Err(err) if err.is_permission_denied() => denied = true,
+                Err(err) => return Err(err),
             }
         }
     } else {
         size_in_bytes = path_metadata.len();
     }
 
-    Ok(size_in_bytes)
+    Ok((size_in_bytes, denied))
+}
+
+/// Owner, group and permission bits of a file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Ownership {
+    /// User name, the uid when it has none
+    pub owner: String,
+    /// Group name, the gid when it has none
+    pub group: String,
+    /// Permission bits, like `0o755`
+    pub mode: u32,
+}
+
+impl Ownership {
+    /// Ownership of `path`, names are looked up in `/etc/passwd` and `/etc/group`
+    pub fn of(path: &Path) -> Result<Self, YmirError> {
+        let metadata = path.metadata().map_err(|err| YmirError::io(path, err))?;
+        let name = |file, id: u32| {
+            fs::read_to_string(file)
+                .ok()
+                .and_then(|v| id_name(&v, id))
+                .unwrap_or_else(|| id.to_string())
+        };
+
+        Ok(Self {
+            owner: name("/etc/passwd", metadata.uid()),
+            group: name("/etc/group", metadata.gid()),
+            mode: metadata.mode() & 0o7777,
+        })
+    }
+}
+
+/// Name of `id` in a file of `name:password:id:...` lines like `/etc/passwd`
+fn id_name(file: &str, id: u32) -> Option<String> {
+    let id = id.to_string();
+    file.lines().find_map(|line| {
+        let mut fields = line.split(':');
+        let name = fields.next()?;
+        (fields.nth(1)? == id).then(|| name.to_string())
+    })
+}
+
+impl Display for Ownership {
+    /// `me:users rwxr-xr-x`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{} ", self.owner, self.group)?;
+        for shift in [6, 3, 0] {
+            let bits = self.mode >> shift;
+            for (bit, char) in [(4, 'r'), (2, 'w'), (1, 'x')] {
+                write!(f, "{}", if bits & bit == 0 { '-' } else { char })?;
+            }
+        }
+        Ok(())
+    }
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
 pub struct GitInfo {
+    /// Url of the primary remote
     pub remote_url: Option<String>,
+    pub remotes: Vec<GitRemote>,
     pub init_date: u32,
     pub last_commit_date: u32,
     pub last_commit_msg: Option<String>,
     pub commit_count: u32,
+    pub state: RepoState,
+    /// Checked out branch, `None` when HEAD is detached
+    pub branch: Option<String>,
+    /// Default branch of the primary remote
+    pub default_branch: Option<String>,
+    /// Value of `core.hooksPath` when it's configured
+    pub hooks_path: Option<String>,
+    /// Names of hooks that git would run
+    pub hooks: Vec<String>,
+    /// Signature of the last commit, `None` when it isn't signed
+    pub signature: Option<CommitSignature>,
+}
+
+impl Display for GitInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "State: {}\nBranch: {}\nLast Commit: {}\nSigned: {}\nCommits: {}\nRemote: {}",
+            self.state,
+            match (&self.branch, &self.default_branch) {
+                (Some(branch), Some(default)) if branch != default => {
+                    format!("{branch} (default is {default})")
+                }
+                (Some(branch), _) => branch.clone(),
+                (None, _) => "None".to_string(),
+            },
+            self.last_commit_msg.as_ref().map_or("Unknown", |v| v),
+            self.signature
+                .as_ref()
+                .map_or_else(|| "No".to_string(), ToString::to_string),
+            self.commit_count,
+            self.remote_url.as_ref().map_or("Unknown", |v| v),
+        )?;
+
+        if !self.remotes.is_empty() {
+            write!(f, "\n\n# Remotes:")?;
+            for remote in &self.remotes {
+                write!(f, "\n{}: {}", remote.name, remote.url)?;
+            }
+        }
+
+        write!(f, "\n\n# Hooks:")?;
+        if let Some(hooks_path) = &self.hooks_path {
+            write!(f, "\nPath: {hooks_path}")?;
+        }
+        if self.hooks.is_empty() {
+            write!(f, "\nNone")?;
+        }
+        for hook in &self.hooks {
+            write!(f, "\n{hook}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GitRemote {
+    pub name: String,
+    pub url: String,
+}
+
+/// Host, owner and repository name parsed from a remote url
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteUrl {
+    pub host: String,
+    /// Everything between host and repository name, includes subgroups on gitlab
+    pub owner: String,
+    pub repo: String,
+}
+
+impl RemoteUrl {
+    /// Parses `scheme://[user@]host[:port]/owner/repo` and scp-like `[user@]host:owner/repo` urls
+    pub fn parse(url: &str) -> Option<Self> {
+        let (host, path) = if let Some((_, rest)) = url.split_once("://") {
+            let (authority, path) = rest.split_once('/')?;
+            let host = authority.rsplit_once('@').map_or(authority, |(_, v)| v);
+            (host.split_once(':').map_or(host, |(v, _)| v), path)
+        } else {
+            let (authority, path) = url.split_once(':')?;
+            if authority.contains('/') {
+                return None;
+            }
+            (
+                authority.rsplit_once('@').map_or(authority, |(_, v)| v),
+                path,
+            )
+        };
+
+        let path = path.trim_matches('/');
+        let path = path.strip_suffix(".git").unwrap_or(path);
+        let (owner, repo) = path.rsplit_once('/')?;
+
+        if host.is_empty() || owner.is_empty() || repo.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            host: host.to_string(),
+            owner: owner.to_string(),
+            repo: repo.to_string(),
+        })
+    }
+}
+
+impl GitInfo {
+    /// Whether a branch other than the remote's default one is checked out
+    pub fn off_default_branch(&self) -> bool {
+        matches!((&self.branch, &self.default_branch), (Some(branch), Some(default)) if branch != default)
+    }
+}
+
+/// Picks the first remote named in `priority`, falling back to whichever remote comes first
+fn primary_remote<'a>(remotes: &'a [GitRemote], priority: &[String]) -> Option<&'a GitRemote> {
+    priority
+        .iter()
+        .find_map(|name| remotes.iter().find(|r| &r.name == name))
+        .or_else(|| remotes.first())
 }
 
-pub fn get_git_info(repo_path: &Path) -> anyhow::Result<GitInfo> {
+/// Default branch of a remote from its remote-tracking refs
+///
+/// `refs/remotes/<remote>/HEAD` is only written on clone, when the branch it points to was since
+/// deleted upstream and pruned locally, a tracking `main` or `master` is taken instead.
+fn tracking_default_branch(
+    head: Option<String>,
+    has_branch: impl Fn(&str) -> bool,
+) -> Option<String> {
+    head.filter(|v| has_branch(v)).or_else(|| {
+        ["main", "master"]
+            .into_iter()
+            .find(|v| has_branch(v))
+            .map(ToString::to_string)
+    })
+}
+
+/// Executable hooks in `hooks_path`, or in the `hooks` directory of `common_dir` when it's unset
+///
+/// A relative `core.hooksPath` is resolved against the working tree, or the git directory for bare
+/// repositories, the same way git does.
+pub(crate) fn find_hooks(
+    common_dir: &Path,
+    work_dir: Option<&Path>,
+    hooks_path: Option<&str>,
+) -> Vec<String> {
+    let dir = hooks_path.map_or_else(
+        || common_dir.join("hooks"),
+        |v| work_dir.unwrap_or(common_dir).join(v),
+    );
+
+    let Ok(entries) = read_dir(&dir) else {
+        return Vec::new();
+    };
+
+    let mut hooks: Vec<String> = entries
+        .flatten()
+        .filter(|entry| {
+            // Git skips hooks that aren't executable, which is also how the samples stay inactive
+            entry
+                .metadata()
+                .is_ok_and(|v| v.is_file() && v.permissions().mode() & 0o111 != 0)
+        })
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| !name.ends_with(".sample"))
+        .collect();
+    hooks.sort();
+    hooks
+}
+
+/// State of a repository at the time it was scanned
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub enum RepoState {
+    #[default]
+    Ok,
+    /// Nothing has been committed to any branch
+    Empty,
+    /// HEAD points directly at a commit
+    Detached,
+    /// Repository couldn't be opened or read
+    Error,
+    /// HEAD points to a branch without commits while other refs have some
+    Unborn,
+}
+
+impl RepoState {
+    /// Whether dates and commit count describe real commits
+    pub const fn has_commits(self) -> bool {
+        matches!(self, Self::Ok | Self::Detached)
+    }
+
+    /// Why the repository shows up as inactive, `None` when it has commits
+    pub const fn inactive_reason(self) -> Option<&'static str> {
+        match self {
+            Self::Ok | Self::Detached => None,
+            Self::Empty => Some("Empty repository, nothing has been committed yet"),
+            Self::Unborn => Some("The checked out branch has no commits yet, other branches do"),
+            Self::Error => Some("The repository couldn't be read, its data is missing"),
+        }
+    }
+
+    /// Text marking the state in the list where color can't, `None` for [`Self::Ok`]
+    pub const fn tag(self) -> Option<&'static str> {
+        match self {
+            Self::Ok => None,
+            Self::Empty => Some("[empty]"),
+            Self::Detached => Some("[detached]"),
+            Self::Error => Some("[unreadable]"),
+            Self::Unborn => Some("[unborn]"),
+        }
+    }
+}
+
+impl TryFrom<u8> for RepoState {
+    type Error = YmirError;
+
+    fn try_from(value: u8) -> Result<Self, YmirError> {
+        match value {
+            0 => Ok(Self::Ok),
+            1 => Ok(Self::Empty),
+            2 => Ok(Self::Detached),
+            3 => Ok(Self::Error),
+            4 => Ok(Self::Unborn),
+            _ => Err(YmirError::CorruptCache(format!(
+                "Invalid repo state {value}"
+            ))),
+        }
+    }
+}
+
+impl Display for RepoState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ok => write!(f, "Ok"),
+            Self::Empty => write!(f, "Empty (no commits)"),
+            Self::Detached => write!(f, "Detached HEAD"),
+            Self::Error => write!(f, "Unreadable"),
+            Self::Unborn => write!(f, "Unborn branch (no commits)"),
+        }
+    }
+}
+
+#[cfg(not(feature = "gix"))]
+pub fn get_git_info(repo_path: &Path, settings: &Settings) -> Result<GitInfo, YmirError> {
     let repo = Repository::open(repo_path)?;
 
-    let remote_url = repo
-        .find_remote("origin")
+    let remotes: Vec<GitRemote> = repo
+        .remotes()?
+        .iter()
+        .flatten()
+        .filter_map(|name| {
+            let remote = repo.find_remote(name).ok()?;
+            Some(GitRemote {
+                name: name.to_string(),
+                url: remote.url()?.to_string(),
+            })
+        })
+        .collect();
+    let primary = primary_remote(&remotes, &settings.remote_priority);
+    let remote_url = primary.map(|v| v.url.clone());
+    let default_branch =
+        primary.and_then(|v| default_branch(&repo, &v.name, settings.fetch_default_branch));
+
+    let branch = repo.find_reference("HEAD").ok().and_then(|v| {
+        v.symbolic_target()
+            .and_then(|v| v.strip_prefix("refs/heads/"))
+            .map(ToString::to_string)
+    });
+
+    let hooks_path = repo.config()?.get_string("core.hooksPath").ok();
+    let hooks = find_hooks(repo.commondir(), repo.workdir(), hooks_path.as_deref());
+
+    let head = match repo.head().and_then(|v| v.peel_to_commit()) {
+        Ok(head) => head,
+        Err(err) if err.code() == git2::ErrorCode::UnbornBranch => {
+            let has_refs = repo.references()?.flatten().any(|v| v.target().is_some());
+            return Ok(GitInfo {
+                remote_url,
+                remotes,
+                state: if has_refs {
+                    RepoState::Unborn
+                } else {
+                    RepoState::Empty
+                },
+                branch,
+                default_branch,
+                hooks_path,
+                hooks,
+                ..Default::default()
+            });
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    let state = if repo.head_detached()? {
+        RepoState::Detached
+    } else {
+        RepoState::Ok
+    };
+
+    let last_commit_time = Some(head.time().seconds());
+    let last_commit_message = Some(
+        head.message()
+            .map_or("No message", |v| v.lines().next().unwrap_or("No message"))
+            .to_string(),
+    );
+
+    let signature = match repo.extract_signature(&head.id(), None) {
+        Ok((signature, _)) => signature.as_str().and_then(CommitSignature::parse),
+        Err(err) if err.code() == git2::ErrorCode::NotFound => None,
+        Err(err) => return Err(err.into()),
+    };
+
+    let (commit_count, first_commit_time) = match CommitGraph::open(repo.commondir()) {
+        Some(graph) => walk_commit_graph(&repo, &graph, head.id())?,
+        None => walk_revisions(&repo, head.id())?,
+    };
+
+    Ok(GitInfo {
+        remote_url,
+        remotes,
+        init_date: format_time(first_commit_time),
+        last_commit_date: format_time(last_commit_time),
+        last_commit_msg: last_commit_message.as_ref().map(|v| v.trim().to_string()),
+        commit_count,
+        state,
+        branch,
+        default_branch,
+        hooks_path,
+        hooks,
+        signature,
+    })
+}
+
+/// Default branch of `remote`, asking the remote itself first when `fetch` is set
+#[cfg(not(feature = "gix"))]
+fn default_branch(repo: &Repository, remote: &str, fetch: bool) -> Option<String> {
+    if fetch {
+        match fetch_default_branch(repo, remote) {
+            Ok(branch) => return Some(branch),
+            Err(err) => warn!("Failed to ask {remote} for its default branch: {err}"),
+        }
+    }
+
+    let prefix = format!("refs/remotes/{remote}/");
+    let head = repo
+        .find_reference(&format!("{prefix}HEAD"))
         .ok()
-        .and_then(|r| r.url().map(String::from));
+        .and_then(|v| {
+            v.symbolic_target()
+                .and_then(|v| v.strip_prefix(&prefix))
+                .map(ToString::to_string)
+        });
 
+    tracking_default_branch(head, |branch| {
+        repo.find_reference(&format!("{prefix}{branch}")).is_ok()
+    })
+}
+
+#[cfg(not(feature = "gix"))]
+fn fetch_default_branch(repo: &Repository, remote: &str) -> Result<String, git2::Error> {
+    let mut callbacks = git2::RemoteCallbacks::new();
+    callbacks
+        .credentials(|_, username, _| git2::Cred::ssh_key_from_agent(username.unwrap_or("git")));
+
+    let mut remote = repo.find_remote(remote)?;
+    remote.connect_auth(git2::Direction::Fetch, Some(callbacks), None)?;
+    let branch = remote.default_branch()?;
+    let Some(branch) = branch.as_str() else {
+        return Err(git2::Error::from_str("Default branch isn't valid utf-8"));
+    };
+
+    Ok(branch
+        .strip_prefix("refs/heads/")
+        .unwrap_or(branch)
+        .to_string())
+}
+
+/// Counts commits reachable from `head` and returns the time of the oldest one
+#[cfg(not(feature = "gix"))]
+fn walk_revisions(repo: &Repository, head: git2::Oid) -> Result<(u32, Option<i64>), git2::Error> {
     let mut revwalk = repo.revwalk()?;
-    if revwalk.push_head().is_err() {
-        // TODO: Log error
-        return Ok(GitInfo::default());
+    revwalk.push(head)?;
+    revwalk.set_sorting(git2::Sort::REVERSE)?;
+
+    let mut first_commit_id = None;
+    let mut commit_count: u32 = 0;
+    for oid in revwalk {
+        first_commit_id.get_or_insert(oid?);
+        commit_count += 1;
     }
 
-    revwalk.set_sorting(git2::Sort::REVERSE)?;
-    let first_commit_id = revwalk.next().and_then(Result::ok);
-    let last_commit_id = revwalk.last().and_then(Result::ok).or(first_commit_id);
+    let first_commit_time = match first_commit_id {
+        Some(id) => Some(repo.find_commit(id)?.time().seconds()),
+        None => None,
+    };
+
+    Ok((commit_count, first_commit_time))
+}
 
+/// Same as [`walk_revisions`], but reads parents from the commit-graph and only inflates commits
+/// made after it was last written
+#[cfg(not(feature = "gix"))]
+fn walk_commit_graph(
+    repo: &Repository,
+    graph: &CommitGraph,
+    head: git2::Oid,
+) -> Result<(u32, Option<i64>), git2::Error> {
+    let mut commit_count: u32 = 0;
     let mut first_commit_time: Option<i64> = None;
 
-    if let Some(first_id) = first_commit_id {
-        let first_commit = repo.find_commit(first_id)?;
-        first_commit_time = Some(first_commit.time().seconds());
-    }
-
-    let mut last_commit_time: Option<i64> = None;
-    let mut last_commit_message: Option<String> = None;
-    if let Some(last_id) = last_commit_id {
-        let last_commit = repo.find_commit(last_id)?;
-        last_commit_time = Some(last_commit.time().seconds());
-        last_commit_message = Some(
-            last_commit
-                .message()
-                .map_or("No message", |v| v.lines().next().unwrap_or("No message"))
-                .to_string(),
-        );
+    let mut pending = vec![head];
+    let mut seen = std::collections::HashSet::new();
+    let mut graph_pending = Vec::new();
+
+    while let Some(oid) = pending.pop() {
+        if let Some(index) = graph.find(oid.as_bytes()) {
+            graph_pending.push(index);
+            continue;
+        }
+
+        if !seen.insert(oid) {
+            continue;
+        }
+
+        let commit = repo.find_commit(oid)?;
+        commit_count += 1;
+        if commit.parent_count() == 0 {
+            let time = commit.time().seconds();
+            first_commit_time = Some(first_commit_time.map_or(time, |v| v.min(time)));
+        }
+        pending.extend(commit.parent_ids());
     }
 
-    let mut revwalk_count = repo.revwalk()?;
-    revwalk_count.push_head()?; // Push HEAD so walker sees commits
-    let commit_count = u32::try_from(revwalk_count.count())?;
+    let mut visited = vec![false; graph.len()];
+    while let Some(index) = graph_pending.pop() {
+        if visited.get(index).is_none_or(|&v| v) {
+            continue;
+        }
+        visited[index] = true;
+        commit_count += 1;
+
+        let parents = graph.parents(index);
+        if parents.is_empty() {
+            let time = graph.commit_time(index);
+            first_commit_time = Some(first_commit_time.map_or(time, |v| v.min(time)));
+        }
+        graph_pending.extend(parents);
+    }
+
+    Ok((commit_count, first_commit_time))
+}
+
+/// Same as the libgit2 version, but walks through commit-graph files when they exist
+///
+/// Built without network support, so the default branch always comes from remote-tracking refs.
+#[cfg(feature = "gix")]
+pub fn get_git_info(repo_path: &Path, settings: &Settings) -> Result<GitInfo, YmirError> {
+    read_gix_info(repo_path, settings).map_err(YmirError::Git)
+}
+
+#[cfg(feature = "gix")]
+fn read_gix_info(
+    repo_path: &Path,
+    settings: &Settings,
+) -> Result<GitInfo, Box<dyn std::error::Error + Send + Sync>> {
+    use gix::{
+        bstr::ByteSlice, revision::walk::Sorting, traverse::commit::simple::CommitTimeOrder,
+    };
+
+    let repo = gix::open(repo_path)?;
+
+    let remotes: Vec<GitRemote> = repo
+        .remote_names()
+        .iter()
+        .filter_map(|name| {
+            let remote = repo.find_remote(name.as_bstr()).ok()?;
+            Some(GitRemote {
+                name: name.to_string(),
+                url: remote
+                    .url(gix::remote::Direction::Fetch)?
+                    .to_bstring()
+                    .to_string(),
+            })
+        })
+        .collect();
+    let primary = primary_remote(&remotes, &settings.remote_priority);
+    let remote_url = primary.map(|v| v.url.clone());
+    let default_branch = primary.and_then(|v| {
+        let prefix = format!("refs/remotes/{}/", v.name);
+        let head = repo
+            .try_find_reference(format!("{prefix}HEAD").as_str())
+            .ok()
+            .flatten()
+            .and_then(|v| match v.target() {
+                gix::refs::TargetRef::Symbolic(name) => name
+                    .as_bstr()
+                    .to_str()
+                    .ok()
+                    .and_then(|v| v.strip_prefix(&prefix))
+                    .map(ToString::to_string),
+                gix::refs::TargetRef::Object(_) => None,
+            });
+
+        tracking_default_branch(head, |branch| {
+            repo.try_find_reference(format!("{prefix}{branch}").as_str())
+                .is_ok_and(|v| v.is_some())
+        })
+    });
+
+    let hooks_path = repo
+        .config_snapshot()
+        .string("core.hooksPath")
+        .map(|v| v.to_string());
+    let hooks = find_hooks(repo.common_dir(), repo.workdir(), hooks_path.as_deref());
+
+    let head_ref = repo.head()?;
+    let branch = head_ref
+        .referent_name()
+        .and_then(|v| v.as_bstr().to_str().ok()?.strip_prefix("refs/heads/"))
+        .map(ToString::to_string);
+    if head_ref.is_unborn() {
+        let has_refs = repo
+            .references()?
+            .all()?
+            .flatten()
+            .any(|v| matches!(v.target(), gix::refs::TargetRef::Object(_)));
+        return Ok(GitInfo {
+            remote_url,
+            remotes,
+            state: if has_refs {
+                RepoState::Unborn
+            } else {
+                RepoState::Empty
+            },
+            branch,
+            default_branch,
+            hooks_path,
+            hooks,
+            ..Default::default()
+        });
+    }
+
+    let state = if head_ref.is_detached() {
+        RepoState::Detached
+    } else {
+        RepoState::Ok
+    };
+    let head = repo.head_commit()?;
+
+    let last_commit_time = head.time()?.seconds;
+    let last_commit_message = head.message()?.summary().to_string();
+    let signature = head
+        .decode()?
+        .extra_headers()
+        .pgp_signature()
+        .and_then(|v| std::str::from_utf8(v).ok())
+        .and_then(CommitSignature::parse);
+
+    let mut first_commit_time = last_commit_time;
+    let mut commit_count: u32 = 0;
+
+    for info in repo
+        .rev_walk([head.id])
+        .sorting(Sorting::ByCommitTime(CommitTimeOrder::NewestFirst))
+        .use_commit_graph(true)
+        .all()?
+    {
+        let info = info?;
+        commit_count += 1;
+        if let Some(time) = info.commit_time {
+            first_commit_time = first_commit_time.min(time);
+        }
+    }
 
     Ok(GitInfo {
         remote_url,
-        init_date: format_time(first_commit_time),
-        last_commit_date: format_time(last_commit_time),
-        last_commit_msg: last_commit_message.as_ref().map(|v| v.trim().to_string()),
+        remotes,
+        init_date: format_time(Some(first_commit_time)),
+        last_commit_date: format_time(Some(last_commit_time)),
+        last_commit_msg: Some(last_commit_message.trim().to_string()),
         commit_count,
+        state,
+        branch,
+        default_branch,
+        hooks_path,
+        hooks,
+        signature,
     })
 }
 
@@ -110,3 +898,224 @@ fn format_time(timestamp: Option<i64>) -> u32 {
             u32::try_from(dt.with_timezone(&Local).timestamp()).unwrap_or_default()
         })
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, os::unix::fs::PermissionsExt, path::Path, process::Command};
+
+    use super::{
+        find_hooks, format_number, get_git_info, id_name, parse_size, run_status, truncate_middle,
+        Ownership, RemoteUrl, RepoState,
+    };
+    use crate::{
+        config::{NumberFormat, Settings},
+        error::YmirError,
+        test_utils::{git, temp_dir},
+    };
+
+    fn parse(url: &str) -> Option<(String, String, String)> {
+        RemoteUrl::parse(url).map(|v| (v.host, v.owner, v.repo))
+    }
+
+    fn parts(host: &str, owner: &str, repo: &str) -> Option<(String, String, String)> {
+        Some((host.to_string(), owner.to_string(), repo.to_string()))
+    }
+
+    #[test]
+    fn formats_numbers() {
+        assert_eq!(format_number(1_234_567, NumberFormat::Raw), "1234567");
+        assert_eq!(
+            format_number(1_234_567, NumberFormat::Separated),
+            "1,234,567"
+        );
+        assert_eq!(format_number(123_456, NumberFormat::Separated), "123,456");
+        assert_eq!(format_number(999, NumberFormat::Separated), "999");
+        assert_eq!(format_number(1_234_567, NumberFormat::Short), "1.2M");
+        assert_eq!(format_number(12_345, NumberFormat::Short), "12.3K");
+        assert_eq!(format_number(999, NumberFormat::Short), "999");
+    }
+
+    #[test]
+    fn parses_sizes() {
+        assert_eq!(parse_size("512").unwrap(), 512);
+        assert_eq!(parse_size("64K").unwrap(), 64 << 10);
+        assert_eq!(parse_size(" 30 G ").unwrap(), 30 << 30);
+        assert!(matches!(parse_size("1d"), Err(YmirError::InvalidSize(v)) if v == "1d"));
+        assert!(parse_size("G").is_err());
+    }
+
+    #[test]
+    fn tells_missing_programs_from_failing_ones() {
+        assert!(run_status(&mut Command::new("true")).is_ok());
+        assert!(matches!(
+            run_status(&mut Command::new("false")),
+            Err(YmirError::Exit { program, .. }) if program == "false"
+        ));
+        assert!(matches!(
+            run_status(&mut Command::new("ymir-missing-program")),
+            Err(YmirError::Spawn { .. })
+        ));
+    }
+
+    #[test]
+    fn truncates_in_the_middle() {
+        assert_eq!(truncate_middle("~/work/foo", 10), "~/work/foo");
+        assert_eq!(truncate_middle("~/work/client/foo", 12), "~/work/…/foo");
+        assert_eq!(truncate_middle("/src/a-very-long-name", 9), "…ong-name");
+        assert_eq!(truncate_middle("abcdef", 1), "…");
+    }
+
+    #[test]
+    fn shows_ownership_like_ls() {
+        let passwd = "root:x:0:0::/root:/bin/sh\nme:x:1000:100::/home/me:/bin/zsh\n";
+        assert_eq!(id_name(passwd, 1000).as_deref(), Some("me"));
+        assert_eq!(id_name(passwd, 100), None);
+
+        let ownership = Ownership {
+            owner: "me".to_string(),
+            group: "users".to_string(),
+            mode: 0o750,
+        };
+        assert_eq!(ownership.to_string(), "me:users rwxr-x---");
+        let root = Ownership::of(Path::new("/")).unwrap();
+        assert_eq!(root.mode & 0o700, 0o700);
+    }
+
+    #[test]
+    fn parses_remote_urls() {
+        let expected = parts("github.com", "ponurakk", "ymir");
+        assert_eq!(parse("https://github.com/ponurakk/ymir"), expected);
+        assert_eq!(parse("https://github.com/ponurakk/ymir.git"), expected);
+        assert_eq!(parse("git@github.com:ponurakk/ymir.git"), expected);
+        assert_eq!(parse("ssh://git@github.com:22/ponurakk/ymir"), expected);
+        assert_eq!(
+            parse("https://gitlab.com/company/team/tool"),
+            parts("gitlab.com", "company/team", "tool")
+        );
+    }
+
+    #[test]
+    fn rejects_local_paths() {
+        assert_eq!(parse("/srv/git/ymir.git"), None);
+        assert_eq!(parse("../ymir"), None);
+        assert_eq!(parse("file:///srv/git/ymir.git"), None);
+    }
+
+    #[test]
+    fn default_branch_from_tracking_refs() {
+        let dir = temp_dir("default-branch");
+        fs::create_dir_all(dir.join("upstream")).unwrap();
+
+        let upstream = dir.join("upstream");
+        git(&upstream, &["init", "-q", "-b", "main"]);
+        git(&upstream, &["commit", "-q", "--allow-empty", "-m", "init"]);
+        git(&dir, &["clone", "-q", "upstream", "clone"]);
+
+        let clone = dir.join("clone");
+        let info = get_git_info(&clone, &Settings::default()).unwrap();
+        assert_eq!(info.branch.as_deref(), Some("main"));
+        assert_eq!(info.default_branch.as_deref(), Some("main"));
+        assert!(!info.off_default_branch());
+
+        git(&clone, &["checkout", "-q", "-b", "feature"]);
+        let info = get_git_info(&clone, &Settings::default()).unwrap();
+        assert_eq!(info.branch.as_deref(), Some("feature"));
+        assert!(info.off_default_branch());
+    }
+
+    #[test]
+    fn tells_empty_and_unborn_apart() {
+        let dir = temp_dir("unborn");
+
+        git(&dir, &["init", "-q", "-b", "main"]);
+        let info = get_git_info(&dir, &Settings::default()).unwrap();
+        assert_eq!(info.state, RepoState::Empty);
+
+        git(&dir, &["commit", "-q", "--allow-empty", "-m", "init"]);
+        git(&dir, &["checkout", "-q", "--orphan", "rewrite"]);
+        let info = get_git_info(&dir, &Settings::default()).unwrap();
+        assert_eq!(info.state, RepoState::Unborn);
+        assert_eq!(info.commit_count, 0);
+    }
+
+    #[test]
+    fn detached_head_keeps_its_commits() {
+        let dir = temp_dir("detached");
+
+        git(&dir, &["init", "-q", "-b", "main"]);
+        git(&dir, &["commit", "-q", "--allow-empty", "-m", "init"]);
+        git(&dir, &["commit", "-q", "--allow-empty", "-m", "second"]);
+        let info = get_git_info(&dir, &Settings::default()).unwrap();
+        assert_eq!(info.state, RepoState::Ok);
+
+        git(&dir, &["checkout", "-q", "--detach", "HEAD~"]);
+        let info = get_git_info(&dir, &Settings::default()).unwrap();
+        assert_eq!(info.state, RepoState::Detached);
+        assert!(info.state.has_commits());
+        assert_eq!(info.commit_count, 1);
+        assert_eq!(info.last_commit_msg.as_deref(), Some("init"));
+        assert!(!RepoState::Empty.has_commits());
+    }
+
+    #[test]
+    fn primary_remote_follows_the_priority() {
+        let dir = temp_dir("remotes");
+        git(&dir, &["init", "-q", "-b", "main"]);
+        for (name, url) in [
+            ("fork", "git@github.com:me/ymir.git"),
+            ("origin", "git@github.com:ponurakk/ymir.git"),
+            ("upstream", "https://codeberg.org/ymir/ymir.git"),
+        ] {
+            git(&dir, &["remote", "add", name, url]);
+        }
+
+        let primary = |priority: &[&str]| {
+            let settings = Settings {
+                remote_priority: priority.iter().map(ToString::to_string).collect(),
+                ..Settings::default()
+            };
+            get_git_info(&dir, &settings).unwrap().remote_url
+        };
+        let info = get_git_info(&dir, &Settings::default()).unwrap();
+        let mut names: Vec<_> = info.remotes.iter().map(|v| v.name.as_str()).collect();
+        names.sort_unstable();
+        assert_eq!(names, ["fork", "origin", "upstream"]);
+        assert_eq!(
+            info.remote_url.as_deref(),
+            Some("git@github.com:ponurakk/ymir.git")
+        );
+        assert_eq!(
+            primary(&["missing", "upstream", "origin"]).as_deref(),
+            Some("https://codeberg.org/ymir/ymir.git")
+        );
+        assert!(primary(&["missing"]).is_some());
+    }
+
+    #[test]
+    fn hooks_are_the_executable_ones_git_would_run() {
+        let dir = temp_dir("hooks");
+        git(&dir, &["init", "-q", "-b", "main"]);
+        let hook = |path: &Path, mode: u32| {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "#!/bin/sh\n").unwrap();
+            fs::set_permissions(path, fs::Permissions::from_mode(mode)).unwrap();
+        };
+        hook(&dir.join(".git/hooks/pre-commit"), 0o755);
+        hook(&dir.join(".git/hooks/commit-msg"), 0o644);
+        hook(&dir.join(".git/hooks/pre-push.sample"), 0o755);
+        hook(&dir.join(".githooks/pre-push"), 0o755);
+
+        let info = get_git_info(&dir, &Settings::default()).unwrap();
+        assert_eq!(info.hooks_path, None);
+        assert_eq!(info.hooks, ["pre-commit"]);
+
+        // Relative paths are taken from the working tree, like git does
+        git(&dir, &["config", "core.hooksPath", ".githooks"]);
+        let info = get_git_info(&dir, &Settings::default()).unwrap();
+        assert_eq!(info.hooks_path.as_deref(), Some(".githooks"));
+        assert_eq!(info.hooks, ["pre-push"]);
+
+        let bare = dir.join(".git");
+        assert_eq!(find_hooks(&bare, None, Some("hooks")), ["pre-commit"]);
+    }
+}
@@ -0,0 +1,62 @@
+//! Frecency from zoxide, enabled with `zoxide`
+//!
+//! The `Frecency` sorting ranks projects by their zoxide score and every project opened from
+//! ymir is added to zoxide, so jumping around with either tool improves the other.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use log::warn;
+
+use crate::{error::YmirError, utils::run_status};
+
+/// Scores zoxide keeps for directories, empty when zoxide isn't installed
+pub fn scores() -> HashMap<PathBuf, f64> {
+    match Command::new("zoxide")
+        .args(["query", "--list", "--score"])
+        .output()
+    {
+        Ok(output) if output.status.success() => parse(&String::from_utf8_lossy(&output.stdout)),
+        Ok(output) => {
+            warn!("zoxide query exited with {}", output.status);
+            HashMap::new()
+        }
+        Err(err) => {
+            warn!("Failed to run zoxide: {err}");
+            HashMap::new()
+        }
+    }
+}
+
+/// Reads `zoxide query --list --score`, one `<score> <path>` line per directory
+fn parse(text: &str) -> HashMap<PathBuf, f64> {
+    text.lines()
+        .filter_map(|line| {
+            let (score, path) = line.trim_start().split_once(' ')?;
+            Some((PathBuf::from(path), score.parse().ok()?))
+        })
+        .collect()
+}
+
+/// Tells zoxide `path` was visited
+pub fn add(path: &Path) -> Result<(), YmirError> {
+    run_status(Command::new("zoxide").arg("add").arg(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::parse;
+
+    #[test]
+    fn parses_scores() {
+        let scores = parse("  12.5 /src/ymir\n   0.3 /src/my project\nbroken\n");
+        assert_eq!(scores.len(), 2);
+        assert_eq!(scores[&PathBuf::from("/src/ymir")], 12.5);
+        assert_eq!(scores[&PathBuf::from("/src/my project")], 0.3);
+    }
+}
@@ -0,0 +1,347 @@
+//! Discovery and the cache against real repositories built in a temporary directory
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::Cursor,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use git2::{Repository, RepositoryInitOptions, Signature, Time};
+use tempfile::TempDir;
+use tokei::LanguageType;
+use ymir::{
+    cache::CacheSerializer,
+    config::{Cache, Settings},
+    projects::{self, Project},
+    utils::RepoState,
+};
+
+/// Directory of repositories, removed on drop
+struct Fixture(TempDir);
+
+impl Fixture {
+    fn new(name: &str) -> Self {
+        Self(
+            tempfile::Builder::new()
+                .prefix(&format!("ymir-e2e-{name}-"))
+                .tempdir()
+                .unwrap(),
+        )
+    }
+
+    /// Repository on `main` at `path`, with a commit of all its files for every `(time, message)`
+    fn repo(&self, path: &str, files: &[(&str, &str)], commits: &[(i64, &str)]) -> Repository {
+        let dir = self.0.path().join(path);
+        let repo =
+            Repository::init_opts(&dir, RepositoryInitOptions::new().initial_head("main")).unwrap();
+        for (file, content) in files {
+            let file = dir.join(file);
+            fs::create_dir_all(file.parent().unwrap()).unwrap();
+            fs::write(file, content).unwrap();
+        }
+
+        for (time, message) in commits {
+            let mut index = repo.index().unwrap();
+            index
+                .add_all(["*"], git2::IndexAddOption::DEFAULT, None)
+                .unwrap();
+            let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+            let signature = Signature::new("ymir", "ymir@localhost", &Time::new(*time, 0)).unwrap();
+            let parent = repo.head().ok().and_then(|v| v.peel_to_commit().ok());
+            repo.commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                message,
+                &tree,
+                &parent.iter().collect::<Vec<_>>(),
+            )
+            .unwrap();
+        }
+        repo
+    }
+
+    fn root(&self) -> PathBuf {
+        self.0.path().to_path_buf()
+    }
+
+    fn path(&self, path: &str) -> PathBuf {
+        self.0.path().join(path)
+    }
+
+    fn find(&self) -> Vec<Project> {
+        let mut found = projects::find(&self.root(), &Settings::default());
+        found.sort_by(|a, b| a.path.cmp(&b.path));
+        found
+    }
+}
+
+fn code_of(project: &Project, language: LanguageType) -> Option<u32> {
+    let id = LanguageType::list().iter().position(|v| *v == language)?;
+    project
+        .languages
+        .get(&u8::try_from(id).ok()?)
+        .map(|v| v.code)
+}
+
+const MAIN_RS: &str = "// Entry point\nfn main() {\n    println!(\"tool\");\n}\n";
+
+#[test]
+fn finds_repositories_and_reads_their_history() {
+    let fixture = Fixture::new("history");
+    let tool = fixture.repo(
+        "tool",
+        &[("src/main.rs", MAIN_RS), ("README.md", "# Tool\n")],
+        &[
+            (1_600_000_000, "Initial commit"),
+            (1_700_000_000, "Print the name\n\nBody"),
+        ],
+    );
+    tool.remote("origin", "https://github.com/acme/tool.git")
+        .unwrap();
+    fixture.repo(
+        "group/lib",
+        &[("lib.py", "def answer():\n    return 42\n")],
+        &[(1_650_000_000, "Add answer")],
+    );
+    fs::create_dir_all(fixture.path("plain/src")).unwrap();
+
+    let found = fixture.find();
+    let paths: Vec<&Path> = found.iter().map(|v| v.path.as_path()).collect();
+    assert_eq!(paths, [fixture.path("group/lib"), fixture.path("tool")]);
+
+    let tool = &found[1].git_info;
+    assert_eq!(tool.state, RepoState::Ok);
+    assert_eq!(tool.branch.as_deref(), Some("main"));
+    assert_eq!(tool.commit_count, 2);
+    assert_eq!(tool.init_date, 1_600_000_000);
+    assert_eq!(tool.last_commit_date, 1_700_000_000);
+    assert_eq!(tool.last_commit_msg.as_deref(), Some("Print the name"));
+    assert_eq!(
+        tool.remote_url.as_deref(),
+        Some("https://github.com/acme/tool.git")
+    );
+    assert_eq!(found[1].git_info.remotes[0].name, "origin");
+
+    let lib = &found[0].git_info;
+    assert_eq!(lib.commit_count, 1);
+    assert_eq!(lib.remote_url, None);
+    assert!(lib.remotes.is_empty());
+}
+
+#[test]
+fn counts_lines_by_language() {
+    let fixture = Fixture::new("languages");
+    fixture.repo(
+        "tool",
+        &[
+            ("src/main.rs", MAIN_RS),
+            ("src/lib.rs", "pub fn answer() -> u32 {\n\n    42\n}\n"),
+            ("build.py", "# Build script\nprint('build')\n"),
+        ],
+        &[(1_600_000_000, "Initial commit")],
+    );
+
+    let found = fixture.find();
+    let [project] = &found[..] else {
+        panic!("{found:?}");
+    };
+    assert_eq!(code_of(project, LanguageType::Rust), Some(6));
+    assert_eq!(code_of(project, LanguageType::Python), Some(1));
+    assert_eq!(code_of(project, LanguageType::Go), None);
+
+    let rust = LanguageType::list()
+        .iter()
+        .position(|v| *v == LanguageType::Rust)
+        .and_then(|v| u8::try_from(v).ok())
+        .unwrap();
+    let rust = &project.languages[&rust];
+    assert_eq!((rust.files, rust.comments, rust.blanks), (2, 1, 1));
+    assert_eq!(project.languages_total.code, 7);
+    assert!(project.size > 0);
+
+    // Ignored languages are still listed, only the total leaves them out
+    let settings = Settings {
+        ignore_languages: vec!["python".to_string()],
+        project_ignore_languages: [(project.path.clone(), vec!["Rust".to_string()])].into(),
+        ..Settings::default()
+    };
+    let found = projects::find(&fixture.root(), &settings);
+    let [project] = &found[..] else {
+        panic!("{found:?}");
+    };
+    assert_eq!(code_of(project, LanguageType::Python), Some(1));
+    assert_eq!(project.languages_total.code, 0);
+}
+
+#[test]
+fn skips_ignored_directories_and_tells_empty_repositories() {
+    let fixture = Fixture::new("ignored");
+    fixture.repo("app", &[("index.js", "console.log(1)\n")], &[]);
+    fixture.repo(
+        "app/node_modules/dep",
+        &[("index.js", "module.exports = 1\n")],
+        &[(1_600_000_000, "Vendored")],
+    );
+
+    let found = fixture.find();
+    let [app] = &found[..] else {
+        panic!("{found:?}");
+    };
+    assert_eq!(app.path, fixture.path("app"));
+    assert_eq!(app.git_info.state, RepoState::Empty);
+    assert_eq!(app.git_info.commit_count, 0);
+    assert_eq!(app.git_info.branch.as_deref(), Some("main"));
+}
+
+#[test]
+fn cache_round_trips_scanned_projects() {
+    let fixture = Fixture::new("cache");
+    let tool = fixture.repo(
+        "tool",
+        &[("src/main.rs", MAIN_RS)],
+        &[(1_600_000_000, "Initial commit")],
+    );
+    tool.remote("origin", "git@github.com:acme/tool.git")
+        .unwrap();
+    fixture.repo("empty", &[], &[]);
+    let found = fixture.find();
+    assert_eq!(found.len(), 2);
+
+    for compressed in [false, true] {
+        let cache = Cache {
+            projects: found.clone(),
+            compressed,
+        };
+        let bytes = cache.serialize().unwrap();
+        let file = fixture.path(if compressed { "cache.huff" } else { "cache" });
+        fs::write(&file, &bytes).unwrap();
+
+        assert_eq!(Cache::read_file(&file).unwrap(), found);
+        let read = Cache::deserialize(&mut Cursor::new(&bytes[..])).unwrap();
+        assert_eq!(read.compressed, compressed);
+        assert_eq!(read.projects, found);
+    }
+}
+
+#[test]
+fn incremental_scans_reuse_unchanged_projects() {
+    let fixture = Fixture::new("incremental");
+    let tool = fixture.repo(
+        "tool",
+        &[("src/main.rs", MAIN_RS)],
+        &[(1_600_000_000, "Initial commit")],
+    );
+    fixture.repo(
+        "lib",
+        &[("lib.py", "def answer():\n    return 42\n")],
+        &[(1_650_000_000, "Add answer")],
+    );
+    let found = fixture.find();
+    assert!(found.iter().all(|v| v.head.is_some()));
+
+    // Scanned after every change, with a size no scan would give to tell reused stats apart
+    let later = found[0].scanned_at + 60;
+    let cached: HashMap<PathBuf, Project> = found
+        .into_iter()
+        .map(|v| {
+            let project = Project {
+                size: 1,
+                scanned_at: later,
+                ..v
+            };
+            (project.path.clone(), project)
+        })
+        .collect();
+    let rescan = || -> Vec<Project> {
+        let (mut found, _, _) =
+            projects::find_reusing(&fixture.root(), &Settings::default(), &cached, |_, _| {});
+        found.sort_by(|a, b| a.path.cmp(&b.path));
+        found
+    };
+    let sizes = || -> Vec<u64> { rescan().into_iter().map(|v| v.size).collect() };
+    assert_eq!(sizes(), [1, 1]);
+
+    // Git state is read again even when the sizes and lines are reused
+    tool.remote("origin", "git@github.com:acme/tool.git")
+        .unwrap();
+    let [_, reused] = &rescan()[..] else {
+        panic!("Expected lib and tool");
+    };
+    assert_eq!(reused.size, 1);
+    assert_eq!(code_of(reused, LanguageType::Rust), Some(3));
+    assert_eq!(
+        reused.git_info.remote_url.as_deref(),
+        Some("git@github.com:acme/tool.git")
+    );
+
+    // A new commit moves HEAD, touching the directory changes its mtime
+    let signature = Signature::new("ymir", "ymir@localhost", &Time::new(1_700_000_000, 0)).unwrap();
+    let parent = tool.head().unwrap().peel_to_commit().unwrap();
+    tool.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        "Empty",
+        &parent.tree().unwrap(),
+        &[&parent],
+    )
+    .unwrap();
+    fs::File::open(fixture.path("lib"))
+        .unwrap()
+        .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(later + 1))
+        .unwrap();
+    let sizes = sizes();
+    assert!(sizes.iter().all(|v| *v > 1), "{sizes:?}");
+}
+
+#[test]
+fn recent_files_leave_out_git_and_can_be_turned_off() {
+    let fixture = Fixture::new("recent");
+    fixture.repo(
+        "tool",
+        &[("src/main.rs", MAIN_RS)],
+        &[(1_600_000_000, "Initial commit")],
+    );
+
+    let (_, _, recent) =
+        projects::find_with_stats(&fixture.root(), &Settings::default(), |_, _| {});
+    let files: Vec<PathBuf> = recent.files.into_iter().map(|v| v.path).collect();
+    assert_eq!(files, [fixture.path("tool/src/main.rs")]);
+
+    let settings = Settings {
+        recent_files: false,
+        ..Settings::default()
+    };
+    let (found, _, recent) = projects::find_with_stats(&fixture.root(), &settings, |_, _| {});
+    assert_eq!(found.len(), 1);
+    assert!(recent.files.is_empty());
+}
+
+#[test]
+fn worktree_checkouts_are_projects() {
+    let fixture = Fixture::new("worktree");
+    let tool = fixture.repo(
+        "tool",
+        &[("src/main.rs", MAIN_RS)],
+        &[(1_600_000_000, "Initial commit")],
+    );
+    tool.worktree("feature", &fixture.path("tool-feature"), None)
+        .unwrap();
+    assert!(fixture.path("tool-feature/.git").is_file());
+
+    let (found, _, recent) =
+        projects::find_with_stats(&fixture.root(), &Settings::default(), |_, _| {});
+    let paths: Vec<&Path> = found.iter().map(|v| v.path.as_path()).collect();
+    assert_eq!(paths.len(), 2, "{paths:?}");
+    let worktree = found
+        .iter()
+        .find(|v| v.path == fixture.path("tool-feature"))
+        .unwrap();
+    assert_eq!(worktree.git_info.state, RepoState::Ok);
+    assert_eq!(worktree.git_info.commit_count, 1);
+    assert_eq!(code_of(worktree, LanguageType::Rust), Some(3));
+    assert!(recent.files.iter().all(|v| !v.path.ends_with(".git")));
+}